@@ -1860,6 +1860,524 @@ impl DiffSummarizeOptions {
     }
 }
 
+/// A change to a single property, as reported to a [`DiffProcessor`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropChange {
+    /// The name of the property.
+    pub name: String,
+    /// The value on the left side, if the property is set there.
+    pub old_value: Option<Vec<u8>>,
+    /// The value on the right side, if the property is set there.
+    pub new_value: Option<Vec<u8>>,
+}
+
+/// One side of a node reported to a [`DiffProcessor`].
+#[derive(Debug, Clone)]
+pub struct DiffSide {
+    /// The path or URL the side was read from.
+    pub path_or_url: String,
+    /// The revision the side was read at.
+    pub revision: Revision,
+    /// The regular properties of the node.  Deleted directories are
+    /// reported without properties.
+    pub props: HashMap<String, Vec<u8>>,
+    /// For files, a temporary file holding the contents, unless only the
+    /// properties changed.  It may be removed once the [`DiffProcessor`]
+    /// callback returns.
+    pub file: Option<std::path::PathBuf>,
+}
+
+/// A node that differs between the two sides of a diff.
+#[derive(Debug, Clone)]
+pub struct DiffNode {
+    /// Path relative to the diff targets; empty for the targets themselves.
+    pub path: String,
+    /// The kind of the node.
+    pub node_kind: crate::NodeKind,
+    /// The left side, unless the node was added.
+    pub left: Option<DiffSide>,
+    /// The right side, unless the node was deleted.
+    pub right: Option<DiffSide>,
+    /// The properties that differ between the sides, sorted by name.
+    pub prop_changes: Vec<PropChange>,
+    /// The copy source of an added node, as a repository path and revision,
+    /// when the diff reports it as copied with history.
+    pub copyfrom: Option<(String, Revnum)>,
+}
+
+impl DiffNode {
+    /// The temporary file holding the left side's contents, for files.
+    pub fn left_file(&self) -> Option<&std::path::Path> {
+        self.left.as_ref().and_then(|side| side.file.as_deref())
+    }
+
+    /// The temporary file holding the right side's contents, for files.
+    pub fn right_file(&self) -> Option<&std::path::Path> {
+        self.right.as_ref().and_then(|side| side.file.as_deref())
+    }
+}
+
+/// Receives the differences found by [`Context::diff_processor`].
+///
+/// All methods default to doing nothing.
+pub trait DiffProcessor {
+    /// A file was added.
+    fn file_added(&mut self, _node: &DiffNode) -> Result<(), Error<'static>> {
+        Ok(())
+    }
+
+    /// A file was deleted.
+    fn file_deleted(&mut self, _node: &DiffNode) -> Result<(), Error<'static>> {
+        Ok(())
+    }
+
+    /// The contents or properties of a file changed.
+    fn file_changed(&mut self, _node: &DiffNode) -> Result<(), Error<'static>> {
+        Ok(())
+    }
+
+    /// A directory was added.
+    fn dir_added(&mut self, _node: &DiffNode) -> Result<(), Error<'static>> {
+        Ok(())
+    }
+
+    /// A directory was deleted.
+    fn dir_deleted(&mut self, _node: &DiffNode) -> Result<(), Error<'static>> {
+        Ok(())
+    }
+
+    /// The properties of a directory changed.
+    fn dir_changed(&mut self, _node: &DiffNode) -> Result<(), Error<'static>> {
+        Ok(())
+    }
+}
+
+/// Join a path relative to a diff target onto the target's path or URL.
+fn join_diff_target(path_or_url: &str, relpath: &str) -> Result<String, Error<'static>> {
+    if relpath.is_empty() {
+        return Ok(path_or_url.to_string());
+    }
+    if !crate::path::is_url(path_or_url) {
+        return Ok(std::path::Path::new(path_or_url)
+            .join(relpath)
+            .to_string_lossy()
+            .into_owned());
+    }
+    with_tmp_pool(|pool| {
+        let url = std::ffi::CString::new(path_or_url)?;
+        let component = std::ffi::CString::new(relpath)?;
+        let joined = unsafe {
+            subversion_sys::svn_path_url_add_component2(
+                url.as_ptr(),
+                component.as_ptr(),
+                pool.as_mut_ptr(),
+            )
+        };
+        Ok(unsafe { std::ffi::CStr::from_ptr(joined) }
+            .to_string_lossy()
+            .into_owned())
+    })
+}
+
+/// Make a working copy path absolute; URLs are returned unchanged.
+fn absolute_path_or_url(path_or_url: &str) -> Result<String, Error<'static>> {
+    if crate::path::is_url(path_or_url) {
+        return Ok(path_or_url.to_string());
+    }
+    Ok(std::env::current_dir()?
+        .join(path_or_url)
+        .to_string_lossy()
+        .into_owned())
+}
+
+/// The properties that differ between `left` and `right`, sorted by name.
+fn diff_props(
+    left: &HashMap<String, Vec<u8>>,
+    right: &HashMap<String, Vec<u8>>,
+) -> Vec<PropChange> {
+    let names: std::collections::BTreeSet<&String> = left.keys().chain(right.keys()).collect();
+    names
+        .into_iter()
+        .filter(|name| left.get(*name) != right.get(*name))
+        .map(|name| PropChange {
+            name: name.clone(),
+            old_value: left.get(name).cloned(),
+            new_value: right.get(name).cloned(),
+        })
+        .collect()
+}
+
+/// Whether a property reported by a diff is a regular property.
+fn is_regular_diff_prop(name: &str) -> bool {
+    matches!(crate::props::kind(name), Ok(crate::props::Kind::Regular))
+}
+
+/// The regular properties in a property hash reported by a diff.
+unsafe fn diff_regular_props(hash: *mut apr_sys::apr_hash_t) -> HashMap<String, Vec<u8>> {
+    if hash.is_null() {
+        return HashMap::new();
+    }
+    let mut props = crate::props::PropHash::from_ptr(hash).to_hashmap();
+    props.retain(|name, _| is_regular_diff_prop(name));
+    props
+}
+
+/// Apply the regular property changes in a `svn_prop_t` array to `props`.
+unsafe fn apply_diff_prop_changes(
+    mut props: HashMap<String, Vec<u8>>,
+    changes: *const apr_sys::apr_array_header_t,
+) -> HashMap<String, Vec<u8>> {
+    for change in crate::wc::diff_prop_array_to_vec(changes) {
+        if !is_regular_diff_prop(&change.name) {
+            continue;
+        }
+        match change.value {
+            Some(value) => props.insert(change.name, value),
+            None => props.remove(&change.name),
+        };
+    }
+    props
+}
+
+/// Baton for the `svn_wc_diff_callbacks4_t` used by
+/// [`Context::diff_processor`], which map the callbacks onto a
+/// [`DiffProcessor`].
+struct DiffProcessorBaton<'a> {
+    processor: &'a mut dyn DiffProcessor,
+    /// The path or URL and revision of each side.
+    left: (String, Revision),
+    right: (String, Revision),
+    /// The diff target within the directory the callbacks report paths
+    /// against; empty if that directory is the target itself.
+    target: String,
+    /// Added directories that have not been closed yet, with their copy
+    /// source and properties.
+    added_dirs: HashMap<String, (Option<(String, Revnum)>, HashMap<String, Vec<u8>>)>,
+}
+
+impl DiffProcessorBaton<'_> {
+    /// The path of a reported node relative to the diff targets, or `None`
+    /// for nodes outside the target.
+    unsafe fn relpath(&self, path: *const std::os::raw::c_char) -> Option<String> {
+        let path = std::ffi::CStr::from_ptr(path).to_str().ok()?;
+        if self.target.is_empty() {
+            return Some(path.to_string());
+        }
+        if path == self.target {
+            return Some(String::new());
+        }
+        path.strip_prefix(self.target.as_str())
+            .and_then(|rest| rest.strip_prefix('/'))
+            .map(str::to_string)
+    }
+
+    /// Report the node at `relpath` with the given sides, each made up of
+    /// its properties and, for files, the temporary file with its contents.
+    unsafe fn report(
+        &mut self,
+        relpath: String,
+        node_kind: crate::NodeKind,
+        left: Option<(HashMap<String, Vec<u8>>, *const std::os::raw::c_char)>,
+        right: Option<(HashMap<String, Vec<u8>>, *const std::os::raw::c_char)>,
+        copyfrom: Option<(String, Revnum)>,
+        report: impl FnOnce(&mut dyn DiffProcessor, &DiffNode) -> Result<(), Error<'static>>,
+    ) -> *mut svn_error_t {
+        let empty = HashMap::new();
+        let prop_changes = diff_props(
+            left.as_ref().map_or(&empty, |(props, _)| props),
+            right.as_ref().map_or(&empty, |(props, _)| props),
+        );
+        let side = |(path_or_url, revision): &(String, Revision),
+                    (props, file): (HashMap<String, Vec<u8>>, *const std::os::raw::c_char)|
+         -> Result<DiffSide, Error<'static>> {
+            Ok(DiffSide {
+                path_or_url: join_diff_target(path_or_url, &relpath)?,
+                revision: *revision,
+                props,
+                file: crate::wc::diff_opt_str(file).map(std::path::PathBuf::from),
+            })
+        };
+        let left = match left.map(|left| side(&self.left, left)).transpose() {
+            Ok(left) => left,
+            Err(e) => return e.into_raw(),
+        };
+        let right = match right.map(|right| side(&self.right, right)).transpose() {
+            Ok(right) => right,
+            Err(e) => return e.into_raw(),
+        };
+        let node = DiffNode {
+            path: relpath,
+            node_kind,
+            left,
+            right,
+            prop_changes,
+            copyfrom,
+        };
+        match report(self.processor, &node) {
+            Ok(()) => std::ptr::null_mut(),
+            Err(e) => e.into_raw(),
+        }
+    }
+}
+
+unsafe extern "C" fn diff_processor_file_opened(
+    _tree_conflicted: *mut subversion_sys::svn_boolean_t,
+    _skip: *mut subversion_sys::svn_boolean_t,
+    _path: *const std::os::raw::c_char,
+    _rev: subversion_sys::svn_revnum_t,
+    _diff_baton: *mut std::ffi::c_void,
+    _scratch_pool: *mut apr_sys::apr_pool_t,
+) -> *mut svn_error_t {
+    std::ptr::null_mut()
+}
+
+unsafe extern "C" fn diff_processor_file_changed(
+    _contentstate: *mut subversion_sys::svn_wc_notify_state_t,
+    _propstate: *mut subversion_sys::svn_wc_notify_state_t,
+    _tree_conflicted: *mut subversion_sys::svn_boolean_t,
+    path: *const std::os::raw::c_char,
+    tmpfile1: *const std::os::raw::c_char,
+    tmpfile2: *const std::os::raw::c_char,
+    _rev1: subversion_sys::svn_revnum_t,
+    _rev2: subversion_sys::svn_revnum_t,
+    _mimetype1: *const std::os::raw::c_char,
+    _mimetype2: *const std::os::raw::c_char,
+    propchanges: *const apr_sys::apr_array_header_t,
+    originalprops: *mut apr_sys::apr_hash_t,
+    diff_baton: *mut std::ffi::c_void,
+    _scratch_pool: *mut apr_sys::apr_pool_t,
+) -> *mut svn_error_t {
+    let baton = &mut *(diff_baton as *mut DiffProcessorBaton);
+    let Some(relpath) = baton.relpath(path) else {
+        return std::ptr::null_mut();
+    };
+    let left_props = diff_regular_props(originalprops);
+    let right_props = apply_diff_prop_changes(left_props.clone(), propchanges);
+    baton.report(
+        relpath,
+        crate::NodeKind::File,
+        Some((left_props, tmpfile1)),
+        Some((right_props, tmpfile2)),
+        None,
+        |processor, node| processor.file_changed(node),
+    )
+}
+
+unsafe extern "C" fn diff_processor_file_added(
+    _contentstate: *mut subversion_sys::svn_wc_notify_state_t,
+    _propstate: *mut subversion_sys::svn_wc_notify_state_t,
+    _tree_conflicted: *mut subversion_sys::svn_boolean_t,
+    path: *const std::os::raw::c_char,
+    _tmpfile1: *const std::os::raw::c_char,
+    tmpfile2: *const std::os::raw::c_char,
+    _rev1: subversion_sys::svn_revnum_t,
+    _rev2: subversion_sys::svn_revnum_t,
+    _mimetype1: *const std::os::raw::c_char,
+    _mimetype2: *const std::os::raw::c_char,
+    copyfrom_path: *const std::os::raw::c_char,
+    copyfrom_revision: subversion_sys::svn_revnum_t,
+    propchanges: *const apr_sys::apr_array_header_t,
+    originalprops: *mut apr_sys::apr_hash_t,
+    diff_baton: *mut std::ffi::c_void,
+    _scratch_pool: *mut apr_sys::apr_pool_t,
+) -> *mut svn_error_t {
+    let baton = &mut *(diff_baton as *mut DiffProcessorBaton);
+    let Some(relpath) = baton.relpath(path) else {
+        return std::ptr::null_mut();
+    };
+    let right_props = apply_diff_prop_changes(diff_regular_props(originalprops), propchanges);
+    let copyfrom = crate::wc::diff_opt_str(copyfrom_path)
+        .map(|path| (path.to_string(), Revnum(copyfrom_revision)));
+    baton.report(
+        relpath,
+        crate::NodeKind::File,
+        None,
+        Some((right_props, tmpfile2)),
+        copyfrom,
+        |processor, node| processor.file_added(node),
+    )
+}
+
+unsafe extern "C" fn diff_processor_file_deleted(
+    _state: *mut subversion_sys::svn_wc_notify_state_t,
+    _tree_conflicted: *mut subversion_sys::svn_boolean_t,
+    path: *const std::os::raw::c_char,
+    tmpfile1: *const std::os::raw::c_char,
+    _tmpfile2: *const std::os::raw::c_char,
+    _mimetype1: *const std::os::raw::c_char,
+    _mimetype2: *const std::os::raw::c_char,
+    originalprops: *mut apr_sys::apr_hash_t,
+    diff_baton: *mut std::ffi::c_void,
+    _scratch_pool: *mut apr_sys::apr_pool_t,
+) -> *mut svn_error_t {
+    let baton = &mut *(diff_baton as *mut DiffProcessorBaton);
+    let Some(relpath) = baton.relpath(path) else {
+        return std::ptr::null_mut();
+    };
+    let left_props = diff_regular_props(originalprops);
+    baton.report(
+        relpath,
+        crate::NodeKind::File,
+        Some((left_props, tmpfile1)),
+        None,
+        None,
+        |processor, node| processor.file_deleted(node),
+    )
+}
+
+unsafe extern "C" fn diff_processor_dir_deleted(
+    _state: *mut subversion_sys::svn_wc_notify_state_t,
+    _tree_conflicted: *mut subversion_sys::svn_boolean_t,
+    path: *const std::os::raw::c_char,
+    diff_baton: *mut std::ffi::c_void,
+    _scratch_pool: *mut apr_sys::apr_pool_t,
+) -> *mut svn_error_t {
+    let baton = &mut *(diff_baton as *mut DiffProcessorBaton);
+    let Some(relpath) = baton.relpath(path) else {
+        return std::ptr::null_mut();
+    };
+    baton.report(
+        relpath,
+        crate::NodeKind::Dir,
+        Some((HashMap::new(), std::ptr::null())),
+        None,
+        None,
+        |processor, node| processor.dir_deleted(node),
+    )
+}
+
+unsafe extern "C" fn diff_processor_dir_opened(
+    _tree_conflicted: *mut subversion_sys::svn_boolean_t,
+    _skip: *mut subversion_sys::svn_boolean_t,
+    _skip_children: *mut subversion_sys::svn_boolean_t,
+    _path: *const std::os::raw::c_char,
+    _rev: subversion_sys::svn_revnum_t,
+    _diff_baton: *mut std::ffi::c_void,
+    _scratch_pool: *mut apr_sys::apr_pool_t,
+) -> *mut svn_error_t {
+    std::ptr::null_mut()
+}
+
+unsafe extern "C" fn diff_processor_dir_added(
+    _state: *mut subversion_sys::svn_wc_notify_state_t,
+    _tree_conflicted: *mut subversion_sys::svn_boolean_t,
+    _skip: *mut subversion_sys::svn_boolean_t,
+    _skip_children: *mut subversion_sys::svn_boolean_t,
+    path: *const std::os::raw::c_char,
+    _rev: subversion_sys::svn_revnum_t,
+    copyfrom_path: *const std::os::raw::c_char,
+    copyfrom_revision: subversion_sys::svn_revnum_t,
+    diff_baton: *mut std::ffi::c_void,
+    _scratch_pool: *mut apr_sys::apr_pool_t,
+) -> *mut svn_error_t {
+    let baton = &mut *(diff_baton as *mut DiffProcessorBaton);
+    if let Some(relpath) = baton.relpath(path) {
+        // The properties follow in dir_props_changed; the directory is
+        // reported once it is closed.
+        let copyfrom = crate::wc::diff_opt_str(copyfrom_path)
+            .map(|path| (path.to_string(), Revnum(copyfrom_revision)));
+        baton.added_dirs.insert(relpath, (copyfrom, HashMap::new()));
+    }
+    std::ptr::null_mut()
+}
+
+unsafe extern "C" fn diff_processor_dir_props_changed(
+    _propstate: *mut subversion_sys::svn_wc_notify_state_t,
+    _tree_conflicted: *mut subversion_sys::svn_boolean_t,
+    path: *const std::os::raw::c_char,
+    dir_was_added: subversion_sys::svn_boolean_t,
+    propchanges: *const apr_sys::apr_array_header_t,
+    original_props: *mut apr_sys::apr_hash_t,
+    diff_baton: *mut std::ffi::c_void,
+    _scratch_pool: *mut apr_sys::apr_pool_t,
+) -> *mut svn_error_t {
+    let baton = &mut *(diff_baton as *mut DiffProcessorBaton);
+    let Some(relpath) = baton.relpath(path) else {
+        return std::ptr::null_mut();
+    };
+    let left_props = diff_regular_props(original_props);
+    let right_props = apply_diff_prop_changes(left_props.clone(), propchanges);
+    if dir_was_added != 0 {
+        if let Some((_, props)) = baton.added_dirs.get_mut(&relpath) {
+            *props = right_props;
+        }
+        return std::ptr::null_mut();
+    }
+    baton.report(
+        relpath,
+        crate::NodeKind::Dir,
+        Some((left_props, std::ptr::null())),
+        Some((right_props, std::ptr::null())),
+        None,
+        |processor, node| processor.dir_changed(node),
+    )
+}
+
+unsafe extern "C" fn diff_processor_dir_closed(
+    _contentstate: *mut subversion_sys::svn_wc_notify_state_t,
+    _propstate: *mut subversion_sys::svn_wc_notify_state_t,
+    _tree_conflicted: *mut subversion_sys::svn_boolean_t,
+    path: *const std::os::raw::c_char,
+    dir_was_added: subversion_sys::svn_boolean_t,
+    diff_baton: *mut std::ffi::c_void,
+    _scratch_pool: *mut apr_sys::apr_pool_t,
+) -> *mut svn_error_t {
+    let baton = &mut *(diff_baton as *mut DiffProcessorBaton);
+    if dir_was_added == 0 {
+        return std::ptr::null_mut();
+    }
+    let Some(relpath) = baton.relpath(path) else {
+        return std::ptr::null_mut();
+    };
+    let Some((copyfrom, right_props)) = baton.added_dirs.remove(&relpath) else {
+        return std::ptr::null_mut();
+    };
+    baton.report(
+        relpath,
+        crate::NodeKind::Dir,
+        None,
+        Some((right_props, std::ptr::null())),
+        copyfrom,
+        |processor, node| processor.dir_added(node),
+    )
+}
+
+/// The changelist filter of `options` as an APR array, along with the
+/// strings it points to.
+fn diff_changelists<'p>(
+    options: &DiffSummarizeOptions,
+    pool: &'p Pool<'p>,
+) -> Option<(
+    apr::tables::TypedArray<'p, *const i8>,
+    Vec<std::ffi::CString>,
+)> {
+    let lists = options.changelists.as_ref()?;
+    let cstrings: Vec<_> = lists
+        .iter()
+        .map(|l| std::ffi::CString::new(l.as_str()).unwrap())
+        .collect();
+    let mut array = apr::tables::TypedArray::<*const i8>::new(pool, cstrings.len() as i32);
+    for cstring in &cstrings {
+        array.push(cstring.as_ptr());
+    }
+    Some((array, cstrings))
+}
+
+/// The `svn_wc_diff_callbacks4_t` that report to a [`DiffProcessorBaton`].
+fn diff_processor_callbacks() -> subversion_sys::svn_wc_diff_callbacks4_t {
+    subversion_sys::svn_wc_diff_callbacks4_t {
+        file_opened: Some(diff_processor_file_opened),
+        file_changed: Some(diff_processor_file_changed),
+        file_added: Some(diff_processor_file_added),
+        file_deleted: Some(diff_processor_file_deleted),
+        dir_deleted: Some(diff_processor_dir_deleted),
+        dir_opened: Some(diff_processor_dir_opened),
+        dir_added: Some(diff_processor_dir_added),
+        dir_props_changed: Some(diff_processor_dir_props_changed),
+        dir_closed: Some(diff_processor_dir_closed),
+    }
+}
+
 /// Options for log operations
 #[derive(Debug, Clone)]
 pub struct LogOptions {
@@ -2342,6 +2860,35 @@ impl InfoOptions {
     }
 }
 
+/// How [`Context::pin_externals`] rewrites external definitions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExternalsPinMode {
+    /// Pin each external to the revision its working copy is at.
+    Current,
+    /// Pin each external to the youngest revision of its repository.
+    Head,
+}
+
+/// An external definition rewritten by [`Context::pin_externals`].
+#[derive(Debug, Clone)]
+pub struct PinnedExternal {
+    /// Absolute path of the directory carrying the `svn:externals` property.
+    pub path: String,
+    /// The definition before it was pinned.
+    pub old: crate::wc::externals::ExternalDefinition,
+    /// The definition after it was pinned.
+    pub new: crate::wc::externals::ExternalDefinition,
+}
+
+/// Result of [`Context::pin_externals`].
+#[derive(Debug, Clone, Default)]
+pub struct PinnedExternals {
+    /// The revision that was committed, or `None` if nothing changed.
+    pub revision: Option<Revnum>,
+    /// The definitions that changed.
+    pub changes: Vec<PinnedExternal>,
+}
+
 /// Represents an item being committed.
 #[derive(Debug)]
 pub struct CommitItem<'a> {
@@ -4612,6 +5159,178 @@ impl Context {
         })
     }
 
+    /// Pin every external defined at or below `path` and commit the result.
+    ///
+    /// Each definition in the `svn:externals` properties under `path` is
+    /// fixed to a revision chosen according to `mode`, and all changed
+    /// properties are committed in a single revision with `message` as the
+    /// log message.  Comments and definitions that already refer to the
+    /// chosen revision are left untouched.  The externals' own working copies
+    /// are not updated.
+    ///
+    /// Nothing is changed if a directory whose externals need pinning has
+    /// other local property modifications, since they would be committed
+    /// along with the pinned definitions.  If the commit fails, the
+    /// original `svn:externals` values are restored.
+    pub fn pin_externals(
+        &mut self,
+        path: &str,
+        mode: ExternalsPinMode,
+        message: &str,
+    ) -> Result<PinnedExternals, Error<'static>> {
+        let abspath = crate::dirent::to_absolute_cstring(path)?;
+        let abspath = abspath.to_str()?;
+        let mut props: Vec<_> = self
+            .propget(
+                "svn:externals",
+                abspath,
+                &PropGetOptions {
+                    depth: Depth::Infinity,
+                    ..Default::default()
+                },
+                None,
+            )
+            .map_err(|e| e.into_static())?
+            .into_iter()
+            .collect();
+        props.sort();
+
+        let mut result = PinnedExternals::default();
+        let mut updated = Vec::new();
+        for (dir, value) in props {
+            let value = String::from_utf8(value).map_err(|_| {
+                Error::from_message(&format!("svn:externals on '{}' is not valid UTF-8", dir))
+            })?;
+            let mut desc = crate::wc::externals::ExternalsDescription::parse(&value)?;
+            let (parent_url, repos_root_url, _) = self.info_summary(&dir, Revision::Unspecified)?;
+
+            let mut changed = false;
+            for def in desc.definitions_mut() {
+                let rev = match mode {
+                    ExternalsPinMode::Current => {
+                        let target = std::path::Path::new(&dir).join(&def.target);
+                        let target = target.to_str().ok_or_else(|| {
+                            Error::from_message(&format!(
+                                "Invalid external target '{}'",
+                                def.target
+                            ))
+                        })?;
+                        self.info_summary(target, Revision::Unspecified)?.2
+                    }
+                    ExternalsPinMode::Head => {
+                        let url = def.resolve_url(&parent_url, &repos_root_url)?;
+                        self.info_summary(&url, Revision::Head)?.2
+                    }
+                };
+                let old = def.clone();
+                def.pin(rev);
+                if *def != old {
+                    result.changes.push(PinnedExternal {
+                        path: dir.clone(),
+                        old,
+                        new: def.clone(),
+                    });
+                    changed = true;
+                }
+            }
+            if changed {
+                updated.push((dir, value, desc.to_string()));
+            }
+        }
+
+        if updated.is_empty() {
+            return Ok(result);
+        }
+        // The directories are committed with all of their property changes.
+        let mut wc_ctx = crate::wc::Context::new()?;
+        for (dir, _, _) in &updated {
+            let status = wc_ctx.status(std::path::Path::new(dir))?;
+            if matches!(
+                status.prop_status(),
+                crate::wc::StatusKind::Modified | crate::wc::StatusKind::Conflicted
+            ) {
+                return Err(Error::from_message(&format!(
+                    "'{}' has local property modifications; commit or revert them first",
+                    dir
+                )));
+            }
+        }
+
+        let mut set = 0;
+        let mut commit = || -> Result<Option<Revnum>, Error<'static>> {
+            for (dir, _, value) in &updated {
+                self.propset(
+                    "svn:externals",
+                    Some(value.as_bytes()),
+                    dir,
+                    &PropSetOptions::default(),
+                )?;
+                set += 1;
+            }
+            let targets: Vec<&str> = updated.iter().map(|(dir, _, _)| dir.as_str()).collect();
+            let mut revision = None;
+            self.commit(
+                &targets,
+                &CommitOptions {
+                    depth: Depth::Empty,
+                    ..Default::default()
+                },
+                HashMap::from([("svn:log", message)]),
+                None,
+                &mut |info| {
+                    revision = Some(info.revision());
+                    Ok(())
+                },
+            )?;
+            Ok(revision)
+        };
+        match commit() {
+            Ok(revision) => {
+                result.revision = revision;
+                Ok(result)
+            }
+            Err(e) => {
+                // Put the definitions back the way they were; the commit
+                // error is the one worth reporting.
+                for (dir, original, _) in &updated[..set] {
+                    let _ = self.propset(
+                        "svn:externals",
+                        Some(original.as_bytes()),
+                        dir,
+                        &PropSetOptions::default(),
+                    );
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Returns the URL, repository root URL and revision of a single item.
+    fn info_summary(
+        &mut self,
+        path_or_url: &str,
+        revision: Revision,
+    ) -> Result<(String, String, Revnum), Error<'static>> {
+        let mut summary = None;
+        self.info(
+            path_or_url,
+            &InfoOptions {
+                peg_revision: revision,
+                revision,
+                ..Default::default()
+            },
+            &|_path, info| {
+                summary = Some((
+                    info.url().to_string(),
+                    info.repos_root_url().to_string(),
+                    info.revision(),
+                ));
+                Ok(())
+            },
+        )?;
+        summary.ok_or_else(|| Error::from_message(&format!("No information for '{}'", path_or_url)))
+    }
+
     /// Sets a property on a remote URL in the repository.
     ///
     /// This sets a property directly on a repository URL, creating a commit.
@@ -5081,6 +5800,364 @@ impl Context {
         })
     }
 
+    /// Reports the differences between two paths or URLs through a
+    /// [`DiffProcessor`].
+    ///
+    /// This supports working copy to working copy (`Base` against `Working`
+    /// of the same path), working copy to repository and repository to
+    /// repository diffs, like `diff()`.  Instead of writing a unified diff,
+    /// each differing node is reported with the properties of both sides and
+    /// the contents of files in temporary files, as libsvn_wc and
+    /// libsvn_client's diff editors produce them.  Copied working copy nodes
+    /// are reported as added, along with their copy source.
+    pub fn diff_processor(
+        &mut self,
+        path_or_url1: &str,
+        revision1: &Revision,
+        path_or_url2: &str,
+        revision2: &Revision,
+        options: &DiffSummarizeOptions,
+        processor: &mut dyn DiffProcessor,
+    ) -> Result<(), Error<'static>> {
+        let is_local = |path_or_url: &str, revision: &Revision| {
+            !crate::path::is_url(path_or_url)
+                && matches!(revision, Revision::Base | Revision::Working)
+        };
+        let mut baton = DiffProcessorBaton {
+            processor,
+            left: (path_or_url1.to_string(), *revision1),
+            right: (path_or_url2.to_string(), *revision2),
+            target: String::new(),
+            added_dirs: HashMap::new(),
+        };
+        match (
+            is_local(path_or_url1, revision1),
+            is_local(path_or_url2, revision2),
+        ) {
+            (true, true) => {
+                if path_or_url1 != path_or_url2
+                    || !matches!(revision1, Revision::Base)
+                    || !matches!(revision2, Revision::Working)
+                {
+                    return Err(Error::from_message(
+                        "Only diffs between a path's text-base and its working files are supported",
+                    ));
+                }
+                self.diff_processor_wc_wc(path_or_url1, options, &mut baton)
+            }
+            (true, false) => self.diff_processor_repos_wc(
+                path_or_url2,
+                revision2,
+                path_or_url1,
+                revision1,
+                true,
+                options,
+                &mut baton,
+            ),
+            (false, true) => self.diff_processor_repos_wc(
+                path_or_url1,
+                revision1,
+                path_or_url2,
+                revision2,
+                false,
+                options,
+                &mut baton,
+            ),
+            (false, false) => self.diff_processor_repos_repos(options, &mut baton),
+        }
+    }
+
+    /// Report the local modifications of a working copy path with
+    /// `svn_wc_diff6`.
+    fn diff_processor_wc_wc(
+        &mut self,
+        path: &str,
+        options: &DiffSummarizeOptions,
+        baton: &mut DiffProcessorBaton,
+    ) -> Result<(), Error<'static>> {
+        // A file target is reported relative to its parent directory.
+        let path = std::env::current_dir()?.join(path);
+        if !path.is_dir() {
+            baton.target = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+        }
+        let abspath = crate::dirent::to_absolute_cstring(path.as_path())?;
+        let callbacks = diff_processor_callbacks();
+        with_tmp_pool(|pool| {
+            let changelists = diff_changelists(options, pool);
+            let err = unsafe {
+                subversion_sys::svn_wc_diff6(
+                    (*self.as_mut_ptr()).wc_ctx,
+                    abspath.as_ptr(),
+                    &callbacks,
+                    baton as *mut DiffProcessorBaton as *mut std::ffi::c_void,
+                    options.depth.into(),
+                    options.ignore_ancestry as i32,
+                    1, // show_copies_as_adds, so copies are added with their source
+                    0, // use_git_diff_format
+                    changelists
+                        .as_ref()
+                        .map_or(std::ptr::null(), |(array, _)| array.as_ptr()),
+                    None,
+                    std::ptr::null_mut(),
+                    pool.as_mut_ptr(),
+                )
+            };
+            svn_result(err)
+        })
+    }
+
+    /// Report the differences between a repository node and a working copy
+    /// path by driving the working copy diff editor from the repository.
+    ///
+    /// `reverse` is set when the working copy is the left side.
+    #[allow(clippy::too_many_arguments)]
+    fn diff_processor_repos_wc(
+        &mut self,
+        repos_path_or_url: &str,
+        repos_revision: &Revision,
+        wc_path: &str,
+        wc_revision: &Revision,
+        reverse: bool,
+        options: &DiffSummarizeOptions,
+        baton: &mut DiffProcessorBaton,
+    ) -> Result<(), Error<'static>> {
+        let (repos_url, _, repos_rev) =
+            self.info_summary(&absolute_path_or_url(repos_path_or_url)?, *repos_revision)?;
+        let repos_side = (repos_url.clone(), Revision::Number(repos_rev));
+        if reverse {
+            baton.right = repos_side;
+        } else {
+            baton.left = repos_side;
+        }
+
+        let wc_abspath = std::env::current_dir()?.join(wc_path);
+        let (anchor, target) = if wc_abspath.is_dir() {
+            (wc_abspath.clone(), String::new())
+        } else {
+            let anchor = wc_abspath.parent().unwrap_or(&wc_abspath).to_path_buf();
+            let target = wc_abspath
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            (anchor, target)
+        };
+        let (anchor_url, _, _) = self.info_summary(&anchor.to_string_lossy(), Revision::Working)?;
+        baton.target = target.clone();
+
+        let wc_abspath = crate::dirent::to_absolute_cstring(wc_abspath.as_path())?;
+        let anchor = crate::dirent::to_absolute_cstring(anchor.as_path())?;
+        let anchor_url = std::ffi::CString::new(anchor_url)?;
+        let repos_url = std::ffi::CString::new(repos_url)?;
+        let target = std::ffi::CString::new(target)?;
+        let callbacks = diff_processor_callbacks();
+        with_tmp_pool(|pool| unsafe {
+            let ctx = self.as_mut_ptr();
+            let changelists = diff_changelists(options, pool);
+
+            let mut session = std::ptr::null_mut();
+            svn_result(subversion_sys::svn_client_open_ra_session2(
+                &mut session,
+                anchor_url.as_ptr(),
+                anchor.as_ptr(),
+                ctx,
+                pool.as_mut_ptr(),
+                pool.as_mut_ptr(),
+            ))?;
+
+            let mut editor = std::ptr::null();
+            let mut edit_baton = std::ptr::null_mut();
+            svn_result(subversion_sys::svn_wc_get_diff_editor6(
+                &mut editor,
+                &mut edit_baton,
+                (*ctx).wc_ctx,
+                anchor.as_ptr(),
+                target.as_ptr(),
+                options.depth.into(),
+                options.ignore_ancestry as i32,
+                1, // show_copies_as_adds, so copies are added with their source
+                0, // use_git_diff_format
+                matches!(wc_revision, Revision::Base) as i32,
+                reverse as i32,
+                0, // server_performs_filtering
+                changelists
+                    .as_ref()
+                    .map_or(std::ptr::null(), |(array, _)| array.as_ptr()),
+                &callbacks,
+                baton as *mut DiffProcessorBaton as *mut std::ffi::c_void,
+                None,
+                std::ptr::null_mut(),
+                pool.as_mut_ptr(),
+                pool.as_mut_ptr(),
+            ))?;
+
+            let mut reporter = std::ptr::null();
+            let mut report_baton = std::ptr::null_mut();
+            svn_result(subversion_sys::svn_ra_do_diff3(
+                session,
+                &mut reporter,
+                &mut report_baton,
+                repos_rev.into(),
+                target.as_ptr(),
+                options.depth.into(),
+                options.ignore_ancestry as i32,
+                1, // text_deltas
+                repos_url.as_ptr(),
+                editor,
+                edit_baton,
+                pool.as_mut_ptr(),
+            ))?;
+
+            svn_result(subversion_sys::svn_wc_crawl_revisions5(
+                (*ctx).wc_ctx,
+                wc_abspath.as_ptr(),
+                reporter,
+                report_baton,
+                0, // restore_files
+                options.depth.into(),
+                1, // honor_depth_exclude
+                0, // depth_compatibility_trick
+                0, // use_commit_times
+                None,
+                std::ptr::null_mut(),
+                None,
+                std::ptr::null_mut(),
+                pool.as_mut_ptr(),
+            ))
+        })
+    }
+
+    /// Report the differences between two repository nodes by driving
+    /// libsvn_client's repository diff editor.
+    fn diff_processor_repos_repos(
+        &mut self,
+        options: &DiffSummarizeOptions,
+        baton: &mut DiffProcessorBaton,
+    ) -> Result<(), Error<'static>> {
+        let (url1, _, rev1) =
+            self.info_summary(&absolute_path_or_url(&baton.left.0)?, baton.left.1)?;
+        let (url2, _, rev2) =
+            self.info_summary(&absolute_path_or_url(&baton.right.0)?, baton.right.1)?;
+        baton.left = (url1.clone(), Revision::Number(rev1));
+        baton.right = (url2.clone(), Revision::Number(rev2));
+
+        let url1 = std::ffi::CString::new(url1)?;
+        let url2 = std::ffi::CString::new(url2)?;
+        let empty = std::ffi::CString::default();
+        let callbacks = diff_processor_callbacks();
+        with_tmp_pool(|pool| unsafe {
+            let ctx = self.as_mut_ptr();
+            let mut session = std::ptr::null_mut();
+            svn_result(subversion_sys::svn_client_open_ra_session2(
+                &mut session,
+                url1.as_ptr(),
+                std::ptr::null(),
+                ctx,
+                pool.as_mut_ptr(),
+                pool.as_mut_ptr(),
+            ))?;
+
+            // The diff editor works on directories, so files are diffed from
+            // their parent directory.
+            let mut kind = subversion_sys::svn_node_kind_t_svn_node_none;
+            svn_result(subversion_sys::svn_ra_check_path(
+                session,
+                empty.as_ptr(),
+                rev1.into(),
+                &mut kind,
+                pool.as_mut_ptr(),
+            ))?;
+            let mut anchor = url1.as_ptr();
+            let mut target: *const std::os::raw::c_char = empty.as_ptr();
+            if kind == subversion_sys::svn_node_kind_t_svn_node_file {
+                subversion_sys::svn_uri_split(
+                    &mut anchor,
+                    &mut target,
+                    url1.as_ptr(),
+                    pool.as_mut_ptr(),
+                );
+                svn_result(subversion_sys::svn_ra_reparent(
+                    session,
+                    anchor,
+                    pool.as_mut_ptr(),
+                ))?;
+                baton.target = std::ffi::CStr::from_ptr(target)
+                    .to_string_lossy()
+                    .into_owned();
+            }
+
+            // The editor fetches the left side through its own session.
+            let mut extra_session = std::ptr::null_mut();
+            svn_result(subversion_sys::svn_client_open_ra_session2(
+                &mut extra_session,
+                anchor,
+                std::ptr::null(),
+                ctx,
+                pool.as_mut_ptr(),
+                pool.as_mut_ptr(),
+            ))?;
+
+            let mut diff_processor = std::ptr::null();
+            svn_result(subversion_sys::svn_wc__wrap_diff_callbacks(
+                &mut diff_processor,
+                &callbacks,
+                baton as *mut DiffProcessorBaton as *mut std::ffi::c_void,
+                1, // walk_deleted_dirs
+                pool.as_mut_ptr(),
+                pool.as_mut_ptr(),
+            ))?;
+
+            let mut editor = std::ptr::null();
+            let mut edit_baton = std::ptr::null_mut();
+            svn_result(subversion_sys::svn_client__get_diff_editor2(
+                &mut editor,
+                &mut edit_baton,
+                extra_session,
+                options.depth.into(),
+                rev1.into(),
+                1, // text_deltas
+                diff_processor,
+                None,
+                std::ptr::null_mut(),
+                pool.as_mut_ptr(),
+            ))?;
+
+            let mut reporter: *const subversion_sys::svn_ra_reporter3_t = std::ptr::null();
+            let mut report_baton = std::ptr::null_mut();
+            svn_result(subversion_sys::svn_ra_do_diff3(
+                session,
+                &mut reporter,
+                &mut report_baton,
+                rev2.into(),
+                target,
+                options.depth.into(),
+                options.ignore_ancestry as i32,
+                1, // text_deltas
+                url2.as_ptr(),
+                editor,
+                edit_baton,
+                pool.as_mut_ptr(),
+            ))?;
+
+            let set_path = (*reporter).set_path.expect("reporter has set_path");
+            let finish_report = (*reporter)
+                .finish_report
+                .expect("reporter has finish_report");
+            svn_result(set_path(
+                report_baton,
+                empty.as_ptr(),
+                rev1.into(),
+                options.depth.into(),
+                0, // start_empty
+                std::ptr::null(),
+                pool.as_mut_ptr(),
+            ))?;
+            svn_result(finish_report(report_baton, pool.as_mut_ptr()))
+        })
+    }
+
     /// List directory contents
     pub fn list(
         &mut self,
@@ -7788,7 +8865,7 @@ impl Conflict {
     }
 
     /// Get the local change type
-    pub fn get_local_change(&self) -> crate::conflict::ConflictAction {
+    pub fn get_local_change(&self) -> crate::conflict::ConflictReason {
         unsafe { subversion_sys::svn_client_conflict_get_local_change(self.ptr).into() }
     }
 
@@ -8458,6 +9535,274 @@ mod tests {
         assert_eq!(o.changelists.as_deref(), Some(&["cl".to_string()][..]));
     }
 
+    /// Records the events reported to a [`DiffProcessor`], reading the
+    /// temporary files while they exist.
+    #[derive(Default)]
+    struct RecordingDiffProcessor {
+        events: Vec<(
+            &'static str,
+            String,
+            Option<Vec<u8>>,
+            Option<Vec<u8>>,
+            Vec<PropChange>,
+        )>,
+        copies: Vec<(String, (String, Revnum))>,
+    }
+
+    impl RecordingDiffProcessor {
+        fn record(&mut self, event: &'static str, node: &DiffNode) -> Result<(), Error<'static>> {
+            self.events.push((
+                event,
+                node.path.clone(),
+                node.left_file().map(std::fs::read).transpose()?,
+                node.right_file().map(std::fs::read).transpose()?,
+                node.prop_changes.clone(),
+            ));
+            if let Some(copyfrom) = &node.copyfrom {
+                self.copies.push((node.path.clone(), copyfrom.clone()));
+            }
+            Ok(())
+        }
+    }
+
+    impl DiffProcessor for RecordingDiffProcessor {
+        fn file_added(&mut self, node: &DiffNode) -> Result<(), Error<'static>> {
+            self.record("file_added", node)
+        }
+
+        fn file_deleted(&mut self, node: &DiffNode) -> Result<(), Error<'static>> {
+            self.record("file_deleted", node)
+        }
+
+        fn file_changed(&mut self, node: &DiffNode) -> Result<(), Error<'static>> {
+            self.record("file_changed", node)
+        }
+
+        fn dir_added(&mut self, node: &DiffNode) -> Result<(), Error<'static>> {
+            self.record("dir_added", node)
+        }
+
+        fn dir_deleted(&mut self, node: &DiffNode) -> Result<(), Error<'static>> {
+            self.record("dir_deleted", node)
+        }
+
+        fn dir_changed(&mut self, node: &DiffNode) -> Result<(), Error<'static>> {
+            self.record("dir_changed", node)
+        }
+    }
+
+    #[test]
+    fn test_diff_processor_wc_and_repos() {
+        let mut fixture = ClientTestFixture::new();
+        let changed = fixture.add_file("changed.txt", "one\n");
+        let deleted = fixture.add_file("deleted.txt", "gone\n");
+        let dir = fixture.add_dir("dir");
+        let rev1 = fixture.commit();
+
+        std::fs::write(&changed, "two\n").unwrap();
+        fixture
+            .ctx
+            .delete(
+                &[deleted.to_str().unwrap()],
+                HashMap::new(),
+                &mut DeleteOptions::default(),
+            )
+            .unwrap();
+        fixture
+            .ctx
+            .propset(
+                "test:prop",
+                Some(b"value"),
+                dir.to_str().unwrap(),
+                &PropSetOptions::new().with_depth(Depth::Empty),
+            )
+            .unwrap();
+        fixture.add_file("added.txt", "new\n");
+
+        let wc = fixture.wc_path_str().to_string();
+        let mut processor = RecordingDiffProcessor::default();
+        fixture
+            .ctx
+            .diff_processor(
+                &wc,
+                &Revision::Base,
+                &wc,
+                &Revision::Working,
+                &DiffSummarizeOptions::default(),
+                &mut processor,
+            )
+            .unwrap();
+        let prop_change = PropChange {
+            name: "test:prop".to_string(),
+            old_value: None,
+            new_value: Some(b"value".to_vec()),
+        };
+        let mut events = processor.events;
+        events.sort_by(|a, b| a.1.cmp(&b.1));
+        assert_eq!(
+            events,
+            vec![
+                (
+                    "file_added",
+                    "added.txt".to_string(),
+                    None,
+                    Some(b"new\n".to_vec()),
+                    vec![]
+                ),
+                (
+                    "file_changed",
+                    "changed.txt".to_string(),
+                    Some(b"one\n".to_vec()),
+                    Some(b"two\n".to_vec()),
+                    vec![]
+                ),
+                (
+                    "file_deleted",
+                    "deleted.txt".to_string(),
+                    Some(b"gone\n".to_vec()),
+                    None,
+                    vec![]
+                ),
+                (
+                    "dir_changed",
+                    "dir".to_string(),
+                    None,
+                    None,
+                    vec![prop_change.clone()]
+                ),
+            ]
+        );
+
+        let rev2 = fixture.commit();
+        let url = fixture.url.clone();
+        let mut processor = RecordingDiffProcessor::default();
+        fixture
+            .ctx
+            .diff_processor(
+                &url,
+                &Revision::Number(rev1),
+                &url,
+                &Revision::Number(rev2),
+                &DiffSummarizeOptions::default(),
+                &mut processor,
+            )
+            .unwrap();
+        let mut events = processor.events;
+        events.sort_by(|a, b| a.1.cmp(&b.1));
+        let kinds: Vec<_> = events.iter().map(|e| (e.0, e.1.as_str())).collect();
+        assert_eq!(
+            kinds,
+            [
+                ("file_added", "added.txt"),
+                ("file_changed", "changed.txt"),
+                ("file_deleted", "deleted.txt"),
+                ("dir_changed", "dir"),
+            ]
+        );
+        assert_eq!(events[1].2.as_deref(), Some(b"one\n".as_slice()));
+        assert_eq!(events[1].3.as_deref(), Some(b"two\n".as_slice()));
+        assert_eq!(events[3].4, [prop_change.clone()]);
+
+        // The repository at rev1 against the working copy.
+        let mut processor = RecordingDiffProcessor::default();
+        fixture
+            .ctx
+            .diff_processor(
+                &url,
+                &Revision::Number(rev1),
+                &wc,
+                &Revision::Working,
+                &DiffSummarizeOptions::default(),
+                &mut processor,
+            )
+            .unwrap();
+        let mut events = processor.events;
+        events.sort_by(|a, b| a.1.cmp(&b.1));
+        let kinds: Vec<_> = events.iter().map(|e| (e.0, e.1.as_str())).collect();
+        assert_eq!(
+            kinds,
+            [
+                ("file_added", "added.txt"),
+                ("file_changed", "changed.txt"),
+                ("file_deleted", "deleted.txt"),
+                ("dir_changed", "dir"),
+            ]
+        );
+        assert_eq!(events[1].2.as_deref(), Some(b"one\n".as_slice()));
+        assert_eq!(events[1].3.as_deref(), Some(b"two\n".as_slice()));
+        assert_eq!(events[3].4, [prop_change]);
+    }
+
+    #[test]
+    fn test_diff_processor_single_file() {
+        let mut fixture = ClientTestFixture::new();
+        let file = fixture.add_file("f.txt", "one\n");
+        fixture.commit();
+        std::fs::write(&file, "two\n").unwrap();
+
+        let mut processor = RecordingDiffProcessor::default();
+        fixture
+            .ctx
+            .diff_processor(
+                file.to_str().unwrap(),
+                &Revision::Base,
+                file.to_str().unwrap(),
+                &Revision::Working,
+                &DiffSummarizeOptions::default(),
+                &mut processor,
+            )
+            .unwrap();
+        assert_eq!(
+            processor.events,
+            vec![(
+                "file_changed",
+                String::new(),
+                Some(b"one\n".to_vec()),
+                Some(b"two\n".to_vec()),
+                vec![]
+            )]
+        );
+    }
+
+    #[test]
+    fn test_diff_processor_copyfrom() {
+        let mut fixture = ClientTestFixture::new();
+        let original = fixture.add_file("original.txt", "content\n");
+        let rev1 = fixture.commit();
+        let copy = fixture.wc_path.join("copy.txt");
+        fixture
+            .ctx
+            .copy(
+                &[(original.to_str().unwrap(), None)],
+                copy.to_str().unwrap(),
+                &mut CopyOptions::new(),
+            )
+            .unwrap();
+
+        let wc = fixture.wc_path_str().to_string();
+        let mut processor = RecordingDiffProcessor::default();
+        fixture
+            .ctx
+            .diff_processor(
+                &wc,
+                &Revision::Base,
+                &wc,
+                &Revision::Working,
+                &DiffSummarizeOptions::default(),
+                &mut processor,
+            )
+            .unwrap();
+        assert_eq!(
+            processor.copies,
+            [("copy.txt".to_string(), ("original.txt".to_string(), rev1))]
+        );
+        assert_eq!(processor.events[0].0, "file_added");
+        assert_eq!(
+            processor.events[0].3.as_deref(),
+            Some(b"content\n".as_slice())
+        );
+    }
+
     #[test]
     fn test_revert_options_builder() {
         let o = RevertOptions::new()
@@ -10732,6 +12077,137 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_pin_externals() {
+        let mut fixture = ClientTestFixture::new();
+        fixture.add_dir("ext-source");
+        fixture.add_file("ext-source/file.txt", "external content");
+        fixture.commit();
+
+        let wc_path_str = fixture.wc_path_str().to_string();
+        fixture
+            .ctx
+            .propset(
+                "svn:externals",
+                Some(b"# vendored\n^/ext-source ext-link\n"),
+                &wc_path_str,
+                &PropSetOptions::default(),
+            )
+            .unwrap();
+        fixture.commit();
+        fixture
+            .ctx
+            .update(
+                &[&wc_path_str],
+                crate::Revision::Head,
+                &UpdateOptions::default(),
+            )
+            .unwrap();
+        assert!(fixture.wc_path.join("ext-link/file.txt").exists());
+
+        let get_externals = |ctx: &mut Context| {
+            let props = ctx
+                .propget(
+                    "svn:externals",
+                    &wc_path_str,
+                    &PropGetOptions::default(),
+                    None,
+                )
+                .unwrap();
+            String::from_utf8(props.into_values().next().unwrap()).unwrap()
+        };
+
+        let pinned = fixture
+            .ctx
+            .pin_externals(&wc_path_str, ExternalsPinMode::Current, "Pin externals")
+            .unwrap();
+        assert_eq!(pinned.revision, Some(Revnum(3)));
+        assert_eq!(pinned.changes.len(), 1);
+        assert_eq!(pinned.changes[0].old.peg_revision, None);
+        assert_eq!(
+            get_externals(&mut fixture.ctx),
+            "# vendored\n^/ext-source@2 ext-link\n"
+        );
+
+        // Pinning again to the same revisions is a no-op.
+        let pinned = fixture
+            .ctx
+            .pin_externals(&wc_path_str, ExternalsPinMode::Current, "Pin externals")
+            .unwrap();
+        assert_eq!(pinned.revision, None);
+        assert!(pinned.changes.is_empty());
+
+        let bumped = fixture
+            .ctx
+            .pin_externals(&wc_path_str, ExternalsPinMode::Head, "Bump externals")
+            .unwrap();
+        assert_eq!(bumped.revision, Some(Revnum(4)));
+        assert_eq!(
+            get_externals(&mut fixture.ctx),
+            "# vendored\n^/ext-source@3 ext-link\n"
+        );
+
+        // An unrelated pending property change is not committed along.
+        fixture.add_file("ext-source/other.txt", "more content");
+        fixture.commit();
+        fixture
+            .ctx
+            .propset(
+                "color",
+                Some(b"red"),
+                &wc_path_str,
+                &PropSetOptions::default(),
+            )
+            .unwrap();
+        assert!(fixture
+            .ctx
+            .pin_externals(&wc_path_str, ExternalsPinMode::Head, "Bump externals")
+            .is_err());
+        assert_eq!(
+            get_externals(&mut fixture.ctx),
+            "# vendored\n^/ext-source@3 ext-link\n"
+        );
+        let color = fixture
+            .ctx
+            .propget("color", &wc_path_str, &PropGetOptions::default(), None)
+            .unwrap();
+        assert_eq!(color.into_values().next().unwrap(), b"red");
+        fixture
+            .ctx
+            .propset("color", None, &wc_path_str, &PropSetOptions::default())
+            .unwrap();
+
+        // A failed commit restores the original definitions.
+        #[cfg(unix)]
+        {
+            fixture
+                .ctx
+                .update(
+                    &[&wc_path_str],
+                    crate::Revision::Head,
+                    &UpdateOptions::default(),
+                )
+                .unwrap();
+            use std::os::unix::fs::PermissionsExt;
+            let hook = fixture.temp_dir.path().join("repo/hooks/pre-commit");
+            std::fs::write(&hook, "#!/bin/sh\nexit 1\n").unwrap();
+            std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755)).unwrap();
+            assert!(fixture
+                .ctx
+                .pin_externals(&wc_path_str, ExternalsPinMode::Head, "Bump externals")
+                .is_err());
+            assert_eq!(
+                get_externals(&mut fixture.ctx),
+                "# vendored\n^/ext-source@3 ext-link\n"
+            );
+            let mut wc_ctx = crate::wc::Context::new().unwrap();
+            assert_eq!(
+                wc_ctx.status(&fixture.wc_path).unwrap().prop_status(),
+                crate::wc::StatusKind::Normal
+            );
+        }
+    }
+
     #[test]
     fn test_import() {
         // Create a repository and a directory to import
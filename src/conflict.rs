@@ -3,6 +3,10 @@
 use crate::{Error, Revnum};
 use std::ffi::CStr;
 
+/// Rule-based automatic conflict resolution.
+#[cfg(feature = "client")]
+pub mod policy;
+
 /// What sort of conflict occurred.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ConflictKind {
@@ -400,6 +404,562 @@ pub(crate) unsafe extern "C" fn conflict_resolver_callback(
     }
 }
 
+/// One region of a file containing conflict markers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergedRegion {
+    /// Text merged without conflict.
+    Common(Vec<u8>),
+    /// A conflicted region.
+    Conflict(ConflictRegion),
+}
+
+/// A region between conflict markers.
+///
+/// With the default `modified-original-latest` style the common ancestor
+/// text is included between `|||||||` and `=======`; other styles only
+/// show the two sides.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConflictRegion {
+    /// Label of the `<<<<<<<` marker, e.g. `.mine` or `.working`.
+    pub mine_label: String,
+    /// Local text.
+    pub mine: Vec<u8>,
+    /// Label of the `|||||||` marker, e.g. `.r1` or `.merge-left.r1`.
+    pub base_label: Option<String>,
+    /// Common ancestor text, if included.
+    pub base: Option<Vec<u8>>,
+    /// Label of the `>>>>>>>` marker, e.g. `.r2` or `.merge-right.r2`.
+    pub theirs_label: String,
+    /// Incoming text.
+    pub theirs: Vec<u8>,
+}
+
+/// Resolution of a single [`ConflictRegion`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegionChoice {
+    /// Keep the local text.
+    Mine,
+    /// Keep the common ancestor text.
+    Base,
+    /// Keep the incoming text.
+    Theirs,
+    /// Keep the local text followed by the incoming text.
+    MineThenTheirs,
+    /// Keep the incoming text followed by the local text.
+    TheirsThenMine,
+    /// Replace the region with the given text.
+    Custom(Vec<u8>),
+}
+
+/// A file with conflict markers, as left behind by a conflicted text merge.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MergedFile {
+    /// The regions of the file, in order.
+    pub regions: Vec<MergedRegion>,
+}
+
+/// If `line` is a conflict marker of the form `<marker>[ label]`, return
+/// the label.
+fn marker_label<'a>(line: &'a [u8], marker: &[u8]) -> Option<&'a [u8]> {
+    let rest = line.strip_prefix(marker)?;
+    let rest = rest
+        .strip_suffix(b"\n")
+        .map(|r| r.strip_suffix(b"\r").unwrap_or(r))
+        .unwrap_or(rest);
+    match rest.first() {
+        None => Some(rest),
+        Some(b' ') => Some(&rest[1..]),
+        Some(_) => None,
+    }
+}
+
+impl MergedFile {
+    /// Parse the contents of a file with conflict markers.
+    ///
+    /// Markers are only recognized at the start of a line; `=======`,
+    /// `|||||||` and `>>>>>>>` lines outside a conflict are ordinary text.
+    pub fn parse(data: &[u8]) -> Result<Self, Error<'static>> {
+        enum Section {
+            Common,
+            Mine,
+            Base,
+            Theirs,
+        }
+        let mut regions = Vec::new();
+        let mut common = Vec::new();
+        let mut region: Option<ConflictRegion> = None;
+        let mut section = Section::Common;
+
+        for line in data.split_inclusive(|c| *c == b'\n') {
+            let label = |marker: &[u8]| {
+                marker_label(line, marker).map(|l| String::from_utf8_lossy(l).into_owned())
+            };
+            match section {
+                Section::Common => {
+                    if let Some(mine_label) = label(b"<<<<<<<") {
+                        if !common.is_empty() {
+                            regions.push(MergedRegion::Common(std::mem::take(&mut common)));
+                        }
+                        region = Some(ConflictRegion {
+                            mine_label,
+                            mine: Vec::new(),
+                            base_label: None,
+                            base: None,
+                            theirs_label: String::new(),
+                            theirs: Vec::new(),
+                        });
+                        section = Section::Mine;
+                    } else {
+                        common.extend_from_slice(line);
+                    }
+                    continue;
+                }
+                Section::Mine | Section::Base | Section::Theirs if label(b"<<<<<<<").is_some() => {
+                    return Err(Error::from_message("Nested conflict marker"));
+                }
+                _ => {}
+            }
+            let current = region.as_mut().unwrap();
+            match section {
+                Section::Mine => {
+                    if let Some(base_label) = label(b"|||||||") {
+                        current.base_label = Some(base_label);
+                        current.base = Some(Vec::new());
+                        section = Section::Base;
+                    } else if label(b"=======").is_some_and(|l| l.is_empty()) {
+                        section = Section::Theirs;
+                    } else {
+                        current.mine.extend_from_slice(line);
+                    }
+                }
+                Section::Base => {
+                    if label(b"=======").is_some_and(|l| l.is_empty()) {
+                        section = Section::Theirs;
+                    } else {
+                        current.base.as_mut().unwrap().extend_from_slice(line);
+                    }
+                }
+                Section::Theirs => {
+                    if let Some(theirs_label) = label(b">>>>>>>") {
+                        current.theirs_label = theirs_label;
+                        regions.push(MergedRegion::Conflict(region.take().unwrap()));
+                        section = Section::Common;
+                    } else {
+                        current.theirs.extend_from_slice(line);
+                    }
+                }
+                Section::Common => unreachable!(),
+            }
+        }
+        if region.is_some() {
+            return Err(Error::from_message("Unterminated conflict marker"));
+        }
+        if !common.is_empty() {
+            regions.push(MergedRegion::Common(common));
+        }
+        Ok(Self { regions })
+    }
+
+    /// Read and parse a file with conflict markers.
+    pub fn read(path: &std::path::Path) -> Result<Self, Error<'static>> {
+        Self::parse(&std::fs::read(path)?)
+    }
+
+    /// The conflicted regions, in order.
+    pub fn conflicts(&self) -> impl Iterator<Item = &ConflictRegion> {
+        self.regions.iter().filter_map(|region| match region {
+            MergedRegion::Conflict(conflict) => Some(conflict),
+            MergedRegion::Common(_) => None,
+        })
+    }
+
+    /// Whether the file has any conflicted region.
+    pub fn has_conflicts(&self) -> bool {
+        self.conflicts().next().is_some()
+    }
+
+    /// Produce the resolved text, using `choices[i]` for the `i`th
+    /// conflicted region.
+    pub fn resolve(&self, choices: &[RegionChoice]) -> Result<Vec<u8>, Error<'static>> {
+        let count = self.conflicts().count();
+        if choices.len() != count {
+            return Err(Error::from_message(&format!(
+                "Expected {} conflict resolutions, got {}",
+                count,
+                choices.len()
+            )));
+        }
+        let mut choices = choices.iter();
+        let mut out = Vec::new();
+        for region in &self.regions {
+            let conflict = match region {
+                MergedRegion::Common(text) => {
+                    out.extend_from_slice(text);
+                    continue;
+                }
+                MergedRegion::Conflict(conflict) => conflict,
+            };
+            match choices.next().unwrap() {
+                RegionChoice::Mine => out.extend_from_slice(&conflict.mine),
+                RegionChoice::Base => {
+                    out.extend_from_slice(conflict.base.as_deref().ok_or_else(|| {
+                        Error::from_message("Conflict region does not include the base text")
+                    })?)
+                }
+                RegionChoice::Theirs => out.extend_from_slice(&conflict.theirs),
+                RegionChoice::MineThenTheirs => {
+                    out.extend_from_slice(&conflict.mine);
+                    out.extend_from_slice(&conflict.theirs);
+                }
+                RegionChoice::TheirsThenMine => {
+                    out.extend_from_slice(&conflict.theirs);
+                    out.extend_from_slice(&conflict.mine);
+                }
+                RegionChoice::Custom(text) => out.extend_from_slice(text),
+            }
+        }
+        Ok(out)
+    }
+
+    /// Write the resolved text to `path`; see [`MergedFile::resolve`].
+    ///
+    /// This only writes the file; the conflict still has to be marked as
+    /// resolved in the working copy.
+    pub fn write_resolved(
+        &self,
+        path: &std::path::Path,
+        choices: &[RegionChoice],
+    ) -> Result<(), Error<'static>> {
+        std::fs::write(path, self.resolve(choices)?)?;
+        Ok(())
+    }
+}
+
+/// The files left next to a file with a text conflict.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextConflictArtifacts {
+    /// The conflicted file itself, with conflict markers.
+    pub merged: MergedFile,
+    /// Common ancestor (`FILE.rOLD` or `FILE.merge-left.rOLD`).
+    pub base: Option<Vec<u8>>,
+    /// Revision of the common ancestor.
+    pub base_revision: Option<Revnum>,
+    /// Local text (`FILE.mine` or `FILE.working`).
+    pub mine: Option<Vec<u8>>,
+    /// Incoming text (`FILE.rNEW` or `FILE.merge-right.rNEW`).
+    pub theirs: Option<Vec<u8>>,
+    /// Revision of the incoming text.
+    pub theirs_revision: Option<Revnum>,
+}
+
+impl TextConflictArtifacts {
+    /// Load the conflicted file at `path` and the artifacts of the text
+    /// conflict recorded on it.
+    ///
+    /// The artifact paths are taken from the working copy, so uniquified
+    /// names such as `foo.c.2.mine` are handled and leftovers of earlier
+    /// conflicts are ignored.  Only if no text conflict is recorded on
+    /// `path` are the artifacts looked up by name with
+    /// [`TextConflictArtifacts::scan`].
+    #[cfg(feature = "client")]
+    pub fn load(path: &std::path::Path) -> Result<Self, Error<'static>> {
+        let mut ctx = crate::client::Context::new()?;
+        let conflict = ctx.conflict_get(path)?;
+        let (text_conflicted, _, _) = conflict.get_conflicted().map_err(Error::into_static)?;
+        if text_conflicted {
+            Self::from_conflict(&conflict)
+        } else {
+            Self::scan(path)
+        }
+    }
+
+    /// Load the artifacts of the text conflict described by `conflict`.
+    #[cfg(feature = "client")]
+    pub fn from_conflict(conflict: &crate::client::Conflict) -> Result<Self, Error<'static>> {
+        let (_, working, incoming_old, incoming_new) =
+            conflict.text_get_contents().map_err(Error::into_static)?;
+        let (_, old_revision, _) = conflict
+            .get_incoming_old_repos_location()
+            .map_err(Error::into_static)?;
+        let (_, new_revision, _) = conflict
+            .get_incoming_new_repos_location()
+            .map_err(Error::into_static)?;
+        let read = |p: &Option<String>| -> Result<Option<Vec<u8>>, Error<'static>> {
+            p.as_ref()
+                .map(std::fs::read)
+                .transpose()
+                .map_err(Into::into)
+        };
+        let revision =
+            |p: &Option<String>, rev: Revnum| p.as_ref().map(|_| rev).filter(|rev| rev.0 >= 0);
+        Ok(Self {
+            merged: MergedFile::read(std::path::Path::new(conflict.get_local_abspath()))?,
+            base: read(&incoming_old)?,
+            base_revision: revision(&incoming_old, old_revision),
+            mine: read(&working)?,
+            theirs: read(&incoming_new)?,
+            theirs_revision: revision(&incoming_new, new_revision),
+        })
+    }
+
+    /// Load the conflicted file at `path` and the artifacts next to it,
+    /// found by name.
+    ///
+    /// This follows the naming scheme used by `svn update` and `svn merge`,
+    /// but cannot tell uniquified names (`foo.c.2.mine`) apart from
+    /// unrelated files, and picks up files left over from earlier
+    /// conflicts.  Prefer [`TextConflictArtifacts::load`] for paths in a
+    /// working copy.
+    pub fn scan(path: &std::path::Path) -> Result<Self, Error<'static>> {
+        let merged = MergedFile::read(path)?;
+        let dir = path.parent().unwrap_or(std::path::Path::new("."));
+        let prefix = format!(
+            "{}.",
+            path.file_name()
+                .ok_or_else(|| Error::from_message("Path has no file name"))?
+                .to_string_lossy()
+        );
+
+        let mut mine = None;
+        let mut merge_left = None;
+        let mut merge_right = None;
+        let mut revisions = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let Some(suffix) = name
+                .to_string_lossy()
+                .strip_prefix(&prefix)
+                .map(str::to_string)
+            else {
+                continue;
+            };
+            let parse_rev = |s: &str| s.strip_prefix('r').and_then(|r| r.parse::<i64>().ok());
+            if suffix == "mine" || suffix == "working" {
+                mine = Some(entry.path());
+            } else if let Some(rev) = suffix.strip_prefix("merge-left.").and_then(parse_rev) {
+                merge_left = Some((Revnum(rev), entry.path()));
+            } else if let Some(rev) = suffix.strip_prefix("merge-right.").and_then(parse_rev) {
+                merge_right = Some((Revnum(rev), entry.path()));
+            } else if let Some(rev) = parse_rev(&suffix) {
+                revisions.push((Revnum(rev), entry.path()));
+            }
+        }
+        // `svn update` names both sides after their revisions; the older
+        // one is the common ancestor.
+        revisions.sort();
+        let mut revisions = revisions.into_iter();
+        let base = merge_left.or_else(|| revisions.next());
+        let theirs = merge_right.or_else(|| revisions.next_back());
+
+        let read = |p: Option<&std::path::PathBuf>| -> Result<Option<Vec<u8>>, Error<'static>> {
+            p.map(std::fs::read).transpose().map_err(Into::into)
+        };
+        Ok(Self {
+            merged,
+            base: read(base.as_ref().map(|(_, p)| p))?,
+            base_revision: base.as_ref().map(|(r, _)| *r),
+            mine: read(mine.as_ref())?,
+            theirs: read(theirs.as_ref().map(|(_, p)| p))?,
+            theirs_revision: theirs.as_ref().map(|(r, _)| *r),
+        })
+    }
+}
+
+/// One property conflict from a property reject (`.prej`) file.
+///
+/// Values are `None` when the file does not show them, e.g. because the
+/// property is absent on that side or its value is binary.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PropReject {
+    /// Name of the property.
+    pub name: String,
+    /// The explanation Subversion gives for the conflict.
+    pub message: String,
+    /// Value in the working copy.
+    pub working: Option<Vec<u8>>,
+    /// Incoming old value ("changed from").
+    pub incoming_old: Option<Vec<u8>>,
+    /// Incoming new value ("changed to").
+    pub incoming_new: Option<Vec<u8>>,
+}
+
+const PREJ_MINE_MARKER: &[u8] = b"<<<<<<< (local property value)";
+const PREJ_OLD_MARKER: &[u8] = b"||||||| (incoming 'changed from' value)";
+const PREJ_SEPARATOR: &[u8] = b"=======";
+const PREJ_NEW_MARKER: &[u8] = b">>>>>>> (incoming 'changed to' value)";
+
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// Split `data` at `marker`, dropping the marker and the rest of its line.
+fn split_at_marker<'a>(data: &'a [u8], marker: &[u8]) -> Option<(&'a [u8], &'a [u8])> {
+    let start = find_bytes(data, marker)?;
+    let rest = &data[start + marker.len()..];
+    let rest = match rest.iter().position(|c| *c == b'\n') {
+        Some(eol) => &rest[eol + 1..],
+        None => &[],
+    };
+    Some((&data[..start], rest))
+}
+
+fn non_empty(value: &[u8]) -> Option<Vec<u8>> {
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_vec())
+    }
+}
+
+/// Split the text before the first conflict marker into Subversion's
+/// message, which ends with its "but ..." line, and the lines all sides
+/// share before the first conflicting hunk.
+fn split_prej_message(text: &[u8]) -> (&[u8], &[u8]) {
+    let mut pos = 0;
+    for line in text.split_inclusive(|c| *c == b'\n') {
+        pos += line.len();
+        if line.starts_with(b"but ") {
+            return text.split_at(pos);
+        }
+    }
+    (text, &[])
+}
+
+/// Rebuild the working, incoming old and incoming new values from the
+/// merged output following the first conflict marker.
+fn parse_prej_values(
+    leading: &[u8],
+    mut rest: &[u8],
+) -> Result<[Option<Vec<u8>>; 3], Error<'static>> {
+    let malformed = || Error::from_message("Malformed property reject values");
+    let mut sides = [leading.to_vec(), leading.to_vec(), leading.to_vec()];
+    let mut has_old = false;
+    loop {
+        let separator = find_bytes(rest, PREJ_SEPARATOR).ok_or_else(malformed)?;
+        let (working, after) = match find_bytes(rest, PREJ_OLD_MARKER) {
+            Some(old) if old < separator => {
+                let (working, after) =
+                    split_at_marker(rest, PREJ_OLD_MARKER).ok_or_else(malformed)?;
+                let (old, after) = split_at_marker(after, PREJ_SEPARATOR).ok_or_else(malformed)?;
+                sides[1].extend_from_slice(old);
+                has_old = true;
+                (working, after)
+            }
+            _ => split_at_marker(rest, PREJ_SEPARATOR).ok_or_else(malformed)?,
+        };
+        sides[0].extend_from_slice(working);
+        let (new, after) = split_at_marker(after, PREJ_NEW_MARKER).ok_or_else(malformed)?;
+        sides[2].extend_from_slice(new);
+
+        // Lines between hunks, and after the last one, are common to all
+        // sides.
+        let (common, next) = match split_at_marker(after, PREJ_MINE_MARKER) {
+            Some((common, next)) => (common, Some(next)),
+            // Drop the blank line that separates conflicts.
+            None => match after.strip_suffix(b"\n") {
+                Some(common) if common.is_empty() || common.ends_with(b"\n") => (common, None),
+                _ => (after, None),
+            },
+        };
+        for side in &mut sides {
+            side.extend_from_slice(common);
+        }
+        match next {
+            Some(next) => rest = next,
+            None => break,
+        }
+    }
+    let [working, old, new] = sides;
+    Ok([
+        non_empty(&working),
+        if has_old { non_empty(&old) } else { None },
+        non_empty(&new),
+    ])
+}
+
+/// Parse the contents of a property reject file.
+///
+/// Both the format written by Subversion 1.8 and later, which shows the
+/// conflicting values between conflict markers, and the older format,
+/// which only has a message per property, are understood.
+///
+/// Subversion writes multi-line values as a merge: lines the sides share
+/// appear once outside the markers, and only the conflicting hunks are
+/// marked.  The values are rebuilt from all hunks plus the shared lines.
+/// Changes that merged without conflict are only shown in their merged
+/// form, so those lines are returned the same on every side.
+pub fn parse_prej(data: &[u8]) -> Result<Vec<PropReject>, Error<'static>> {
+    // Every conflict starts with a "Trying to ..." line.
+    let mut starts = Vec::new();
+    let mut pos = 0;
+    let mut in_values = false;
+    for line in data.split_inclusive(|c| *c == b'\n') {
+        if !in_values && line.starts_with(b"Trying to ") {
+            starts.push(pos);
+        }
+        if find_bytes(line, PREJ_MINE_MARKER).is_some() {
+            in_values = true;
+        }
+        if find_bytes(line, PREJ_NEW_MARKER).is_some() {
+            in_values = false;
+        }
+        pos += line.len();
+    }
+    if starts.is_empty() && data.iter().any(|c| !c.is_ascii_whitespace()) {
+        return Err(Error::from_message("Not a property reject file"));
+    }
+    starts.push(data.len());
+
+    let mut rejects = Vec::new();
+    for bounds in starts.windows(2) {
+        let entry = &data[bounds[0]..bounds[1]];
+        let (message, leading, values) = match split_at_marker(entry, PREJ_MINE_MARKER) {
+            Some((message, values)) => {
+                let (message, leading) = split_prej_message(message);
+                (message, leading, Some(values))
+            }
+            None => (entry, &[][..], None),
+        };
+        let message = String::from_utf8_lossy(message).trim_end().to_string();
+        let name = message
+            .split_once("property '")
+            .and_then(|(_, rest)| rest.split_once('\''))
+            .map(|(name, _)| name.to_string())
+            .ok_or_else(|| Error::from_message(&format!("No property name in '{}'", message)))?;
+        let mut reject = PropReject {
+            name,
+            message,
+            ..Default::default()
+        };
+        if let Some(values) = values {
+            let [working, incoming_old, incoming_new] = parse_prej_values(leading, values)?;
+            reject.working = working;
+            reject.incoming_old = incoming_old;
+            reject.incoming_new = incoming_new;
+        }
+        rejects.push(reject);
+    }
+    Ok(rejects)
+}
+
+/// Read and parse a property reject file; see [`parse_prej`].
+pub fn read_prej(path: &std::path::Path) -> Result<Vec<PropReject>, Error<'static>> {
+    parse_prej(&std::fs::read(path)?)
+}
+
+/// Read and parse the property reject file recorded for `conflict`, if
+/// any; see [`parse_prej`].
+#[cfg(feature = "client")]
+pub fn read_conflict_prej(
+    conflict: &crate::client::Conflict,
+) -> Result<Vec<PropReject>, Error<'static>> {
+    match conflict.prop_get_reject_abspath() {
+        Some(path) => read_prej(std::path::Path::new(&path)),
+        None => Ok(Vec::new()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -553,4 +1113,272 @@ mod tests {
             subversion_sys::svn_client_conflict_option_id_t_svn_client_conflict_option_incoming_delete_ignore
         );
     }
+
+    #[test]
+    fn test_merged_file_parse_and_resolve() {
+        let data = b"header\n\
+<<<<<<< .mine\n\
+mine 1\n\
+||||||| .r1\n\
+base 1\n\
+=======\n\
+theirs 1\n\
+>>>>>>> .r2\n\
+middle\n\
+=======\n\
+<<<<<<< .working\r\n\
+mine 2\r\n\
+=======\r\n\
+theirs 2\r\n\
+>>>>>>> .merge-right.r5\r\n";
+        let merged = MergedFile::parse(data).unwrap();
+        assert_eq!(merged.regions.len(), 4);
+        assert_eq!(
+            merged.regions[0],
+            MergedRegion::Common(b"header\n".to_vec())
+        );
+        assert_eq!(
+            merged.regions[2],
+            MergedRegion::Common(b"middle\n=======\n".to_vec())
+        );
+        let conflicts: Vec<_> = merged.conflicts().collect();
+        assert_eq!(
+            conflicts[0],
+            &ConflictRegion {
+                mine_label: ".mine".to_string(),
+                mine: b"mine 1\n".to_vec(),
+                base_label: Some(".r1".to_string()),
+                base: Some(b"base 1\n".to_vec()),
+                theirs_label: ".r2".to_string(),
+                theirs: b"theirs 1\n".to_vec(),
+            }
+        );
+        assert_eq!(conflicts[1].mine_label, ".working");
+        assert_eq!(conflicts[1].mine, b"mine 2\r\n");
+        assert_eq!(conflicts[1].base, None);
+        assert_eq!(conflicts[1].theirs_label, ".merge-right.r5");
+        assert!(merged.has_conflicts());
+
+        assert_eq!(
+            merged
+                .resolve(&[RegionChoice::Base, RegionChoice::TheirsThenMine])
+                .unwrap(),
+            b"header\nbase 1\nmiddle\n=======\ntheirs 2\r\nmine 2\r\n"
+        );
+        assert_eq!(
+            merged
+                .resolve(&[
+                    RegionChoice::Custom(b"custom\n".to_vec()),
+                    RegionChoice::Mine
+                ])
+                .unwrap(),
+            b"header\ncustom\nmiddle\n=======\nmine 2\r\n"
+        );
+        assert!(merged
+            .resolve(&[RegionChoice::Mine, RegionChoice::Base])
+            .is_err());
+        assert!(merged.resolve(&[RegionChoice::Mine]).is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("resolved.txt");
+        merged
+            .write_resolved(&path, &[RegionChoice::Theirs, RegionChoice::MineThenTheirs])
+            .unwrap();
+        assert_eq!(
+            std::fs::read(&path).unwrap(),
+            b"header\ntheirs 1\nmiddle\n=======\nmine 2\r\ntheirs 2\r\n"
+        );
+
+        assert!(!MergedFile::parse(b"plain\n").unwrap().has_conflicts());
+        assert!(MergedFile::parse(b"<<<<<<< .mine\na\n=======\nb\n").is_err());
+        assert!(MergedFile::parse(b"<<<<<<< .mine\n<<<<<<< .mine\n").is_err());
+    }
+
+    #[test]
+    fn test_text_conflict_artifacts_scan() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("foo.txt");
+        std::fs::write(&path, b"<<<<<<< .mine\nb\n=======\nc\n>>>>>>> .r3\n").unwrap();
+        std::fs::write(dir.path().join("foo.txt.mine"), b"b\n").unwrap();
+        std::fs::write(dir.path().join("foo.txt.r1"), b"a\n").unwrap();
+        std::fs::write(dir.path().join("foo.txt.r3"), b"c\n").unwrap();
+        std::fs::write(dir.path().join("foo.txt.orig"), b"unrelated\n").unwrap();
+
+        let artifacts = TextConflictArtifacts::scan(&path).unwrap();
+        assert_eq!(artifacts.merged.conflicts().count(), 1);
+        assert_eq!(artifacts.mine.as_deref(), Some(&b"b\n"[..]));
+        assert_eq!(artifacts.base.as_deref(), Some(&b"a\n"[..]));
+        assert_eq!(artifacts.base_revision, Some(Revnum(1)));
+        assert_eq!(artifacts.theirs.as_deref(), Some(&b"c\n"[..]));
+        assert_eq!(artifacts.theirs_revision, Some(Revnum(3)));
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn test_text_conflict_artifacts_load() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let repos_path = temp.path().join("repos");
+        let wc_path = temp.path().join("wc");
+        let repos = crate::repos::Repos::create(&repos_path).unwrap();
+        let fs = repos.fs().unwrap();
+        let commit = |rev: i64, text: &[u8], prop: &[u8]| {
+            let mut txn = fs.begin_txn(Revnum(rev), 0).unwrap();
+            {
+                let mut root = txn.root().unwrap();
+                if rev == 0 {
+                    root.make_file("/a.txt").unwrap();
+                }
+                root.set_file_contents("/a.txt", text).unwrap();
+                root.change_node_prop("/a.txt", "color", prop).unwrap();
+            }
+            txn.commit().unwrap();
+        };
+        commit(0, b"one\ntwo\n", b"red");
+
+        let url = crate::path_to_file_url(&repos_path);
+        let mut ctx = crate::client::Context::new().unwrap();
+        ctx.checkout(
+            crate::uri::Uri::new(&url).unwrap(),
+            &wc_path,
+            &crate::client::CheckoutOptions {
+                peg_revision: crate::Revision::Head,
+                revision: crate::Revision::Head,
+                depth: crate::Depth::Infinity,
+                ignore_externals: false,
+                allow_unver_obstructions: false,
+            },
+        )
+        .unwrap();
+
+        commit(1, b"one\ntheirs\n", b"blue");
+        std::fs::write(wc_path.join("a.txt"), b"one\nmine\n").unwrap();
+        ctx.propset(
+            "color",
+            Some(b"green"),
+            wc_path.join("a.txt").to_str().unwrap(),
+            &crate::client::PropSetOptions::default(),
+        )
+        .unwrap();
+        // Stale files that take the names the update would use.
+        std::fs::write(wc_path.join("a.txt.mine"), b"stale\n").unwrap();
+        std::fs::write(wc_path.join("a.txt.r1"), b"stale\n").unwrap();
+        std::fs::write(wc_path.join("a.txt.prej"), b"").unwrap();
+        ctx.update(
+            &[wc_path.to_str().unwrap()],
+            crate::Revision::Head,
+            &crate::client::UpdateOptions::default(),
+        )
+        .unwrap();
+
+        let artifacts = TextConflictArtifacts::load(&wc_path.join("a.txt")).unwrap();
+        assert_eq!(artifacts.merged.conflicts().count(), 1);
+        assert_eq!(artifacts.mine.as_deref(), Some(&b"one\nmine\n"[..]));
+        assert_eq!(artifacts.base.as_deref(), Some(&b"one\ntwo\n"[..]));
+        assert_eq!(artifacts.base_revision, Some(Revnum(1)));
+        assert_eq!(artifacts.theirs.as_deref(), Some(&b"one\ntheirs\n"[..]));
+        assert_eq!(artifacts.theirs_revision, Some(Revnum(2)));
+
+        let conflict = ctx.conflict_get(&wc_path.join("a.txt")).unwrap();
+        let rejects = read_conflict_prej(&conflict).unwrap();
+        assert_eq!(rejects.len(), 1);
+        assert_eq!(rejects[0].name, "color");
+        assert_eq!(rejects[0].working.as_deref(), Some(&b"green"[..]));
+    }
+
+    #[test]
+    fn test_parse_prej() {
+        let data = b"Trying to change property 'svn:ignore'\n\
+but the local property value conflicts with the incoming change.\n\
+<<<<<<< (local property value)\n\
+*.o\n\
+||||||| (incoming 'changed from' value)\n\
+*.a\n\
+=======\n\
+*.obj\n\
+>>>>>>> (incoming 'changed to' value)\n\
+\n\
+Trying to add new property 'color'\n\
+but the property already exists.\n\
+<<<<<<< (local property value)\n\
+red||||||| (incoming 'changed from' value)\n\
+=======\n\
+blue>>>>>>> (incoming 'changed to' value)\n";
+        let rejects = parse_prej(data).unwrap();
+        assert_eq!(rejects.len(), 2);
+        assert_eq!(
+            rejects[0],
+            PropReject {
+                name: "svn:ignore".to_string(),
+                message: "Trying to change property 'svn:ignore'\n\
+but the local property value conflicts with the incoming change."
+                    .to_string(),
+                working: Some(b"*.o\n".to_vec()),
+                incoming_old: Some(b"*.a\n".to_vec()),
+                incoming_new: Some(b"*.obj\n".to_vec()),
+            }
+        );
+        assert_eq!(rejects[1].name, "color");
+        assert_eq!(rejects[1].working.as_deref(), Some(&b"red"[..]));
+        assert_eq!(rejects[1].incoming_old, None);
+        assert_eq!(rejects[1].incoming_new.as_deref(), Some(&b"blue"[..]));
+
+        // Pre-1.8 reject files only carry a message.
+        let legacy = parse_prej(
+            b"Trying to change property 'foo' from 'a' to 'b',\n\
+but property has been locally changed from 'a' to 'c'.\n",
+        )
+        .unwrap();
+        assert_eq!(legacy.len(), 1);
+        assert_eq!(legacy[0].name, "foo");
+        assert_eq!(legacy[0].working, None);
+
+        assert!(parse_prej(b"").unwrap().is_empty());
+        assert!(parse_prej(b"garbage\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_prej_multiple_hunks() {
+        let data = b"Trying to change property 'svn:ignore'\n\
+but the local property value conflicts with the incoming change.\n\
+first\n\
+<<<<<<< (local property value)\n\
+a-mine\n\
+||||||| (incoming 'changed from' value)\n\
+a\n\
+=======\n\
+a-new\n\
+>>>>>>> (incoming 'changed to' value)\n\
+middle\n\
+<<<<<<< (local property value)\n\
+b-mine\n\
+||||||| (incoming 'changed from' value)\n\
+b\n\
+=======\n\
+b-new\n\
+>>>>>>> (incoming 'changed to' value)\n\
+last\n\
+\n\
+Trying to add new property 'color'\n\
+but the property already exists.\n\
+<<<<<<< (local property value)\n\
+red||||||| (incoming 'changed from' value)\n\
+=======\n\
+blue>>>>>>> (incoming 'changed to' value)\n";
+        let rejects = parse_prej(data).unwrap();
+        assert_eq!(rejects.len(), 2);
+        assert_eq!(
+            rejects[0],
+            PropReject {
+                name: "svn:ignore".to_string(),
+                message: "Trying to change property 'svn:ignore'\n\
+but the local property value conflicts with the incoming change."
+                    .to_string(),
+                working: Some(b"first\na-mine\nmiddle\nb-mine\nlast\n".to_vec()),
+                incoming_old: Some(b"first\na\nmiddle\nb\nlast\n".to_vec()),
+                incoming_new: Some(b"first\na-new\nmiddle\nb-new\nlast\n".to_vec()),
+            }
+        );
+        assert_eq!(rejects[1].working.as_deref(), Some(&b"red"[..]));
+        assert_eq!(rejects[1].incoming_new.as_deref(), Some(&b"blue"[..]));
+    }
 }
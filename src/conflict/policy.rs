@@ -0,0 +1,525 @@
+//! Rule-based automatic conflict resolution.
+//!
+//! A [`ConflictPolicy`] is an ordered list of [`ConflictRule`]s.  Every
+//! conflict found below a path is matched against the rules in order, and
+//! the option of the first matching rule is used to resolve it.  Conflicts
+//! no rule matches get the policy's default option, which postpones them
+//! unless configured otherwise.
+//!
+//! ```no_run
+//! use subversion::conflict::policy::{ConflictPolicy, ConflictRule};
+//! use subversion::conflict::ConflictKind;
+//! use subversion::ClientConflictOptionId;
+//!
+//! let policy = ConflictPolicy::new()
+//!     .with_rule(ConflictRule::new(ClientConflictOptionId::IncomingText).with_path("*.lock"))
+//!     .with_rule(
+//!         ConflictRule::new(ClientConflictOptionId::WorkingText)
+//!             .with_kind(ConflictKind::Text)
+//!             .with_mime_type("image/*"),
+//!     );
+//! ```
+
+use super::{ConflictAction, ConflictKind, ConflictReason};
+use crate::client::{Conflict, Context};
+use crate::{ClientConflictOptionId, Error};
+
+/// The properties of a conflict that rules are matched against.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConflictFacts {
+    /// Path of the conflicted node relative to the root of the walk, using
+    /// `/` as separator; `""` for the root itself.
+    pub path: String,
+    /// Kind of the conflict.
+    pub kind: ConflictKind,
+    /// The incoming change.
+    pub incoming_change: ConflictAction,
+    /// The local change.
+    pub local_change: ConflictReason,
+    /// MIME type of the file, for text conflicts.
+    pub mime_type: Option<String>,
+    /// Name of the conflicted property, for property conflicts.
+    pub property_name: Option<String>,
+}
+
+/// A rule of a [`ConflictPolicy`].
+///
+/// Criteria that are not set match any conflict.  Path, MIME type and
+/// property name criteria are glob patterns; a rule with several patterns
+/// for the same criterion matches if any of them does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConflictRule {
+    option: ClientConflictOptionId,
+    paths: Vec<String>,
+    kind: Option<ConflictKind>,
+    incoming_change: Option<ConflictAction>,
+    local_change: Option<ConflictReason>,
+    mime_types: Vec<String>,
+    property_names: Vec<String>,
+}
+
+fn matches_any(value: Option<&str>, patterns: &[String]) -> Result<bool, Error<'static>> {
+    if patterns.is_empty() {
+        return Ok(true);
+    }
+    match value {
+        Some(value) => {
+            let patterns: Vec<&str> = patterns.iter().map(String::as_str).collect();
+            crate::string::match_glob_list(value, &patterns)
+        }
+        None => Ok(false),
+    }
+}
+
+impl ConflictRule {
+    /// Create a rule resolving matching conflicts with `option`.
+    ///
+    /// Use [`ClientConflictOptionId::Postpone`] to leave matching conflicts
+    /// alone, e.g. to exempt them from later rules.
+    pub fn new(option: ClientConflictOptionId) -> Self {
+        Self {
+            option,
+            paths: Vec::new(),
+            kind: None,
+            incoming_change: None,
+            local_change: None,
+            mime_types: Vec::new(),
+            property_names: Vec::new(),
+        }
+    }
+
+    /// Only match conflicts on paths matching `pattern`.
+    ///
+    /// The pattern is matched against the path relative to the root of
+    /// the walk; `*` also matches `/`, so `*.png` matches PNG files at
+    /// any depth.
+    pub fn with_path(mut self, pattern: &str) -> Self {
+        self.paths.push(pattern.to_string());
+        self
+    }
+
+    /// Only match conflicts of the given kind.
+    pub fn with_kind(mut self, kind: ConflictKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    /// Only match conflicts caused by the given incoming change.
+    pub fn with_incoming_change(mut self, change: ConflictAction) -> Self {
+        self.incoming_change = Some(change);
+        self
+    }
+
+    /// Only match conflicts with the given local change.
+    pub fn with_local_change(mut self, change: ConflictReason) -> Self {
+        self.local_change = Some(change);
+        self
+    }
+
+    /// Only match text conflicts on files whose MIME type matches
+    /// `pattern`.
+    pub fn with_mime_type(mut self, pattern: &str) -> Self {
+        self.mime_types.push(pattern.to_string());
+        self
+    }
+
+    /// Only match property conflicts on properties matching `pattern`.
+    pub fn with_property_name(mut self, pattern: &str) -> Self {
+        self.property_names.push(pattern.to_string());
+        self
+    }
+
+    /// The option this rule resolves conflicts with.
+    pub fn option(&self) -> ClientConflictOptionId {
+        self.option
+    }
+
+    /// Check whether the rule matches a conflict.
+    pub fn matches(&self, facts: &ConflictFacts) -> Result<bool, Error<'static>> {
+        if self.kind.is_some_and(|kind| kind != facts.kind)
+            || self
+                .incoming_change
+                .is_some_and(|change| change != facts.incoming_change)
+            || self
+                .local_change
+                .is_some_and(|change| change != facts.local_change)
+        {
+            return Ok(false);
+        }
+        Ok(matches_any(Some(&facts.path), &self.paths)?
+            && matches_any(facts.mime_type.as_deref(), &self.mime_types)?
+            && matches_any(facts.property_name.as_deref(), &self.property_names)?)
+    }
+}
+
+/// What happened to a conflict when applying a [`ConflictPolicy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecisionOutcome {
+    /// The conflict was resolved.
+    Resolved,
+    /// The conflict would be resolved; dry run.
+    WouldResolve,
+    /// The conflict was left alone, as chosen by the policy.
+    Postponed,
+    /// The chosen option is not offered for this conflict, so it was left
+    /// alone.
+    Unavailable,
+}
+
+/// The decision taken for one conflict.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyDecision {
+    /// Absolute path of the conflicted node.
+    pub local_abspath: String,
+    /// The properties the rules were matched against.
+    pub facts: ConflictFacts,
+    /// Index of the matching rule, or `None` if the default applied.
+    pub rule: Option<usize>,
+    /// The chosen option.
+    pub option: ClientConflictOptionId,
+    /// What happened.
+    pub outcome: DecisionOutcome,
+}
+
+/// An ordered set of rules for resolving conflicts automatically.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConflictPolicy {
+    rules: Vec<ConflictRule>,
+    default: ClientConflictOptionId,
+}
+
+impl Default for ConflictPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConflictPolicy {
+    /// Create a policy without rules that postpones all conflicts.
+    pub fn new() -> Self {
+        Self {
+            rules: Vec::new(),
+            default: ClientConflictOptionId::Postpone,
+        }
+    }
+
+    /// Append a rule; rules are tried in the order they were added.
+    pub fn with_rule(mut self, rule: ConflictRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Set the option used for conflicts no rule matches.
+    pub fn with_default(mut self, option: ClientConflictOptionId) -> Self {
+        self.default = option;
+        self
+    }
+
+    /// The rules of the policy.
+    pub fn rules(&self) -> &[ConflictRule] {
+        &self.rules
+    }
+
+    /// Choose the option for a conflict, returning the index of the
+    /// matching rule (if any) and the option.
+    pub fn choose(
+        &self,
+        facts: &ConflictFacts,
+    ) -> Result<(Option<usize>, ClientConflictOptionId), Error<'static>> {
+        for (i, rule) in self.rules.iter().enumerate() {
+            if rule.matches(facts)? {
+                return Ok((Some(i), rule.option));
+            }
+        }
+        Ok((None, self.default))
+    }
+
+    /// Apply the policy to all conflicts below `local_abspath`.
+    ///
+    /// Text, property and tree conflicts on the same node are decided
+    /// separately, in that order.  With `dry_run` nothing is resolved, but
+    /// the report still says whether the chosen option is offered for each
+    /// conflict.
+    pub fn apply(
+        &self,
+        ctx: &mut Context,
+        local_abspath: &std::path::Path,
+        depth: crate::Depth,
+        dry_run: bool,
+    ) -> Result<Vec<PolicyDecision>, Error<'static>> {
+        let root = crate::dirent::to_absolute_cstring(local_abspath)?
+            .to_str()?
+            .to_string();
+
+        // Resolving needs the client context, which the walk holds on to,
+        // so collect the paths first.
+        let mut paths = Vec::new();
+        ctx.conflict_walk(local_abspath, depth, |conflict| {
+            paths.push(conflict.get_local_abspath().to_string());
+            Ok(())
+        })?;
+
+        let mut decisions = Vec::new();
+        for path in paths {
+            let relpath = path
+                .strip_prefix(&root)
+                .unwrap_or(&path)
+                .trim_start_matches('/')
+                .to_string();
+            let mut conflict = ctx.conflict_get(path.as_str())?;
+            let (text, props, tree) = conflict.get_conflicted().map_err(Error::into_static)?;
+            let facts = |kind, mime_type, property_name| ConflictFacts {
+                path: relpath.clone(),
+                kind,
+                incoming_change: conflict.get_incoming_change(),
+                local_change: conflict.get_local_change(),
+                mime_type,
+                property_name,
+            };
+
+            let mut pending = Vec::new();
+            if text {
+                let mime_type = conflict.text_get_mime_type().map_err(Error::into_static)?;
+                pending.push(facts(ConflictKind::Text, mime_type, None));
+            }
+            for name in props {
+                pending.push(facts(ConflictKind::Property, None, Some(name)));
+            }
+            if tree {
+                pending.push(facts(ConflictKind::Tree, None, None));
+            }
+
+            for facts in pending {
+                let (rule, option) = self.choose(&facts)?;
+                let outcome = if option == ClientConflictOptionId::Postpone {
+                    DecisionOutcome::Postponed
+                } else {
+                    resolve(&mut conflict, ctx, &facts, option, dry_run)?
+                };
+                decisions.push(PolicyDecision {
+                    local_abspath: path.clone(),
+                    facts,
+                    rule,
+                    option,
+                    outcome,
+                });
+            }
+        }
+        Ok(decisions)
+    }
+}
+
+/// Resolve one conflict of `conflict` with `option`, if it is offered.
+fn resolve(
+    conflict: &mut Conflict,
+    ctx: &mut Context,
+    facts: &ConflictFacts,
+    option: ClientConflictOptionId,
+    dry_run: bool,
+) -> Result<DecisionOutcome, Error<'static>> {
+    let options = match facts.kind {
+        ConflictKind::Text => conflict.text_get_resolution_options(ctx),
+        ConflictKind::Property => conflict.prop_get_resolution_options(ctx),
+        ConflictKind::Tree => {
+            // Move-related options are only offered once the details have
+            // been fetched from the repository.
+            conflict.tree_get_details(ctx)?;
+            conflict.tree_get_resolution_options(ctx)
+        }
+    }
+    .map_err(Error::into_static)?;
+    let Some(chosen) = options.iter().find(|o| o.get_id() == option) else {
+        return Ok(DecisionOutcome::Unavailable);
+    };
+    if dry_run {
+        return Ok(DecisionOutcome::WouldResolve);
+    }
+    match facts.kind {
+        ConflictKind::Text => conflict.text_resolve(chosen, ctx)?,
+        ConflictKind::Property => conflict.prop_resolve_with_option(
+            facts.property_name.as_deref().unwrap_or_default(),
+            chosen,
+            ctx,
+        )?,
+        ConflictKind::Tree => conflict.tree_resolve(chosen, ctx)?,
+    }
+    Ok(DecisionOutcome::Resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Revnum;
+
+    fn facts(path: &str, kind: ConflictKind) -> ConflictFacts {
+        ConflictFacts {
+            path: path.to_string(),
+            kind,
+            incoming_change: ConflictAction::Edit,
+            local_change: ConflictReason::Edited,
+            mime_type: None,
+            property_name: None,
+        }
+    }
+
+    #[test]
+    fn test_choose() {
+        let policy = ConflictPolicy::new()
+            .with_rule(ConflictRule::new(ClientConflictOptionId::Postpone).with_path("vendor/*"))
+            .with_rule(
+                ConflictRule::new(ClientConflictOptionId::WorkingText)
+                    .with_kind(ConflictKind::Text)
+                    .with_mime_type("image/*"),
+            )
+            .with_rule(
+                ConflictRule::new(ClientConflictOptionId::IncomingText)
+                    .with_kind(ConflictKind::Property)
+                    .with_property_name("svn:mergeinfo")
+                    .with_property_name("svn:ignore"),
+            )
+            .with_rule(
+                ConflictRule::new(ClientConflictOptionId::IncomingDeleteAccept)
+                    .with_kind(ConflictKind::Tree)
+                    .with_incoming_change(ConflictAction::Delete),
+            )
+            .with_default(ClientConflictOptionId::MergedText);
+
+        let mut image = facts("dir/logo.png", ConflictKind::Text);
+        image.mime_type = Some("image/png".to_string());
+        assert_eq!(
+            policy.choose(&image).unwrap(),
+            (Some(1), ClientConflictOptionId::WorkingText)
+        );
+
+        image.path = "vendor/lib/logo.png".to_string();
+        assert_eq!(
+            policy.choose(&image).unwrap(),
+            (Some(0), ClientConflictOptionId::Postpone)
+        );
+
+        // No MIME type: the image rule does not apply.
+        assert_eq!(
+            policy.choose(&facts("a.txt", ConflictKind::Text)).unwrap(),
+            (None, ClientConflictOptionId::MergedText)
+        );
+
+        let mut prop = facts("", ConflictKind::Property);
+        prop.property_name = Some("svn:ignore".to_string());
+        assert_eq!(
+            policy.choose(&prop).unwrap(),
+            (Some(2), ClientConflictOptionId::IncomingText)
+        );
+        prop.property_name = Some("svn:externals".to_string());
+        assert_eq!(policy.choose(&prop).unwrap().0, None);
+
+        let mut tree = facts("gone", ConflictKind::Tree);
+        assert_eq!(policy.choose(&tree).unwrap().0, None);
+        tree.incoming_change = ConflictAction::Delete;
+        assert_eq!(
+            policy.choose(&tree).unwrap(),
+            (Some(3), ClientConflictOptionId::IncomingDeleteAccept)
+        );
+
+        assert_eq!(
+            ConflictPolicy::default()
+                .choose(&facts("a.txt", ConflictKind::Text))
+                .unwrap(),
+            (None, ClientConflictOptionId::Postpone)
+        );
+    }
+
+    #[test]
+    fn test_apply() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let repos_path = temp.path().join("repos");
+        let wc_path = temp.path().join("wc");
+        let repos = crate::repos::Repos::create(&repos_path).unwrap();
+        let fs = repos.fs().unwrap();
+        let commit = |rev: i64, txt: &[u8], png: &[u8]| {
+            let mut txn = fs.begin_txn(Revnum(rev), 0).unwrap();
+            {
+                let mut root = txn.root().unwrap();
+                if rev == 0 {
+                    root.make_file("/a.txt").unwrap();
+                    root.make_file("/logo.png").unwrap();
+                    root.change_node_prop("/logo.png", "svn:mime-type", b"image/png")
+                        .unwrap();
+                }
+                root.set_file_contents("/a.txt", txt).unwrap();
+                root.set_file_contents("/logo.png", png).unwrap();
+            }
+            txn.commit().unwrap();
+        };
+        commit(0, b"one\ntwo\nthree\n", b"\x89PNG base");
+
+        let url = crate::path_to_file_url(&repos_path);
+        let mut ctx = Context::new().unwrap();
+        ctx.checkout(
+            crate::uri::Uri::new(&url).unwrap(),
+            &wc_path,
+            &crate::client::CheckoutOptions {
+                peg_revision: crate::Revision::Head,
+                revision: crate::Revision::Head,
+                depth: crate::Depth::Infinity,
+                ignore_externals: false,
+                allow_unver_obstructions: false,
+            },
+        )
+        .unwrap();
+
+        commit(1, b"one\ntheirs\nthree\n", b"\x89PNG theirs");
+        std::fs::write(wc_path.join("a.txt"), b"one\nmine\nthree\n").unwrap();
+        std::fs::write(wc_path.join("logo.png"), b"\x89PNG mine").unwrap();
+        ctx.update(
+            &[wc_path.to_str().unwrap()],
+            crate::Revision::Head,
+            &crate::client::UpdateOptions::default(),
+        )
+        .unwrap();
+
+        let policy = ConflictPolicy::new()
+            .with_rule(
+                ConflictRule::new(ClientConflictOptionId::WorkingText).with_mime_type("image/*"),
+            )
+            .with_rule(ConflictRule::new(ClientConflictOptionId::IncomingText).with_path("*.txt"));
+
+        let report = policy
+            .apply(&mut ctx, &wc_path, crate::Depth::Infinity, true)
+            .unwrap();
+        assert_eq!(report.len(), 2);
+        assert!(report
+            .iter()
+            .all(|d| d.outcome == DecisionOutcome::WouldResolve));
+        let png = report.iter().find(|d| d.facts.path == "logo.png").unwrap();
+        assert_eq!(png.rule, Some(0));
+        assert_eq!(png.facts.mime_type.as_deref(), Some("image/png"));
+        assert_eq!(png.facts.kind, ConflictKind::Text);
+        // A dry run leaves the conflicts in place.
+        assert!(
+            ctx.conflict_get(&wc_path.join("a.txt"))
+                .unwrap()
+                .get_conflicted()
+                .unwrap()
+                .0
+        );
+
+        let report = policy
+            .apply(&mut ctx, &wc_path, crate::Depth::Infinity, false)
+            .unwrap();
+        assert!(report
+            .iter()
+            .all(|d| d.outcome == DecisionOutcome::Resolved));
+        assert_eq!(
+            std::fs::read(wc_path.join("a.txt")).unwrap(),
+            b"one\ntheirs\nthree\n"
+        );
+        assert_eq!(
+            std::fs::read(wc_path.join("logo.png")).unwrap(),
+            b"\x89PNG mine"
+        );
+        assert!(policy
+            .apply(&mut ctx, &wc_path, crate::Depth::Infinity, false)
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -46,6 +46,25 @@ use crate::Revnum;
 use apr::pool::Pool;
 use std::marker::PhantomData;
 
+/// Composable editor wrappers: tee, path filtering, cancellation and tracing.
+pub mod combinators;
+
+/// Recording and replaying of editor drives.
+pub mod record;
+
+/// In-memory trees built from editor drives.
+pub mod tree;
+
+/// Bindings for the path-based Ev2 editor.
+pub mod ev2;
+
+/// Owned text delta windows.
+pub mod window;
+
+/// Pure-Rust svndiff encoder and decoder.
+#[cfg(feature = "svndiff")]
+pub mod svndiff;
+
 /// Returns the version of the delta library.
 pub fn version() -> crate::Version {
     crate::Version(unsafe { subversion_sys::svn_delta_version() })
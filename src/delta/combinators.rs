@@ -0,0 +1,1036 @@
+//! Composable wrappers around [`Editor`] implementations.
+//!
+//! Each combinator is itself an [`Editor`] whose directory editors keep
+//! `SubDirectory = Self` whenever the wrapped one does, so the result can be
+//! handed to C through [`WrapEditor::from_rust_editor`](super::WrapEditor::from_rust_editor)
+//! and used with `ra::Session::do_update`, `replay` and friends:
+//!
+//! ```no_run
+//! use subversion::delta::{combinators, default_editor, WrapEditor};
+//!
+//! let editor = combinators::TracingEditor::to_stderr(default_editor(apr::Pool::new()));
+//! let editor = combinators::CancelEditor::new(editor, || false);
+//! let mut editor = WrapEditor::from_rust_editor(editor);
+//! // session.replay(revnum, low_water_mark, true, &mut editor)?;
+//! ```
+
+use super::{DirectoryEditor, Editor, FileEditor, TxDeltaWindow};
+use crate::{Error, Revnum};
+use std::sync::Arc;
+
+/// A text delta window handler, as returned by
+/// [`FileEditor::apply_textdelta`].
+type WindowHandler =
+    Box<dyn for<'a> Fn(&'a mut TxDeltaWindow) -> Result<(), crate::Error<'static>>>;
+
+/// An editor that drives two editors with the same calls.
+///
+/// Every call is made on the first editor and then on the second; an error
+/// from the first editor is returned without calling the second.
+pub struct TeeEditor<A, B> {
+    a: A,
+    b: B,
+}
+
+impl<A: Editor, B: Editor> TeeEditor<A, B> {
+    /// Create an editor forwarding to both `a` and `b`.
+    pub fn new(a: A, b: B) -> Self {
+        Self { a, b }
+    }
+
+    /// Return the wrapped editors.
+    pub fn into_inner(self) -> (A, B) {
+        (self.a, self.b)
+    }
+}
+
+impl<A: Editor, B: Editor> Editor for TeeEditor<A, B> {
+    type RootEditor = TeeDirectory<A::RootEditor, B::RootEditor>;
+
+    fn set_target_revision(&mut self, revision: Option<Revnum>) -> Result<(), Error<'_>> {
+        self.a.set_target_revision(revision)?;
+        self.b.set_target_revision(revision)
+    }
+
+    fn open_root(&mut self, base_revision: Option<Revnum>) -> Result<Self::RootEditor, Error<'_>> {
+        Ok(TeeDirectory {
+            a: self.a.open_root(base_revision)?,
+            b: self.b.open_root(base_revision)?,
+        })
+    }
+
+    fn close(&mut self) -> Result<(), Error<'_>> {
+        self.a.close()?;
+        self.b.close()
+    }
+
+    fn abort(&mut self) -> Result<(), Error<'_>> {
+        // Give both editors a chance to clean up.
+        let a = self.a.abort().map_err(|e| e.into_static());
+        self.b.abort()?;
+        a
+    }
+}
+
+/// Directory editor of a [`TeeEditor`].
+pub struct TeeDirectory<A, B> {
+    a: A,
+    b: B,
+}
+
+impl<A: DirectoryEditor, B: DirectoryEditor> DirectoryEditor for TeeDirectory<A, B> {
+    type SubDirectory = TeeDirectory<A::SubDirectory, B::SubDirectory>;
+    type File = TeeFile<A::File, B::File>;
+
+    fn delete_entry(&mut self, path: &str, revision: Option<Revnum>) -> Result<(), Error<'_>> {
+        self.a.delete_entry(path, revision)?;
+        self.b.delete_entry(path, revision)
+    }
+
+    fn add_directory(
+        &mut self,
+        path: &str,
+        copyfrom: Option<(&str, Revnum)>,
+    ) -> Result<Self::SubDirectory, Error<'_>> {
+        Ok(TeeDirectory {
+            a: self.a.add_directory(path, copyfrom)?,
+            b: self.b.add_directory(path, copyfrom)?,
+        })
+    }
+
+    fn open_directory(
+        &mut self,
+        path: &str,
+        base_revision: Option<Revnum>,
+    ) -> Result<Self::SubDirectory, Error<'_>> {
+        Ok(TeeDirectory {
+            a: self.a.open_directory(path, base_revision)?,
+            b: self.b.open_directory(path, base_revision)?,
+        })
+    }
+
+    fn change_prop(&mut self, name: &str, value: Option<&[u8]>) -> Result<(), Error<'_>> {
+        self.a.change_prop(name, value)?;
+        self.b.change_prop(name, value)
+    }
+
+    fn close(&mut self) -> Result<(), Error<'_>> {
+        self.a.close()?;
+        self.b.close()
+    }
+
+    fn absent_directory(&mut self, path: &str) -> Result<(), Error<'_>> {
+        self.a.absent_directory(path)?;
+        self.b.absent_directory(path)
+    }
+
+    fn add_file(
+        &mut self,
+        path: &str,
+        copyfrom: Option<(&str, Revnum)>,
+    ) -> Result<Self::File, Error<'_>> {
+        Ok(TeeFile {
+            a: self.a.add_file(path, copyfrom)?,
+            b: self.b.add_file(path, copyfrom)?,
+        })
+    }
+
+    fn open_file(
+        &mut self,
+        path: &str,
+        base_revision: Option<Revnum>,
+    ) -> Result<Self::File, Error<'_>> {
+        Ok(TeeFile {
+            a: self.a.open_file(path, base_revision)?,
+            b: self.b.open_file(path, base_revision)?,
+        })
+    }
+
+    fn absent_file(&mut self, path: &str) -> Result<(), Error<'_>> {
+        self.a.absent_file(path)?;
+        self.b.absent_file(path)
+    }
+}
+
+/// File editor of a [`TeeEditor`].
+pub struct TeeFile<A, B> {
+    a: A,
+    b: B,
+}
+
+impl<A: FileEditor, B: FileEditor> FileEditor for TeeFile<A, B> {
+    fn apply_textdelta(
+        &mut self,
+        base_checksum: Option<&str>,
+    ) -> Result<WindowHandler, Error<'static>> {
+        let a = self.a.apply_textdelta(base_checksum)?;
+        let b = self.b.apply_textdelta(base_checksum)?;
+        Ok(Box::new(move |window| {
+            a(window)?;
+            b(window)
+        }))
+    }
+
+    fn change_prop(&mut self, name: &str, value: Option<&[u8]>) -> Result<(), Error<'static>> {
+        self.a.change_prop(name, value)?;
+        self.b.change_prop(name, value)
+    }
+
+    fn close(&mut self, text_checksum: Option<&str>) -> Result<(), Error<'static>> {
+        self.a.close(text_checksum)?;
+        self.b.close(text_checksum)
+    }
+}
+
+fn is_ancestor(parent: &str, child: &str) -> bool {
+    parent.is_empty()
+        || child == parent
+        || (child.starts_with(parent) && child.as_bytes()[parent.len()] == b'/')
+}
+
+/// Selects the paths a [`PathFilterEditor`] lets through.
+///
+/// Paths are relative to the root of the edit.  A path is selected if it
+/// is at or below one of the included paths (or nothing was included) and
+/// not at or below any excluded path.
+#[derive(Debug, Clone, Default)]
+pub struct PathFilter {
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl PathFilter {
+    /// Create a filter that selects everything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Select `path` and everything below it.
+    pub fn include(mut self, path: &str) -> Self {
+        self.include.push(path.trim_matches('/').to_string());
+        self
+    }
+
+    /// Reject `path` and everything below it.
+    pub fn exclude(mut self, path: &str) -> Self {
+        self.exclude.push(path.trim_matches('/').to_string());
+        self
+    }
+
+    fn is_excluded(&self, path: &str) -> bool {
+        self.exclude.iter().any(|e| is_ancestor(e, path))
+    }
+
+    /// Whether `path` is selected.
+    pub fn matches(&self, path: &str) -> bool {
+        let path = path.trim_matches('/');
+        !self.is_excluded(path)
+            && (self.include.is_empty() || self.include.iter().any(|i| is_ancestor(i, path)))
+    }
+
+    /// Whether `path` is selected or contains selected paths.
+    fn traverses(&self, path: &str) -> bool {
+        let path = path.trim_matches('/');
+        self.matches(path)
+            || (!self.is_excluded(path) && self.include.iter().any(|i| is_ancestor(path, i)))
+    }
+}
+
+/// An editor that only forwards changes to paths selected by a
+/// [`PathFilter`].
+///
+/// Directories that contain selected paths are opened (or added) in the
+/// wrapped editor so the selected paths can be reached, but their own
+/// property changes are dropped.  Deleting such a directory is forwarded,
+/// since it removes selected paths too.
+pub struct PathFilterEditor<E> {
+    inner: E,
+    filter: Arc<PathFilter>,
+}
+
+impl<E: Editor> PathFilterEditor<E> {
+    /// Wrap `inner`, forwarding only the paths selected by `filter`.
+    pub fn new(inner: E, filter: PathFilter) -> Self {
+        Self {
+            inner,
+            filter: Arc::new(filter),
+        }
+    }
+
+    /// Return the wrapped editor.
+    pub fn into_inner(self) -> E {
+        self.inner
+    }
+}
+
+impl<E: Editor> Editor for PathFilterEditor<E> {
+    type RootEditor = PathFilterDirectory<E::RootEditor>;
+
+    fn set_target_revision(&mut self, revision: Option<Revnum>) -> Result<(), Error<'_>> {
+        self.inner.set_target_revision(revision)
+    }
+
+    fn open_root(&mut self, base_revision: Option<Revnum>) -> Result<Self::RootEditor, Error<'_>> {
+        Ok(PathFilterDirectory {
+            inner: Some(self.inner.open_root(base_revision)?),
+            path: String::new(),
+            filter: self.filter.clone(),
+        })
+    }
+
+    fn close(&mut self) -> Result<(), Error<'_>> {
+        self.inner.close()
+    }
+
+    fn abort(&mut self) -> Result<(), Error<'_>> {
+        self.inner.abort()
+    }
+}
+
+/// Directory editor of a [`PathFilterEditor`].
+///
+/// Directories outside the filter have no wrapped editor and ignore all
+/// calls.
+pub struct PathFilterDirectory<D> {
+    inner: Option<D>,
+    path: String,
+    filter: Arc<PathFilter>,
+}
+
+impl<D: DirectoryEditor> PathFilterDirectory<D> {
+    fn child<C>(&self, inner: Option<C>, path: &str) -> PathFilterDirectory<C> {
+        PathFilterDirectory {
+            inner,
+            path: path.trim_matches('/').to_string(),
+            filter: self.filter.clone(),
+        }
+    }
+}
+
+impl<D: DirectoryEditor> DirectoryEditor for PathFilterDirectory<D> {
+    type SubDirectory = PathFilterDirectory<D::SubDirectory>;
+    type File = PathFilterFile<D::File>;
+
+    fn delete_entry(&mut self, path: &str, revision: Option<Revnum>) -> Result<(), Error<'_>> {
+        match &mut self.inner {
+            Some(inner) if self.filter.traverses(path) => inner.delete_entry(path, revision),
+            _ => Ok(()),
+        }
+    }
+
+    fn add_directory(
+        &mut self,
+        path: &str,
+        copyfrom: Option<(&str, Revnum)>,
+    ) -> Result<Self::SubDirectory, Error<'_>> {
+        let inner = match &mut self.inner {
+            Some(inner) if self.filter.traverses(path) => Some(
+                inner
+                    .add_directory(path, copyfrom)
+                    .map_err(|e| e.into_static())?,
+            ),
+            _ => None,
+        };
+        Ok(self.child(inner, path))
+    }
+
+    fn open_directory(
+        &mut self,
+        path: &str,
+        base_revision: Option<Revnum>,
+    ) -> Result<Self::SubDirectory, Error<'_>> {
+        let inner = match &mut self.inner {
+            Some(inner) if self.filter.traverses(path) => Some(
+                inner
+                    .open_directory(path, base_revision)
+                    .map_err(|e| e.into_static())?,
+            ),
+            _ => None,
+        };
+        Ok(self.child(inner, path))
+    }
+
+    fn change_prop(&mut self, name: &str, value: Option<&[u8]>) -> Result<(), Error<'_>> {
+        match &mut self.inner {
+            Some(inner) if self.filter.matches(&self.path) => inner.change_prop(name, value),
+            _ => Ok(()),
+        }
+    }
+
+    fn close(&mut self) -> Result<(), Error<'_>> {
+        match &mut self.inner {
+            Some(inner) => inner.close(),
+            None => Ok(()),
+        }
+    }
+
+    fn absent_directory(&mut self, path: &str) -> Result<(), Error<'_>> {
+        match &mut self.inner {
+            Some(inner) if self.filter.matches(path) => inner.absent_directory(path),
+            _ => Ok(()),
+        }
+    }
+
+    fn add_file(
+        &mut self,
+        path: &str,
+        copyfrom: Option<(&str, Revnum)>,
+    ) -> Result<Self::File, Error<'_>> {
+        Ok(PathFilterFile {
+            inner: match &mut self.inner {
+                Some(inner) if self.filter.matches(path) => Some(inner.add_file(path, copyfrom)?),
+                _ => None,
+            },
+        })
+    }
+
+    fn open_file(
+        &mut self,
+        path: &str,
+        base_revision: Option<Revnum>,
+    ) -> Result<Self::File, Error<'_>> {
+        Ok(PathFilterFile {
+            inner: match &mut self.inner {
+                Some(inner) if self.filter.matches(path) => {
+                    Some(inner.open_file(path, base_revision)?)
+                }
+                _ => None,
+            },
+        })
+    }
+
+    fn absent_file(&mut self, path: &str) -> Result<(), Error<'_>> {
+        match &mut self.inner {
+            Some(inner) if self.filter.matches(path) => inner.absent_file(path),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// File editor of a [`PathFilterEditor`].
+pub struct PathFilterFile<F> {
+    inner: Option<F>,
+}
+
+impl<F: FileEditor> FileEditor for PathFilterFile<F> {
+    fn apply_textdelta(
+        &mut self,
+        base_checksum: Option<&str>,
+    ) -> Result<WindowHandler, Error<'static>> {
+        match &mut self.inner {
+            Some(inner) => inner.apply_textdelta(base_checksum),
+            None => Ok(Box::new(|_| Ok(()))),
+        }
+    }
+
+    fn change_prop(&mut self, name: &str, value: Option<&[u8]>) -> Result<(), Error<'static>> {
+        match &mut self.inner {
+            Some(inner) => inner.change_prop(name, value),
+            None => Ok(()),
+        }
+    }
+
+    fn close(&mut self, text_checksum: Option<&str>) -> Result<(), Error<'static>> {
+        match &mut self.inner {
+            Some(inner) => inner.close(text_checksum),
+            None => Ok(()),
+        }
+    }
+}
+
+type CancelFunc = Arc<dyn Fn() -> bool + Send + Sync>;
+
+fn check_cancelled(cancel: &CancelFunc) -> Result<(), Error<'static>> {
+    if cancel() {
+        return Err(Error::with_raw_status(
+            subversion_sys::svn_errno_t_SVN_ERR_CANCELLED as i32,
+            None,
+            "Operation cancelled",
+        ));
+    }
+    Ok(())
+}
+
+/// An editor that checks for cancellation before every call, like
+/// `svn_delta_get_cancellation_editor`.
+///
+/// Once the cancel function returns `true`, calls fail with
+/// `SVN_ERR_CANCELLED` without reaching the wrapped editor.  Aborting the
+/// edit is always forwarded.
+pub struct CancelEditor<E> {
+    inner: E,
+    cancel: CancelFunc,
+}
+
+impl<E: Editor> CancelEditor<E> {
+    /// Wrap `inner`, consulting `cancel` before every call.
+    pub fn new(inner: E, cancel: impl Fn() -> bool + Send + Sync + 'static) -> Self {
+        Self {
+            inner,
+            cancel: Arc::new(cancel),
+        }
+    }
+
+    /// Return the wrapped editor.
+    pub fn into_inner(self) -> E {
+        self.inner
+    }
+}
+
+impl<E: Editor> Editor for CancelEditor<E> {
+    type RootEditor = CancelDirectory<E::RootEditor>;
+
+    fn set_target_revision(&mut self, revision: Option<Revnum>) -> Result<(), Error<'_>> {
+        check_cancelled(&self.cancel)?;
+        self.inner.set_target_revision(revision)
+    }
+
+    fn open_root(&mut self, base_revision: Option<Revnum>) -> Result<Self::RootEditor, Error<'_>> {
+        check_cancelled(&self.cancel)?;
+        Ok(CancelDirectory {
+            inner: self.inner.open_root(base_revision)?,
+            cancel: self.cancel.clone(),
+        })
+    }
+
+    fn close(&mut self) -> Result<(), Error<'_>> {
+        check_cancelled(&self.cancel)?;
+        self.inner.close()
+    }
+
+    fn abort(&mut self) -> Result<(), Error<'_>> {
+        self.inner.abort()
+    }
+}
+
+/// Directory editor of a [`CancelEditor`].
+pub struct CancelDirectory<D> {
+    inner: D,
+    cancel: CancelFunc,
+}
+
+impl<D: DirectoryEditor> DirectoryEditor for CancelDirectory<D> {
+    type SubDirectory = CancelDirectory<D::SubDirectory>;
+    type File = CancelFile<D::File>;
+
+    fn delete_entry(&mut self, path: &str, revision: Option<Revnum>) -> Result<(), Error<'_>> {
+        check_cancelled(&self.cancel)?;
+        self.inner.delete_entry(path, revision)
+    }
+
+    fn add_directory(
+        &mut self,
+        path: &str,
+        copyfrom: Option<(&str, Revnum)>,
+    ) -> Result<Self::SubDirectory, Error<'_>> {
+        check_cancelled(&self.cancel)?;
+        Ok(CancelDirectory {
+            inner: self.inner.add_directory(path, copyfrom)?,
+            cancel: self.cancel.clone(),
+        })
+    }
+
+    fn open_directory(
+        &mut self,
+        path: &str,
+        base_revision: Option<Revnum>,
+    ) -> Result<Self::SubDirectory, Error<'_>> {
+        check_cancelled(&self.cancel)?;
+        Ok(CancelDirectory {
+            inner: self.inner.open_directory(path, base_revision)?,
+            cancel: self.cancel.clone(),
+        })
+    }
+
+    fn change_prop(&mut self, name: &str, value: Option<&[u8]>) -> Result<(), Error<'_>> {
+        check_cancelled(&self.cancel)?;
+        self.inner.change_prop(name, value)
+    }
+
+    fn close(&mut self) -> Result<(), Error<'_>> {
+        check_cancelled(&self.cancel)?;
+        self.inner.close()
+    }
+
+    fn absent_directory(&mut self, path: &str) -> Result<(), Error<'_>> {
+        check_cancelled(&self.cancel)?;
+        self.inner.absent_directory(path)
+    }
+
+    fn add_file(
+        &mut self,
+        path: &str,
+        copyfrom: Option<(&str, Revnum)>,
+    ) -> Result<Self::File, Error<'_>> {
+        check_cancelled(&self.cancel)?;
+        Ok(CancelFile {
+            inner: self.inner.add_file(path, copyfrom)?,
+            cancel: self.cancel.clone(),
+        })
+    }
+
+    fn open_file(
+        &mut self,
+        path: &str,
+        base_revision: Option<Revnum>,
+    ) -> Result<Self::File, Error<'_>> {
+        check_cancelled(&self.cancel)?;
+        Ok(CancelFile {
+            inner: self.inner.open_file(path, base_revision)?,
+            cancel: self.cancel.clone(),
+        })
+    }
+
+    fn absent_file(&mut self, path: &str) -> Result<(), Error<'_>> {
+        check_cancelled(&self.cancel)?;
+        self.inner.absent_file(path)
+    }
+}
+
+/// File editor of a [`CancelEditor`].
+pub struct CancelFile<F> {
+    inner: F,
+    cancel: CancelFunc,
+}
+
+impl<F: FileEditor> FileEditor for CancelFile<F> {
+    fn apply_textdelta(
+        &mut self,
+        base_checksum: Option<&str>,
+    ) -> Result<WindowHandler, Error<'static>> {
+        check_cancelled(&self.cancel)?;
+        self.inner.apply_textdelta(base_checksum)
+    }
+
+    fn change_prop(&mut self, name: &str, value: Option<&[u8]>) -> Result<(), Error<'static>> {
+        check_cancelled(&self.cancel)?;
+        self.inner.change_prop(name, value)
+    }
+
+    fn close(&mut self, text_checksum: Option<&str>) -> Result<(), Error<'static>> {
+        check_cancelled(&self.cancel)?;
+        self.inner.close(text_checksum)
+    }
+}
+
+type TraceSink = Arc<dyn Fn(&str) + Send + Sync>;
+
+#[derive(Clone)]
+struct Tracer {
+    sink: TraceSink,
+    prefix: Arc<str>,
+    depth: usize,
+}
+
+impl Tracer {
+    fn emit(&self, line: std::fmt::Arguments<'_>) {
+        (self.sink)(&format!(
+            "{}{:indent$}{}",
+            self.prefix,
+            "",
+            line,
+            indent = self.depth * 2
+        ));
+    }
+
+    fn nested(&self) -> Self {
+        Self {
+            depth: self.depth + 1,
+            ..self.clone()
+        }
+    }
+}
+
+fn rev(revision: Option<Revnum>) -> i64 {
+    revision.map_or(-1, |r| r.0)
+}
+
+fn copyfrom(copyfrom: Option<(&str, Revnum)>) -> String {
+    match copyfrom {
+        Some((path, revision)) => format!("'{}':{}", path, revision.0),
+        None => "''".to_string(),
+    }
+}
+
+/// An editor that reports every call before forwarding it, in the format
+/// of `svn_delta__get_debug_editor`.
+///
+/// Each call is passed to the sink as a single line without a trailing
+/// newline, indented by two spaces per directory level.
+pub struct TracingEditor<E> {
+    inner: E,
+    tracer: Tracer,
+}
+
+impl<E: Editor> TracingEditor<E> {
+    /// Wrap `inner`, passing a description of every call to `sink`.
+    pub fn new(inner: E, sink: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        Self {
+            inner,
+            tracer: Tracer {
+                sink: Arc::new(sink),
+                prefix: "".into(),
+                depth: 0,
+            },
+        }
+    }
+
+    /// Wrap `inner`, writing every call to standard error.
+    pub fn to_stderr(inner: E) -> Self {
+        Self::new(inner, |line| eprintln!("{}", line))
+    }
+
+    /// Prepend `prefix` to every line.
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.tracer.prefix = prefix.into();
+        self
+    }
+
+    /// Return the wrapped editor.
+    pub fn into_inner(self) -> E {
+        self.inner
+    }
+}
+
+impl<E: Editor> Editor for TracingEditor<E> {
+    type RootEditor = TracingDirectory<E::RootEditor>;
+
+    fn set_target_revision(&mut self, revision: Option<Revnum>) -> Result<(), Error<'_>> {
+        self.tracer
+            .emit(format_args!("set_target_revision : {}", rev(revision)));
+        self.inner.set_target_revision(revision)
+    }
+
+    fn open_root(&mut self, base_revision: Option<Revnum>) -> Result<Self::RootEditor, Error<'_>> {
+        self.tracer
+            .emit(format_args!("open_root : {}", rev(base_revision)));
+        Ok(TracingDirectory {
+            inner: self.inner.open_root(base_revision)?,
+            tracer: self.tracer.nested(),
+        })
+    }
+
+    fn close(&mut self) -> Result<(), Error<'_>> {
+        self.tracer.emit(format_args!("close_edit"));
+        self.inner.close()
+    }
+
+    fn abort(&mut self) -> Result<(), Error<'_>> {
+        self.tracer.emit(format_args!("abort_edit"));
+        self.inner.abort()
+    }
+}
+
+/// Directory editor of a [`TracingEditor`].
+pub struct TracingDirectory<D> {
+    inner: D,
+    tracer: Tracer,
+}
+
+impl<D: DirectoryEditor> DirectoryEditor for TracingDirectory<D> {
+    type SubDirectory = TracingDirectory<D::SubDirectory>;
+    type File = TracingFile<D::File>;
+
+    fn delete_entry(&mut self, path: &str, revision: Option<Revnum>) -> Result<(), Error<'_>> {
+        self.tracer
+            .emit(format_args!("delete_entry : {}:{}", path, rev(revision)));
+        self.inner.delete_entry(path, revision)
+    }
+
+    fn add_directory(
+        &mut self,
+        path: &str,
+        from: Option<(&str, Revnum)>,
+    ) -> Result<Self::SubDirectory, Error<'_>> {
+        self.tracer.emit(format_args!(
+            "add_directory : '{}' [from {}]",
+            path,
+            copyfrom(from)
+        ));
+        Ok(TracingDirectory {
+            inner: self.inner.add_directory(path, from)?,
+            tracer: self.tracer.nested(),
+        })
+    }
+
+    fn open_directory(
+        &mut self,
+        path: &str,
+        base_revision: Option<Revnum>,
+    ) -> Result<Self::SubDirectory, Error<'_>> {
+        self.tracer.emit(format_args!(
+            "open_directory : '{}':{}",
+            path,
+            rev(base_revision)
+        ));
+        Ok(TracingDirectory {
+            inner: self.inner.open_directory(path, base_revision)?,
+            tracer: self.tracer.nested(),
+        })
+    }
+
+    fn change_prop(&mut self, name: &str, value: Option<&[u8]>) -> Result<(), Error<'_>> {
+        self.tracer.emit(format_args!("change_dir_prop : {}", name));
+        self.inner.change_prop(name, value)
+    }
+
+    fn close(&mut self) -> Result<(), Error<'_>> {
+        self.tracer.emit(format_args!("close_directory"));
+        self.inner.close()
+    }
+
+    fn absent_directory(&mut self, path: &str) -> Result<(), Error<'_>> {
+        self.tracer
+            .emit(format_args!("absent_directory : {}", path));
+        self.inner.absent_directory(path)
+    }
+
+    fn add_file(
+        &mut self,
+        path: &str,
+        from: Option<(&str, Revnum)>,
+    ) -> Result<Self::File, Error<'_>> {
+        self.tracer.emit(format_args!(
+            "add_file : '{}' [from {}]",
+            path,
+            copyfrom(from)
+        ));
+        Ok(TracingFile {
+            inner: self.inner.add_file(path, from)?,
+            tracer: self.tracer.nested(),
+        })
+    }
+
+    fn open_file(
+        &mut self,
+        path: &str,
+        base_revision: Option<Revnum>,
+    ) -> Result<Self::File, Error<'_>> {
+        self.tracer.emit(format_args!(
+            "open_file : '{}':{}",
+            path,
+            rev(base_revision)
+        ));
+        Ok(TracingFile {
+            inner: self.inner.open_file(path, base_revision)?,
+            tracer: self.tracer.nested(),
+        })
+    }
+
+    fn absent_file(&mut self, path: &str) -> Result<(), Error<'_>> {
+        self.tracer.emit(format_args!("absent_file : {}", path));
+        self.inner.absent_file(path)
+    }
+}
+
+/// File editor of a [`TracingEditor`].
+pub struct TracingFile<F> {
+    inner: F,
+    tracer: Tracer,
+}
+
+impl<F: FileEditor> FileEditor for TracingFile<F> {
+    fn apply_textdelta(
+        &mut self,
+        base_checksum: Option<&str>,
+    ) -> Result<WindowHandler, Error<'static>> {
+        self.tracer.emit(format_args!(
+            "apply_textdelta : {}",
+            base_checksum.unwrap_or("")
+        ));
+        self.inner.apply_textdelta(base_checksum)
+    }
+
+    fn change_prop(&mut self, name: &str, value: Option<&[u8]>) -> Result<(), Error<'static>> {
+        self.tracer
+            .emit(format_args!("change_file_prop : {}", name));
+        self.inner.change_prop(name, value)
+    }
+
+    fn close(&mut self, text_checksum: Option<&str>) -> Result<(), Error<'static>> {
+        self.tracer
+            .emit(format_args!("close_file : {}", text_checksum.unwrap_or("")));
+        self.inner.close(text_checksum)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::delta::{default_editor, WrapEditor};
+    use std::sync::Mutex;
+
+    fn recorder(
+        inner: WrapEditor<'static>,
+    ) -> (TracingEditor<WrapEditor<'static>>, Arc<Mutex<Vec<String>>>) {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let sink = lines.clone();
+        let editor = TracingEditor::new(inner, move |line| {
+            sink.lock().unwrap().push(line.to_string())
+        });
+        (editor, lines)
+    }
+
+    /// Drive a small edit: modify a file, add a directory with a file and
+    /// delete an entry.
+    fn drive<E: Editor>(editor: &mut E) -> Result<(), Error<'static>> {
+        editor
+            .set_target_revision(Some(Revnum(2)))
+            .map_err(|e| e.into_static())?;
+        let mut root = editor
+            .open_root(Some(Revnum(1)))
+            .map_err(|e| e.into_static())?;
+        root.change_prop("svn:ignore", Some(b"*.o"))
+            .map_err(|e| e.into_static())?;
+        let mut trunk = root
+            .open_directory("trunk", Some(Revnum(1)))
+            .map_err(|e| e.into_static())?;
+        let mut file = trunk
+            .open_file("trunk/README", Some(Revnum(1)))
+            .map_err(|e| e.into_static())?;
+        file.change_prop("svn:eol-style", Some(b"native"))?;
+        file.close(None)?;
+        trunk
+            .delete_entry("trunk/old", Some(Revnum(1)))
+            .map_err(|e| e.into_static())?;
+        trunk.close().map_err(|e| e.into_static())?;
+        let mut tags = root
+            .add_directory("tags", None)
+            .map_err(|e| e.into_static())?;
+        let mut file = tags
+            .add_file("tags/file", Some(("/trunk/README", Revnum(1))))
+            .map_err(|e| e.into_static())?;
+        file.close(None)?;
+        tags.close().map_err(|e| e.into_static())?;
+        root.close().map_err(|e| e.into_static())?;
+        editor.close().map_err(|e| e.into_static())
+    }
+
+    #[test]
+    fn test_tracing_editor() {
+        let (mut editor, lines) = recorder(default_editor(apr::Pool::new()));
+        drive(&mut editor).unwrap();
+        assert_eq!(
+            *lines.lock().unwrap(),
+            [
+                "set_target_revision : 2",
+                "open_root : 1",
+                "  change_dir_prop : svn:ignore",
+                "  open_directory : 'trunk':1",
+                "    open_file : 'trunk/README':1",
+                "      change_file_prop : svn:eol-style",
+                "      close_file : ",
+                "    delete_entry : trunk/old:1",
+                "    close_directory",
+                "  add_directory : 'tags' [from '']",
+                "    add_file : 'tags/file' [from '/trunk/README':1]",
+                "      close_file : ",
+                "    close_directory",
+                "  close_directory",
+                "close_edit",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tee_editor() {
+        let (a, a_lines) = recorder(default_editor(apr::Pool::new()));
+        let (b, b_lines) = recorder(default_editor(apr::Pool::new()));
+        let mut editor = TeeEditor::new(a, b.with_prefix("B: "));
+        drive(&mut editor).unwrap();
+
+        let a_lines = a_lines.lock().unwrap();
+        let b_lines = b_lines.lock().unwrap();
+        assert_eq!(a_lines.len(), 15);
+        assert_eq!(
+            b_lines.iter().map(|l| &l[3..]).collect::<Vec<_>>(),
+            *a_lines
+        );
+    }
+
+    #[test]
+    fn test_path_filter() {
+        let filter = PathFilter::new().include("trunk").exclude("trunk/old");
+        assert!(filter.matches("trunk"));
+        assert!(filter.matches("/trunk/README"));
+        assert!(!filter.matches("trunk/old/file"));
+        assert!(!filter.matches("trunkish"));
+        assert!(!filter.matches("tags"));
+        assert!(!filter.matches(""));
+
+        let (inner, lines) = recorder(default_editor(apr::Pool::new()));
+        let mut editor = PathFilterEditor::new(inner, filter);
+        drive(&mut editor).unwrap();
+        assert_eq!(
+            *lines.lock().unwrap(),
+            [
+                "set_target_revision : 2",
+                "open_root : 1",
+                "  open_directory : 'trunk':1",
+                "    open_file : 'trunk/README':1",
+                "      change_file_prop : svn:eol-style",
+                "      close_file : ",
+                "    close_directory",
+                "  close_directory",
+                "close_edit",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cancel_editor() {
+        let cancelled = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let flag = cancelled.clone();
+        let (inner, lines) = recorder(default_editor(apr::Pool::new()));
+        let mut editor = CancelEditor::new(inner, move || {
+            flag.load(std::sync::atomic::Ordering::SeqCst)
+        });
+
+        let mut root = editor.open_root(None).unwrap();
+        root.change_prop("a", None).unwrap();
+        cancelled.store(true, std::sync::atomic::Ordering::SeqCst);
+        let err = root.change_prop("b", None).unwrap_err();
+        assert_eq!(
+            err.raw_apr_err(),
+            subversion_sys::svn_errno_t_SVN_ERR_CANCELLED as i32
+        );
+        drop(root);
+        editor.abort().unwrap();
+
+        assert_eq!(
+            *lines.lock().unwrap(),
+            ["open_root : -1", "  change_dir_prop : a", "abort_edit"]
+        );
+    }
+
+    #[cfg(all(feature = "ra", feature = "repos"))]
+    #[test]
+    fn test_replay_through_combinators() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repos_path = temp_dir.path().join("repo");
+        let repos = crate::repos::Repos::create(&repos_path).unwrap();
+        let fs = repos.fs().unwrap();
+        let mut txn = fs.begin_txn(Revnum(0), 0).unwrap();
+        let mut root = txn.root().unwrap();
+        root.make_dir("/trunk").unwrap();
+        root.make_file("/trunk/a.txt").unwrap();
+        root.make_dir("/branches").unwrap();
+        txn.commit().unwrap();
+
+        let url = crate::path_to_file_url(&repos_path);
+        let (mut session, _, _) = crate::ra::Session::open(&url, None, None, None).unwrap();
+
+        let (traced, lines) = recorder(default_editor(apr::Pool::new()));
+        let editor = PathFilterEditor::new(traced, PathFilter::new().include("trunk"));
+        let editor = CancelEditor::new(editor, || false);
+        let mut editor = WrapEditor::from_rust_editor(editor);
+        session
+            .replay(Revnum(1), Revnum(0), true, &mut editor)
+            .unwrap();
+
+        let lines = lines.lock().unwrap();
+        assert!(lines.iter().any(|l| l.contains("add_directory : 'trunk'")));
+        assert!(lines.iter().any(|l| l.contains("add_file : 'trunk/a.txt'")));
+        assert!(!lines.iter().any(|l| l.contains("branches")));
+    }
+}
@@ -0,0 +1,1285 @@
+//! Bindings for the Ev2 editor (`svn_editor_t`).
+//!
+//! Unlike the classic delta editor, Ev2 is path based: every operation names
+//! the node it applies to by its path relative to the repository root,
+//! directories list their children when they are added, files are sent as
+//! full contents rather than deltas and moves are first-class operations.
+//!
+//! [`Editor`] is the Rust side of the interface.  [`WrapEditor`] wraps a C
+//! `svn_editor_t`, either one created from a Rust [`Editor`] with
+//! [`WrapEditor::from_rust_editor`] or one produced by the shims.
+//! [`editor_from_delta`] and [`delta_from_editor`] convert between Ev2 and
+//! the delta editor in [`crate::delta`].
+//!
+//! Subversion does not install the Ev2 headers, so the functions used here
+//! are declared by hand in `subversion-sys`; they are exported by
+//! libsvn_delta.
+
+use crate::delta::DropperFn;
+use crate::{Checksum, Error, NodeKind, Revnum};
+use std::collections::HashMap;
+use std::ffi::{c_char, c_void, CStr, CString};
+
+/// A path-based (Ev2) editor.
+///
+/// All paths are relative to the repository root.  Revisions passed to the
+/// `alter_*`, `delete`, `copy` and `move_node` operations are the revisions
+/// the driver expects the nodes to be at; `replaces_rev` is set when an
+/// added node replaces an existing one.
+pub trait Editor {
+    /// Add a directory.  `children` lists the names of the entries that will
+    /// be added below it.
+    fn add_directory(
+        &mut self,
+        relpath: &str,
+        children: &[&str],
+        props: &HashMap<String, Vec<u8>>,
+        replaces_rev: Option<Revnum>,
+    ) -> Result<(), Error<'static>>;
+
+    /// Add a file with the given full contents.  `checksum` is the SHA-1
+    /// checksum of `contents`.
+    fn add_file(
+        &mut self,
+        relpath: &str,
+        checksum: &Checksum,
+        contents: &mut crate::io::Stream,
+        props: &HashMap<String, Vec<u8>>,
+        replaces_rev: Option<Revnum>,
+    ) -> Result<(), Error<'static>>;
+
+    /// Add a symbolic link pointing at `target`.
+    fn add_symlink(
+        &mut self,
+        relpath: &str,
+        target: &str,
+        props: &HashMap<String, Vec<u8>>,
+        replaces_rev: Option<Revnum>,
+    ) -> Result<(), Error<'static>>;
+
+    /// Add a node that exists but cannot be transmitted, e.g. because it
+    /// is not readable.
+    fn add_absent(
+        &mut self,
+        relpath: &str,
+        kind: NodeKind,
+        replaces_rev: Option<Revnum>,
+    ) -> Result<(), Error<'static>>;
+
+    /// Change a directory's children and/or properties.  `None` leaves the
+    /// respective part unchanged; `props` replaces all properties.
+    fn alter_directory(
+        &mut self,
+        relpath: &str,
+        revision: Option<Revnum>,
+        children: Option<&[&str]>,
+        props: Option<&HashMap<String, Vec<u8>>>,
+    ) -> Result<(), Error<'static>>;
+
+    /// Change a file's contents and/or properties.
+    fn alter_file(
+        &mut self,
+        relpath: &str,
+        revision: Option<Revnum>,
+        contents: Option<(&Checksum, &mut crate::io::Stream)>,
+        props: Option<&HashMap<String, Vec<u8>>>,
+    ) -> Result<(), Error<'static>>;
+
+    /// Change a symbolic link's target and/or properties.
+    fn alter_symlink(
+        &mut self,
+        relpath: &str,
+        revision: Option<Revnum>,
+        target: Option<&str>,
+        props: Option<&HashMap<String, Vec<u8>>>,
+    ) -> Result<(), Error<'static>>;
+
+    /// Delete a node.
+    fn delete(&mut self, relpath: &str, revision: Option<Revnum>) -> Result<(), Error<'static>>;
+
+    /// Copy a node.
+    fn copy(
+        &mut self,
+        src_relpath: &str,
+        src_revision: Revnum,
+        dst_relpath: &str,
+        replaces_rev: Option<Revnum>,
+    ) -> Result<(), Error<'static>>;
+
+    /// Move a node.
+    fn move_node(
+        &mut self,
+        src_relpath: &str,
+        src_revision: Revnum,
+        dst_relpath: &str,
+        replaces_rev: Option<Revnum>,
+    ) -> Result<(), Error<'static>>;
+
+    /// Finish the edit.
+    fn complete(&mut self) -> Result<(), Error<'static>>;
+
+    /// Abandon the edit.
+    fn abort(&mut self) -> Result<(), Error<'static>>;
+}
+
+/// Callbacks the shims use to look up the state of nodes the driver does
+/// not describe in full.
+///
+/// The defaults report nothing, which is sufficient for drives that only
+/// add nodes.
+pub trait ShimCallbacks {
+    /// The kind of `path` in `base_revision`.
+    fn fetch_kind(
+        &mut self,
+        _path: &str,
+        _base_revision: Option<Revnum>,
+    ) -> Result<NodeKind, Error<'static>> {
+        Ok(NodeKind::Unknown)
+    }
+
+    /// All properties of `path` in `base_revision`.
+    fn fetch_props(
+        &mut self,
+        _path: &str,
+        _base_revision: Option<Revnum>,
+    ) -> Result<HashMap<String, Vec<u8>>, Error<'static>> {
+        Ok(HashMap::new())
+    }
+
+    /// The contents of the file at `path` in `base_revision`, used as the
+    /// base for text deltas.
+    fn fetch_base(
+        &mut self,
+        _path: &str,
+        _base_revision: Option<Revnum>,
+    ) -> Result<Option<Vec<u8>>, Error<'static>> {
+        Ok(None)
+    }
+}
+
+/// Shim callbacks that know nothing about existing nodes.
+pub struct NoShimCallbacks;
+
+impl ShimCallbacks for NoShimCallbacks {}
+
+fn raw_revnum(revision: Option<Revnum>) -> subversion_sys::svn_revnum_t {
+    revision.map_or(-1, |r| r.into())
+}
+
+fn into_raw_result(result: Result<(), Error<'static>>) -> *mut subversion_sys::svn_error_t {
+    match result {
+        Ok(()) => std::ptr::null_mut(),
+        Err(e) => unsafe { e.into_raw() },
+    }
+}
+
+unsafe fn str_from<'a>(ptr: *const c_char) -> &'a str {
+    CStr::from_ptr(ptr).to_str().unwrap()
+}
+
+unsafe fn children_from(ptr: *const apr_sys::apr_array_header_t) -> Option<Vec<String>> {
+    if ptr.is_null() {
+        return None;
+    }
+    let array = apr::tables::TypedArray::<*const c_char>::from_ptr(ptr as *mut _);
+    Some(
+        array
+            .iter()
+            .map(|name| CStr::from_ptr(name).to_string_lossy().into_owned())
+            .collect(),
+    )
+}
+
+unsafe fn props_from(ptr: *mut apr_sys::apr_hash_t) -> Option<HashMap<String, Vec<u8>>> {
+    if ptr.is_null() {
+        None
+    } else {
+        Some(crate::props::PropHash::from_ptr(ptr).to_hashmap())
+    }
+}
+
+/// Allocate `children` as an array of C strings in `pool`.
+fn children_to_array(
+    children: &[&str],
+    pool: &apr::Pool,
+) -> Result<*const apr_sys::apr_array_header_t, Error<'static>> {
+    let mut array = apr::tables::TypedArray::<*const c_char>::new(pool, children.len() as i32);
+    for child in children {
+        let child = CString::new(*child)?;
+        array.push(unsafe { apr_sys::apr_pstrdup(pool.as_mut_ptr(), child.as_ptr()) });
+    }
+    Ok(unsafe { array.as_ptr() })
+}
+
+fn props_to_hash(props: &HashMap<String, Vec<u8>>, pool: &apr::Pool) -> *mut apr_sys::apr_hash_t {
+    crate::props::PropHash::from_hashmap(props, pool).as_mut_ptr()
+}
+
+unsafe extern "C" fn rust_ev2_add_directory<E: Editor>(
+    baton: *mut c_void,
+    relpath: *const c_char,
+    children: *const apr_sys::apr_array_header_t,
+    props: *mut apr_sys::apr_hash_t,
+    replaces_rev: subversion_sys::svn_revnum_t,
+    _scratch_pool: *mut apr_sys::apr_pool_t,
+) -> *mut subversion_sys::svn_error_t {
+    let editor = &mut *(baton as *mut E);
+    let children = children_from(children).unwrap_or_default();
+    let children = children.iter().map(String::as_str).collect::<Vec<_>>();
+    into_raw_result(editor.add_directory(
+        str_from(relpath),
+        &children,
+        &props_from(props).unwrap_or_default(),
+        Revnum::from_raw(replaces_rev),
+    ))
+}
+
+unsafe extern "C" fn rust_ev2_add_file<E: Editor>(
+    baton: *mut c_void,
+    relpath: *const c_char,
+    checksum: *const subversion_sys::svn_checksum_t,
+    contents: *mut subversion_sys::svn_stream_t,
+    props: *mut apr_sys::apr_hash_t,
+    replaces_rev: subversion_sys::svn_revnum_t,
+    _scratch_pool: *mut apr_sys::apr_pool_t,
+) -> *mut subversion_sys::svn_error_t {
+    let editor = &mut *(baton as *mut E);
+    let mut contents = crate::io::Stream::from_ptr(contents, apr::Pool::new());
+    into_raw_result(editor.add_file(
+        str_from(relpath),
+        &Checksum::from_raw(checksum),
+        &mut contents,
+        &props_from(props).unwrap_or_default(),
+        Revnum::from_raw(replaces_rev),
+    ))
+}
+
+unsafe extern "C" fn rust_ev2_add_symlink<E: Editor>(
+    baton: *mut c_void,
+    relpath: *const c_char,
+    target: *const c_char,
+    props: *mut apr_sys::apr_hash_t,
+    replaces_rev: subversion_sys::svn_revnum_t,
+    _scratch_pool: *mut apr_sys::apr_pool_t,
+) -> *mut subversion_sys::svn_error_t {
+    let editor = &mut *(baton as *mut E);
+    into_raw_result(editor.add_symlink(
+        str_from(relpath),
+        str_from(target),
+        &props_from(props).unwrap_or_default(),
+        Revnum::from_raw(replaces_rev),
+    ))
+}
+
+unsafe extern "C" fn rust_ev2_add_absent<E: Editor>(
+    baton: *mut c_void,
+    relpath: *const c_char,
+    kind: subversion_sys::svn_node_kind_t,
+    replaces_rev: subversion_sys::svn_revnum_t,
+    _scratch_pool: *mut apr_sys::apr_pool_t,
+) -> *mut subversion_sys::svn_error_t {
+    let editor = &mut *(baton as *mut E);
+    into_raw_result(editor.add_absent(
+        str_from(relpath),
+        kind.into(),
+        Revnum::from_raw(replaces_rev),
+    ))
+}
+
+unsafe extern "C" fn rust_ev2_alter_directory<E: Editor>(
+    baton: *mut c_void,
+    relpath: *const c_char,
+    revision: subversion_sys::svn_revnum_t,
+    children: *const apr_sys::apr_array_header_t,
+    props: *mut apr_sys::apr_hash_t,
+    _scratch_pool: *mut apr_sys::apr_pool_t,
+) -> *mut subversion_sys::svn_error_t {
+    let editor = &mut *(baton as *mut E);
+    let children = children_from(children);
+    let children = children
+        .as_ref()
+        .map(|c| c.iter().map(String::as_str).collect::<Vec<_>>());
+    into_raw_result(editor.alter_directory(
+        str_from(relpath),
+        Revnum::from_raw(revision),
+        children.as_deref(),
+        props_from(props).as_ref(),
+    ))
+}
+
+unsafe extern "C" fn rust_ev2_alter_file<E: Editor>(
+    baton: *mut c_void,
+    relpath: *const c_char,
+    revision: subversion_sys::svn_revnum_t,
+    checksum: *const subversion_sys::svn_checksum_t,
+    contents: *mut subversion_sys::svn_stream_t,
+    props: *mut apr_sys::apr_hash_t,
+    _scratch_pool: *mut apr_sys::apr_pool_t,
+) -> *mut subversion_sys::svn_error_t {
+    let editor = &mut *(baton as *mut E);
+    let checksum = Checksum::from_raw(checksum);
+    let mut stream = crate::io::Stream::from_ptr(contents, apr::Pool::new());
+    let contents = if contents.is_null() {
+        None
+    } else {
+        Some((&checksum, &mut stream))
+    };
+    into_raw_result(editor.alter_file(
+        str_from(relpath),
+        Revnum::from_raw(revision),
+        contents,
+        props_from(props).as_ref(),
+    ))
+}
+
+unsafe extern "C" fn rust_ev2_alter_symlink<E: Editor>(
+    baton: *mut c_void,
+    relpath: *const c_char,
+    revision: subversion_sys::svn_revnum_t,
+    target: *const c_char,
+    props: *mut apr_sys::apr_hash_t,
+    _scratch_pool: *mut apr_sys::apr_pool_t,
+) -> *mut subversion_sys::svn_error_t {
+    let editor = &mut *(baton as *mut E);
+    let target = if target.is_null() {
+        None
+    } else {
+        Some(str_from(target))
+    };
+    into_raw_result(editor.alter_symlink(
+        str_from(relpath),
+        Revnum::from_raw(revision),
+        target,
+        props_from(props).as_ref(),
+    ))
+}
+
+unsafe extern "C" fn rust_ev2_delete<E: Editor>(
+    baton: *mut c_void,
+    relpath: *const c_char,
+    revision: subversion_sys::svn_revnum_t,
+    _scratch_pool: *mut apr_sys::apr_pool_t,
+) -> *mut subversion_sys::svn_error_t {
+    let editor = &mut *(baton as *mut E);
+    into_raw_result(editor.delete(str_from(relpath), Revnum::from_raw(revision)))
+}
+
+unsafe extern "C" fn rust_ev2_copy<E: Editor>(
+    baton: *mut c_void,
+    src_relpath: *const c_char,
+    src_revision: subversion_sys::svn_revnum_t,
+    dst_relpath: *const c_char,
+    replaces_rev: subversion_sys::svn_revnum_t,
+    _scratch_pool: *mut apr_sys::apr_pool_t,
+) -> *mut subversion_sys::svn_error_t {
+    let editor = &mut *(baton as *mut E);
+    into_raw_result(editor.copy(
+        str_from(src_relpath),
+        Revnum(src_revision),
+        str_from(dst_relpath),
+        Revnum::from_raw(replaces_rev),
+    ))
+}
+
+unsafe extern "C" fn rust_ev2_move<E: Editor>(
+    baton: *mut c_void,
+    src_relpath: *const c_char,
+    src_revision: subversion_sys::svn_revnum_t,
+    dst_relpath: *const c_char,
+    replaces_rev: subversion_sys::svn_revnum_t,
+    _scratch_pool: *mut apr_sys::apr_pool_t,
+) -> *mut subversion_sys::svn_error_t {
+    let editor = &mut *(baton as *mut E);
+    into_raw_result(editor.move_node(
+        str_from(src_relpath),
+        Revnum(src_revision),
+        str_from(dst_relpath),
+        Revnum::from_raw(replaces_rev),
+    ))
+}
+
+unsafe extern "C" fn rust_ev2_complete<E: Editor>(
+    baton: *mut c_void,
+    _scratch_pool: *mut apr_sys::apr_pool_t,
+) -> *mut subversion_sys::svn_error_t {
+    let editor = &mut *(baton as *mut E);
+    into_raw_result(editor.complete())
+}
+
+unsafe extern "C" fn rust_ev2_abort<E: Editor>(
+    baton: *mut c_void,
+    _scratch_pool: *mut apr_sys::apr_pool_t,
+) -> *mut subversion_sys::svn_error_t {
+    let editor = &mut *(baton as *mut E);
+    into_raw_result(editor.abort())
+}
+
+unsafe extern "C" fn shim_fetch_kind(
+    kind: *mut subversion_sys::svn_node_kind_t,
+    baton: *mut c_void,
+    path: *const c_char,
+    base_revision: subversion_sys::svn_revnum_t,
+    _scratch_pool: *mut apr_sys::apr_pool_t,
+) -> *mut subversion_sys::svn_error_t {
+    let callbacks = &mut *(baton as *mut Box<dyn ShimCallbacks>);
+    match callbacks.fetch_kind(str_from(path), Revnum::from_raw(base_revision)) {
+        Ok(k) => {
+            *kind = k.into();
+            std::ptr::null_mut()
+        }
+        Err(e) => e.into_raw(),
+    }
+}
+
+unsafe extern "C" fn shim_fetch_props(
+    props: *mut *mut apr_sys::apr_hash_t,
+    baton: *mut c_void,
+    path: *const c_char,
+    base_revision: subversion_sys::svn_revnum_t,
+    result_pool: *mut apr_sys::apr_pool_t,
+    _scratch_pool: *mut apr_sys::apr_pool_t,
+) -> *mut subversion_sys::svn_error_t {
+    let callbacks = &mut *(baton as *mut Box<dyn ShimCallbacks>);
+    match callbacks.fetch_props(str_from(path), Revnum::from_raw(base_revision)) {
+        Ok(p) => {
+            let pool = apr::PoolHandle::from_borrowed_raw(result_pool);
+            *props = props_to_hash(&p, &pool);
+            std::ptr::null_mut()
+        }
+        Err(e) => e.into_raw(),
+    }
+}
+
+unsafe extern "C" fn shim_fetch_base(
+    filename: *mut *const c_char,
+    baton: *mut c_void,
+    path: *const c_char,
+    base_revision: subversion_sys::svn_revnum_t,
+    result_pool: *mut apr_sys::apr_pool_t,
+    _scratch_pool: *mut apr_sys::apr_pool_t,
+) -> *mut subversion_sys::svn_error_t {
+    let callbacks = &mut *(baton as *mut Box<dyn ShimCallbacks>);
+    match callbacks.fetch_base(str_from(path), Revnum::from_raw(base_revision)) {
+        Ok(Some(contents)) => subversion_sys::svn_io_write_unique(
+            filename,
+            std::ptr::null(),
+            contents.as_ptr() as *const c_void,
+            contents.len(),
+            subversion_sys::svn_io_file_del_t_svn_io_file_del_on_pool_cleanup,
+            result_pool,
+        ),
+        Ok(None) => {
+            *filename = std::ptr::null();
+            std::ptr::null_mut()
+        }
+        Err(e) => e.into_raw(),
+    }
+}
+
+unsafe extern "C" fn shim_no_unlock(
+    _baton: *mut c_void,
+    _path: *const c_char,
+    _scratch_pool: *mut apr_sys::apr_pool_t,
+) -> *mut subversion_sys::svn_error_t {
+    std::ptr::null_mut()
+}
+
+/// Box `callbacks` so it can be passed as a C baton, returning the baton and
+/// a dropper for it.
+fn callbacks_baton(callbacks: impl ShimCallbacks + 'static) -> (*mut c_void, DropperFn) {
+    let boxed: Box<Box<dyn ShimCallbacks>> = Box::new(Box::new(callbacks));
+    let dropper: DropperFn = |ptr| unsafe {
+        let _ = Box::from_raw(ptr as *mut Box<dyn ShimCallbacks>);
+    };
+    (Box::into_raw(boxed) as *mut c_void, dropper)
+}
+
+fn pstrdup(s: &str, pool: &apr::Pool) -> Result<*const c_char, Error<'static>> {
+    let s = CString::new(s)?;
+    Ok(unsafe { apr_sys::apr_pstrdup(pool.as_mut_ptr(), s.as_ptr()) })
+}
+
+/// Wrapper for a C Ev2 editor.
+pub struct WrapEditor {
+    ptr: *mut subversion_sys::svn_editor_t,
+    // Set for editors created by `editor_from_delta`, so that they can be
+    // chained with `delta_from_editor`.
+    extra: *mut subversion_sys::svn_delta__extra_baton,
+    unlock_func: subversion_sys::svn_delta__unlock_func_t,
+    unlock_baton: *mut c_void,
+    abs_paths: *mut subversion_sys::svn_boolean_t,
+    callback_batons: Vec<(*mut c_void, DropperFn)>,
+    pool: apr::Pool<'static>,
+}
+unsafe impl Send for WrapEditor {}
+
+impl Drop for WrapEditor {
+    fn drop(&mut self) {
+        for (baton, dropper) in &self.callback_batons {
+            if !baton.is_null() {
+                unsafe {
+                    dropper(*baton);
+                }
+            }
+        }
+    }
+}
+
+impl WrapEditor {
+    /// Create a C Ev2 editor that forwards to a Rust [`Editor`].
+    pub fn from_rust_editor<E: Editor + 'static>(editor: E) -> Result<Self, Error<'static>> {
+        let pool = apr::Pool::new();
+        let baton = Box::into_raw(Box::new(editor)) as *mut c_void;
+        let dropper: DropperFn = |ptr| unsafe {
+            let _ = Box::from_raw(ptr as *mut E);
+        };
+        let mut result = Self {
+            ptr: std::ptr::null_mut(),
+            extra: std::ptr::null_mut(),
+            unlock_func: None,
+            unlock_baton: std::ptr::null_mut(),
+            abs_paths: std::ptr::null_mut(),
+            callback_batons: vec![(baton, dropper)],
+            pool,
+        };
+        let callbacks = subversion_sys::svn_editor_cb_many_t {
+            cb_add_directory: Some(rust_ev2_add_directory::<E>),
+            cb_add_file: Some(rust_ev2_add_file::<E>),
+            cb_add_symlink: Some(rust_ev2_add_symlink::<E>),
+            cb_add_absent: Some(rust_ev2_add_absent::<E>),
+            cb_alter_directory: Some(rust_ev2_alter_directory::<E>),
+            cb_alter_file: Some(rust_ev2_alter_file::<E>),
+            cb_alter_symlink: Some(rust_ev2_alter_symlink::<E>),
+            cb_delete: Some(rust_ev2_delete::<E>),
+            cb_copy: Some(rust_ev2_copy::<E>),
+            cb_move: Some(rust_ev2_move::<E>),
+            cb_complete: Some(rust_ev2_complete::<E>),
+            cb_abort: Some(rust_ev2_abort::<E>),
+        };
+        let scratch_pool = apr::Pool::new();
+        unsafe {
+            Error::from_raw(subversion_sys::svn_editor_create(
+                &mut result.ptr,
+                baton,
+                None,
+                std::ptr::null_mut(),
+                result.pool.as_mut_ptr(),
+                scratch_pool.as_mut_ptr(),
+            ))?;
+            Error::from_raw(subversion_sys::svn_editor_setcb_many(
+                result.ptr,
+                &callbacks,
+                scratch_pool.as_mut_ptr(),
+            ))?;
+        }
+        Ok(result)
+    }
+
+    /// Open the edit with the given base revision.
+    ///
+    /// Ev2 itself has no notion of this, but editors returned by
+    /// [`editor_from_delta`] need it before any other call, to open the
+    /// root of the delta editor.  For other editors this does nothing.
+    pub fn start_edit(&mut self, base_revision: Option<Revnum>) -> Result<(), Error<'static>> {
+        unsafe {
+            if let Some(start_edit) = self.extra.as_ref().and_then(|exb| exb.start_edit) {
+                Error::from_raw(start_edit((*self.extra).baton, raw_revnum(base_revision)))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Set the target revision of the edit.
+    ///
+    /// Like [`start_edit`](Self::start_edit), this is only forwarded by
+    /// editors returned by [`editor_from_delta`].
+    pub fn set_target_revision(&mut self, revision: Option<Revnum>) -> Result<(), Error<'static>> {
+        let scratch_pool = apr::Pool::new();
+        unsafe {
+            if let Some(target_revision) = self.extra.as_ref().and_then(|exb| exb.target_revision) {
+                Error::from_raw(target_revision(
+                    (*self.extra).baton,
+                    raw_revnum(revision),
+                    scratch_pool.as_mut_ptr(),
+                ))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the raw pointer to the editor.
+    pub fn as_mut_ptr(&mut self) -> *mut c_void {
+        self.ptr as *mut c_void
+    }
+}
+
+impl Editor for WrapEditor {
+    fn add_directory(
+        &mut self,
+        relpath: &str,
+        children: &[&str],
+        props: &HashMap<String, Vec<u8>>,
+        replaces_rev: Option<Revnum>,
+    ) -> Result<(), Error<'static>> {
+        let pool = apr::Pool::new();
+        let relpath = CString::new(relpath)?;
+        let children = children_to_array(children, &pool)?;
+        unsafe {
+            Error::from_raw(subversion_sys::svn_editor_add_directory(
+                self.ptr,
+                relpath.as_ptr(),
+                children,
+                props_to_hash(props, &pool),
+                raw_revnum(replaces_rev),
+            ))
+        }
+    }
+
+    fn add_file(
+        &mut self,
+        relpath: &str,
+        checksum: &Checksum,
+        contents: &mut crate::io::Stream,
+        props: &HashMap<String, Vec<u8>>,
+        replaces_rev: Option<Revnum>,
+    ) -> Result<(), Error<'static>> {
+        let pool = apr::Pool::new();
+        let relpath = CString::new(relpath)?;
+        unsafe {
+            Error::from_raw(subversion_sys::svn_editor_add_file(
+                self.ptr,
+                relpath.as_ptr(),
+                checksum.ptr,
+                contents.as_mut_ptr(),
+                props_to_hash(props, &pool),
+                raw_revnum(replaces_rev),
+            ))
+        }
+    }
+
+    fn add_symlink(
+        &mut self,
+        relpath: &str,
+        target: &str,
+        props: &HashMap<String, Vec<u8>>,
+        replaces_rev: Option<Revnum>,
+    ) -> Result<(), Error<'static>> {
+        let pool = apr::Pool::new();
+        let relpath = CString::new(relpath)?;
+        let target = CString::new(target)?;
+        unsafe {
+            Error::from_raw(subversion_sys::svn_editor_add_symlink(
+                self.ptr,
+                relpath.as_ptr(),
+                target.as_ptr(),
+                props_to_hash(props, &pool),
+                raw_revnum(replaces_rev),
+            ))
+        }
+    }
+
+    fn add_absent(
+        &mut self,
+        relpath: &str,
+        kind: NodeKind,
+        replaces_rev: Option<Revnum>,
+    ) -> Result<(), Error<'static>> {
+        let relpath = CString::new(relpath)?;
+        unsafe {
+            Error::from_raw(subversion_sys::svn_editor_add_absent(
+                self.ptr,
+                relpath.as_ptr(),
+                kind.into(),
+                raw_revnum(replaces_rev),
+            ))
+        }
+    }
+
+    fn alter_directory(
+        &mut self,
+        relpath: &str,
+        revision: Option<Revnum>,
+        children: Option<&[&str]>,
+        props: Option<&HashMap<String, Vec<u8>>>,
+    ) -> Result<(), Error<'static>> {
+        let pool = apr::Pool::new();
+        let relpath = CString::new(relpath)?;
+        let children = match children {
+            Some(children) => children_to_array(children, &pool)?,
+            None => std::ptr::null(),
+        };
+        unsafe {
+            Error::from_raw(subversion_sys::svn_editor_alter_directory(
+                self.ptr,
+                relpath.as_ptr(),
+                raw_revnum(revision),
+                children,
+                props.map_or(std::ptr::null_mut(), |p| props_to_hash(p, &pool)),
+            ))
+        }
+    }
+
+    fn alter_file(
+        &mut self,
+        relpath: &str,
+        revision: Option<Revnum>,
+        contents: Option<(&Checksum, &mut crate::io::Stream)>,
+        props: Option<&HashMap<String, Vec<u8>>>,
+    ) -> Result<(), Error<'static>> {
+        let pool = apr::Pool::new();
+        let relpath = CString::new(relpath)?;
+        let (checksum, contents) = match contents {
+            Some((checksum, contents)) => (checksum.ptr, contents.as_mut_ptr()),
+            None => (std::ptr::null(), std::ptr::null_mut()),
+        };
+        unsafe {
+            Error::from_raw(subversion_sys::svn_editor_alter_file(
+                self.ptr,
+                relpath.as_ptr(),
+                raw_revnum(revision),
+                checksum,
+                contents,
+                props.map_or(std::ptr::null_mut(), |p| props_to_hash(p, &pool)),
+            ))
+        }
+    }
+
+    fn alter_symlink(
+        &mut self,
+        relpath: &str,
+        revision: Option<Revnum>,
+        target: Option<&str>,
+        props: Option<&HashMap<String, Vec<u8>>>,
+    ) -> Result<(), Error<'static>> {
+        let pool = apr::Pool::new();
+        let relpath = CString::new(relpath)?;
+        let target = target.map(CString::new).transpose()?;
+        unsafe {
+            Error::from_raw(subversion_sys::svn_editor_alter_symlink(
+                self.ptr,
+                relpath.as_ptr(),
+                raw_revnum(revision),
+                target.as_ref().map_or(std::ptr::null(), |t| t.as_ptr()),
+                props.map_or(std::ptr::null_mut(), |p| props_to_hash(p, &pool)),
+            ))
+        }
+    }
+
+    fn delete(&mut self, relpath: &str, revision: Option<Revnum>) -> Result<(), Error<'static>> {
+        let relpath = CString::new(relpath)?;
+        unsafe {
+            Error::from_raw(subversion_sys::svn_editor_delete(
+                self.ptr,
+                relpath.as_ptr(),
+                raw_revnum(revision),
+            ))
+        }
+    }
+
+    fn copy(
+        &mut self,
+        src_relpath: &str,
+        src_revision: Revnum,
+        dst_relpath: &str,
+        replaces_rev: Option<Revnum>,
+    ) -> Result<(), Error<'static>> {
+        let src_relpath = CString::new(src_relpath)?;
+        let dst_relpath = CString::new(dst_relpath)?;
+        unsafe {
+            Error::from_raw(subversion_sys::svn_editor_copy(
+                self.ptr,
+                src_relpath.as_ptr(),
+                src_revision.into(),
+                dst_relpath.as_ptr(),
+                raw_revnum(replaces_rev),
+            ))
+        }
+    }
+
+    fn move_node(
+        &mut self,
+        src_relpath: &str,
+        src_revision: Revnum,
+        dst_relpath: &str,
+        replaces_rev: Option<Revnum>,
+    ) -> Result<(), Error<'static>> {
+        let src_relpath = CString::new(src_relpath)?;
+        let dst_relpath = CString::new(dst_relpath)?;
+        unsafe {
+            Error::from_raw(subversion_sys::svn_editor_move(
+                self.ptr,
+                src_relpath.as_ptr(),
+                src_revision.into(),
+                dst_relpath.as_ptr(),
+                raw_revnum(replaces_rev),
+            ))
+        }
+    }
+
+    fn complete(&mut self) -> Result<(), Error<'static>> {
+        unsafe { Error::from_raw(subversion_sys::svn_editor_complete(self.ptr)) }
+    }
+
+    fn abort(&mut self) -> Result<(), Error<'static>> {
+        unsafe { Error::from_raw(subversion_sys::svn_editor_abort(self.ptr)) }
+    }
+}
+
+/// Create an Ev2 editor that drives the delta editor `editor`.
+///
+/// `repos_root` is the URL of the repository root and `base_relpath` the
+/// path of the delta editor's root relative to it.  Call
+/// [`WrapEditor::start_edit`] before any other operation on the result.
+///
+/// Wraps `svn_delta__editor_from_delta`.
+pub fn editor_from_delta(
+    editor: crate::delta::WrapEditor<'static>,
+    repos_root: &str,
+    base_relpath: &str,
+    callbacks: impl ShimCallbacks + 'static,
+) -> Result<WrapEditor, Error<'static>> {
+    let pool = apr::Pool::new();
+    let scratch_pool = apr::Pool::new();
+    let (callbacks, callbacks_dropper) = callbacks_baton(callbacks);
+    let delta_editor = Box::into_raw(Box::new(editor));
+    let delta_dropper: DropperFn = |ptr| unsafe {
+        let _ = Box::from_raw(ptr as *mut crate::delta::WrapEditor<'static>);
+    };
+    let mut result = WrapEditor {
+        ptr: std::ptr::null_mut(),
+        extra: std::ptr::null_mut(),
+        unlock_func: None,
+        unlock_baton: std::ptr::null_mut(),
+        abs_paths: pool.calloc::<subversion_sys::svn_boolean_t>(),
+        callback_batons: vec![
+            (delta_editor as *mut c_void, delta_dropper),
+            (callbacks, callbacks_dropper),
+        ],
+        pool,
+    };
+    let repos_root = pstrdup(repos_root, &result.pool)?;
+    let base_relpath = pstrdup(base_relpath, &result.pool)?;
+    unsafe {
+        Error::from_raw(subversion_sys::svn_delta__editor_from_delta(
+            &mut result.ptr,
+            &mut result.extra,
+            &mut result.unlock_func,
+            &mut result.unlock_baton,
+            (*delta_editor).editor,
+            (*delta_editor).baton,
+            result.abs_paths,
+            repos_root,
+            base_relpath,
+            None,
+            std::ptr::null_mut(),
+            Some(shim_fetch_kind),
+            callbacks,
+            Some(shim_fetch_props),
+            callbacks,
+            result.pool.as_mut_ptr(),
+            scratch_pool.as_mut_ptr(),
+        ))?;
+    }
+    Ok(result)
+}
+
+/// Create a delta editor that drives the Ev2 editor `editor`.
+///
+/// The delta drive is collected and sent to `editor` as Ev2 operations when
+/// the edit is closed.  If `editor` was returned by [`editor_from_delta`],
+/// the base and target revisions of the delta drive are passed through.
+///
+/// Wraps `svn_delta__delta_from_editor`.
+pub fn delta_from_editor(
+    editor: WrapEditor,
+    repos_root: &str,
+    base_relpath: &str,
+    callbacks: impl ShimCallbacks + 'static,
+) -> Result<crate::delta::WrapEditor<'static>, Error<'static>> {
+    let pool = apr::Pool::new();
+    let (callbacks, callbacks_dropper) = callbacks_baton(callbacks);
+    let repos_root = pstrdup(repos_root, &pool)?;
+    let base_relpath = pstrdup(base_relpath, &pool)?;
+
+    let extra = if editor.extra.is_null() {
+        pool.calloc::<subversion_sys::svn_delta__extra_baton>()
+    } else {
+        editor.extra
+    };
+    let (unlock_func, unlock_baton) = match editor.unlock_func {
+        Some(_) => (editor.unlock_func, editor.unlock_baton),
+        None => (
+            Some(shim_no_unlock as unsafe extern "C" fn(_, _, _) -> _),
+            std::ptr::null_mut(),
+        ),
+    };
+    let abs_paths = if editor.abs_paths.is_null() {
+        pool.calloc::<subversion_sys::svn_boolean_t>()
+    } else {
+        editor.abs_paths
+    };
+
+    let ev2_editor = Box::into_raw(Box::new(editor));
+    let ev2_dropper: DropperFn = |ptr| unsafe {
+        let _ = Box::from_raw(ptr as *mut WrapEditor);
+    };
+
+    let mut deditor: *const subversion_sys::svn_delta_editor_t = std::ptr::null();
+    let mut dedit_baton: *mut c_void = std::ptr::null_mut();
+    let err = unsafe {
+        subversion_sys::svn_delta__delta_from_editor(
+            &mut deditor,
+            &mut dedit_baton,
+            (*ev2_editor).ptr,
+            unlock_func,
+            unlock_baton,
+            abs_paths,
+            repos_root,
+            base_relpath,
+            Some(shim_fetch_props),
+            callbacks,
+            Some(shim_fetch_base),
+            callbacks,
+            extra,
+            pool.as_mut_ptr(),
+        )
+    };
+    let result = crate::delta::WrapEditor {
+        editor: deditor,
+        baton: dedit_baton,
+        _pool: apr::PoolHandle::owned(pool),
+        callback_batons: vec![
+            (ev2_editor as *mut c_void, ev2_dropper),
+            (callbacks, callbacks_dropper),
+        ],
+    };
+    Error::from_raw(err)?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::delta::record::{EditorEvent, RecordingEditor};
+    use crate::delta::{DirectoryEditor as _, Editor as _, FileEditor as _, TxDeltaWindow};
+    use std::sync::{Arc, Mutex};
+
+    /// An Ev2 editor that records calls as strings.
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl Recorder {
+        fn push(&self, line: String) -> Result<(), Error<'static>> {
+            self.0.lock().unwrap().push(line);
+            Ok(())
+        }
+    }
+
+    fn read_all(stream: &mut crate::io::Stream) -> String {
+        let mut contents = String::new();
+        std::io::Read::read_to_string(stream, &mut contents).unwrap();
+        contents
+    }
+
+    fn sorted_keys(props: &HashMap<String, Vec<u8>>) -> Vec<&str> {
+        let mut keys = props.keys().map(String::as_str).collect::<Vec<_>>();
+        keys.sort();
+        keys
+    }
+
+    impl Editor for Recorder {
+        fn add_directory(
+            &mut self,
+            relpath: &str,
+            children: &[&str],
+            props: &HashMap<String, Vec<u8>>,
+            _replaces_rev: Option<Revnum>,
+        ) -> Result<(), Error<'static>> {
+            let mut children = children.to_vec();
+            children.sort();
+            self.push(format!(
+                "add_directory {} {:?} {:?}",
+                relpath,
+                children,
+                sorted_keys(props)
+            ))
+        }
+
+        fn add_file(
+            &mut self,
+            relpath: &str,
+            _checksum: &Checksum,
+            contents: &mut crate::io::Stream,
+            props: &HashMap<String, Vec<u8>>,
+            _replaces_rev: Option<Revnum>,
+        ) -> Result<(), Error<'static>> {
+            self.push(format!(
+                "add_file {} {:?} {:?}",
+                relpath,
+                read_all(contents),
+                sorted_keys(props)
+            ))
+        }
+
+        fn add_symlink(
+            &mut self,
+            relpath: &str,
+            target: &str,
+            _props: &HashMap<String, Vec<u8>>,
+            _replaces_rev: Option<Revnum>,
+        ) -> Result<(), Error<'static>> {
+            self.push(format!("add_symlink {} {}", relpath, target))
+        }
+
+        fn add_absent(
+            &mut self,
+            relpath: &str,
+            kind: NodeKind,
+            _replaces_rev: Option<Revnum>,
+        ) -> Result<(), Error<'static>> {
+            self.push(format!("add_absent {} {:?}", relpath, kind))
+        }
+
+        fn alter_directory(
+            &mut self,
+            relpath: &str,
+            _revision: Option<Revnum>,
+            children: Option<&[&str]>,
+            _props: Option<&HashMap<String, Vec<u8>>>,
+        ) -> Result<(), Error<'static>> {
+            self.push(format!("alter_directory {} {:?}", relpath, children))
+        }
+
+        fn alter_file(
+            &mut self,
+            relpath: &str,
+            _revision: Option<Revnum>,
+            contents: Option<(&Checksum, &mut crate::io::Stream)>,
+            _props: Option<&HashMap<String, Vec<u8>>>,
+        ) -> Result<(), Error<'static>> {
+            let contents = contents.map(|(_, stream)| read_all(stream));
+            self.push(format!("alter_file {} {:?}", relpath, contents))
+        }
+
+        fn alter_symlink(
+            &mut self,
+            relpath: &str,
+            _revision: Option<Revnum>,
+            target: Option<&str>,
+            _props: Option<&HashMap<String, Vec<u8>>>,
+        ) -> Result<(), Error<'static>> {
+            self.push(format!("alter_symlink {} {:?}", relpath, target))
+        }
+
+        fn delete(
+            &mut self,
+            relpath: &str,
+            revision: Option<Revnum>,
+        ) -> Result<(), Error<'static>> {
+            self.push(format!("delete {} {:?}", relpath, revision))
+        }
+
+        fn copy(
+            &mut self,
+            src_relpath: &str,
+            src_revision: Revnum,
+            dst_relpath: &str,
+            _replaces_rev: Option<Revnum>,
+        ) -> Result<(), Error<'static>> {
+            self.push(format!(
+                "copy {}@{} {}",
+                src_relpath,
+                src_revision.as_i64(),
+                dst_relpath
+            ))
+        }
+
+        fn move_node(
+            &mut self,
+            src_relpath: &str,
+            src_revision: Revnum,
+            dst_relpath: &str,
+            _replaces_rev: Option<Revnum>,
+        ) -> Result<(), Error<'static>> {
+            self.push(format!(
+                "move {}@{} {}",
+                src_relpath,
+                src_revision.as_i64(),
+                dst_relpath
+            ))
+        }
+
+        fn complete(&mut self) -> Result<(), Error<'static>> {
+            self.push("complete".to_string())
+        }
+
+        fn abort(&mut self) -> Result<(), Error<'static>> {
+            self.push("abort".to_string())
+        }
+    }
+
+    fn recorder() -> (Recorder, Arc<Mutex<Vec<String>>>) {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        (Recorder(calls.clone()), calls)
+    }
+
+    #[test]
+    fn test_from_rust_editor() {
+        let (editor, calls) = recorder();
+        let mut editor = WrapEditor::from_rust_editor(editor).unwrap();
+
+        let mut props = HashMap::new();
+        props.insert("svn:ignore".to_string(), b"*.o\n".to_vec());
+        editor
+            .add_directory("trunk", &["a.txt"], &props, None)
+            .unwrap();
+        let pool = apr::Pool::new();
+        let checksum = crate::checksum(crate::ChecksumKind::SHA1, b"hello\n", &pool).unwrap();
+        editor
+            .add_file(
+                "trunk/a.txt",
+                &checksum,
+                &mut crate::io::Stream::from(&b"hello\n"[..]),
+                &HashMap::new(),
+                None,
+            )
+            .unwrap();
+        editor.move_node("old", Revnum(1), "new", None).unwrap();
+        editor.delete("gone", Some(Revnum(1))).unwrap();
+        editor.complete().unwrap();
+
+        assert_eq!(
+            *calls.lock().unwrap(),
+            [
+                "add_directory trunk [\"a.txt\"] [\"svn:ignore\"]",
+                "add_file trunk/a.txt \"hello\\n\" []",
+                "move old@1 new",
+                "delete gone Some(Revnum(1))",
+                "complete",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_delta_from_editor() {
+        let (editor, calls) = recorder();
+        let editor = WrapEditor::from_rust_editor(editor).unwrap();
+        let mut deditor = delta_from_editor(editor, "file:///repo", "", NoShimCallbacks).unwrap();
+
+        let mut root = deditor.open_root(Some(Revnum(0))).unwrap();
+        let mut trunk = root.add_directory("trunk", None).unwrap();
+        let mut file = trunk.add_file("trunk/a.txt", None).unwrap();
+        let handler = file.apply_textdelta(None).unwrap();
+        handler(&mut TxDeltaWindow::from_parts(
+            0,
+            0,
+            6,
+            0,
+            &[(2, 0, 6)],
+            b"hello\n",
+        ))
+        .unwrap();
+        handler(&mut crate::delta::record::end_of_delta()).unwrap();
+        file.close(None).unwrap();
+        trunk.close().unwrap();
+        root.close().unwrap();
+        deditor.close().unwrap();
+
+        let calls = calls.lock().unwrap();
+        assert!(calls.contains(&"add_directory trunk [\"a.txt\"] []".to_string()));
+        assert!(calls.contains(&"add_file trunk/a.txt \"hello\\n\" []".to_string()));
+        assert_eq!(calls.last().unwrap(), "complete");
+    }
+
+    #[test]
+    fn test_editor_from_delta() {
+        let recorder = RecordingEditor::new();
+        let recording = recorder.recording();
+        let deditor = crate::delta::WrapEditor::from_rust_editor(recorder);
+        let mut editor = editor_from_delta(deditor, "file:///repo", "", NoShimCallbacks).unwrap();
+
+        editor.start_edit(Some(Revnum(0))).unwrap();
+        editor
+            .add_directory("trunk", &[], &HashMap::new(), None)
+            .unwrap();
+        editor.complete().unwrap();
+
+        let trace = recording.trace();
+        let events = trace.events();
+        assert!(matches!(events[0], EditorEvent::OpenRoot { .. }));
+        assert!(events.iter().any(|e| matches!(
+            e,
+            EditorEvent::AddDirectory { path, copyfrom: None, .. } if path == "trunk"
+        )));
+        assert_eq!(events.last(), Some(&EditorEvent::CloseEdit));
+    }
+
+    #[test]
+    fn test_delta_from_editor_copy() {
+        let (editor, calls) = recorder();
+        let editor = WrapEditor::from_rust_editor(editor).unwrap();
+        let mut deditor = delta_from_editor(editor, "file:///repo", "", NoShimCallbacks).unwrap();
+
+        let mut root = deditor.open_root(Some(Revnum(1))).unwrap();
+        let mut branch = root
+            .add_directory("branch", Some(("file:///repo/trunk", Revnum(1))))
+            .unwrap();
+        branch.close().unwrap();
+        root.close().unwrap();
+        deditor.close().unwrap();
+
+        let calls = calls.lock().unwrap();
+        assert!(calls.contains(&"copy trunk@1 branch".to_string()));
+        assert!(!calls.iter().any(|call| call.starts_with("add_directory")));
+        assert_eq!(calls.last().unwrap(), "complete");
+    }
+
+    /// Shim callbacks for a repository that only contains files.
+    struct OnlyFiles;
+
+    impl ShimCallbacks for OnlyFiles {
+        fn fetch_kind(
+            &mut self,
+            _path: &str,
+            _base_revision: Option<Revnum>,
+        ) -> Result<NodeKind, Error<'static>> {
+            Ok(NodeKind::File)
+        }
+    }
+
+    #[test]
+    fn test_editor_from_delta_copy_and_move() {
+        let recorder = RecordingEditor::new();
+        let recording = recorder.recording();
+        let deditor = crate::delta::WrapEditor::from_rust_editor(recorder);
+        let mut editor = editor_from_delta(deditor, "file:///repo", "", OnlyFiles).unwrap();
+
+        editor.start_edit(Some(Revnum(1))).unwrap();
+        editor.copy("a.txt", Revnum(1), "b.txt", None).unwrap();
+        editor.move_node("c.txt", Revnum(1), "d.txt", None).unwrap();
+        editor.complete().unwrap();
+
+        let trace = recording.trace();
+        let events = trace.events();
+        let added = |name: &str, source: &str| {
+            events.iter().any(|e| {
+                matches!(
+                    e,
+                    EditorEvent::AddFile { path, copyfrom: Some((from, Revnum(1))), .. }
+                        if path == name && from.ends_with(source)
+                )
+            })
+        };
+        // The delta editor has no moves: they become a delete and a copy.
+        assert!(added("b.txt", "a.txt"));
+        assert!(added("d.txt", "c.txt"));
+        assert!(events.iter().any(|e| matches!(
+            e,
+            EditorEvent::DeleteEntry { path, .. } if path == "c.txt"
+        )));
+        assert!(!events.iter().any(|e| matches!(
+            e,
+            EditorEvent::DeleteEntry { path, .. } if path == "a.txt"
+        )));
+        assert_eq!(events.last(), Some(&EditorEvent::CloseEdit));
+    }
+}
@@ -0,0 +1,1191 @@
+//! Recording and replaying editor drives.
+//!
+//! A [`RecordingEditor`] captures every call made on it, text delta windows
+//! included, into an [`EditorTrace`].  The trace is plain owned data: it can
+//! be stored with [`EditorTrace::to_bytes`], loaded again with
+//! [`EditorTrace::from_bytes`] and replayed into any editor with
+//! [`EditorTrace::replay`].  This makes it possible to capture a drive from a
+//! real repository once and test custom editors against it without one.
+//!
+//! To record a drive while it is also being handled, combine the recorder
+//! with the real editor using [`TeeEditor`](super::combinators::TeeEditor).
+
+use super::window::{DeltaOp, DeltaOpKind, Window};
+use super::{DirectoryEditor, Editor, FileEditor, TxDeltaWindow};
+use crate::{Error, Revnum};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+
+type WindowHandler =
+    Box<dyn for<'a> Fn(&'a mut TxDeltaWindow) -> Result<(), crate::Error<'static>>>;
+
+/// The window passed to a window handler to mark the end of a delta.
+pub(super) fn end_of_delta() -> TxDeltaWindow {
+    TxDeltaWindow {
+        ptr: std::ptr::null_mut(),
+        pool: apr::Pool::new(),
+        _phantom: PhantomData,
+    }
+}
+
+/// A single editor call.
+///
+/// Directories and files are identified by numbers assigned in the order
+/// they were opened or added, so drives that keep several files open at
+/// once (as commits do) are reproduced faithfully.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditorEvent {
+    /// `set_target_revision`.
+    SetTargetRevision {
+        /// The target revision.
+        revision: Option<Revnum>,
+    },
+    /// `open_root`.
+    OpenRoot {
+        /// Identifier of the root directory.
+        id: usize,
+        /// The base revision.
+        base_revision: Option<Revnum>,
+    },
+    /// `delete_entry`.
+    DeleteEntry {
+        /// The parent directory.
+        parent: usize,
+        /// Path of the deleted entry.
+        path: String,
+        /// The revision the entry is expected to be at.
+        revision: Option<Revnum>,
+    },
+    /// `add_directory`.
+    AddDirectory {
+        /// The parent directory.
+        parent: usize,
+        /// Identifier of the new directory.
+        id: usize,
+        /// Path of the new directory.
+        path: String,
+        /// Copy source.
+        copyfrom: Option<(String, Revnum)>,
+    },
+    /// `open_directory`.
+    OpenDirectory {
+        /// The parent directory.
+        parent: usize,
+        /// Identifier of the opened directory.
+        id: usize,
+        /// Path of the directory.
+        path: String,
+        /// The base revision.
+        base_revision: Option<Revnum>,
+    },
+    /// `change_dir_prop`.
+    ChangeDirProp {
+        /// The directory.
+        dir: usize,
+        /// Property name.
+        name: String,
+        /// New value, or `None` to delete.
+        value: Option<Vec<u8>>,
+    },
+    /// `close_directory`.
+    CloseDirectory {
+        /// The directory.
+        dir: usize,
+    },
+    /// `absent_directory`.
+    AbsentDirectory {
+        /// The parent directory.
+        parent: usize,
+        /// Path of the absent directory.
+        path: String,
+    },
+    /// `add_file`.
+    AddFile {
+        /// The parent directory.
+        parent: usize,
+        /// Identifier of the new file.
+        id: usize,
+        /// Path of the new file.
+        path: String,
+        /// Copy source.
+        copyfrom: Option<(String, Revnum)>,
+    },
+    /// `open_file`.
+    OpenFile {
+        /// The parent directory.
+        parent: usize,
+        /// Identifier of the opened file.
+        id: usize,
+        /// Path of the file.
+        path: String,
+        /// The base revision.
+        base_revision: Option<Revnum>,
+    },
+    /// `apply_textdelta`.
+    ApplyTextDelta {
+        /// The file.
+        file: usize,
+        /// Checksum of the base text.
+        base_checksum: Option<String>,
+    },
+    /// A window passed to the handler returned by `apply_textdelta`;
+    /// `None` marks the end of the delta.
+    TextDeltaWindow {
+        /// The file.
+        file: usize,
+        /// The window.
+        window: Option<Window>,
+    },
+    /// `change_file_prop`.
+    ChangeFileProp {
+        /// The file.
+        file: usize,
+        /// Property name.
+        name: String,
+        /// New value, or `None` to delete.
+        value: Option<Vec<u8>>,
+    },
+    /// `close_file`.
+    CloseFile {
+        /// The file.
+        file: usize,
+        /// Checksum of the resulting text.
+        text_checksum: Option<String>,
+    },
+    /// `absent_file`.
+    AbsentFile {
+        /// The parent directory.
+        parent: usize,
+        /// Path of the absent file.
+        path: String,
+    },
+    /// `close_edit`.
+    CloseEdit,
+    /// `abort_edit`.
+    AbortEdit,
+}
+
+/// A recorded editor drive.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EditorTrace {
+    events: Vec<EditorEvent>,
+}
+
+const TRACE_MAGIC: &[u8] = b"svn-editor-trace 1\n";
+
+fn invalid_trace(what: &str) -> Error<'static> {
+    Error::from_message(&format!("Invalid editor trace: {}", what))
+}
+
+struct TraceWriter(Vec<u8>);
+
+impl TraceWriter {
+    fn number(&mut self, mut value: u64) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                self.0.push(byte);
+                return;
+            }
+            self.0.push(byte | 0x80);
+        }
+    }
+
+    fn id(&mut self, id: usize) {
+        self.number(id as u64);
+    }
+
+    /// Revisions are stored zigzag-encoded behind a presence flag, so that
+    /// every value, including negative ones, survives a round trip.
+    fn revision(&mut self, revision: Option<Revnum>) {
+        match revision {
+            Some(r) => {
+                self.0.push(1);
+                self.number(((r.0 << 1) ^ (r.0 >> 63)) as u64);
+            }
+            None => self.0.push(0),
+        }
+    }
+
+    fn bytes(&mut self, data: &[u8]) {
+        self.number(data.len() as u64);
+        self.0.extend_from_slice(data);
+    }
+
+    fn opt_bytes(&mut self, data: Option<&[u8]>) {
+        match data {
+            Some(data) => {
+                self.0.push(1);
+                self.bytes(data);
+            }
+            None => self.0.push(0),
+        }
+    }
+
+    fn copyfrom(&mut self, copyfrom: &Option<(String, Revnum)>) {
+        match copyfrom {
+            Some((path, revision)) => {
+                self.0.push(1);
+                self.bytes(path.as_bytes());
+                self.revision(Some(*revision));
+            }
+            None => self.0.push(0),
+        }
+    }
+}
+
+struct TraceReader<'a> {
+    data: &'a [u8],
+}
+
+impl TraceReader<'_> {
+    fn byte(&mut self) -> Result<u8, Error<'static>> {
+        let (&byte, rest) = self
+            .data
+            .split_first()
+            .ok_or_else(|| invalid_trace("unexpected end of data"))?;
+        self.data = rest;
+        Ok(byte)
+    }
+
+    fn number(&mut self) -> Result<u64, Error<'static>> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(invalid_trace("number too large"))
+    }
+
+    fn id(&mut self) -> Result<usize, Error<'static>> {
+        usize::try_from(self.number()?).map_err(|_| invalid_trace("identifier too large"))
+    }
+
+    fn revision(&mut self) -> Result<Option<Revnum>, Error<'static>> {
+        if !self.flag()? {
+            return Ok(None);
+        }
+        let n = self.number()?;
+        Ok(Some(Revnum((n >> 1) as i64 ^ -((n & 1) as i64))))
+    }
+
+    fn bytes(&mut self) -> Result<Vec<u8>, Error<'static>> {
+        let len = self.number()?;
+        if len > self.data.len() as u64 {
+            return Err(invalid_trace("unexpected end of data"));
+        }
+        let (data, rest) = self.data.split_at(len as usize);
+        self.data = rest;
+        Ok(data.to_vec())
+    }
+
+    fn string(&mut self) -> Result<String, Error<'static>> {
+        String::from_utf8(self.bytes()?).map_err(|_| invalid_trace("invalid UTF-8"))
+    }
+
+    fn flag(&mut self) -> Result<bool, Error<'static>> {
+        match self.byte()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(invalid_trace("invalid flag")),
+        }
+    }
+
+    fn opt_bytes(&mut self) -> Result<Option<Vec<u8>>, Error<'static>> {
+        if self.flag()? {
+            Ok(Some(self.bytes()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn opt_string(&mut self) -> Result<Option<String>, Error<'static>> {
+        if self.flag()? {
+            Ok(Some(self.string()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn copyfrom(&mut self) -> Result<Option<(String, Revnum)>, Error<'static>> {
+        if !self.flag()? {
+            return Ok(None);
+        }
+        let path = self.string()?;
+        let revision = self
+            .revision()?
+            .ok_or_else(|| invalid_trace("copy source without revision"))?;
+        Ok(Some((path, revision)))
+    }
+}
+
+impl EditorTrace {
+    /// Create an empty trace.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The recorded events, in order.
+    pub fn events(&self) -> &[EditorEvent] {
+        &self.events
+    }
+
+    /// Append an event.
+    pub fn push(&mut self, event: EditorEvent) {
+        self.events.push(event);
+    }
+
+    /// Serialize the trace.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = TraceWriter(TRACE_MAGIC.to_vec());
+        for event in &self.events {
+            match event {
+                EditorEvent::SetTargetRevision { revision } => {
+                    w.0.push(0);
+                    w.revision(*revision);
+                }
+                EditorEvent::OpenRoot { id, base_revision } => {
+                    w.0.push(1);
+                    w.id(*id);
+                    w.revision(*base_revision);
+                }
+                EditorEvent::DeleteEntry {
+                    parent,
+                    path,
+                    revision,
+                } => {
+                    w.0.push(2);
+                    w.id(*parent);
+                    w.bytes(path.as_bytes());
+                    w.revision(*revision);
+                }
+                EditorEvent::AddDirectory {
+                    parent,
+                    id,
+                    path,
+                    copyfrom,
+                } => {
+                    w.0.push(3);
+                    w.id(*parent);
+                    w.id(*id);
+                    w.bytes(path.as_bytes());
+                    w.copyfrom(copyfrom);
+                }
+                EditorEvent::OpenDirectory {
+                    parent,
+                    id,
+                    path,
+                    base_revision,
+                } => {
+                    w.0.push(4);
+                    w.id(*parent);
+                    w.id(*id);
+                    w.bytes(path.as_bytes());
+                    w.revision(*base_revision);
+                }
+                EditorEvent::ChangeDirProp { dir, name, value } => {
+                    w.0.push(5);
+                    w.id(*dir);
+                    w.bytes(name.as_bytes());
+                    w.opt_bytes(value.as_deref());
+                }
+                EditorEvent::CloseDirectory { dir } => {
+                    w.0.push(6);
+                    w.id(*dir);
+                }
+                EditorEvent::AbsentDirectory { parent, path } => {
+                    w.0.push(7);
+                    w.id(*parent);
+                    w.bytes(path.as_bytes());
+                }
+                EditorEvent::AddFile {
+                    parent,
+                    id,
+                    path,
+                    copyfrom,
+                } => {
+                    w.0.push(8);
+                    w.id(*parent);
+                    w.id(*id);
+                    w.bytes(path.as_bytes());
+                    w.copyfrom(copyfrom);
+                }
+                EditorEvent::OpenFile {
+                    parent,
+                    id,
+                    path,
+                    base_revision,
+                } => {
+                    w.0.push(9);
+                    w.id(*parent);
+                    w.id(*id);
+                    w.bytes(path.as_bytes());
+                    w.revision(*base_revision);
+                }
+                EditorEvent::ApplyTextDelta {
+                    file,
+                    base_checksum,
+                } => {
+                    w.0.push(10);
+                    w.id(*file);
+                    w.opt_bytes(base_checksum.as_deref().map(str::as_bytes));
+                }
+                EditorEvent::TextDeltaWindow { file, window } => {
+                    w.0.push(11);
+                    w.id(*file);
+                    match window {
+                        Some(window) => {
+                            w.0.push(1);
+                            w.number(window.sview_offset);
+                            w.number(window.sview_len);
+                            w.number(window.tview_len);
+                            w.number(window.ops.len() as u64);
+                            for op in &window.ops {
+                                w.number(u64::from(op.kind.action_code()));
+                                w.number(op.offset);
+                                w.number(op.length);
+                            }
+                            w.bytes(&window.new_data);
+                        }
+                        None => w.0.push(0),
+                    }
+                }
+                EditorEvent::ChangeFileProp { file, name, value } => {
+                    w.0.push(12);
+                    w.id(*file);
+                    w.bytes(name.as_bytes());
+                    w.opt_bytes(value.as_deref());
+                }
+                EditorEvent::CloseFile {
+                    file,
+                    text_checksum,
+                } => {
+                    w.0.push(13);
+                    w.id(*file);
+                    w.opt_bytes(text_checksum.as_deref().map(str::as_bytes));
+                }
+                EditorEvent::AbsentFile { parent, path } => {
+                    w.0.push(14);
+                    w.id(*parent);
+                    w.bytes(path.as_bytes());
+                }
+                EditorEvent::CloseEdit => w.0.push(15),
+                EditorEvent::AbortEdit => w.0.push(16),
+            }
+        }
+        w.0
+    }
+
+    /// Deserialize a trace produced by [`to_bytes`](Self::to_bytes).
+    pub fn from_bytes(data: &[u8]) -> Result<Self, Error<'static>> {
+        let data = data
+            .strip_prefix(TRACE_MAGIC)
+            .ok_or_else(|| invalid_trace("missing header"))?;
+        let mut r = TraceReader { data };
+        let mut events = Vec::new();
+        while !r.data.is_empty() {
+            let event = match r.byte()? {
+                0 => EditorEvent::SetTargetRevision {
+                    revision: r.revision()?,
+                },
+                1 => EditorEvent::OpenRoot {
+                    id: r.id()?,
+                    base_revision: r.revision()?,
+                },
+                2 => EditorEvent::DeleteEntry {
+                    parent: r.id()?,
+                    path: r.string()?,
+                    revision: r.revision()?,
+                },
+                3 => EditorEvent::AddDirectory {
+                    parent: r.id()?,
+                    id: r.id()?,
+                    path: r.string()?,
+                    copyfrom: r.copyfrom()?,
+                },
+                4 => EditorEvent::OpenDirectory {
+                    parent: r.id()?,
+                    id: r.id()?,
+                    path: r.string()?,
+                    base_revision: r.revision()?,
+                },
+                5 => EditorEvent::ChangeDirProp {
+                    dir: r.id()?,
+                    name: r.string()?,
+                    value: r.opt_bytes()?,
+                },
+                6 => EditorEvent::CloseDirectory { dir: r.id()? },
+                7 => EditorEvent::AbsentDirectory {
+                    parent: r.id()?,
+                    path: r.string()?,
+                },
+                8 => EditorEvent::AddFile {
+                    parent: r.id()?,
+                    id: r.id()?,
+                    path: r.string()?,
+                    copyfrom: r.copyfrom()?,
+                },
+                9 => EditorEvent::OpenFile {
+                    parent: r.id()?,
+                    id: r.id()?,
+                    path: r.string()?,
+                    base_revision: r.revision()?,
+                },
+                10 => EditorEvent::ApplyTextDelta {
+                    file: r.id()?,
+                    base_checksum: r.opt_string()?,
+                },
+                11 => {
+                    let file = r.id()?;
+                    let window = if r.flag()? {
+                        let sview_offset = r.number()?;
+                        let sview_len = r.number()?;
+                        let tview_len = r.number()?;
+                        let num_ops = r.number()?;
+                        let mut ops = Vec::new();
+                        for _ in 0..num_ops {
+                            let kind = u8::try_from(r.number()?)
+                                .ok()
+                                .and_then(DeltaOpKind::from_action_code)
+                                .ok_or_else(|| invalid_trace("invalid delta instruction"))?;
+                            ops.push(DeltaOp {
+                                kind,
+                                offset: r.number()?,
+                                length: r.number()?,
+                            });
+                        }
+                        let window = Window {
+                            sview_offset,
+                            sview_len,
+                            tview_len,
+                            ops,
+                            new_data: r.bytes()?,
+                        };
+                        // Replaying hands the window to libsvn, which trusts
+                        // the instructions to stay within their views.
+                        window.validate()?;
+                        Some(window)
+                    } else {
+                        None
+                    };
+                    EditorEvent::TextDeltaWindow { file, window }
+                }
+                12 => EditorEvent::ChangeFileProp {
+                    file: r.id()?,
+                    name: r.string()?,
+                    value: r.opt_bytes()?,
+                },
+                13 => EditorEvent::CloseFile {
+                    file: r.id()?,
+                    text_checksum: r.opt_string()?,
+                },
+                14 => EditorEvent::AbsentFile {
+                    parent: r.id()?,
+                    path: r.string()?,
+                },
+                15 => EditorEvent::CloseEdit,
+                16 => EditorEvent::AbortEdit,
+                tag => return Err(invalid_trace(&format!("unknown event {}", tag))),
+            };
+            events.push(event);
+        }
+        Ok(Self { events })
+    }
+
+    /// Drive `editor` with the recorded calls.
+    ///
+    /// Like [`WrapEditor::from_rust_editor`](super::WrapEditor::from_rust_editor),
+    /// this requires all directories to share one editor type, which holds
+    /// for [`WrapEditor`](super::WrapEditor) and the editors in this crate.
+    pub fn replay<E, D>(&self, editor: &mut E) -> Result<(), Error<'static>>
+    where
+        E: Editor<RootEditor = D>,
+        D: DirectoryEditor<SubDirectory = D>,
+    {
+        let mut dirs: HashMap<usize, D> = HashMap::new();
+        let mut files: HashMap<usize, D::File> = HashMap::new();
+        let mut handlers: HashMap<usize, WindowHandler> = HashMap::new();
+
+        fn lookup<'a, T>(
+            map: &'a mut HashMap<usize, T>,
+            id: usize,
+            what: &str,
+        ) -> Result<&'a mut T, Error<'static>> {
+            map.get_mut(&id)
+                .ok_or_else(|| invalid_trace(&format!("unknown {} {}", what, id)))
+        }
+
+        for event in &self.events {
+            match event {
+                EditorEvent::SetTargetRevision { revision } => editor
+                    .set_target_revision(*revision)
+                    .map_err(|e| e.into_static())?,
+                EditorEvent::OpenRoot { id, base_revision } => {
+                    let root = editor
+                        .open_root(*base_revision)
+                        .map_err(|e| e.into_static())?;
+                    dirs.insert(*id, root);
+                }
+                EditorEvent::DeleteEntry {
+                    parent,
+                    path,
+                    revision,
+                } => lookup(&mut dirs, *parent, "directory")?
+                    .delete_entry(path, *revision)
+                    .map_err(|e| e.into_static())?,
+                EditorEvent::AddDirectory {
+                    parent,
+                    id,
+                    path,
+                    copyfrom,
+                } => {
+                    let dir = lookup(&mut dirs, *parent, "directory")?
+                        .add_directory(path, copyfrom.as_ref().map(|(p, r)| (p.as_str(), *r)))
+                        .map_err(|e| e.into_static())?;
+                    dirs.insert(*id, dir);
+                }
+                EditorEvent::OpenDirectory {
+                    parent,
+                    id,
+                    path,
+                    base_revision,
+                } => {
+                    let dir = lookup(&mut dirs, *parent, "directory")?
+                        .open_directory(path, *base_revision)
+                        .map_err(|e| e.into_static())?;
+                    dirs.insert(*id, dir);
+                }
+                EditorEvent::ChangeDirProp { dir, name, value } => {
+                    lookup(&mut dirs, *dir, "directory")?
+                        .change_prop(name, value.as_deref())
+                        .map_err(|e| e.into_static())?
+                }
+                EditorEvent::CloseDirectory { dir } => {
+                    lookup(&mut dirs, *dir, "directory")?
+                        .close()
+                        .map_err(|e| e.into_static())?;
+                    dirs.remove(dir);
+                }
+                EditorEvent::AbsentDirectory { parent, path } => {
+                    lookup(&mut dirs, *parent, "directory")?
+                        .absent_directory(path)
+                        .map_err(|e| e.into_static())?
+                }
+                EditorEvent::AddFile {
+                    parent,
+                    id,
+                    path,
+                    copyfrom,
+                } => {
+                    let file = lookup(&mut dirs, *parent, "directory")?
+                        .add_file(path, copyfrom.as_ref().map(|(p, r)| (p.as_str(), *r)))
+                        .map_err(|e| e.into_static())?;
+                    files.insert(*id, file);
+                }
+                EditorEvent::OpenFile {
+                    parent,
+                    id,
+                    path,
+                    base_revision,
+                } => {
+                    let file = lookup(&mut dirs, *parent, "directory")?
+                        .open_file(path, *base_revision)
+                        .map_err(|e| e.into_static())?;
+                    files.insert(*id, file);
+                }
+                EditorEvent::ApplyTextDelta {
+                    file,
+                    base_checksum,
+                } => {
+                    let handler = lookup(&mut files, *file, "file")?
+                        .apply_textdelta(base_checksum.as_deref())?;
+                    handlers.insert(*file, handler);
+                }
+                EditorEvent::TextDeltaWindow { file, window } => {
+                    let handler = lookup(&mut handlers, *file, "text delta")?;
+                    match window {
+                        Some(window) => handler(&mut window.to_tx_delta_window())?,
+                        None => {
+                            handler(&mut end_of_delta())?;
+                            handlers.remove(file);
+                        }
+                    }
+                }
+                EditorEvent::ChangeFileProp { file, name, value } => {
+                    lookup(&mut files, *file, "file")?.change_prop(name, value.as_deref())?
+                }
+                EditorEvent::CloseFile {
+                    file,
+                    text_checksum,
+                } => {
+                    lookup(&mut files, *file, "file")?.close(text_checksum.as_deref())?;
+                    files.remove(file);
+                }
+                EditorEvent::AbsentFile { parent, path } => {
+                    lookup(&mut dirs, *parent, "directory")?
+                        .absent_file(path)
+                        .map_err(|e| e.into_static())?
+                }
+                EditorEvent::CloseEdit => editor.close().map_err(|e| e.into_static())?,
+                EditorEvent::AbortEdit => editor.abort().map_err(|e| e.into_static())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl IntoIterator for EditorTrace {
+    type Item = EditorEvent;
+    type IntoIter = std::vec::IntoIter<EditorEvent>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.events.into_iter()
+    }
+}
+
+impl FromIterator<EditorEvent> for EditorTrace {
+    fn from_iter<I: IntoIterator<Item = EditorEvent>>(iter: I) -> Self {
+        Self {
+            events: iter.into_iter().collect(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct RecorderState {
+    trace: EditorTrace,
+    next_id: usize,
+}
+
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<RecorderState>>);
+
+impl Recorder {
+    fn record(&self, event: EditorEvent) {
+        self.0.lock().unwrap().trace.push(event);
+    }
+
+    fn next_id(&self) -> usize {
+        let mut state = self.0.lock().unwrap();
+        state.next_id += 1;
+        state.next_id - 1
+    }
+}
+
+/// A handle to the trace of a [`RecordingEditor`], which stays usable after
+/// the editor was handed to [`WrapEditor::from_rust_editor`](super::WrapEditor::from_rust_editor).
+#[derive(Clone)]
+pub struct Recording(Recorder);
+
+impl Recording {
+    /// A copy of the calls recorded so far.
+    pub fn trace(&self) -> EditorTrace {
+        self.0 .0.lock().unwrap().trace.clone()
+    }
+}
+
+/// An editor that records every call made on it.
+#[derive(Default)]
+pub struct RecordingEditor {
+    recorder: Recorder,
+}
+
+impl RecordingEditor {
+    /// Create a recording editor.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A handle to the recorded trace.
+    pub fn recording(&self) -> Recording {
+        Recording(self.recorder.clone())
+    }
+
+    /// The calls recorded so far.
+    pub fn trace(&self) -> EditorTrace {
+        self.recording().trace()
+    }
+}
+
+impl Editor for RecordingEditor {
+    type RootEditor = RecordingDirectory;
+
+    fn set_target_revision(&mut self, revision: Option<Revnum>) -> Result<(), Error<'_>> {
+        self.recorder
+            .record(EditorEvent::SetTargetRevision { revision });
+        Ok(())
+    }
+
+    fn open_root(
+        &mut self,
+        base_revision: Option<Revnum>,
+    ) -> Result<RecordingDirectory, Error<'_>> {
+        let id = self.recorder.next_id();
+        self.recorder
+            .record(EditorEvent::OpenRoot { id, base_revision });
+        Ok(RecordingDirectory {
+            id,
+            recorder: self.recorder.clone(),
+        })
+    }
+
+    fn close(&mut self) -> Result<(), Error<'_>> {
+        self.recorder.record(EditorEvent::CloseEdit);
+        Ok(())
+    }
+
+    fn abort(&mut self) -> Result<(), Error<'_>> {
+        self.recorder.record(EditorEvent::AbortEdit);
+        Ok(())
+    }
+}
+
+/// Directory editor of a [`RecordingEditor`].
+pub struct RecordingDirectory {
+    id: usize,
+    recorder: Recorder,
+}
+
+impl DirectoryEditor for RecordingDirectory {
+    type SubDirectory = RecordingDirectory;
+    type File = RecordingFile;
+
+    fn delete_entry(&mut self, path: &str, revision: Option<Revnum>) -> Result<(), Error<'_>> {
+        self.recorder.record(EditorEvent::DeleteEntry {
+            parent: self.id,
+            path: path.to_string(),
+            revision,
+        });
+        Ok(())
+    }
+
+    fn add_directory(
+        &mut self,
+        path: &str,
+        copyfrom: Option<(&str, Revnum)>,
+    ) -> Result<RecordingDirectory, Error<'_>> {
+        let id = self.recorder.next_id();
+        self.recorder.record(EditorEvent::AddDirectory {
+            parent: self.id,
+            id,
+            path: path.to_string(),
+            copyfrom: copyfrom.map(|(p, r)| (p.to_string(), r)),
+        });
+        Ok(RecordingDirectory {
+            id,
+            recorder: self.recorder.clone(),
+        })
+    }
+
+    fn open_directory(
+        &mut self,
+        path: &str,
+        base_revision: Option<Revnum>,
+    ) -> Result<RecordingDirectory, Error<'_>> {
+        let id = self.recorder.next_id();
+        self.recorder.record(EditorEvent::OpenDirectory {
+            parent: self.id,
+            id,
+            path: path.to_string(),
+            base_revision,
+        });
+        Ok(RecordingDirectory {
+            id,
+            recorder: self.recorder.clone(),
+        })
+    }
+
+    fn change_prop(&mut self, name: &str, value: Option<&[u8]>) -> Result<(), Error<'_>> {
+        self.recorder.record(EditorEvent::ChangeDirProp {
+            dir: self.id,
+            name: name.to_string(),
+            value: value.map(|v| v.to_vec()),
+        });
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<(), Error<'_>> {
+        self.recorder
+            .record(EditorEvent::CloseDirectory { dir: self.id });
+        Ok(())
+    }
+
+    fn absent_directory(&mut self, path: &str) -> Result<(), Error<'_>> {
+        self.recorder.record(EditorEvent::AbsentDirectory {
+            parent: self.id,
+            path: path.to_string(),
+        });
+        Ok(())
+    }
+
+    fn add_file(
+        &mut self,
+        path: &str,
+        copyfrom: Option<(&str, Revnum)>,
+    ) -> Result<RecordingFile, Error<'_>> {
+        let id = self.recorder.next_id();
+        self.recorder.record(EditorEvent::AddFile {
+            parent: self.id,
+            id,
+            path: path.to_string(),
+            copyfrom: copyfrom.map(|(p, r)| (p.to_string(), r)),
+        });
+        Ok(RecordingFile {
+            id,
+            recorder: self.recorder.clone(),
+        })
+    }
+
+    fn open_file(
+        &mut self,
+        path: &str,
+        base_revision: Option<Revnum>,
+    ) -> Result<RecordingFile, Error<'_>> {
+        let id = self.recorder.next_id();
+        self.recorder.record(EditorEvent::OpenFile {
+            parent: self.id,
+            id,
+            path: path.to_string(),
+            base_revision,
+        });
+        Ok(RecordingFile {
+            id,
+            recorder: self.recorder.clone(),
+        })
+    }
+
+    fn absent_file(&mut self, path: &str) -> Result<(), Error<'_>> {
+        self.recorder.record(EditorEvent::AbsentFile {
+            parent: self.id,
+            path: path.to_string(),
+        });
+        Ok(())
+    }
+}
+
+/// File editor of a [`RecordingEditor`].
+pub struct RecordingFile {
+    id: usize,
+    recorder: Recorder,
+}
+
+impl FileEditor for RecordingFile {
+    fn apply_textdelta(
+        &mut self,
+        base_checksum: Option<&str>,
+    ) -> Result<WindowHandler, Error<'static>> {
+        self.recorder.record(EditorEvent::ApplyTextDelta {
+            file: self.id,
+            base_checksum: base_checksum.map(str::to_string),
+        });
+        let file = self.id;
+        let recorder = self.recorder.clone();
+        Ok(Box::new(move |window| {
+            let window = if window.as_ptr().is_null() {
+                None
+            } else {
+                Some(Window::try_from(&*window)?)
+            };
+            recorder.record(EditorEvent::TextDeltaWindow { file, window });
+            Ok(())
+        }))
+    }
+
+    fn change_prop(&mut self, name: &str, value: Option<&[u8]>) -> Result<(), Error<'static>> {
+        self.recorder.record(EditorEvent::ChangeFileProp {
+            file: self.id,
+            name: name.to_string(),
+            value: value.map(|v| v.to_vec()),
+        });
+        Ok(())
+    }
+
+    fn close(&mut self, text_checksum: Option<&str>) -> Result<(), Error<'static>> {
+        self.recorder.record(EditorEvent::CloseFile {
+            file: self.id,
+            text_checksum: text_checksum.map(str::to_string),
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::delta::WrapEditor;
+
+    fn sample_trace() -> EditorTrace {
+        let mut editor = RecordingEditor::new();
+        editor.set_target_revision(Some(Revnum(3))).unwrap();
+        let mut root = editor.open_root(Some(Revnum(2))).unwrap();
+        root.change_prop("svn:ignore", Some(b"*.o\n")).unwrap();
+        let mut dir = root
+            .add_directory("branches/b", Some(("/trunk", Revnum(2))))
+            .unwrap();
+        let mut a = dir.open_file("branches/b/a.txt", Some(Revnum(2))).unwrap();
+        let mut b = dir.add_file("branches/b/b.txt", None).unwrap();
+        dir.delete_entry("branches/b/old", Some(Revnum(2))).unwrap();
+        dir.close().unwrap();
+        // Text is sent after the directories were closed, as commits do.
+        let handler = b.apply_textdelta(None).unwrap();
+        handler(&mut TxDeltaWindow::from_parts(
+            0,
+            0,
+            6,
+            0,
+            &[(2, 0, 6)],
+            b"hello\n",
+        ))
+        .unwrap();
+        handler(&mut end_of_delta()).unwrap();
+        b.change_prop("svn:eol-style", Some(b"native")).unwrap();
+        b.close(Some("b1946ac92492d2347c6235b4d2611184")).unwrap();
+        a.change_prop("svn:executable", None).unwrap();
+        a.close(None).unwrap();
+        root.absent_file("secret").unwrap();
+        root.close().unwrap();
+        editor.close().unwrap();
+        editor.trace()
+    }
+
+    #[test]
+    fn test_record() {
+        let trace = sample_trace();
+        assert_eq!(trace.events().len(), 18);
+        assert_eq!(
+            trace.events()[4],
+            EditorEvent::OpenFile {
+                parent: 1,
+                id: 2,
+                path: "branches/b/a.txt".to_string(),
+                base_revision: Some(Revnum(2)),
+            }
+        );
+        assert_eq!(
+            trace.events()[9],
+            EditorEvent::TextDeltaWindow {
+                file: 3,
+                window: Some(Window {
+                    sview_offset: 0,
+                    sview_len: 0,
+                    tview_len: 6,
+                    ops: vec![DeltaOp {
+                        kind: DeltaOpKind::New,
+                        offset: 0,
+                        length: 6,
+                    }],
+                    new_data: b"hello\n".to_vec(),
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn test_serialize_round_trip() {
+        let trace = sample_trace();
+        let bytes = trace.to_bytes();
+        assert_eq!(EditorTrace::from_bytes(&bytes).unwrap(), trace);
+
+        assert!(EditorTrace::from_bytes(b"garbage").is_err());
+        for len in TRACE_MAGIC.len()..bytes.len() {
+            // Truncated traces fail or decode to a prefix, but never panic.
+            if let Ok(partial) = EditorTrace::from_bytes(&bytes[..len]) {
+                assert!(partial.events().len() < trace.events().len());
+            }
+        }
+
+        let revisions: EditorTrace = [
+            None,
+            Some(Revnum(-1)),
+            Some(Revnum(0)),
+            Some(Revnum(i64::MAX)),
+        ]
+        .into_iter()
+        .map(|revision| EditorEvent::SetTargetRevision { revision })
+        .collect();
+        assert_eq!(
+            EditorTrace::from_bytes(&revisions.to_bytes()).unwrap(),
+            revisions
+        );
+
+        // Windows whose instructions leave their views are rejected.
+        let bad: EditorTrace = [EditorEvent::TextDeltaWindow {
+            file: 0,
+            window: Some(Window {
+                sview_len: 2,
+                tview_len: 4,
+                ops: vec![DeltaOp {
+                    kind: DeltaOpKind::Source,
+                    offset: 0,
+                    length: 4,
+                }],
+                ..Default::default()
+            }),
+        }]
+        .into_iter()
+        .collect();
+        assert!(EditorTrace::from_bytes(&bad.to_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_replay() {
+        let trace = sample_trace();
+
+        let mut editor = RecordingEditor::new();
+        trace.replay(&mut editor).unwrap();
+        assert_eq!(editor.trace(), trace);
+
+        // Through the C vtable and back.
+        let recorder = RecordingEditor::new();
+        let recording = recorder.recording();
+        let mut wrapped = WrapEditor::from_rust_editor(recorder);
+        trace.replay(&mut wrapped).unwrap();
+        assert_eq!(recording.trace(), trace);
+    }
+
+    #[test]
+    fn test_replay_invalid() {
+        let trace: EditorTrace = [EditorEvent::CloseDirectory { dir: 7 }]
+            .into_iter()
+            .collect();
+        assert!(trace.replay(&mut RecordingEditor::new()).is_err());
+    }
+
+    #[cfg(all(feature = "ra", feature = "repos"))]
+    #[test]
+    fn test_record_ra_replay() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repos_path = temp_dir.path().join("repo");
+        let repos = crate::repos::Repos::create(&repos_path).unwrap();
+        let fs = repos.fs().unwrap();
+        let mut txn = fs.begin_txn(Revnum(0), 0).unwrap();
+        let mut root = txn.root().unwrap();
+        root.make_file("/a.txt").unwrap();
+        root.set_file_contents("/a.txt", b"recorded\n").unwrap();
+        txn.commit().unwrap();
+
+        let url = crate::path_to_file_url(&repos_path);
+        let (mut session, _, _) = crate::ra::Session::open(&url, None, None, None).unwrap();
+        let recorder = RecordingEditor::new();
+        let recording = recorder.recording();
+        let mut editor = WrapEditor::from_rust_editor(recorder);
+        session
+            .replay(Revnum(1), Revnum(0), true, &mut editor)
+            .unwrap();
+
+        let trace = EditorTrace::from_bytes(&recording.trace().to_bytes()).unwrap();
+        assert!(trace.events().iter().any(|e| matches!(
+            e,
+            EditorEvent::TextDeltaWindow { window: Some(w), .. } if w.new_data == b"recorded\n"
+        )));
+
+        let mut replayed = RecordingEditor::new();
+        trace.replay(&mut replayed).unwrap();
+        assert_eq!(replayed.trace(), trace);
+    }
+}
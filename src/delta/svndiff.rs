@@ -0,0 +1,844 @@
+//! Pure-Rust svndiff encoder and decoder.
+//!
+//! svndiff is the serialization of text deltas used in dump files, the
+//! repository and the wire protocols.  It starts with the four bytes
+//! `SVN\x00` (the last byte being the format version), followed by a
+//! sequence of windows.  Version 0 stores the instruction and new data
+//! sections as-is, version 1 compresses them with zlib and version 2 with
+//! LZ4.
+//!
+//! Unlike [`to_svndiff_stream`](super::to_svndiff_stream) and
+//! [`read_svndiff_window`](super::read_svndiff_window) nothing here touches
+//! libsvn_delta or APR pools, which makes it suitable for processing
+//! untrusted input: malformed data results in an error, never a panic.
+//!
+//! # Example
+//!
+//! ```
+//! use subversion::delta::svndiff;
+//!
+//! let source = b"The quick brown fox jumps over the lazy dog".repeat(10);
+//! let target = b"The quick brown cat jumps over the lazy dog".repeat(10);
+//!
+//! let windows = svndiff::delta(&source[..], &target[..])
+//!     .collect::<Result<Vec<_>, _>>()
+//!     .unwrap();
+//! let encoded = svndiff::encode(&windows, 1).unwrap();
+//!
+//! let (version, decoded) = svndiff::decode(&encoded).unwrap();
+//! assert_eq!(version, 1);
+//! let mut result = Vec::new();
+//! svndiff::apply(std::io::Cursor::new(&source), decoded, &mut result).unwrap();
+//! assert_eq!(result, target);
+//! ```
+
+pub use super::window::{DeltaOp, DeltaOpKind, Window};
+use crate::Error;
+use std::io::{Read, Seek, SeekFrom, Write};
+
+/// The size of the windows produced by [`DeltaGenerator`], the same as
+/// `SVN_DELTA_WINDOW_SIZE`.
+pub const WINDOW_SIZE: usize = 102400;
+
+/// The default compression level for versions 1 and 2.
+pub const DEFAULT_COMPRESSION_LEVEL: u32 = 5;
+
+/// Sections shorter than this are never compressed.
+const MIN_COMPRESS_SIZE: usize = 512;
+
+/// Upper bound for any length read from a window header, so that corrupt
+/// input cannot make the decoder allocate unbounded amounts of memory.
+const MAX_SECTION_LEN: u64 = 64 * 1024 * 1024;
+
+/// Longest possible encoding of a 64-bit integer.
+const MAX_VARINT_LEN: usize = 10;
+
+fn malformed(what: &str) -> Error<'static> {
+    Error::from_message(&format!("Malformed svndiff data: {}", what))
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    let mut bytes = [0u8; MAX_VARINT_LEN];
+    let mut i = MAX_VARINT_LEN;
+    loop {
+        i -= 1;
+        bytes[i] = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            break;
+        }
+    }
+    for b in &mut bytes[i..MAX_VARINT_LEN - 1] {
+        *b |= 0x80;
+    }
+    out.extend_from_slice(&bytes[i..]);
+}
+
+/// Parse a varint at the start of `data`, returning it and its length.
+fn parse_varint(data: &[u8]) -> Result<(u64, usize), Error<'static>> {
+    let mut value = 0u64;
+    for (i, &b) in data.iter().enumerate().take(MAX_VARINT_LEN) {
+        if value > u64::MAX >> 7 {
+            return Err(malformed("integer overflow"));
+        }
+        value = (value << 7) | u64::from(b & 0x7f);
+        if b & 0x80 == 0 {
+            return Ok((value, i + 1));
+        }
+    }
+    Err(malformed("truncated or overlong integer"))
+}
+
+/// Read a varint from `reader`; `Ok(None)` if the reader is at EOF.
+fn read_varint(reader: &mut impl Read) -> Result<Option<u64>, Error<'static>> {
+    let mut buf = Vec::with_capacity(MAX_VARINT_LEN);
+    loop {
+        let mut byte = [0u8];
+        if reader.read(&mut byte)? == 0 {
+            if buf.is_empty() {
+                return Ok(None);
+            }
+            return Err(malformed("unexpected end of data"));
+        }
+        buf.push(byte[0]);
+        if byte[0] & 0x80 == 0 || buf.len() == MAX_VARINT_LEN {
+            return parse_varint(&buf).map(|(value, _)| Some(value));
+        }
+    }
+}
+
+fn compress(version: u8, data: &[u8], level: u32) -> Result<Vec<u8>, Error<'static>> {
+    let mut out = Vec::new();
+    write_varint(&mut out, data.len() as u64);
+    let compressed = if level == 0 || data.len() < MIN_COMPRESS_SIZE {
+        None
+    } else if version == 1 {
+        let mut encoder =
+            flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::new(level.min(9)));
+        encoder.write_all(data)?;
+        Some(encoder.finish()?)
+    } else {
+        Some(lz4_flex::block::compress(data))
+    };
+    match compressed {
+        // Compressed data is only used if it is strictly shorter, which is
+        // how the decoder tells the two apart.
+        Some(compressed) if compressed.len() < data.len() => out.extend_from_slice(&compressed),
+        _ => out.extend_from_slice(data),
+    }
+    Ok(out)
+}
+
+fn decompress(version: u8, data: &[u8]) -> Result<Vec<u8>, Error<'static>> {
+    if data.is_empty() {
+        return Ok(Vec::new());
+    }
+    let (len, header) = parse_varint(data)?;
+    if len > MAX_SECTION_LEN {
+        return Err(malformed("section too large"));
+    }
+    let payload = &data[header..];
+    if payload.len() as u64 == len {
+        return Ok(payload.to_vec());
+    }
+    let out = if version == 1 {
+        let mut out = Vec::new();
+        flate2::read::ZlibDecoder::new(payload)
+            .take(len + 1)
+            .read_to_end(&mut out)
+            .map_err(|e| malformed(&format!("zlib: {}", e)))?;
+        out
+    } else {
+        lz4_flex::block::decompress(payload, len as usize)
+            .map_err(|e| malformed(&format!("lz4: {}", e)))?
+    };
+    if out.len() as u64 != len {
+        return Err(malformed("decompressed section has the wrong length"));
+    }
+    Ok(out)
+}
+
+fn check_version(version: u8) -> Result<(), Error<'static>> {
+    if version > 2 {
+        return Err(Error::from_message(&format!(
+            "Unsupported svndiff version {}",
+            version
+        )));
+    }
+    Ok(())
+}
+
+/// Serialize a single window, without the stream header.
+pub fn encode_window(
+    window: &Window,
+    version: u8,
+    compression_level: u32,
+) -> Result<Vec<u8>, Error<'static>> {
+    check_version(version)?;
+    window.validate()?;
+
+    let mut instructions = Vec::new();
+    let mut new_data = Vec::new();
+    for op in &window.ops {
+        let code = op.kind.action_code() << 6;
+        if op.length > 0 && op.length < 0x40 {
+            instructions.push(code | op.length as u8);
+        } else {
+            instructions.push(code);
+            write_varint(&mut instructions, op.length);
+        }
+        match op.kind {
+            DeltaOpKind::New => {
+                // New data is consumed in instruction order, so the offsets
+                // are implicit.
+                let start = op.offset as usize;
+                new_data.extend_from_slice(&window.new_data[start..start + op.length as usize]);
+            }
+            _ => write_varint(&mut instructions, op.offset),
+        }
+    }
+    if version > 0 {
+        instructions = compress(version, &instructions, compression_level)?;
+        new_data = compress(version, &new_data, compression_level)?;
+    }
+
+    let mut out = Vec::with_capacity(instructions.len() + new_data.len() + 5 * MAX_VARINT_LEN);
+    write_varint(&mut out, window.sview_offset);
+    write_varint(&mut out, window.sview_len);
+    write_varint(&mut out, window.tview_len);
+    write_varint(&mut out, instructions.len() as u64);
+    write_varint(&mut out, new_data.len() as u64);
+    out.extend_from_slice(&instructions);
+    out.extend_from_slice(&new_data);
+    Ok(out)
+}
+
+fn parse_instructions(data: &[u8], new_len: u64) -> Result<Vec<DeltaOp>, Error<'static>> {
+    let mut ops = Vec::new();
+    let mut pos = 0;
+    let mut npos = 0u64;
+    while pos < data.len() {
+        let byte = data[pos];
+        pos += 1;
+        let kind = DeltaOpKind::from_action_code(byte >> 6)
+            .ok_or_else(|| malformed("invalid instruction"))?;
+        let mut length = u64::from(byte & 0x3f);
+        if length == 0 {
+            let (value, n) = parse_varint(&data[pos..])?;
+            length = value;
+            pos += n;
+        }
+        let offset = if kind == DeltaOpKind::New {
+            let offset = npos;
+            npos = npos
+                .checked_add(length)
+                .filter(|&end| end <= new_len)
+                .ok_or_else(|| malformed("new data copy beyond the new data"))?;
+            offset
+        } else {
+            let (value, n) = parse_varint(&data[pos..])?;
+            pos += n;
+            value
+        };
+        ops.push(DeltaOp {
+            kind,
+            offset,
+            length,
+        });
+    }
+    if npos != new_len {
+        return Err(malformed("unused new data"));
+    }
+    Ok(ops)
+}
+
+/// Writes svndiff data to a [`Write`].
+pub struct Encoder<W: Write> {
+    writer: W,
+    version: u8,
+    compression_level: u32,
+    header_written: bool,
+}
+
+impl<W: Write> Encoder<W> {
+    /// Create an encoder producing svndiff `version` (0, 1 or 2).
+    pub fn new(writer: W, version: u8) -> Result<Self, Error<'static>> {
+        check_version(version)?;
+        Ok(Self {
+            writer,
+            version,
+            compression_level: DEFAULT_COMPRESSION_LEVEL,
+            header_written: false,
+        })
+    }
+
+    /// Set the compression level (0-9) used by versions 1 and 2; 0 disables
+    /// compression.
+    pub fn with_compression_level(mut self, level: u32) -> Self {
+        self.compression_level = level;
+        self
+    }
+
+    fn write_header(&mut self) -> Result<(), Error<'static>> {
+        if !self.header_written {
+            self.writer.write_all(&[b'S', b'V', b'N', self.version])?;
+            self.header_written = true;
+        }
+        Ok(())
+    }
+
+    /// Write a window.
+    pub fn write_window(&mut self, window: &Window) -> Result<(), Error<'static>> {
+        let data = encode_window(window, self.version, self.compression_level)?;
+        self.write_header()?;
+        self.writer.write_all(&data)?;
+        Ok(())
+    }
+
+    /// Finish the stream, returning the underlying writer.
+    ///
+    /// The header is written even if no windows were.
+    pub fn finish(mut self) -> Result<W, Error<'static>> {
+        self.write_header()?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Reads svndiff data from a [`Read`].
+pub struct Decoder<R: Read> {
+    reader: R,
+    version: u8,
+    last_source_view: (u64, u64),
+}
+
+impl<R: Read> Decoder<R> {
+    /// Create a decoder, reading the stream header.
+    pub fn new(mut reader: R) -> Result<Self, Error<'static>> {
+        let mut header = [0u8; 4];
+        reader
+            .read_exact(&mut header)
+            .map_err(|_| malformed("missing header"))?;
+        if &header[..3] != b"SVN" {
+            return Err(malformed("invalid header"));
+        }
+        check_version(header[3])?;
+        Ok(Self {
+            reader,
+            version: header[3],
+            last_source_view: (0, 0),
+        })
+    }
+
+    /// The svndiff version from the header.
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn read_length(&mut self) -> Result<u64, Error<'static>> {
+        let len =
+            read_varint(&mut self.reader)?.ok_or_else(|| malformed("unexpected end of data"))?;
+        if len > MAX_SECTION_LEN {
+            return Err(malformed("window too large"));
+        }
+        Ok(len)
+    }
+
+    fn read_section(&mut self, len: u64) -> Result<Vec<u8>, Error<'static>> {
+        let mut data = Vec::new();
+        self.reader.by_ref().take(len).read_to_end(&mut data)?;
+        if data.len() as u64 != len {
+            return Err(malformed("unexpected end of data"));
+        }
+        if self.version > 0 {
+            data = decompress(self.version, &data)?;
+        }
+        Ok(data)
+    }
+
+    /// Read the next window, or `None` at the end of the stream.
+    pub fn next_window(&mut self) -> Result<Option<Window>, Error<'static>> {
+        let Some(sview_offset) = read_varint(&mut self.reader)? else {
+            return Ok(None);
+        };
+        let sview_len = self.read_length()?;
+        let tview_len = self.read_length()?;
+        let ins_len = self.read_length()?;
+        let new_len = self.read_length()?;
+
+        // Source views may only slide forward.
+        let sview_end = sview_offset
+            .checked_add(sview_len)
+            .ok_or_else(|| malformed("source view overflows"))?;
+        let (last_offset, last_end) = self.last_source_view;
+        if sview_len > 0 && (sview_offset < last_offset || sview_end < last_end) {
+            return Err(malformed("source view moved backwards"));
+        }
+        if sview_len > 0 {
+            self.last_source_view = (sview_offset, sview_end);
+        }
+
+        let instructions = self.read_section(ins_len)?;
+        let new_data = self.read_section(new_len)?;
+        let window = Window {
+            sview_offset,
+            sview_len,
+            tview_len,
+            ops: parse_instructions(&instructions, new_data.len() as u64)?,
+            new_data,
+        };
+        window.validate()?;
+        Ok(Some(window))
+    }
+}
+
+impl<R: Read> Iterator for Decoder<R> {
+    type Item = Result<Window, Error<'static>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_window().transpose()
+    }
+}
+
+/// Encode `windows` as a complete svndiff stream.
+pub fn encode(windows: &[Window], version: u8) -> Result<Vec<u8>, Error<'static>> {
+    let mut encoder = Encoder::new(Vec::new(), version)?;
+    for window in windows {
+        encoder.write_window(window)?;
+    }
+    encoder.finish()
+}
+
+/// Decode a complete svndiff stream, returning its version and windows.
+pub fn decode(data: &[u8]) -> Result<(u8, Vec<Window>), Error<'static>> {
+    let decoder = Decoder::new(data)?;
+    let version = decoder.version();
+    Ok((version, decoder.collect::<Result<_, _>>()?))
+}
+
+/// Apply `windows` to `source`, writing the result to `target`.
+pub fn apply<S: Read + Seek, W: Write>(
+    mut source: S,
+    windows: impl IntoIterator<Item = Window>,
+    mut target: W,
+) -> Result<(), Error<'static>> {
+    let mut source_view = Vec::new();
+    for window in windows {
+        source_view.clear();
+        if window.sview_len > 0 {
+            source.seek(SeekFrom::Start(window.sview_offset))?;
+            source
+                .by_ref()
+                .take(window.sview_len)
+                .read_to_end(&mut source_view)?;
+        }
+        target.write_all(&window.apply(&source_view)?)?;
+    }
+    target.flush()?;
+    Ok(())
+}
+
+/// Block size used to find matches between source and target.
+const MATCH_BLOCKSIZE: usize = 64;
+
+/// Adler-32 style rolling checksum over a [`MATCH_BLOCKSIZE`] block.
+struct RollingHash {
+    a: u32,
+    b: u32,
+}
+
+impl RollingHash {
+    fn new(block: &[u8]) -> Self {
+        let mut hash = RollingHash { a: 0, b: 0 };
+        for (i, &x) in block.iter().enumerate() {
+            hash.a = hash.a.wrapping_add(u32::from(x));
+            hash.b = hash
+                .b
+                .wrapping_add(((MATCH_BLOCKSIZE - i) as u32).wrapping_mul(u32::from(x)));
+        }
+        hash
+    }
+
+    fn roll(&mut self, out: u8, input: u8) {
+        self.a = self
+            .a
+            .wrapping_sub(u32::from(out))
+            .wrapping_add(u32::from(input));
+        self.b = self
+            .b
+            .wrapping_sub((MATCH_BLOCKSIZE as u32).wrapping_mul(u32::from(out)))
+            .wrapping_add(self.a);
+    }
+
+    fn digest(&self) -> u32 {
+        (self.b << 16) | (self.a & 0xffff)
+    }
+}
+
+#[derive(Default)]
+struct WindowBuilder {
+    ops: Vec<DeltaOp>,
+    new_data: Vec<u8>,
+}
+
+impl WindowBuilder {
+    fn copy_source(&mut self, offset: usize, length: usize) {
+        if let Some(last) = self.ops.last_mut() {
+            if last.kind == DeltaOpKind::Source && last.offset + last.length == offset as u64 {
+                last.length += length as u64;
+                return;
+            }
+        }
+        self.ops.push(DeltaOp {
+            kind: DeltaOpKind::Source,
+            offset: offset as u64,
+            length: length as u64,
+        });
+    }
+
+    fn insert(&mut self, data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+        match self.ops.last_mut() {
+            Some(last) if last.kind == DeltaOpKind::New => last.length += data.len() as u64,
+            _ => self.ops.push(DeltaOp {
+                kind: DeltaOpKind::New,
+                offset: self.new_data.len() as u64,
+                length: data.len() as u64,
+            }),
+        }
+        self.new_data.extend_from_slice(data);
+    }
+}
+
+/// Compute the instructions turning `source` into `target`.
+fn compute_delta(source: &[u8], target: &[u8]) -> WindowBuilder {
+    let mut builder = WindowBuilder::default();
+    if source.len() < MATCH_BLOCKSIZE || target.len() < MATCH_BLOCKSIZE {
+        builder.insert(target);
+        return builder;
+    }
+
+    let mut blocks = std::collections::HashMap::new();
+    for offset in (0..=source.len() - MATCH_BLOCKSIZE).step_by(MATCH_BLOCKSIZE) {
+        blocks
+            .entry(RollingHash::new(&source[offset..offset + MATCH_BLOCKSIZE]).digest())
+            .or_insert(offset);
+    }
+
+    let mut pending = 0;
+    let mut pos = 0;
+    let mut hash = RollingHash::new(&target[..MATCH_BLOCKSIZE]);
+    while pos + MATCH_BLOCKSIZE <= target.len() {
+        if let Some(&offset) = blocks.get(&hash.digest()) {
+            if source[offset..offset + MATCH_BLOCKSIZE] == target[pos..pos + MATCH_BLOCKSIZE] {
+                // Grow the match backwards into the pending data and then
+                // forwards as far as it goes.
+                let (mut s, mut t) = (offset, pos);
+                while s > 0 && t > pending && source[s - 1] == target[t - 1] {
+                    s -= 1;
+                    t -= 1;
+                }
+                let mut length = pos + MATCH_BLOCKSIZE - t;
+                while s + length < source.len()
+                    && t + length < target.len()
+                    && source[s + length] == target[t + length]
+                {
+                    length += 1;
+                }
+                builder.insert(&target[pending..t]);
+                builder.copy_source(s, length);
+                pos = t + length;
+                pending = pos;
+                if pos + MATCH_BLOCKSIZE <= target.len() {
+                    hash = RollingHash::new(&target[pos..pos + MATCH_BLOCKSIZE]);
+                }
+                continue;
+            }
+        }
+        if pos + MATCH_BLOCKSIZE < target.len() {
+            hash.roll(target[pos], target[pos + MATCH_BLOCKSIZE]);
+        }
+        pos += 1;
+    }
+    builder.insert(&target[pending..]);
+    builder
+}
+
+fn read_chunk(reader: &mut impl Read, len: usize) -> Result<Vec<u8>, Error<'static>> {
+    let mut buf = Vec::with_capacity(len);
+    reader.take(len as u64).read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+/// Produces the delta windows turning one byte stream into another.
+///
+/// Like `svn_txdelta`, each window covers the same-numbered chunk of source
+/// and target, so the generator only ever holds one window's worth of data.
+pub struct DeltaGenerator<S: Read, T: Read> {
+    source: S,
+    target: T,
+    window_size: usize,
+    source_offset: u64,
+    done: bool,
+}
+
+impl<S: Read, T: Read> DeltaGenerator<S, T> {
+    /// Create a generator for the delta from `source` to `target`.
+    pub fn new(source: S, target: T) -> Self {
+        Self {
+            source,
+            target,
+            window_size: WINDOW_SIZE,
+            source_offset: 0,
+            done: false,
+        }
+    }
+
+    /// Set the amount of target data covered by each window.
+    pub fn with_window_size(mut self, window_size: usize) -> Self {
+        self.window_size = window_size.max(1);
+        self
+    }
+
+    /// Produce the next window, or `None` once the target is exhausted.
+    pub fn next_window(&mut self) -> Result<Option<Window>, Error<'static>> {
+        if self.done {
+            return Ok(None);
+        }
+        let target = read_chunk(&mut self.target, self.window_size)?;
+        if target.len() < self.window_size {
+            self.done = true;
+        }
+        if target.is_empty() {
+            return Ok(None);
+        }
+        let source = read_chunk(&mut self.source, self.window_size)?;
+        let builder = compute_delta(&source, &target);
+        let window = Window {
+            sview_offset: if source.is_empty() {
+                0
+            } else {
+                self.source_offset
+            },
+            sview_len: source.len() as u64,
+            tview_len: target.len() as u64,
+            ops: builder.ops,
+            new_data: builder.new_data,
+        };
+        self.source_offset += source.len() as u64;
+        Ok(Some(window))
+    }
+}
+
+impl<S: Read, T: Read> Iterator for DeltaGenerator<S, T> {
+    type Item = Result<Window, Error<'static>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_window().transpose()
+    }
+}
+
+/// Produce the delta windows turning `source` into `target`.
+pub fn delta<S: Read, T: Read>(source: S, target: T) -> DeltaGenerator<S, T> {
+    DeltaGenerator::new(source, target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic pseudo-random data that does not compress.
+    fn noise(len: usize, seed: u32) -> Vec<u8> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1103515245).wrapping_add(12345);
+                (state >> 16) as u8
+            })
+            .collect()
+    }
+
+    fn round_trip(source: &[u8], target: &[u8], version: u8) -> Vec<Window> {
+        let windows = delta(source, target)
+            .with_window_size(4096)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let encoded = encode(&windows, version).unwrap();
+        let (decoded_version, decoded) = decode(&encoded).unwrap();
+        assert_eq!(decoded_version, version);
+        assert_eq!(decoded, windows);
+
+        let mut result = Vec::new();
+        apply(std::io::Cursor::new(source), decoded, &mut result).unwrap();
+        assert_eq!(result, target);
+        windows
+    }
+
+    #[test]
+    fn test_varint() {
+        for value in [0, 1, 0x7f, 0x80, 0x3fff, 0x4000, u32::MAX as u64, u64::MAX] {
+            let mut buf = Vec::new();
+            write_varint(&mut buf, value);
+            assert_eq!(parse_varint(&buf).unwrap(), (value, buf.len()));
+            assert_eq!(read_varint(&mut &buf[..]).unwrap(), Some(value));
+        }
+        let mut buf = Vec::new();
+        write_varint(&mut buf, 0x80);
+        assert_eq!(buf, [0x81, 0x00]);
+        assert!(parse_varint(&[0x81]).is_err());
+        assert!(parse_varint(&[0xff; 11]).is_err());
+    }
+
+    #[test]
+    fn test_encode_known_bytes() {
+        let window = Window {
+            tview_len: 5,
+            ops: vec![DeltaOp {
+                kind: DeltaOpKind::New,
+                offset: 0,
+                length: 5,
+            }],
+            new_data: b"hello".to_vec(),
+            ..Default::default()
+        };
+        let encoded = encode(std::slice::from_ref(&window), 0).unwrap();
+        assert_eq!(encoded, b"SVN\x00\x00\x00\x05\x01\x05\x85hello");
+        assert_eq!(decode(&encoded).unwrap(), (0, vec![window]));
+        assert_eq!(encode(&[], 2).unwrap(), b"SVN\x02");
+    }
+
+    #[test]
+    fn test_round_trip_all_versions() {
+        let mut source = noise(20000, 1);
+        source.extend(b"some text that repeats ".repeat(200));
+        let mut target = source.clone();
+        target[5000..5010].copy_from_slice(b"0123456789");
+        target.drain(9000..9500);
+        target.splice(15000..15000, noise(300, 2));
+        target.extend(b"appended".repeat(100));
+
+        for version in 0..=2 {
+            let windows = round_trip(&source, &target, version);
+            assert!(windows.iter().any(|w| w.src_ops() > 0));
+        }
+        // No source at all, and an empty target.
+        round_trip(b"", &target, 1);
+        assert!(round_trip(&source, b"", 0).is_empty());
+    }
+
+    #[test]
+    fn test_compression_used() {
+        let target = b"abcdefgh".repeat(1000);
+        let windows = delta(&b""[..], &target[..])
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let plain = encode(&windows, 0).unwrap();
+        for version in 1..=2 {
+            let compressed = encode(&windows, version).unwrap();
+            assert!(compressed.len() < plain.len() / 4);
+            assert_eq!(decode(&compressed).unwrap().1, windows);
+        }
+        let mut uncompressed = Encoder::new(Vec::new(), 1)
+            .unwrap()
+            .with_compression_level(0);
+        uncompressed.write_window(&windows[0]).unwrap();
+        assert!(uncompressed.finish().unwrap().len() > plain.len());
+    }
+
+    #[test]
+    fn test_target_copy() {
+        let window = Window {
+            tview_len: 8,
+            ops: vec![
+                DeltaOp {
+                    kind: DeltaOpKind::New,
+                    offset: 0,
+                    length: 2,
+                },
+                DeltaOp {
+                    kind: DeltaOpKind::Target,
+                    offset: 0,
+                    length: 6,
+                },
+            ],
+            new_data: b"ab".to_vec(),
+            ..Default::default()
+        };
+        assert_eq!(window.apply(b"").unwrap(), b"abababab");
+        let (_, decoded) = decode(&encode(std::slice::from_ref(&window), 0).unwrap()).unwrap();
+        assert_eq!(decoded, vec![window]);
+    }
+
+    #[test]
+    fn test_malformed() {
+        assert!(decode(b"").is_err());
+        assert!(decode(b"SVX\x00").is_err());
+        assert!(decode(b"SVN\x03").is_err());
+        // Truncated new data.
+        assert!(decode(b"SVN\x00\x00\x00\x05\x01\x05\x85hel").is_err());
+        // Instructions produce fewer bytes than the target view.
+        assert!(decode(b"SVN\x00\x00\x00\x06\x01\x05\x85hello").is_err());
+        // Source copy outside an empty source view.
+        assert!(decode(b"SVN\x00\x00\x00\x05\x02\x00\x05\x00").is_err());
+        // Target copy from data not yet produced.
+        assert!(decode(b"SVN\x00\x00\x00\x05\x02\x00\x45\x00").is_err());
+        // Invalid instruction code.
+        assert!(decode(b"SVN\x00\x00\x00\x05\x01\x00\xc5").is_err());
+        // Corrupt compressed section.
+        assert!(decode(b"SVN\x01\x00\x00\x05\x01\x05\x00\x03\x10xyz").is_err());
+        // Every prefix of a valid stream is either valid or an error.
+        let windows = delta(&b""[..], &b"abc".repeat(300)[..])
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let encoded = encode(&windows, 1).unwrap();
+        for len in 0..encoded.len() {
+            let _ = decode(&encoded[..len]);
+        }
+    }
+
+    #[test]
+    fn test_matches_libsvn() {
+        let source = noise(5000, 3);
+        let mut target = source.clone();
+        target[100..200].copy_from_slice(&noise(100, 4));
+
+        for version in 0..=2 {
+            let mut source_stream = crate::io::Stream::from(&source[..]);
+            let mut target_stream = crate::io::Stream::from(&target[..]);
+            let mut output = crate::io::Stream::buffered();
+            super::super::svndiff_from_streams(
+                Some(&mut source_stream),
+                &mut target_stream,
+                &mut output,
+                version as i32,
+                -1,
+                crate::ChecksumKind::MD5,
+                None,
+            )
+            .unwrap();
+            let mut encoded = vec![0u8; 65536];
+            let n = output.read_full(&mut encoded).unwrap();
+            encoded.truncate(n);
+
+            let (decoded_version, windows) = decode(&encoded).unwrap();
+            assert_eq!(decoded_version, version);
+            let mut result = Vec::new();
+            apply(std::io::Cursor::new(&source), windows, &mut result).unwrap();
+            assert_eq!(result, target);
+        }
+
+        // And the other way around: libsvn reads our windows.
+        let windows = delta(&source[..], &target[..])
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let encoded = encode(&windows, 2).unwrap();
+        let mut stream = crate::io::Stream::from(&encoded[4..]);
+        let window = super::super::read_svndiff_window(&mut stream, 2)
+            .unwrap()
+            .unwrap();
+        assert_eq!(Window::try_from(&window).unwrap(), windows[0]);
+        assert_eq!(windows[0].to_tx_delta_window().ops(), window.ops());
+    }
+}
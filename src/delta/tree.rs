@@ -0,0 +1,830 @@
+//! Materializing editor drives as in-memory trees.
+//!
+//! [`TreeEditor`] applies an editor drive, such as the one produced by
+//! [`ra::Session::do_update`](crate::ra::Session::do_update) or
+//! [`ra::Session::replay`](crate::ra::Session::replay), to a [`Directory`]
+//! held in memory.  Starting from an empty tree yields the full tree at the
+//! target revision; starting from an existing tree applies the drive
+//! incrementally.  Text deltas are reconstructed into full file contents and
+//! checked against the checksums the driver sends.
+//!
+//! Only regular properties are kept; entry and working copy properties
+//! (`svn:entry:*`, `svn:wc:*`) are ignored.
+
+use super::window::Window;
+use super::{DirectoryEditor, Editor, FileEditor, TxDeltaWindow};
+use crate::props::Kind;
+use crate::{Error, Revnum};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
+
+type WindowHandler =
+    Box<dyn for<'a> Fn(&'a mut TxDeltaWindow) -> Result<(), crate::Error<'static>>>;
+
+/// A file in an in-memory tree.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct File {
+    /// Regular properties of the file.
+    pub props: BTreeMap<String, Vec<u8>>,
+    /// Full text of the file.
+    pub contents: Vec<u8>,
+}
+
+impl File {
+    /// Create a file with the given contents and no properties.
+    pub fn new(contents: &[u8]) -> Self {
+        Self {
+            props: BTreeMap::new(),
+            contents: contents.to_vec(),
+        }
+    }
+}
+
+/// A node in an in-memory tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node {
+    /// A directory.
+    Directory(Directory),
+    /// A file.
+    File(File),
+}
+
+impl Node {
+    /// The node as a directory, if it is one.
+    pub fn as_directory(&self) -> Option<&Directory> {
+        match self {
+            Node::Directory(dir) => Some(dir),
+            Node::File(_) => None,
+        }
+    }
+
+    /// The node as a file, if it is one.
+    pub fn as_file(&self) -> Option<&File> {
+        match self {
+            Node::File(file) => Some(file),
+            Node::Directory(_) => None,
+        }
+    }
+
+    /// Regular properties of the node.
+    pub fn props(&self) -> &BTreeMap<String, Vec<u8>> {
+        match self {
+            Node::Directory(dir) => &dir.props,
+            Node::File(file) => &file.props,
+        }
+    }
+
+    /// The kind of the node.
+    pub fn kind(&self) -> crate::NodeKind {
+        match self {
+            Node::Directory(_) => crate::NodeKind::Dir,
+            Node::File(_) => crate::NodeKind::File,
+        }
+    }
+}
+
+/// A directory in an in-memory tree.
+///
+/// Paths taken by the methods are relative to this directory and use `/`
+/// as separator, like the paths passed to a delta editor.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Directory {
+    /// Regular properties of the directory.
+    pub props: BTreeMap<String, Vec<u8>>,
+    /// Children, by name.
+    pub entries: BTreeMap<String, Node>,
+    /// Names of children the driver reported as absent, typically because
+    /// they are not readable.
+    pub absent: BTreeSet<String>,
+}
+
+fn split_path(path: &str) -> (&str, &str) {
+    path.rsplit_once('/').unwrap_or(("", path))
+}
+
+impl Directory {
+    /// Create an empty directory.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Look up the node at `path`.  The empty path has no node, use the
+    /// directory itself instead.
+    pub fn get(&self, path: &str) -> Option<&Node> {
+        let (parent, name) = split_path(path);
+        self.directory(parent)?.entries.get(name)
+    }
+
+    /// Look up the node at `path` for modification.
+    pub fn get_mut(&mut self, path: &str) -> Option<&mut Node> {
+        let (parent, name) = split_path(path);
+        self.directory_mut(parent)?.entries.get_mut(name)
+    }
+
+    /// Look up the directory at `path`; the empty path is this directory.
+    pub fn directory(&self, path: &str) -> Option<&Directory> {
+        if path.is_empty() {
+            return Some(self);
+        }
+        path.split('/')
+            .try_fold(self, |dir, name| match dir.entries.get(name)? {
+                Node::Directory(child) => Some(child),
+                Node::File(_) => None,
+            })
+    }
+
+    /// Look up the directory at `path` for modification.
+    pub fn directory_mut(&mut self, path: &str) -> Option<&mut Directory> {
+        if path.is_empty() {
+            return Some(self);
+        }
+        path.split('/')
+            .try_fold(self, |dir, name| match dir.entries.get_mut(name)? {
+                Node::Directory(child) => Some(child),
+                Node::File(_) => None,
+            })
+    }
+
+    /// Look up the file at `path`.
+    pub fn file(&self, path: &str) -> Option<&File> {
+        self.get(path)?.as_file()
+    }
+
+    /// Look up the file at `path` for modification.
+    pub fn file_mut(&mut self, path: &str) -> Option<&mut File> {
+        match self.get_mut(path)? {
+            Node::File(file) => Some(file),
+            Node::Directory(_) => None,
+        }
+    }
+
+    /// Insert `node` at `path`, returning the node it replaced.  The parent
+    /// directory must exist.
+    pub fn insert(&mut self, path: &str, node: Node) -> Option<Node> {
+        let (parent, name) = split_path(path);
+        let dir = self.directory_mut(parent)?;
+        dir.absent.remove(name);
+        dir.entries.insert(name.to_string(), node)
+    }
+
+    /// Remove and return the node at `path`.
+    pub fn remove(&mut self, path: &str) -> Option<Node> {
+        let (parent, name) = split_path(path);
+        self.directory_mut(parent)?.entries.remove(name)
+    }
+
+    /// All nodes below this directory, depth first in name order, with
+    /// their paths.
+    pub fn walk(&self) -> Vec<(String, &Node)> {
+        fn visit<'a>(dir: &'a Directory, prefix: &str, out: &mut Vec<(String, &'a Node)>) {
+            for (name, node) in &dir.entries {
+                let path = if prefix.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{}/{}", prefix, name)
+                };
+                out.push((path.clone(), node));
+                if let Node::Directory(child) = node {
+                    visit(child, &path, out);
+                }
+            }
+        }
+        let mut out = Vec::new();
+        visit(self, "", &mut out);
+        out
+    }
+}
+
+fn tree_error(code: subversion_sys::svn_errno_t, message: String) -> Error<'static> {
+    Error::with_raw_status(code as i32, None, &message)
+}
+
+fn not_found(path: &str) -> Error<'static> {
+    tree_error(
+        subversion_sys::svn_errno_t_SVN_ERR_ENTRY_NOT_FOUND,
+        format!("Path '{}' not found in tree", path),
+    )
+}
+
+fn md5_hex(data: &[u8]) -> Result<String, Error<'static>> {
+    let pool = apr::Pool::new();
+    let checksum = crate::checksum(crate::ChecksumKind::MD5, data, &pool)?;
+    Ok(checksum.to_hex(&pool))
+}
+
+fn verify_checksum(path: &str, data: &[u8], expected: Option<&str>) -> Result<(), Error<'static>> {
+    let Some(expected) = expected else {
+        return Ok(());
+    };
+    let actual = md5_hex(data)?;
+    if actual.eq_ignore_ascii_case(expected) {
+        Ok(())
+    } else {
+        Err(tree_error(
+            subversion_sys::svn_errno_t_SVN_ERR_CHECKSUM_MISMATCH,
+            format!(
+                "Checksum mismatch for '{}': expected {}, actual {}",
+                path, expected, actual
+            ),
+        ))
+    }
+}
+
+fn is_regular_prop(name: &str) -> Result<bool, Error<'static>> {
+    Ok(crate::props::kind(name).map_err(|e| e.into_static())? == Kind::Regular)
+}
+
+type CopySource = Box<dyn Fn(&str, Revnum) -> Option<Node> + Send>;
+
+#[derive(Default)]
+struct TreeState {
+    root: Directory,
+    /// The tree as it was when the drive started, for resolving copies.
+    base: Option<Directory>,
+    copy_source: Option<CopySource>,
+    target_revision: Option<Revnum>,
+}
+
+#[derive(Clone, Default)]
+struct SharedTree(Arc<Mutex<TreeState>>);
+
+impl SharedTree {
+    fn with<T>(&self, f: impl FnOnce(&mut TreeState) -> T) -> T {
+        f(&mut self.0.lock().unwrap())
+    }
+}
+
+/// A handle to the tree of a [`TreeEditor`], which stays usable after the
+/// editor was handed to [`WrapEditor::from_rust_editor`](super::WrapEditor::from_rust_editor).
+#[derive(Clone)]
+pub struct TreeHandle(SharedTree);
+
+impl TreeHandle {
+    /// A copy of the tree in its current state.
+    pub fn tree(&self) -> Directory {
+        self.0.with(|state| state.root.clone())
+    }
+
+    /// The target revision set by the driver, if any.
+    pub fn target_revision(&self) -> Option<Revnum> {
+        self.0.with(|state| state.target_revision)
+    }
+}
+
+/// An editor that applies the drive to an in-memory [`Directory`].
+///
+/// Copy sources are looked up relative to the root of the tree, as it was
+/// when [`open_root`](Editor::open_root) was called, so that later changes
+/// in the same drive (such as the deletion half of a rename) do not affect
+/// them.  This is only correct when the tree is rooted at the repository
+/// root and the drive copies from the revision the tree represents; use
+/// [`with_copy_source`](Self::with_copy_source) otherwise.
+#[derive(Default)]
+pub struct TreeEditor {
+    shared: SharedTree,
+}
+
+impl TreeEditor {
+    /// Create an editor that builds a tree from scratch.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an editor that applies the drive on top of `tree`.
+    pub fn from_tree(tree: Directory) -> Self {
+        Self {
+            shared: SharedTree(Arc::new(Mutex::new(TreeState {
+                root: tree,
+                ..Default::default()
+            }))),
+        }
+    }
+
+    /// Resolve copy sources with `lookup`, which is given the source path
+    /// (without leading slash) and revision and returns the node there.
+    ///
+    /// Needed when the drive copies from revisions other than the one the
+    /// tree represents, as replays of a range of revisions do.
+    pub fn with_copy_source(
+        self,
+        lookup: impl Fn(&str, Revnum) -> Option<Node> + Send + 'static,
+    ) -> Self {
+        self.shared
+            .with(|state| state.copy_source = Some(Box::new(lookup)));
+        self
+    }
+
+    /// A handle to the tree being built.
+    pub fn handle(&self) -> TreeHandle {
+        TreeHandle(self.shared.clone())
+    }
+
+    /// A copy of the tree in its current state.
+    pub fn tree(&self) -> Directory {
+        self.handle().tree()
+    }
+}
+
+impl Editor for TreeEditor {
+    type RootEditor = TreeDirectory;
+
+    fn set_target_revision(&mut self, revision: Option<Revnum>) -> Result<(), Error<'_>> {
+        self.shared.with(|state| state.target_revision = revision);
+        Ok(())
+    }
+
+    fn open_root(&mut self, _base_revision: Option<Revnum>) -> Result<TreeDirectory, Error<'_>> {
+        self.shared.with(|state| {
+            if state.copy_source.is_none() {
+                state.base = Some(state.root.clone());
+            }
+        });
+        Ok(TreeDirectory {
+            path: String::new(),
+            shared: self.shared.clone(),
+        })
+    }
+
+    fn close(&mut self) -> Result<(), Error<'_>> {
+        Ok(())
+    }
+
+    fn abort(&mut self) -> Result<(), Error<'_>> {
+        Ok(())
+    }
+}
+
+/// Directory editor of a [`TreeEditor`].
+pub struct TreeDirectory {
+    path: String,
+    shared: SharedTree,
+}
+
+impl TreeDirectory {
+    fn add_node(
+        &self,
+        path: &str,
+        copyfrom: Option<(&str, Revnum)>,
+        empty: Node,
+    ) -> Result<(), Error<'static>> {
+        self.shared.with(|state| {
+            let parent = split_path(path).0;
+            if state.root.directory(parent).is_none() {
+                return Err(not_found(parent));
+            }
+            if state.root.get(path).is_some() {
+                return Err(tree_error(
+                    subversion_sys::svn_errno_t_SVN_ERR_ENTRY_EXISTS,
+                    format!("Path '{}' already exists in tree", path),
+                ));
+            }
+            let node = match copyfrom {
+                Some((source, revision)) => {
+                    let source = source.trim_start_matches('/');
+                    let node = match &state.copy_source {
+                        Some(lookup) => lookup(source, revision),
+                        None => state
+                            .base
+                            .as_ref()
+                            .unwrap_or(&state.root)
+                            .get(source)
+                            .cloned(),
+                    }
+                    .ok_or_else(|| not_found(source))?;
+                    if node.kind() != empty.kind() {
+                        return Err(tree_error(
+                            subversion_sys::svn_errno_t_SVN_ERR_NODE_UNEXPECTED_KIND,
+                            format!("Copy source '{}' has unexpected kind", source),
+                        ));
+                    }
+                    node
+                }
+                None => empty,
+            };
+            state.root.insert(path, node);
+            Ok(())
+        })
+    }
+}
+
+impl DirectoryEditor for TreeDirectory {
+    type SubDirectory = TreeDirectory;
+    type File = TreeFile;
+
+    fn delete_entry(&mut self, path: &str, _revision: Option<Revnum>) -> Result<(), Error<'_>> {
+        self.shared.with(|state| {
+            state
+                .root
+                .remove(path)
+                .map(|_| ())
+                .ok_or_else(|| not_found(path))
+        })
+    }
+
+    fn add_directory(
+        &mut self,
+        path: &str,
+        copyfrom: Option<(&str, Revnum)>,
+    ) -> Result<TreeDirectory, Error<'_>> {
+        self.add_node(path, copyfrom, Node::Directory(Directory::new()))?;
+        Ok(TreeDirectory {
+            path: path.to_string(),
+            shared: self.shared.clone(),
+        })
+    }
+
+    fn open_directory(
+        &mut self,
+        path: &str,
+        _base_revision: Option<Revnum>,
+    ) -> Result<TreeDirectory, Error<'_>> {
+        self.shared.with(|state| {
+            state
+                .root
+                .directory(path)
+                .map(|_| ())
+                .ok_or_else(|| not_found(path))
+        })?;
+        Ok(TreeDirectory {
+            path: path.to_string(),
+            shared: self.shared.clone(),
+        })
+    }
+
+    fn change_prop(&mut self, name: &str, value: Option<&[u8]>) -> Result<(), Error<'_>> {
+        if !is_regular_prop(name)? {
+            return Ok(());
+        }
+        self.shared.with(|state| {
+            let dir = state
+                .root
+                .directory_mut(&self.path)
+                .ok_or_else(|| not_found(&self.path))?;
+            match value {
+                Some(value) => dir.props.insert(name.to_string(), value.to_vec()),
+                None => dir.props.remove(name),
+            };
+            Ok(())
+        })
+    }
+
+    fn close(&mut self) -> Result<(), Error<'_>> {
+        Ok(())
+    }
+
+    fn absent_directory(&mut self, path: &str) -> Result<(), Error<'_>> {
+        self.absent_file(path)
+    }
+
+    fn add_file(
+        &mut self,
+        path: &str,
+        copyfrom: Option<(&str, Revnum)>,
+    ) -> Result<TreeFile, Error<'_>> {
+        self.add_node(path, copyfrom, Node::File(File::default()))?;
+        Ok(TreeFile {
+            path: path.to_string(),
+            shared: self.shared.clone(),
+        })
+    }
+
+    fn open_file(
+        &mut self,
+        path: &str,
+        _base_revision: Option<Revnum>,
+    ) -> Result<TreeFile, Error<'_>> {
+        self.shared.with(|state| {
+            state
+                .root
+                .file(path)
+                .map(|_| ())
+                .ok_or_else(|| not_found(path))
+        })?;
+        Ok(TreeFile {
+            path: path.to_string(),
+            shared: self.shared.clone(),
+        })
+    }
+
+    fn absent_file(&mut self, path: &str) -> Result<(), Error<'_>> {
+        self.shared.with(|state| {
+            let (parent, name) = split_path(path);
+            let dir = state
+                .root
+                .directory_mut(parent)
+                .ok_or_else(|| not_found(parent))?;
+            dir.entries.remove(name);
+            dir.absent.insert(name.to_string());
+            Ok(())
+        })
+    }
+}
+
+/// File editor of a [`TreeEditor`].
+pub struct TreeFile {
+    path: String,
+    shared: SharedTree,
+}
+
+impl TreeFile {
+    fn contents(&self) -> Result<Vec<u8>, Error<'static>> {
+        self.shared.with(|state| {
+            state
+                .root
+                .file(&self.path)
+                .map(|file| file.contents.clone())
+                .ok_or_else(|| not_found(&self.path))
+        })
+    }
+}
+
+impl FileEditor for TreeFile {
+    fn apply_textdelta(
+        &mut self,
+        base_checksum: Option<&str>,
+    ) -> Result<WindowHandler, Error<'static>> {
+        let source = self.contents()?;
+        verify_checksum(&self.path, &source, base_checksum)?;
+        let target = Mutex::new(Vec::new());
+        let path = self.path.clone();
+        let shared = self.shared.clone();
+        Ok(Box::new(move |window| {
+            if window.as_ptr().is_null() {
+                let contents = std::mem::take(&mut *target.lock().unwrap());
+                return shared.with(|state| {
+                    let file = state.root.file_mut(&path).ok_or_else(|| not_found(&path))?;
+                    file.contents = contents;
+                    Ok(())
+                });
+            }
+            let window = Window::try_from(&*window)?;
+            let view = source
+                .get(window.sview_offset as usize..)
+                .unwrap_or_default();
+            target.lock().unwrap().extend(window.apply(view)?);
+            Ok(())
+        }))
+    }
+
+    fn change_prop(&mut self, name: &str, value: Option<&[u8]>) -> Result<(), Error<'static>> {
+        if !is_regular_prop(name)? {
+            return Ok(());
+        }
+        self.shared.with(|state| {
+            let file = state
+                .root
+                .file_mut(&self.path)
+                .ok_or_else(|| not_found(&self.path))?;
+            match value {
+                Some(value) => file.props.insert(name.to_string(), value.to_vec()),
+                None => file.props.remove(name),
+            };
+            Ok(())
+        })
+    }
+
+    fn close(&mut self, text_checksum: Option<&str>) -> Result<(), Error<'static>> {
+        let contents = self.contents()?;
+        verify_checksum(&self.path, &contents, text_checksum)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn send_text(file: &mut TreeFile, base: Option<&str>, ops: &[(i32, u64, u64)], data: &[u8]) {
+        let handler = file.apply_textdelta(base).unwrap();
+        let sview_len = ops
+            .iter()
+            .filter(|op| op.0 == 0)
+            .map(|op| op.1 + op.2)
+            .max()
+            .unwrap_or(0);
+        let tview_len = ops.iter().map(|op| op.2).sum();
+        handler(&mut TxDeltaWindow::from_parts(
+            0, sview_len, tview_len, 0, ops, data,
+        ))
+        .unwrap();
+        handler(&mut crate::delta::record::end_of_delta()).unwrap();
+    }
+
+    #[test]
+    fn test_build_tree() {
+        let mut editor = TreeEditor::new();
+        editor.set_target_revision(Some(Revnum(1))).unwrap();
+        let mut root = editor.open_root(None).unwrap();
+        root.change_prop("svn:entry:committed-rev", Some(b"1"))
+            .unwrap();
+        let mut trunk = root.add_directory("trunk", None).unwrap();
+        trunk.change_prop("svn:ignore", Some(b"*.o\n")).unwrap();
+        let mut file = trunk.add_file("trunk/a.txt", None).unwrap();
+        send_text(&mut file, None, &[(2, 0, 6)], b"hello\n");
+        file.change_prop("svn:eol-style", Some(b"native")).unwrap();
+        file.close(Some("b1946ac92492d2347c6235b4d2611184"))
+            .unwrap();
+        trunk.close().unwrap();
+        root.absent_directory("secret").unwrap();
+        root.close().unwrap();
+        editor.close().unwrap();
+
+        let tree = editor.tree();
+        assert_eq!(editor.handle().target_revision(), Some(Revnum(1)));
+        assert!(tree.props.is_empty());
+        assert_eq!(
+            tree.directory("trunk").unwrap().props["svn:ignore"],
+            b"*.o\n"
+        );
+        let file = tree.file("trunk/a.txt").unwrap();
+        assert_eq!(file.contents, b"hello\n");
+        assert_eq!(file.props["svn:eol-style"], b"native");
+        assert!(tree.absent.contains("secret"));
+        assert_eq!(
+            tree.walk()
+                .into_iter()
+                .map(|(path, _)| path)
+                .collect::<Vec<_>>(),
+            ["trunk", "trunk/a.txt"]
+        );
+    }
+
+    #[test]
+    fn test_incremental() {
+        let mut base = Directory::new();
+        base.insert("a.txt", Node::File(File::new(b"hello\n")));
+        base.insert("old", Node::Directory(Directory::new()));
+
+        let mut editor = TreeEditor::from_tree(base);
+        let mut root = editor.open_root(Some(Revnum(1))).unwrap();
+        root.delete_entry("old", Some(Revnum(1))).unwrap();
+        let mut file = root.open_file("a.txt", Some(Revnum(1))).unwrap();
+        // "hello\n" + "hello\n" via a target copy + "bye\n".
+        send_text(
+            &mut file,
+            Some("b1946ac92492d2347c6235b4d2611184"),
+            &[(0, 0, 6), (1, 0, 6), (2, 0, 4)],
+            b"bye\n",
+        );
+        file.close(None).unwrap();
+        let mut copy = root.add_file("b.txt", Some(("/a.txt", Revnum(1)))).unwrap();
+        copy.close(None).unwrap();
+        root.close().unwrap();
+
+        let tree = editor.tree();
+        assert!(tree.get("old").is_none());
+        assert_eq!(tree.file("a.txt").unwrap().contents, b"hello\nhello\nbye\n");
+        // The copy is of a.txt@1, not of the text changed in this drive.
+        assert_eq!(tree.file("b.txt").unwrap().contents, b"hello\n");
+    }
+
+    #[test]
+    fn test_rename() {
+        let mut base = Directory::new();
+        base.insert("dir", Node::Directory(Directory::new()));
+        base.insert("dir/old.txt", Node::File(File::new(b"hello\n")));
+
+        // A replayed rename deletes the source before adding the copy.
+        let mut editor = TreeEditor::from_tree(base);
+        let mut root = editor.open_root(Some(Revnum(1))).unwrap();
+        let mut dir = root.open_directory("dir", Some(Revnum(1))).unwrap();
+        dir.delete_entry("dir/old.txt", Some(Revnum(1))).unwrap();
+        let mut file = dir
+            .add_file("dir/new.txt", Some(("/dir/old.txt", Revnum(1))))
+            .unwrap();
+        file.close(Some("b1946ac92492d2347c6235b4d2611184"))
+            .unwrap();
+        dir.close().unwrap();
+        root.close().unwrap();
+        editor.close().unwrap();
+
+        let tree = editor.tree();
+        assert!(tree.get("dir/old.txt").is_none());
+        assert_eq!(tree.file("dir/new.txt").unwrap().contents, b"hello\n");
+
+        // Copies from other revisions go through the lookup.
+        let mut editor = TreeEditor::from_tree(tree).with_copy_source(|path, revision| {
+            (path == "dir/old.txt" && revision == Revnum(1))
+                .then(|| Node::File(File::new(b"older\n")))
+        });
+        let mut root = editor.open_root(Some(Revnum(2))).unwrap();
+        let mut file = root
+            .add_file("restored.txt", Some(("/dir/old.txt", Revnum(1))))
+            .unwrap();
+        file.close(None).unwrap();
+        assert!(root
+            .add_file("missing.txt", Some(("/dir/new.txt", Revnum(2))))
+            .is_err());
+        root.close().unwrap();
+        assert_eq!(
+            editor.tree().file("restored.txt").unwrap().contents,
+            b"older\n"
+        );
+    }
+
+    #[test]
+    fn test_checksum_mismatch() {
+        let mut base = Directory::new();
+        base.insert("a.txt", Node::File(File::new(b"hello\n")));
+        let mut editor = TreeEditor::from_tree(base);
+        let mut root = editor.open_root(None).unwrap();
+
+        let mut file = root.open_file("a.txt", None).unwrap();
+        let err = file
+            .apply_textdelta(Some("00000000000000000000000000000000"))
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.raw_apr_err(),
+            subversion_sys::svn_errno_t_SVN_ERR_CHECKSUM_MISMATCH as i32
+        );
+        let err = file
+            .close(Some("00000000000000000000000000000000"))
+            .unwrap_err();
+        assert_eq!(
+            err.raw_apr_err(),
+            subversion_sys::svn_errno_t_SVN_ERR_CHECKSUM_MISMATCH as i32
+        );
+
+        assert!(root.open_file("missing", None).is_err());
+        assert!(root.add_file("a.txt", None).is_err());
+    }
+
+    #[cfg(all(feature = "ra", feature = "repos"))]
+    #[test]
+    fn test_do_update() {
+        use crate::ra::Reporter;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repos_path = temp_dir.path().join("repo");
+        let repos = crate::repos::Repos::create(&repos_path).unwrap();
+        let fs = repos.fs().unwrap();
+        let mut txn = fs.begin_txn(Revnum(0), 0).unwrap();
+        let mut root = txn.root().unwrap();
+        root.make_dir("/trunk").unwrap();
+        root.make_file("/trunk/a.txt").unwrap();
+        root.set_file_contents("/trunk/a.txt", b"one\n").unwrap();
+        root.change_node_prop("/trunk/a.txt", "custom", b"value")
+            .unwrap();
+        txn.commit().unwrap();
+        let mut txn = fs.begin_txn(Revnum(1), 0).unwrap();
+        let mut root = txn.root().unwrap();
+        root.set_file_contents("/trunk/a.txt", b"one\ntwo\n")
+            .unwrap();
+        root.make_file("/trunk/b.txt").unwrap();
+        root.set_file_contents("/trunk/b.txt", b"new\n").unwrap();
+        txn.commit().unwrap();
+
+        let url = crate::path_to_file_url(&repos_path);
+        let (mut session, _, _) = crate::ra::Session::open(&url, None, None, None).unwrap();
+
+        let tree_editor = TreeEditor::new();
+        let handle = tree_editor.handle();
+        let mut editor = crate::delta::WrapEditor::from_rust_editor(tree_editor);
+        let mut reporter = session
+            .do_update(
+                Revnum(1),
+                "",
+                crate::Depth::Infinity,
+                false,
+                false,
+                &mut editor,
+            )
+            .unwrap();
+        reporter
+            .set_path("", Revnum(1), crate::Depth::Infinity, true, "")
+            .unwrap();
+        reporter.finish_report().unwrap();
+        drop(reporter);
+
+        let tree = handle.tree();
+        assert_eq!(handle.target_revision(), Some(Revnum(1)));
+        let a = tree.file("trunk/a.txt").unwrap();
+        assert_eq!(a.contents, b"one\n");
+        assert_eq!(a.props["custom"], b"value");
+        assert!(tree.get("trunk/b.txt").is_none());
+
+        let tree_editor = TreeEditor::from_tree(tree);
+        let handle = tree_editor.handle();
+        let mut editor = crate::delta::WrapEditor::from_rust_editor(tree_editor);
+        let mut reporter = session
+            .do_update(
+                Revnum(2),
+                "",
+                crate::Depth::Infinity,
+                false,
+                false,
+                &mut editor,
+            )
+            .unwrap();
+        reporter
+            .set_path("", Revnum(1), crate::Depth::Infinity, false, "")
+            .unwrap();
+        reporter.finish_report().unwrap();
+        drop(reporter);
+
+        let tree = handle.tree();
+        assert_eq!(tree.file("trunk/a.txt").unwrap().contents, b"one\ntwo\n");
+        assert_eq!(tree.file("trunk/a.txt").unwrap().props["custom"], b"value");
+        assert_eq!(tree.file("trunk/b.txt").unwrap().contents, b"new\n");
+    }
+}
@@ -0,0 +1,279 @@
+//! Owned text delta windows.
+//!
+//! [`Window`] is a plain-data copy of a text delta window.  It is what
+//! [`record`](super::record) stores in editor traces, what
+//! [`tree`](super::tree) applies to file contents and, with the `svndiff`
+//! feature, what the pure-Rust svndiff codec reads and writes.
+
+use crate::Error;
+
+fn invalid(what: &str) -> Error<'static> {
+    Error::with_raw_status(
+        subversion_sys::svn_errno_t_SVN_ERR_SVNDIFF_INVALID_OPS as i32,
+        None,
+        &format!("Invalid delta window: {}", what),
+    )
+}
+
+/// What a [`DeltaOp`] copies from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DeltaOpKind {
+    /// Copy from the source view.
+    Source,
+    /// Copy from the part of the target view that was already produced.
+    Target,
+    /// Copy from the window's new data.
+    New,
+}
+
+impl DeltaOpKind {
+    pub(crate) fn action_code(self) -> u8 {
+        match self {
+            DeltaOpKind::Source => 0,
+            DeltaOpKind::Target => 1,
+            DeltaOpKind::New => 2,
+        }
+    }
+
+    pub(crate) fn from_action_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(DeltaOpKind::Source),
+            1 => Some(DeltaOpKind::Target),
+            2 => Some(DeltaOpKind::New),
+            _ => None,
+        }
+    }
+}
+
+/// A single delta instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DeltaOp {
+    /// Where the data is copied from.
+    pub kind: DeltaOpKind,
+    /// Offset into the source view, target view or new data.
+    pub offset: u64,
+    /// Number of bytes to copy.
+    pub length: u64,
+}
+
+/// An owned delta window, the pure-Rust equivalent of
+/// [`TxDeltaWindow`](super::TxDeltaWindow).
+///
+/// Unlike a [`TxDeltaWindow`](super::TxDeltaWindow) it does not live in an
+/// APR pool, so it can be stored, compared and sent between threads.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Window {
+    /// Offset of the source view in the source.
+    pub sview_offset: u64,
+    /// Length of the source view.
+    pub sview_len: u64,
+    /// Length of the target view produced by this window.
+    pub tview_len: u64,
+    /// The instructions.
+    pub ops: Vec<DeltaOp>,
+    /// Data referenced by [`DeltaOpKind::New`] instructions.
+    pub new_data: Vec<u8>,
+}
+
+impl Window {
+    /// Number of instructions that copy from the source view.
+    pub fn src_ops(&self) -> usize {
+        self.ops
+            .iter()
+            .filter(|op| op.kind == DeltaOpKind::Source)
+            .count()
+    }
+
+    /// Check that every instruction stays within its view and that the
+    /// instructions produce exactly `tview_len` bytes.
+    pub fn validate(&self) -> Result<(), Error<'static>> {
+        let mut tpos = 0u64;
+        for op in &self.ops {
+            let end = op
+                .offset
+                .checked_add(op.length)
+                .ok_or_else(|| invalid("instruction length overflows"))?;
+            match op.kind {
+                DeltaOpKind::Source if end > self.sview_len => {
+                    return Err(invalid("source copy beyond the source view"));
+                }
+                DeltaOpKind::Target if op.offset >= tpos => {
+                    return Err(invalid("target copy starts beyond the produced data"));
+                }
+                DeltaOpKind::New if end > self.new_data.len() as u64 => {
+                    return Err(invalid("new data copy beyond the new data"));
+                }
+                _ => {}
+            }
+            tpos = tpos
+                .checked_add(op.length)
+                .ok_or_else(|| invalid("target view length overflows"))?;
+        }
+        if tpos != self.tview_len {
+            return Err(invalid(
+                "instructions do not produce the target view length",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Apply the window to `source_view`, returning the target view.
+    pub fn apply(&self, source_view: &[u8]) -> Result<Vec<u8>, Error<'static>> {
+        self.validate()?;
+        if (source_view.len() as u64) < self.sview_len {
+            return Err(invalid("source view is shorter than the window"));
+        }
+        // Target copies can repeat data arbitrarily often, so only reserve
+        // what the source view and new data can provide; the rest grows.
+        let reserve = self
+            .tview_len
+            .min(self.sview_len.saturating_add(self.new_data.len() as u64));
+        let mut target = Vec::with_capacity(reserve as usize);
+        for op in &self.ops {
+            let (offset, length) = (op.offset as usize, op.length as usize);
+            match op.kind {
+                DeltaOpKind::Source => {
+                    target.extend_from_slice(&source_view[offset..offset + length])
+                }
+                DeltaOpKind::New => {
+                    target.extend_from_slice(&self.new_data[offset..offset + length])
+                }
+                // Target copies may overlap the bytes they produce, which
+                // is how runs are encoded, so copy byte by byte.
+                DeltaOpKind::Target => {
+                    for i in 0..length {
+                        target.push(target[offset + i]);
+                    }
+                }
+            }
+        }
+        Ok(target)
+    }
+
+    /// Convert into a pool-allocated [`TxDeltaWindow`](super::TxDeltaWindow).
+    pub fn to_tx_delta_window(&self) -> super::TxDeltaWindow {
+        let ops: Vec<_> = self
+            .ops
+            .iter()
+            .map(|op| (op.kind.action_code() as i32, op.offset, op.length))
+            .collect();
+        super::TxDeltaWindow::from_parts(
+            self.sview_offset,
+            self.sview_len,
+            self.tview_len,
+            self.src_ops() as i32,
+            &ops,
+            &self.new_data,
+        )
+    }
+}
+
+impl TryFrom<&super::TxDeltaWindow> for Window {
+    type Error = Error<'static>;
+
+    fn try_from(window: &super::TxDeltaWindow) -> Result<Self, Self::Error> {
+        let ops = window
+            .ops()
+            .into_iter()
+            .map(|(code, offset, length)| {
+                let kind = u8::try_from(code)
+                    .ok()
+                    .and_then(DeltaOpKind::from_action_code)
+                    .ok_or_else(|| invalid(&format!("invalid action code {}", code)))?;
+                Ok(DeltaOp {
+                    kind,
+                    offset,
+                    length,
+                })
+            })
+            .collect::<Result<Vec<_>, Error<'static>>>()?;
+        Ok(Self {
+            sview_offset: window.sview_offset() as u64,
+            sview_len: window.sview_len() as u64,
+            tview_len: window.tview_len() as u64,
+            ops,
+            new_data: window.new_data().to_vec(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_target_copy() {
+        let window = Window {
+            tview_len: 8,
+            ops: vec![
+                DeltaOp {
+                    kind: DeltaOpKind::New,
+                    offset: 0,
+                    length: 2,
+                },
+                DeltaOp {
+                    kind: DeltaOpKind::Target,
+                    offset: 0,
+                    length: 6,
+                },
+            ],
+            new_data: b"ab".to_vec(),
+            ..Default::default()
+        };
+        assert_eq!(window.apply(b"").unwrap(), b"abababab");
+    }
+
+    #[test]
+    fn test_tx_delta_window_round_trip() {
+        let window = Window {
+            sview_offset: 2,
+            sview_len: 3,
+            tview_len: 5,
+            ops: vec![
+                DeltaOp {
+                    kind: DeltaOpKind::Source,
+                    offset: 1,
+                    length: 2,
+                },
+                DeltaOp {
+                    kind: DeltaOpKind::New,
+                    offset: 0,
+                    length: 3,
+                },
+            ],
+            new_data: b"xyz".to_vec(),
+        };
+        let tx = window.to_tx_delta_window();
+        assert_eq!(tx.ops(), vec![(0, 1, 2), (2, 0, 3)]);
+        assert_eq!(Window::try_from(&tx).unwrap(), window);
+        assert_eq!(window.apply(b"abc").unwrap(), b"bcxyz");
+        assert!(window.apply(b"ab").is_err());
+
+        let bogus = super::super::TxDeltaWindow::from_parts(0, 0, 3, 0, &[(7, 0, 3)], b"abc");
+        assert!(Window::try_from(&bogus).is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut window = Window {
+            sview_len: 2,
+            tview_len: 3,
+            ops: vec![DeltaOp {
+                kind: DeltaOpKind::Source,
+                offset: 0,
+                length: 3,
+            }],
+            ..Default::default()
+        };
+        assert!(window.validate().is_err());
+        window.sview_len = 3;
+        window.validate().unwrap();
+        window.tview_len = 4;
+        assert!(window.validate().is_err());
+        window.tview_len = 3;
+        window.ops[0].kind = DeltaOpKind::New;
+        assert!(window.validate().is_err());
+        window.ops[0].kind = DeltaOpKind::Target;
+        assert!(window.validate().is_err());
+    }
+}
@@ -1,5 +1,10 @@
 use crate::{svn_result, with_tmp_pool, Error};
 
+/// Applying patches to in-memory content.
+pub mod apply;
+/// An owned patch model, and writing it out as a unified diff.
+pub mod patch;
+
 /// Options for diff operations
 #[derive(Debug, Clone, Copy, Default)]
 pub struct DiffOptions {
@@ -82,6 +87,60 @@ impl DiffHunk<'_> {
     pub fn trailing_context(&self) -> u64 {
         unsafe { subversion_sys::svn_diff_hunk_get_trailing_context(self.ptr).into() }
     }
+
+    /// Read the lines of the hunk, including their line endings.
+    ///
+    /// A line without a line ending is one that was followed by a
+    /// "\\ No newline at end of ..." marker.
+    pub fn lines(&self) -> Result<Vec<patch::HunkLine>, Error<'static>> {
+        let pool = apr::Pool::new();
+        let mut lines: Vec<patch::HunkLine> = Vec::new();
+        unsafe { subversion_sys::svn_diff_hunk_reset_diff_text(self.ptr) };
+        loop {
+            let mut buf = std::ptr::null_mut();
+            let mut eol = std::ptr::null();
+            let mut eof = 0;
+            let err = unsafe {
+                subversion_sys::svn_diff_hunk_readline_diff_text(
+                    self.ptr,
+                    &mut buf,
+                    &mut eol,
+                    &mut eof,
+                    pool.as_mut_ptr(),
+                    pool.as_mut_ptr(),
+                )
+            };
+            svn_result(err)?;
+            let text = unsafe { std::slice::from_raw_parts((*buf).data as *const u8, (*buf).len) };
+            let eol: &[u8] = if eol.is_null() {
+                b""
+            } else {
+                unsafe { std::ffi::CStr::from_ptr(eol) }.to_bytes()
+            };
+            if text.is_empty() && !eol.is_empty() {
+                // An empty line is context whose leading space was trimmed.
+                lines.push(patch::HunkLine::Context(eol.to_vec()));
+            } else if let Some((&marker, rest)) = text.split_first() {
+                let mut content = rest.to_vec();
+                content.extend_from_slice(eol);
+                match marker {
+                    b' ' => lines.push(patch::HunkLine::Context(content)),
+                    b'-' => lines.push(patch::HunkLine::Removed(content)),
+                    b'+' => lines.push(patch::HunkLine::Added(content)),
+                    b'\\' => {
+                        if let Some(last) = lines.last_mut() {
+                            last.strip_line_ending();
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            if eof != 0 {
+                break;
+            }
+        }
+        Ok(lines)
+    }
 }
 
 /// The operation a patch performs on a file.
@@ -97,6 +156,8 @@ pub enum PatchOperation {
     Copied,
     /// The file is moved.
     Moved,
+    /// The file is modified in place.
+    Modified,
 }
 
 impl From<subversion_sys::svn_diff_operation_kind_t> for PatchOperation {
@@ -111,6 +172,9 @@ impl From<subversion_sys::svn_diff_operation_kind_t> for PatchOperation {
             }
             subversion_sys::svn_diff_operation_kind_e_svn_diff_op_copied => PatchOperation::Copied,
             subversion_sys::svn_diff_operation_kind_e_svn_diff_op_moved => PatchOperation::Moved,
+            subversion_sys::svn_diff_operation_kind_e_svn_diff_op_modified => {
+                PatchOperation::Modified
+            }
             _ => unreachable!("unknown svn_diff_operation_kind_t value: {}", kind),
         }
     }
@@ -156,18 +220,164 @@ impl Patch<'_> {
 
     /// The hunks that make up this patch.
     pub fn hunks(&self) -> Vec<DiffHunk<'_>> {
+        unsafe { hunks_from_array((*self.ptr).hunks) }
+    }
+
+    /// Whether the patch was parsed in reverse.
+    pub fn reverse(&self) -> bool {
+        unsafe { (*self.ptr).reverse != 0 }
+    }
+
+    /// The property changes in this patch, sorted by property name.
+    pub fn prop_patches(&self) -> Vec<PropPatch<'_>> {
+        let hash = unsafe { (*self.ptr).prop_patches };
+        if hash.is_null() {
+            return Vec::new();
+        }
+        let hash =
+            unsafe { apr::hash::TypedHash::<subversion_sys::svn_prop_patch_t>::from_ptr(hash) };
+        let mut prop_patches: Vec<_> = hash
+            .iter()
+            .map(|(_, prop_patch)| PropPatch {
+                ptr: prop_patch as *const _ as *mut _,
+                _phantom: std::marker::PhantomData,
+            })
+            .collect();
+        prop_patches.sort_by(|a, b| a.name().cmp(b.name()));
+        prop_patches
+    }
+
+    /// The binary contents of this patch, from a git "GIT binary patch"
+    /// section.
+    pub fn binary_patch(&self) -> Option<BinaryPatch<'_>> {
+        let ptr = unsafe { (*self.ptr).binary_patch };
+        if ptr.is_null() {
+            None
+        } else {
+            Some(BinaryPatch {
+                ptr,
+                _phantom: std::marker::PhantomData,
+            })
+        }
+    }
+
+    /// Whether the file was executable before the patch, if known.
+    pub fn old_executable_bit(&self) -> Option<bool> {
+        tristate_to_option(unsafe { (*self.ptr).old_executable_bit })
+    }
+
+    /// Whether the file is executable after the patch, if known.
+    pub fn new_executable_bit(&self) -> Option<bool> {
+        tristate_to_option(unsafe { (*self.ptr).new_executable_bit })
+    }
+
+    /// Whether the file was a symlink before the patch, if known.
+    pub fn old_symlink_bit(&self) -> Option<bool> {
+        tristate_to_option(unsafe { (*self.ptr).old_symlink_bit })
+    }
+
+    /// Whether the file is a symlink after the patch, if known.
+    pub fn new_symlink_bit(&self) -> Option<bool> {
+        tristate_to_option(unsafe { (*self.ptr).new_symlink_bit })
+    }
+
+    /// Apply the text changes of this patch to `content`, without a working
+    /// copy.
+    ///
+    /// See [`apply::apply_patch`].  If the patch was parsed in reverse, its
+    /// hunks are already reversed; [`apply::ApplyOptions::reverse`] reverses
+    /// them again.
+    pub fn apply_to(
+        &self,
+        content: &[u8],
+        options: &apply::ApplyOptions,
+    ) -> Result<apply::PatchedContent, Error<'static>> {
+        let patch = patch::Patch::try_from(self)?;
+        Ok(apply::apply_patch(&patch, content, options))
+    }
+}
+
+unsafe fn hunks_from_array<'a>(array: *mut apr::tables::apr_array_header_t) -> Vec<DiffHunk<'a>> {
+    if array.is_null() {
+        return Vec::new();
+    }
+    let arr = &*array;
+    let elts = arr.elts as *const *mut subversion_sys::svn_diff_hunk_t;
+    (0..arr.nelts as isize)
+        .map(|i| DiffHunk::from_raw(*elts.offset(i)))
+        .collect()
+}
+
+fn tristate_to_option(ts: subversion_sys::svn_tristate_t) -> Option<bool> {
+    match ts {
+        subversion_sys::svn_tristate_t_svn_tristate_false => Some(false),
+        subversion_sys::svn_tristate_t_svn_tristate_true => Some(true),
+        _ => None, // svn_tristate_unknown
+    }
+}
+
+/// The changes to a single property within a [`Patch`].
+///
+/// Borrows from the [`PatchFile`] that produced it.
+pub struct PropPatch<'a> {
+    ptr: *mut subversion_sys::svn_prop_patch_t,
+    _phantom: std::marker::PhantomData<&'a PatchFile>,
+}
+
+impl PropPatch<'_> {
+    /// The name of the property.
+    pub fn name(&self) -> &str {
         unsafe {
-            let array = (*self.ptr).hunks;
-            if array.is_null() {
-                return Vec::new();
-            }
-            let arr = &*array;
-            let elts = arr.elts as *const *mut subversion_sys::svn_diff_hunk_t;
-            (0..arr.nelts as isize)
-                .map(|i| DiffHunk::from_raw(*elts.offset(i)))
-                .collect()
+            std::ffi::CStr::from_ptr((*self.ptr).name)
+                .to_str()
+                .unwrap_or_default()
         }
     }
+
+    /// Whether the property is added, deleted or modified.
+    pub fn operation(&self) -> PatchOperation {
+        unsafe { (*self.ptr).operation.into() }
+    }
+
+    /// The hunks that make up the property change.
+    pub fn hunks(&self) -> Vec<DiffHunk<'_>> {
+        unsafe { hunks_from_array((*self.ptr).hunks) }
+    }
+}
+
+/// The contents of a git binary patch within a [`Patch`].
+///
+/// Borrows from the [`PatchFile`] that produced it.
+pub struct BinaryPatch<'a> {
+    ptr: *mut subversion_sys::svn_diff_binary_patch_t,
+    _phantom: std::marker::PhantomData<&'a PatchFile>,
+}
+
+impl BinaryPatch<'_> {
+    fn read(
+        &self,
+        get_stream: unsafe extern "C" fn(
+            *const subversion_sys::svn_diff_binary_patch_t,
+            *mut apr_sys::apr_pool_t,
+        ) -> *mut subversion_sys::svn_stream_t,
+    ) -> Result<Vec<u8>, Error<'static>> {
+        let pool = apr::Pool::new();
+        let ptr = unsafe { get_stream(self.ptr, pool.as_mut_ptr()) };
+        let mut stream = crate::io::Stream::from_ptr(ptr, pool);
+        let mut contents = Vec::new();
+        std::io::Read::read_to_end(&mut stream, &mut contents)?;
+        Ok(contents)
+    }
+
+    /// The contents of the file before the patch.
+    pub fn original(&self) -> Result<Vec<u8>, Error<'static>> {
+        self.read(subversion_sys::svn_diff_get_binary_diff_original_stream)
+    }
+
+    /// The contents of the file after the patch.
+    pub fn result(&self) -> Result<Vec<u8>, Error<'static>> {
+        self.read(subversion_sys::svn_diff_get_binary_diff_result_stream)
+    }
 }
 
 /// A patch file opened for parsing.
@@ -241,11 +451,20 @@ impl Drop for PatchFile {
 pub struct Diff {
     ptr: *mut subversion_sys::svn_diff_t,
     _pool: apr::Pool<'static>,
+    three_way: bool,
 }
 
 impl Diff {
-    unsafe fn from_raw(ptr: *mut subversion_sys::svn_diff_t, pool: apr::Pool<'static>) -> Self {
-        Self { ptr, _pool: pool }
+    unsafe fn from_raw(
+        ptr: *mut subversion_sys::svn_diff_t,
+        pool: apr::Pool<'static>,
+        three_way: bool,
+    ) -> Self {
+        Self {
+            ptr,
+            _pool: pool,
+            three_way,
+        }
     }
 
     /// Check if the diff contains any changes
@@ -262,6 +481,269 @@ impl Diff {
     pub fn as_ptr(&self) -> *mut subversion_sys::svn_diff_t {
         self.ptr
     }
+
+    /// Iterate over the regions of the diff, in order.
+    ///
+    /// The regions cover all lines of all sources, so unchanged regions are
+    /// included as [`ChunkKind::Common`].
+    pub fn chunks(&self) -> Result<Chunks, Error<'static>> {
+        Ok(Chunks(
+            collect_chunks(self.ptr, self.three_way)?.into_iter(),
+        ))
+    }
+}
+
+/// The kind of a region of a [`Diff`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChunkKind {
+    /// Lines that are the same in all sources.
+    Common,
+    /// Lines changed in the modified source.
+    Modified,
+    /// Lines changed in the latest source (three-way diffs only).
+    Latest,
+    /// Lines changed the same way in the modified and latest sources.
+    DiffCommon,
+    /// Lines changed differently in the modified and latest sources.
+    Conflict,
+}
+
+/// A range of lines, in zero-based line numbers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct LineRange {
+    /// The first line.
+    pub start: usize,
+    /// The number of lines.
+    pub length: usize,
+}
+
+impl LineRange {
+    fn from_raw(start: apr_sys::apr_off_t, length: apr_sys::apr_off_t) -> Self {
+        Self {
+            start: start as usize,
+            length: length as usize,
+        }
+    }
+
+    /// The line after the last line of the range.
+    pub fn end(&self) -> usize {
+        self.start + self.length
+    }
+
+    /// Whether the range contains no lines.
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// The part of `lines` in this range.
+    ///
+    /// `lines` should be the source the range refers to, split once with
+    /// [`split_lines`] so that looking up the ranges of every chunk stays
+    /// linear; lines past its end are omitted.
+    pub fn lines<'a, T>(&self, lines: &'a [T]) -> &'a [T] {
+        let start = self.start.min(lines.len());
+        &lines[start..self.end().min(lines.len())]
+    }
+}
+
+/// A region of a [`Diff`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffChunk {
+    /// The kind of region.
+    pub kind: ChunkKind,
+    /// The lines in the original source.
+    pub original: LineRange,
+    /// The lines in the modified source.
+    pub modified: LineRange,
+    /// The lines in the latest source, for three-way diffs.
+    pub latest: Option<LineRange>,
+    /// For conflicts, the regions of the diff between the modified and
+    /// latest sides of the conflict, if Subversion computed one.
+    pub resolved: Option<Vec<DiffChunk>>,
+}
+
+/// Iterator over the regions of a [`Diff`], returned by [`Diff::chunks`].
+pub struct Chunks(std::vec::IntoIter<DiffChunk>);
+
+impl Iterator for Chunks {
+    type Item = DiffChunk;
+
+    fn next(&mut self) -> Option<DiffChunk> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Chunks {
+    fn next_back(&mut self) -> Option<DiffChunk> {
+        self.0.next_back()
+    }
+}
+
+impl ExactSizeIterator for Chunks {}
+
+struct ChunkCollector {
+    three_way: bool,
+    chunks: Vec<DiffChunk>,
+}
+
+impl ChunkCollector {
+    #[allow(clippy::too_many_arguments)]
+    unsafe fn push(
+        baton: *mut std::ffi::c_void,
+        kind: ChunkKind,
+        original_start: apr_sys::apr_off_t,
+        original_length: apr_sys::apr_off_t,
+        modified_start: apr_sys::apr_off_t,
+        modified_length: apr_sys::apr_off_t,
+        latest_start: apr_sys::apr_off_t,
+        latest_length: apr_sys::apr_off_t,
+        resolved: Option<Vec<DiffChunk>>,
+    ) -> *mut subversion_sys::svn_error_t {
+        let collector = &mut *(baton as *mut ChunkCollector);
+        let latest = collector
+            .three_way
+            .then(|| LineRange::from_raw(latest_start, latest_length));
+        collector.chunks.push(DiffChunk {
+            kind,
+            original: LineRange::from_raw(original_start, original_length),
+            modified: LineRange::from_raw(modified_start, modified_length),
+            latest,
+            resolved,
+        });
+        std::ptr::null_mut()
+    }
+}
+
+macro_rules! chunk_callback {
+    ($name:ident, $kind:expr) => {
+        unsafe extern "C" fn $name(
+            baton: *mut std::ffi::c_void,
+            original_start: apr_sys::apr_off_t,
+            original_length: apr_sys::apr_off_t,
+            modified_start: apr_sys::apr_off_t,
+            modified_length: apr_sys::apr_off_t,
+            latest_start: apr_sys::apr_off_t,
+            latest_length: apr_sys::apr_off_t,
+        ) -> *mut subversion_sys::svn_error_t {
+            ChunkCollector::push(
+                baton,
+                $kind,
+                original_start,
+                original_length,
+                modified_start,
+                modified_length,
+                latest_start,
+                latest_length,
+                None,
+            )
+        }
+    };
+}
+
+chunk_callback!(chunk_common, ChunkKind::Common);
+chunk_callback!(chunk_modified, ChunkKind::Modified);
+chunk_callback!(chunk_latest, ChunkKind::Latest);
+chunk_callback!(chunk_diff_common, ChunkKind::DiffCommon);
+
+#[allow(clippy::too_many_arguments)]
+unsafe extern "C" fn chunk_conflict(
+    baton: *mut std::ffi::c_void,
+    original_start: apr_sys::apr_off_t,
+    original_length: apr_sys::apr_off_t,
+    modified_start: apr_sys::apr_off_t,
+    modified_length: apr_sys::apr_off_t,
+    latest_start: apr_sys::apr_off_t,
+    latest_length: apr_sys::apr_off_t,
+    resolved_diff: *mut subversion_sys::svn_diff_t,
+) -> *mut subversion_sys::svn_error_t {
+    let resolved = if resolved_diff.is_null() {
+        None
+    } else {
+        match collect_chunks(resolved_diff, true) {
+            Ok(chunks) => Some(chunks),
+            Err(e) => return e.into_raw(),
+        }
+    };
+    ChunkCollector::push(
+        baton,
+        ChunkKind::Conflict,
+        original_start,
+        original_length,
+        modified_start,
+        modified_length,
+        latest_start,
+        latest_length,
+        resolved,
+    )
+}
+
+fn collect_chunks(
+    diff: *mut subversion_sys::svn_diff_t,
+    three_way: bool,
+) -> Result<Vec<DiffChunk>, Error<'static>> {
+    let fns = subversion_sys::svn_diff_output_fns_t {
+        output_common: Some(chunk_common),
+        output_diff_modified: Some(chunk_modified),
+        output_diff_latest: Some(chunk_latest),
+        output_diff_common: Some(chunk_diff_common),
+        output_conflict: Some(chunk_conflict),
+    };
+    let mut collector = ChunkCollector {
+        three_way,
+        chunks: Vec::new(),
+    };
+    let err = unsafe {
+        subversion_sys::svn_diff_output2(
+            diff,
+            &mut collector as *mut _ as *mut std::ffi::c_void,
+            &fns,
+            None,
+            std::ptr::null_mut(),
+        )
+    };
+    svn_result(err)?;
+    Ok(collector.chunks)
+}
+
+/// The byte ranges of the lines of `text`, including line endings.
+fn line_ranges(text: &[u8]) -> Vec<std::ops::Range<usize>> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    while i < text.len() {
+        match text[i] {
+            b'\n' => {
+                lines.push(start..i + 1);
+                start = i + 1;
+            }
+            b'\r' => {
+                if text.get(i + 1) == Some(&b'\n') {
+                    i += 1;
+                }
+                lines.push(start..i + 1);
+                start = i + 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    if start < text.len() {
+        lines.push(start..text.len());
+    }
+    lines
+}
+
+/// Split `text` into lines the way `svn_diff` tokenizes it, keeping line
+/// endings.  `\n`, `\r\n` and a lone `\r` all end a line.
+pub fn split_lines(text: &str) -> Vec<&str> {
+    line_ranges(text.as_bytes())
+        .into_iter()
+        .map(|range| &text[range])
+        .collect()
 }
 
 /// File options for diff operations
@@ -363,7 +845,7 @@ pub fn file_diff(
     };
 
     svn_result(err)?;
-    Ok(unsafe { Diff::from_raw(diff_ptr, pool) })
+    Ok(unsafe { Diff::from_raw(diff_ptr, pool, false) })
 }
 
 /// Diff three files (three-way comparison)
@@ -400,7 +882,7 @@ pub fn file_diff3(
     };
 
     svn_result(err)?;
-    Ok(unsafe { Diff::from_raw(diff_ptr, pool) })
+    Ok(unsafe { Diff::from_raw(diff_ptr, pool, true) })
 }
 
 /// Diff four files (four-way comparison with ancestor)
@@ -440,7 +922,7 @@ pub fn file_diff4(
     };
 
     svn_result(err)?;
-    Ok(unsafe { Diff::from_raw(diff_ptr, pool) })
+    Ok(unsafe { Diff::from_raw(diff_ptr, pool, true) })
 }
 
 /// Output unified diff format
@@ -527,7 +1009,65 @@ pub fn mem_string_diff(
     };
 
     svn_result(err)?;
-    Ok(unsafe { Diff::from_raw(diff_ptr, pool) })
+    Ok(unsafe { Diff::from_raw(diff_ptr, pool, false) })
+}
+
+/// Output a unified diff of two memory strings previously compared with
+/// [`mem_string_diff`].
+///
+/// If `with_diff_header` is false the `---`/`+++` header lines are omitted.
+/// `hunk_delimiter` defaults to `@@` when `None`.
+///
+/// Wraps `svn_diff_mem_string_output_unified3`.
+pub fn mem_string_output_unified(
+    output_stream: &mut crate::io::Stream,
+    diff: &Diff,
+    with_diff_header: bool,
+    hunk_delimiter: Option<&str>,
+    original_header: &str,
+    modified_header: &str,
+    header_encoding: &str,
+    original: &str,
+    modified: &str,
+    context_size: i32,
+) -> Result<(), Error<'static>> {
+    let hunk_delimiter_cstr = hunk_delimiter.map(std::ffi::CString::new).transpose()?;
+    let original_header_cstr = std::ffi::CString::new(original_header)?;
+    let modified_header_cstr = std::ffi::CString::new(modified_header)?;
+    let header_encoding_cstr = std::ffi::CString::new(header_encoding)?;
+
+    let original_svn_str = subversion_sys::svn_string_t {
+        data: original.as_ptr() as *const std::os::raw::c_char,
+        len: original.len(),
+    };
+    let modified_svn_str = subversion_sys::svn_string_t {
+        data: modified.as_ptr() as *const std::os::raw::c_char,
+        len: modified.len(),
+    };
+
+    with_tmp_pool(|scratch_pool| {
+        let err = unsafe {
+            subversion_sys::svn_diff_mem_string_output_unified3(
+                output_stream.as_mut_ptr(),
+                diff.as_ptr(),
+                if with_diff_header { 1 } else { 0 },
+                hunk_delimiter_cstr
+                    .as_ref()
+                    .map_or(std::ptr::null(), |c| c.as_ptr()),
+                original_header_cstr.as_ptr(),
+                modified_header_cstr.as_ptr(),
+                header_encoding_cstr.as_ptr(),
+                &original_svn_str,
+                &modified_svn_str,
+                context_size,
+                None,                 // cancel_func
+                std::ptr::null_mut(), // cancel_baton
+                scratch_pool.as_mut_ptr(),
+            )
+        };
+
+        svn_result(err)
+    })
 }
 
 /// Conflict display style for merge output
@@ -615,6 +1155,300 @@ pub fn file_output_merge(
     })
 }
 
+/// Diff three memory strings (three-way comparison).
+pub fn mem_string_diff3(
+    original: &str,
+    modified: &str,
+    latest: &str,
+    options: FileOptions,
+) -> Result<Diff, Error<'static>> {
+    mem_diff3(
+        original.as_bytes(),
+        modified.as_bytes(),
+        latest.as_bytes(),
+        options,
+    )
+}
+
+fn mem_diff3(
+    original: &[u8],
+    modified: &[u8],
+    latest: &[u8],
+    options: FileOptions,
+) -> Result<Diff, Error<'static>> {
+    let pool = apr::Pool::new();
+    let mut diff_ptr = std::ptr::null_mut();
+
+    let original_svn_str = subversion_sys::svn_string_t {
+        data: original.as_ptr() as *const std::os::raw::c_char,
+        len: original.len(),
+    };
+    let modified_svn_str = subversion_sys::svn_string_t {
+        data: modified.as_ptr() as *const std::os::raw::c_char,
+        len: modified.len(),
+    };
+    let latest_svn_str = subversion_sys::svn_string_t {
+        data: latest.as_ptr() as *const std::os::raw::c_char,
+        len: latest.len(),
+    };
+
+    // Create diff options
+    let diff_options = unsafe { subversion_sys::svn_diff_file_options_create(pool.as_mut_ptr()) };
+    unsafe {
+        (*diff_options).ignore_space = options.ignore_space.into();
+        (*diff_options).ignore_eol_style = if options.ignore_eol_style { 1 } else { 0 };
+        (*diff_options).show_c_function = if options.show_c_function { 1 } else { 0 };
+    }
+
+    let err = unsafe {
+        subversion_sys::svn_diff_mem_string_diff3(
+            &mut diff_ptr,
+            &original_svn_str,
+            &modified_svn_str,
+            &latest_svn_str,
+            diff_options,
+            pool.as_mut_ptr(),
+        )
+    };
+
+    svn_result(err)?;
+    Ok(unsafe { Diff::from_raw(diff_ptr, pool, true) })
+}
+
+/// Options for [`mem_string_merge`].
+#[derive(Debug, Clone)]
+pub struct MergeOptions {
+    /// Options for comparing the texts.
+    pub file_options: FileOptions,
+    /// How conflicts are written to the merged text.
+    pub conflict_style: ConflictDisplayStyle,
+    /// Label for the original text in conflict markers.
+    pub conflict_original: Option<String>,
+    /// Label for the modified text in conflict markers.
+    pub conflict_modified: Option<String>,
+    /// Label for the latest text in conflict markers.
+    pub conflict_latest: Option<String>,
+    /// Separator between the sides of a conflict.
+    pub conflict_separator: Option<String>,
+}
+
+impl Default for MergeOptions {
+    fn default() -> Self {
+        Self {
+            file_options: FileOptions::default(),
+            conflict_style: ConflictDisplayStyle::ModifiedLatest,
+            conflict_original: None,
+            conflict_modified: None,
+            conflict_latest: None,
+            conflict_separator: None,
+        }
+    }
+}
+
+impl MergeOptions {
+    /// Create new merge options
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the options for comparing the texts
+    pub fn with_file_options(mut self, options: FileOptions) -> Self {
+        self.file_options = options;
+        self
+    }
+
+    /// Set how conflicts are written to the merged text
+    pub fn with_conflict_style(mut self, style: ConflictDisplayStyle) -> Self {
+        self.conflict_style = style;
+        self
+    }
+
+    /// Set the labels used in conflict markers
+    pub fn with_conflict_labels(mut self, original: &str, modified: &str, latest: &str) -> Self {
+        self.conflict_original = Some(original.to_string());
+        self.conflict_modified = Some(modified.to_string());
+        self.conflict_latest = Some(latest.to_string());
+        self
+    }
+
+    /// Set the separator between the sides of a conflict
+    pub fn with_conflict_separator(mut self, separator: &str) -> Self {
+        self.conflict_separator = Some(separator.to_string());
+        self
+    }
+}
+
+/// A region where both sides of a three-way merge changed the base
+/// differently.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeConflict {
+    /// The lines in the base text.
+    pub base: LineRange,
+    /// The lines in our text.
+    pub mine: LineRange,
+    /// The lines in their text.
+    pub theirs: LineRange,
+    /// The base text of the region.
+    pub base_text: Vec<u8>,
+    /// Our text of the region.
+    pub mine_text: Vec<u8>,
+    /// Their text of the region.
+    pub theirs_text: Vec<u8>,
+}
+
+/// The result of [`mem_string_merge`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeOutcome {
+    /// Both sides merged cleanly.
+    Merged(Vec<u8>),
+    /// Some regions conflict.
+    Conflicted {
+        /// The merged text, with conflicts written according to the
+        /// conflict display style.
+        text: Vec<u8>,
+        /// The conflicting regions, in order.
+        conflicts: Vec<MergeConflict>,
+    },
+}
+
+impl MergeOutcome {
+    /// The merged text, including conflict markers if there are conflicts.
+    pub fn text(&self) -> &[u8] {
+        match self {
+            MergeOutcome::Merged(text) => text,
+            MergeOutcome::Conflicted { text, .. } => text,
+        }
+    }
+
+    /// The conflicting regions; empty if the merge is clean.
+    pub fn conflicts(&self) -> &[MergeConflict] {
+        match self {
+            MergeOutcome::Merged(_) => &[],
+            MergeOutcome::Conflicted { conflicts, .. } => conflicts,
+        }
+    }
+
+    /// Whether the merge has conflicts.
+    pub fn has_conflicts(&self) -> bool {
+        matches!(self, MergeOutcome::Conflicted { .. })
+    }
+}
+
+fn range_text(text: &[u8], lines: &[std::ops::Range<usize>], range: LineRange) -> Vec<u8> {
+    let lines = &lines[range.start.min(lines.len())..range.end().min(lines.len())];
+    match (lines.first(), lines.last()) {
+        (Some(first), Some(last)) => text[first.start..last.end].to_vec(),
+        _ => Vec::new(),
+    }
+}
+
+/// Merge the changes between `base` and `theirs` into `mine`, in memory.
+///
+/// Texts may be given as strings or bytes.
+///
+/// Wraps `svn_diff_mem_string_diff3` and `svn_diff_mem_string_output_merge3`.
+pub fn mem_string_merge(
+    base: impl AsRef<[u8]>,
+    mine: impl AsRef<[u8]>,
+    theirs: impl AsRef<[u8]>,
+    options: &MergeOptions,
+) -> Result<MergeOutcome, Error<'static>> {
+    let (base, mine, theirs) = (base.as_ref(), mine.as_ref(), theirs.as_ref());
+    let diff = mem_diff3(base, mine, theirs, options.file_options)?;
+
+    let conflict_original_cstr = options
+        .conflict_original
+        .as_deref()
+        .map(std::ffi::CString::new)
+        .transpose()?;
+    let conflict_modified_cstr = options
+        .conflict_modified
+        .as_deref()
+        .map(std::ffi::CString::new)
+        .transpose()?;
+    let conflict_latest_cstr = options
+        .conflict_latest
+        .as_deref()
+        .map(std::ffi::CString::new)
+        .transpose()?;
+    let conflict_separator_cstr = options
+        .conflict_separator
+        .as_deref()
+        .map(std::ffi::CString::new)
+        .transpose()?;
+
+    let base_svn_str = subversion_sys::svn_string_t {
+        data: base.as_ptr() as *const std::os::raw::c_char,
+        len: base.len(),
+    };
+    let mine_svn_str = subversion_sys::svn_string_t {
+        data: mine.as_ptr() as *const std::os::raw::c_char,
+        len: mine.len(),
+    };
+    let theirs_svn_str = subversion_sys::svn_string_t {
+        data: theirs.as_ptr() as *const std::os::raw::c_char,
+        len: theirs.len(),
+    };
+
+    let mut stringbuf = crate::io::StringBuf::new();
+    {
+        let mut output_stream = crate::io::Stream::from_stringbuf(&mut stringbuf);
+        with_tmp_pool(|scratch_pool| {
+            let err = unsafe {
+                subversion_sys::svn_diff_mem_string_output_merge3(
+                    output_stream.as_mut_ptr(),
+                    diff.as_ptr(),
+                    &base_svn_str,
+                    &mine_svn_str,
+                    &theirs_svn_str,
+                    conflict_original_cstr
+                        .as_ref()
+                        .map_or(std::ptr::null(), |c| c.as_ptr()),
+                    conflict_modified_cstr
+                        .as_ref()
+                        .map_or(std::ptr::null(), |c| c.as_ptr()),
+                    conflict_latest_cstr
+                        .as_ref()
+                        .map_or(std::ptr::null(), |c| c.as_ptr()),
+                    conflict_separator_cstr
+                        .as_ref()
+                        .map_or(std::ptr::null(), |c| c.as_ptr()),
+                    options.conflict_style.into(),
+                    None,                 // cancel_func
+                    std::ptr::null_mut(), // cancel_baton
+                    scratch_pool.as_mut_ptr(),
+                )
+            };
+
+            svn_result(err)
+        })?;
+    }
+    let text = stringbuf.as_bytes().to_vec();
+
+    if !diff.contains_conflicts() {
+        return Ok(MergeOutcome::Merged(text));
+    }
+
+    let (base_lines, mine_lines, theirs_lines) =
+        (line_ranges(base), line_ranges(mine), line_ranges(theirs));
+    let conflicts = diff
+        .chunks()?
+        .filter(|chunk| chunk.kind == ChunkKind::Conflict)
+        .map(|chunk| {
+            let theirs_range = chunk.latest.unwrap_or_default();
+            MergeConflict {
+                base: chunk.original,
+                mine: chunk.modified,
+                theirs: theirs_range,
+                base_text: range_text(base, &base_lines, chunk.original),
+                mine_text: range_text(mine, &mine_lines, chunk.modified),
+                theirs_text: range_text(theirs, &theirs_lines, theirs_range),
+            }
+        })
+        .collect();
+    Ok(MergeOutcome::Conflicted { text, conflicts })
+}
+
 /// Generic diff output using callback functions
 ///
 /// # Safety
@@ -639,6 +1473,26 @@ pub unsafe fn output(
     svn_result(err)
 }
 
+/// Return the modified ranges of a two-way diff as
+/// `(original_start, original_length, modified_start, modified_length)`
+/// tuples, in zero-based line numbers.
+pub(crate) fn modified_ranges(
+    diff: &Diff,
+) -> Result<Vec<(usize, usize, usize, usize)>, Error<'static>> {
+    Ok(diff
+        .chunks()?
+        .filter(|chunk| chunk.kind == ChunkKind::Modified)
+        .map(|chunk| {
+            (
+                chunk.original.start,
+                chunk.original.length,
+                chunk.modified.start,
+                chunk.modified.length,
+            )
+        })
+        .collect())
+}
+
 /// Output unified diff with more options
 pub fn file_output_unified_with_options(
     output_stream: &mut crate::io::Stream,
@@ -782,6 +1636,244 @@ mod tests {
         assert!(diff.contains_changes());
     }
 
+    #[test]
+    fn test_mem_string_output_unified() {
+        let original = "line 1\nline 2\nline 3\n";
+        let modified = "line 1\nline 2 modified\nline 3\n";
+        let diff = mem_string_diff(original, modified, FileOptions::default()).unwrap();
+
+        let mut stringbuf = crate::io::StringBuf::new();
+        let mut stream = crate::io::Stream::from_stringbuf(&mut stringbuf);
+        mem_string_output_unified(
+            &mut stream,
+            &diff,
+            true,
+            None,
+            "a/file.txt",
+            "b/file.txt",
+            "UTF-8",
+            original,
+            modified,
+            3,
+        )
+        .unwrap();
+
+        let output = stringbuf.to_string();
+        assert!(output.contains("--- a/file.txt"));
+        assert!(output.contains("+++ b/file.txt"));
+        assert!(output.contains("-line 2\n"));
+        assert!(output.contains("+line 2 modified\n"));
+    }
+
+    #[test]
+    fn test_chunks() {
+        let original = "a\nb\nc\n";
+        let modified = "a\nB\nc\nd\n";
+        let diff = mem_string_diff(original, modified, FileOptions::default()).unwrap();
+
+        let chunks = diff.chunks().unwrap().collect::<Vec<_>>();
+        assert_eq!(
+            chunks
+                .iter()
+                .map(|c| (c.kind, c.original, c.modified, c.latest))
+                .collect::<Vec<_>>(),
+            [
+                (
+                    ChunkKind::Common,
+                    LineRange {
+                        start: 0,
+                        length: 1
+                    },
+                    LineRange {
+                        start: 0,
+                        length: 1
+                    },
+                    None
+                ),
+                (
+                    ChunkKind::Modified,
+                    LineRange {
+                        start: 1,
+                        length: 1
+                    },
+                    LineRange {
+                        start: 1,
+                        length: 1
+                    },
+                    None
+                ),
+                (
+                    ChunkKind::Common,
+                    LineRange {
+                        start: 2,
+                        length: 1
+                    },
+                    LineRange {
+                        start: 2,
+                        length: 1
+                    },
+                    None
+                ),
+                (
+                    ChunkKind::Modified,
+                    LineRange {
+                        start: 3,
+                        length: 0
+                    },
+                    LineRange {
+                        start: 3,
+                        length: 1
+                    },
+                    None
+                ),
+            ]
+        );
+        let original_lines = split_lines(original);
+        let modified_lines = split_lines(modified);
+        assert_eq!(chunks[1].original.lines(&original_lines), ["b\n"]);
+        assert_eq!(chunks[1].modified.lines(&modified_lines), ["B\n"]);
+        assert!(chunks[3].original.is_empty());
+        assert_eq!(chunks[3].modified.lines(&modified_lines), ["d\n"]);
+
+        assert_eq!(
+            modified_ranges(&diff).unwrap(),
+            [(1, 1, 1, 1), (3, 0, 3, 1)]
+        );
+    }
+
+    #[test]
+    fn test_chunks_diff3() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempdir()?;
+        let original = temp_dir.path().join("orig.txt");
+        let modified = temp_dir.path().join("mine.txt");
+        let latest = temp_dir.path().join("theirs.txt");
+        std::fs::write(&original, b"line 1\nline 2\nline 3\nline 4\n")?;
+        std::fs::write(&modified, b"line 1\nMINE\nline 3\nline 4\n")?;
+        std::fs::write(&latest, b"line 1\nTHEIRS\nline 3\nline 4 changed\n")?;
+
+        let diff = file_diff3(&original, &modified, &latest, FileOptions::default())?;
+        let chunks = diff.chunks()?.collect::<Vec<_>>();
+        let kinds = chunks.iter().map(|c| c.kind).collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                ChunkKind::Common,
+                ChunkKind::Conflict,
+                ChunkKind::Common,
+                ChunkKind::Latest
+            ]
+        );
+        let conflict = &chunks[1];
+        assert_eq!(
+            conflict.modified,
+            LineRange {
+                start: 1,
+                length: 1
+            }
+        );
+        assert_eq!(
+            conflict.latest,
+            Some(LineRange {
+                start: 1,
+                length: 1
+            })
+        );
+        assert_eq!(chunks[3].latest.unwrap().end(), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_mem_string_merge_clean() {
+        let outcome = mem_string_merge(
+            "a\nb\nc\n",
+            "A\nb\nc\n",
+            "a\nb\nC\n",
+            &MergeOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(outcome, MergeOutcome::Merged(b"A\nb\nC\n".to_vec()));
+        assert!(outcome.conflicts().is_empty());
+    }
+
+    #[test]
+    fn test_mem_string_merge_conflict() {
+        let options = MergeOptions::new()
+            .with_conflict_labels("base", "mine", "theirs")
+            .with_conflict_separator("=======");
+        let outcome = mem_string_merge(
+            b"a\nb\nc\n".as_slice(),
+            b"a\nMINE\nc\n".as_slice(),
+            b"a\nTHEIRS\nc\n".as_slice(),
+            &options,
+        )
+        .unwrap();
+
+        assert!(outcome.has_conflicts());
+        assert_eq!(
+            String::from_utf8_lossy(outcome.text()),
+            "a\n<<<<<<< mine\nMINE\n=======\nTHEIRS\n>>>>>>> theirs\nc\n"
+        );
+        assert_eq!(
+            outcome.conflicts(),
+            [MergeConflict {
+                base: LineRange {
+                    start: 1,
+                    length: 1
+                },
+                mine: LineRange {
+                    start: 1,
+                    length: 1
+                },
+                theirs: LineRange {
+                    start: 1,
+                    length: 1
+                },
+                base_text: b"b\n".to_vec(),
+                mine_text: b"MINE\n".to_vec(),
+                theirs_text: b"THEIRS\n".to_vec(),
+            }]
+        );
+
+        let outcome = mem_string_merge(
+            "a\nb\nc\n",
+            "a\nMINE\nc\n",
+            "a\nTHEIRS\nc\n",
+            &options
+                .clone()
+                .with_conflict_style(ConflictDisplayStyle::ModifiedOriginalLatest),
+        )
+        .unwrap();
+        assert!(String::from_utf8_lossy(outcome.text()).contains("||||||| base\nb\n"));
+    }
+
+    #[test]
+    fn test_mem_string_merge_ignore_space() {
+        let options =
+            MergeOptions::new().with_file_options(FileOptions::new().with_ignore_whitespace(true));
+        let outcome = mem_string_merge("a b\nc\n", "a  b\nc\n", "a\tb\nC\n", &options).unwrap();
+        assert!(!outcome.has_conflicts());
+        assert_eq!(outcome.text(), b"a  b\nC\n");
+    }
+
+    #[test]
+    fn test_split_lines() {
+        assert_eq!(split_lines("a\r\nb\rc\nd"), ["a\r\n", "b\r", "c\n", "d"]);
+        assert_eq!(
+            LineRange {
+                start: 1,
+                length: 5
+            }
+            .lines(&split_lines("x\ny\n")),
+            ["y\n"]
+        );
+        assert!(LineRange {
+            start: 4,
+            length: 1
+        }
+        .lines(&line_ranges(b"x\ny\n"))
+        .is_empty());
+    }
+
     #[test]
     fn test_file_diff() -> Result<(), Box<dyn std::error::Error>> {
         let temp_dir = tempdir()?;
@@ -1102,5 +2194,9 @@ mod tests {
             PatchOperation::from(svn_diff_operation_kind_e_svn_diff_op_moved),
             PatchOperation::Moved
         );
+        assert_eq!(
+            PatchOperation::from(svn_diff_operation_kind_e_svn_diff_op_modified),
+            PatchOperation::Modified
+        );
     }
 }
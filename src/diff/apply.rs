@@ -0,0 +1,597 @@
+//! Applying patches to in-memory content.
+//!
+//! This follows the rules `svn patch` uses for a working copy file, without
+//! needing one: each hunk is first tried at the line it names, adjusted by
+//! the offset at which the previous hunk applied, and then at the nearest
+//! line where its original text matches.  If a hunk matches nowhere, up to
+//! [`ApplyOptions::max_fuzz`] lines of leading and trailing context are
+//! ignored in turn.  Hunks that still do not match are rejected and leave the
+//! content untouched.
+//!
+//! Lines are compared without their line endings, so a patch with `\n` line
+//! endings applies to content with `\r\n` line endings and vice versa.  Added
+//! lines take the line ending of the content they are added to.
+
+use super::patch::{Hunk, HunkLine, Patch};
+
+/// Options for applying patches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ApplyOptions {
+    /// Apply the patch in reverse, swapping added and removed lines.
+    pub reverse: bool,
+    /// Ignore whitespace when matching lines.
+    pub ignore_whitespace: bool,
+    /// The maximum number of context lines that may be ignored at either end
+    /// of a hunk.
+    pub max_fuzz: usize,
+}
+
+impl Default for ApplyOptions {
+    fn default() -> Self {
+        Self {
+            reverse: false,
+            ignore_whitespace: false,
+            max_fuzz: 2,
+        }
+    }
+}
+
+impl ApplyOptions {
+    /// Create new apply options
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set whether to apply the patch in reverse
+    pub fn with_reverse(mut self, reverse: bool) -> Self {
+        self.reverse = reverse;
+        self
+    }
+
+    /// Set whether to ignore whitespace when matching lines
+    pub fn with_ignore_whitespace(mut self, ignore: bool) -> Self {
+        self.ignore_whitespace = ignore;
+        self
+    }
+
+    /// Set the maximum fuzz
+    pub fn with_max_fuzz(mut self, max_fuzz: usize) -> Self {
+        self.max_fuzz = max_fuzz;
+        self
+    }
+}
+
+/// The outcome of applying a single hunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HunkResult {
+    /// The hunk applied with all of its context matching.
+    Applied {
+        /// The line of the original content the hunk applied at, one-based.
+        line: u64,
+        /// How many lines away from its expected position the hunk applied.
+        offset: i64,
+    },
+    /// The hunk applied after ignoring some of its context.
+    AppliedWithFuzz {
+        /// The line of the original content the hunk applied at, one-based.
+        line: u64,
+        /// How many lines away from its expected position the hunk applied.
+        offset: i64,
+        /// How many context lines were ignored at either end of the hunk.
+        fuzz: usize,
+    },
+    /// The hunk did not match the content.
+    Rejected,
+}
+
+impl HunkResult {
+    /// Whether the hunk applied, with or without fuzz.
+    pub fn is_applied(&self) -> bool {
+        !matches!(self, HunkResult::Rejected)
+    }
+}
+
+/// Content with a patch applied to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatchedContent {
+    /// The patched content.  Rejected hunks are left out.
+    pub content: Vec<u8>,
+    /// The outcome of each hunk, in order.
+    pub hunks: Vec<HunkResult>,
+}
+
+impl PatchedContent {
+    /// Whether every hunk applied without fuzz or offset.
+    pub fn is_exact(&self) -> bool {
+        self.hunks
+            .iter()
+            .all(|hunk| matches!(hunk, HunkResult::Applied { offset: 0, .. }))
+    }
+
+    /// Whether no hunk was rejected.
+    pub fn is_clean(&self) -> bool {
+        self.hunks.iter().all(HunkResult::is_applied)
+    }
+
+    /// The number of rejected hunks.
+    pub fn rejected(&self) -> usize {
+        self.hunks.iter().filter(|hunk| !hunk.is_applied()).count()
+    }
+}
+
+/// Apply the text changes of `patch` to `content`.
+///
+/// For a patch with binary contents, the whole file is replaced if it
+/// matches the binary original, and reported as a single hunk.
+pub fn apply_patch(patch: &Patch, content: &[u8], options: &ApplyOptions) -> PatchedContent {
+    match &patch.binary_patch {
+        Some(binary_patch) => {
+            let (original, result) = if options.reverse {
+                (&binary_patch.result, &binary_patch.original)
+            } else {
+                (&binary_patch.original, &binary_patch.result)
+            };
+            if content == original.as_slice() {
+                PatchedContent {
+                    content: result.clone(),
+                    hunks: vec![HunkResult::Applied { line: 1, offset: 0 }],
+                }
+            } else {
+                PatchedContent {
+                    content: content.to_vec(),
+                    hunks: vec![HunkResult::Rejected],
+                }
+            }
+        }
+        None => apply_hunks(&patch.hunks, content, options),
+    }
+}
+
+/// Apply `hunks` to `content`, in order.
+pub fn apply_hunks(hunks: &[Hunk], content: &[u8], options: &ApplyOptions) -> PatchedContent {
+    let lines: Vec<&[u8]> = super::line_ranges(content)
+        .into_iter()
+        .map(|range| &content[range])
+        .collect();
+    let eol = lines
+        .iter()
+        .map(|line| line_ending(line))
+        .find(|ending| !ending.is_empty());
+    let keys: Vec<Vec<u8>> = lines
+        .iter()
+        .map(|line| match_key(line, options.ignore_whitespace))
+        .collect();
+
+    let mut output = Vec::with_capacity(content.len());
+    let mut results = Vec::with_capacity(hunks.len());
+    // The first line not yet copied to the output; hunks may not match
+    // before it.
+    let mut copied = 0;
+    let mut offset = 0i64;
+
+    for hunk in hunks {
+        let hunk_lines = oriented_lines(hunk, options.reverse);
+        let Some(found) = find_hunk(
+            &hunk_lines,
+            original_start(hunk, options.reverse),
+            &keys,
+            copied,
+            offset,
+            options,
+        ) else {
+            results.push(HunkResult::Rejected);
+            continue;
+        };
+
+        let leading = found.leading;
+        let trailing = found.trailing;
+        let applied = &hunk_lines[leading..hunk_lines.len() - trailing];
+        for line in &lines[copied..found.position] {
+            output.extend_from_slice(line);
+        }
+        let mut position = found.position;
+        for line in applied {
+            match line {
+                HunkLine::Context(_) => {
+                    output.extend_from_slice(lines[position]);
+                    position += 1;
+                }
+                HunkLine::Removed(_) => position += 1,
+                HunkLine::Added(text) => {
+                    let ending = line_ending(text);
+                    // The last line of the content may have no line ending.
+                    if matches!(output.last(), Some(c) if !matches!(c, b'\n' | b'\r')) {
+                        let separator = match ending {
+                            b"" => eol.unwrap_or(b"\n"),
+                            ending => eol.unwrap_or(ending),
+                        };
+                        output.extend_from_slice(separator);
+                    }
+                    output.extend_from_slice(&text[..text.len() - ending.len()]);
+                    if !ending.is_empty() {
+                        output.extend_from_slice(eol.unwrap_or(ending));
+                    }
+                }
+            }
+        }
+        copied = position;
+
+        let line = found.position.saturating_sub(leading) as u64 + 1;
+        offset = found.offset;
+        results.push(if found.fuzz == 0 {
+            HunkResult::Applied {
+                line,
+                offset: found.offset,
+            }
+        } else {
+            HunkResult::AppliedWithFuzz {
+                line,
+                offset: found.offset,
+                fuzz: found.fuzz,
+            }
+        });
+    }
+
+    for line in &lines[copied..] {
+        output.extend_from_slice(line);
+    }
+    PatchedContent {
+        content: output,
+        hunks: results,
+    }
+}
+
+/// Where a hunk matched.
+struct Match {
+    /// The index of the first line matched by the trimmed hunk.
+    position: usize,
+    /// The hunk lines ignored at the start.
+    leading: usize,
+    /// The hunk lines ignored at the end.
+    trailing: usize,
+    fuzz: usize,
+    offset: i64,
+}
+
+fn oriented_lines(hunk: &Hunk, reverse: bool) -> Vec<HunkLine> {
+    if !reverse {
+        return hunk.lines.clone();
+    }
+    hunk.lines
+        .iter()
+        .map(|line| match line {
+            HunkLine::Added(text) => HunkLine::Removed(text.clone()),
+            HunkLine::Removed(text) => HunkLine::Added(text.clone()),
+            HunkLine::Context(text) => HunkLine::Context(text.clone()),
+        })
+        .collect()
+}
+
+/// The line ending of `line`, empty if it has none.
+fn line_ending(line: &[u8]) -> &'static [u8] {
+    if line.ends_with(b"\r\n") {
+        b"\r\n"
+    } else if line.ends_with(b"\n") {
+        b"\n"
+    } else if line.ends_with(b"\r") {
+        b"\r"
+    } else {
+        b""
+    }
+}
+
+/// The form of a line used for comparisons: without its line ending, and
+/// without any whitespace if it is ignored.
+fn match_key(line: &[u8], ignore_whitespace: bool) -> Vec<u8> {
+    let mut line = line;
+    while let Some((&(b'\n' | b'\r'), rest)) = line.split_last() {
+        line = rest;
+    }
+    if ignore_whitespace {
+        line.iter()
+            .copied()
+            .filter(|c| !c.is_ascii_whitespace())
+            .collect()
+    } else {
+        line.to_vec()
+    }
+}
+
+fn find_hunk(
+    hunk_lines: &[HunkLine],
+    original_start: usize,
+    keys: &[Vec<u8>],
+    start: usize,
+    offset: i64,
+    options: &ApplyOptions,
+) -> Option<Match> {
+    let original: Vec<Vec<u8>> = hunk_lines
+        .iter()
+        .filter(|line| !matches!(line, HunkLine::Added(_)))
+        .map(|line| match_key(line.text(), options.ignore_whitespace))
+        .collect();
+    let leading_context = hunk_lines
+        .iter()
+        .take_while(|line| matches!(line, HunkLine::Context(_)))
+        .count();
+    let trailing_context = hunk_lines
+        .iter()
+        .rev()
+        .take_while(|line| matches!(line, HunkLine::Context(_)))
+        .count();
+
+    for fuzz in 0..=options.max_fuzz {
+        let leading = fuzz.min(leading_context);
+        let trailing = fuzz.min(trailing_context);
+        if fuzz > leading.max(trailing) {
+            break;
+        }
+        // Fuzz may not ignore all of the hunk's original lines.
+        if !original.is_empty() && leading + trailing >= original.len() {
+            break;
+        }
+        let needle = &original[leading..original.len() - trailing];
+
+        let expected = original_start as i64 + leading as i64 + offset;
+        if let Some(position) = find_nearest(needle, keys, start, expected) {
+            return Some(Match {
+                position,
+                leading,
+                trailing,
+                fuzz,
+                offset: position as i64 - leading as i64 - original_start as i64,
+            });
+        }
+    }
+    None
+}
+
+/// The index of the first line the hunk's original text covers.  A hunk
+/// without original lines names the line it inserts after instead.
+fn original_start(hunk: &Hunk, reverse: bool) -> usize {
+    let (start, length) = if reverse {
+        (hunk.modified_start, hunk.modified_length())
+    } else {
+        (hunk.original_start, hunk.original_length())
+    };
+    if length == 0 {
+        start as usize
+    } else {
+        start.saturating_sub(1) as usize
+    }
+}
+
+/// Find the position nearest to `expected`, at or after `start`, where the
+/// lines of `keys` equal `needle`.
+fn find_nearest(
+    needle: &[Vec<u8>],
+    keys: &[Vec<u8>],
+    start: usize,
+    expected: i64,
+) -> Option<usize> {
+    if keys.len() < start + needle.len() {
+        return None;
+    }
+    let last = keys.len() - needle.len();
+    let expected = expected.clamp(start as i64, last as i64) as usize;
+    let matches_at = |position: usize| keys[position..position + needle.len()] == *needle;
+
+    if needle.is_empty() {
+        return Some(expected);
+    }
+    for distance in 0..=(last - start) {
+        if let Some(position) = expected.checked_add(distance).filter(|&p| p <= last) {
+            if matches_at(position) {
+                return Some(position);
+            }
+        }
+        if let Some(position) = expected.checked_sub(distance).filter(|&p| p >= start) {
+            if distance > 0 && matches_at(position) {
+                return Some(position);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::patch::BinaryPatch;
+    use crate::diff::{PatchFile, PatchOperation};
+    use tempfile::tempdir;
+
+    const CONTENT: &[u8] = b"one\ntwo\nthree\nfour\nfive\nsix\nseven\n";
+
+    fn hunk(original_start: u64, modified_start: u64, lines: &[(u8, &str)]) -> Hunk {
+        let mut hunk = Hunk::new(original_start, modified_start);
+        for (marker, text) in lines {
+            let text = text.as_bytes().to_vec();
+            hunk.push(match marker {
+                b' ' => HunkLine::Context(text),
+                b'-' => HunkLine::Removed(text),
+                _ => HunkLine::Added(text),
+            });
+        }
+        hunk
+    }
+
+    fn change_four() -> Hunk {
+        hunk(
+            3,
+            3,
+            &[
+                (b' ', "three\n"),
+                (b'-', "four\n"),
+                (b'+', "FOUR\n"),
+                (b' ', "five\n"),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_apply_exact() {
+        let patched = apply_hunks(&[change_four()], CONTENT, &ApplyOptions::default());
+        assert_eq!(
+            patched.content,
+            b"one\ntwo\nthree\nFOUR\nfive\nsix\nseven\n"
+        );
+        assert_eq!(patched.hunks, [HunkResult::Applied { line: 3, offset: 0 }]);
+        assert!(patched.is_exact());
+
+        let reversed = apply_hunks(
+            &[change_four()],
+            &patched.content,
+            &ApplyOptions::new().with_reverse(true),
+        );
+        assert_eq!(reversed.content, CONTENT);
+        assert!(reversed.is_exact());
+    }
+
+    #[test]
+    fn test_apply_offset() {
+        let content = [b"zero\n".as_slice(), b"half\n", CONTENT].concat();
+        let second = hunk(6, 6, &[(b' ', "six\n"), (b'+', "six and a half\n")]);
+        let patched = apply_hunks(&[change_four(), second], &content, &ApplyOptions::default());
+        assert_eq!(
+            patched.content,
+            b"zero\nhalf\none\ntwo\nthree\nFOUR\nfive\nsix\nsix and a half\nseven\n"
+        );
+        assert_eq!(
+            patched.hunks,
+            [
+                HunkResult::Applied { line: 5, offset: 2 },
+                HunkResult::Applied { line: 8, offset: 2 },
+            ]
+        );
+        assert!(patched.is_clean());
+        assert!(!patched.is_exact());
+    }
+
+    #[test]
+    fn test_apply_fuzz_and_reject() {
+        let content = b"one\ntwo\nTHREE\nfour\nfive\nsix\nseven\n";
+        let patched = apply_hunks(&[change_four()], content, &ApplyOptions::default());
+        assert_eq!(
+            patched.content,
+            b"one\ntwo\nTHREE\nFOUR\nfive\nsix\nseven\n"
+        );
+        assert_eq!(
+            patched.hunks,
+            [HunkResult::AppliedWithFuzz {
+                line: 3,
+                offset: 0,
+                fuzz: 1
+            }]
+        );
+
+        let patched = apply_hunks(
+            &[change_four()],
+            content,
+            &ApplyOptions::new().with_max_fuzz(0),
+        );
+        assert_eq!(patched.content, content);
+        assert_eq!(patched.hunks, [HunkResult::Rejected]);
+        assert_eq!(patched.rejected(), 1);
+    }
+
+    #[test]
+    fn test_apply_context_only_mismatch() {
+        let context = hunk(2, 2, &[(b' ', "x\n"), (b' ', "y\n"), (b' ', "z\n")]);
+        let patched = apply_hunks(&[context], CONTENT, &ApplyOptions::new().with_max_fuzz(3));
+        assert_eq!(patched.content, CONTENT);
+        assert_eq!(patched.hunks, [HunkResult::Rejected]);
+    }
+
+    #[test]
+    fn test_apply_ignore_whitespace_and_eol() {
+        let content = b"one\r\ntwo\r\n  three\r\nfour\r\nfive\r\n";
+        let patched = apply_hunks(
+            &[change_four()],
+            content,
+            &ApplyOptions::new()
+                .with_ignore_whitespace(true)
+                .with_max_fuzz(0),
+        );
+        assert_eq!(
+            patched.content,
+            b"one\r\ntwo\r\n  three\r\nFOUR\r\nfive\r\n"
+        );
+        assert!(patched.is_exact());
+    }
+
+    #[test]
+    fn test_apply_after_missing_newline() {
+        let append = hunk(2, 2, &[(b' ', "two\n"), (b'+', "three\n")]);
+        let patched = apply_hunks(
+            std::slice::from_ref(&append),
+            b"one\ntwo",
+            &ApplyOptions::default(),
+        );
+        assert_eq!(patched.content, b"one\ntwo\nthree\n");
+        assert!(patched.is_exact());
+
+        let patched = apply_hunks(
+            std::slice::from_ref(&append),
+            b"one\r\ntwo",
+            &ApplyOptions::default(),
+        );
+        assert_eq!(patched.content, b"one\r\ntwo\r\nthree\r\n");
+
+        // An added last line without a line ending stays without one.
+        let append = hunk(1, 1, &[(b' ', "one\n"), (b'+', "two")]);
+        let patched = apply_hunks(&[append], b"one", &ApplyOptions::default());
+        assert_eq!(patched.content, b"one\ntwo");
+    }
+
+    #[test]
+    fn test_apply_converts_eol() {
+        let patched = apply_hunks(
+            &[change_four()],
+            b"one\r\ntwo\r\nthree\r\nfour\r\nfive\r\n",
+            &ApplyOptions::default(),
+        );
+        assert_eq!(patched.content, b"one\r\ntwo\r\nthree\r\nFOUR\r\nfive\r\n");
+
+        let crlf_hunk = hunk(1, 1, &[(b' ', "one\r\n"), (b'+', "one and a half\r\n")]);
+        let patched = apply_hunks(&[crlf_hunk], CONTENT, &ApplyOptions::default());
+        assert_eq!(
+            patched.content,
+            b"one\none and a half\ntwo\nthree\nfour\nfive\nsix\nseven\n"
+        );
+    }
+
+    #[test]
+    fn test_apply_binary() {
+        let mut patch = Patch::new("a.bin", "a.bin", PatchOperation::Modified);
+        patch.binary_patch = Some(BinaryPatch {
+            original: b"\0old".to_vec(),
+            result: b"\0new".to_vec(),
+        });
+        let patched = apply_patch(&patch, b"\0old", &ApplyOptions::default());
+        assert_eq!(patched.content, b"\0new");
+        assert!(patched.is_clean());
+        let patched = apply_patch(&patch, b"\0other", &ApplyOptions::default());
+        assert_eq!(patched.hunks, [HunkResult::Rejected]);
+    }
+
+    #[test]
+    fn test_apply_parsed_patch() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempdir()?;
+        let patch_path = temp_dir.path().join("change.patch");
+        std::fs::write(
+            &patch_path,
+            "Index: f\n===================================================================\n\
+             --- f\n+++ f\n@@ -3,3 +3,3 @@\n three\n-four\n+FOUR\n five\n",
+        )?;
+        let mut patch_file = PatchFile::open(&patch_path)?;
+        let patch = patch_file.next_patch(false, false)?.unwrap();
+        let patched = patch.apply_to(CONTENT, &ApplyOptions::default())?;
+        assert_eq!(
+            patched.content,
+            b"one\ntwo\nthree\nFOUR\nfive\nsix\nseven\n"
+        );
+        assert!(patched.is_exact());
+        Ok(())
+    }
+}
@@ -0,0 +1,704 @@
+//! An owned model of patches, and writing it out as a unified diff.
+//!
+//! [`Patch`] holds everything a parsed [`super::Patch`] exposes — file names,
+//! the tree operation, text hunks, property changes, git binary contents and
+//! mode bits — as plain data.  Convert a parsed patch with
+//! [`Patch::try_from`], or build one by hand, and write it out in svn or git
+//! style with a [`PatchWriter`]:
+//!
+//! ```
+//! use subversion::diff::patch::{Patch, PatchFormat, PropPatch};
+//! use subversion::diff::PatchOperation;
+//!
+//! let mut patch = Patch::new("trunk/README", "trunk/README", PatchOperation::Modified);
+//! patch.prop_patches.push(PropPatch::added("svn:eol-style", b"native"));
+//! let text = patch.to_bytes(PatchFormat::Git).unwrap();
+//! assert!(text.starts_with(b"diff --git a/trunk/README b/trunk/README\n"));
+//! ```
+
+use super::PatchOperation;
+use crate::Error;
+use std::io::Write;
+
+const INDEX_SEPARATOR: &str = "===================================================================";
+const PROPERTY_SEPARATOR: &str =
+    "___________________________________________________________________";
+/// The object name git uses for a missing side of a binary patch.
+const GIT_NULL_ID: &str = "0000000000000000000000000000000000000000";
+const BASE85_ALPHABET: &[u8; 85] =
+    b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz!#$%&()*+-;<=>?@^_`{|}~";
+
+/// A single line of a [`Hunk`].
+///
+/// The text includes the line ending, if any.  A line without one is written
+/// followed by a "\ No newline at end of ..." marker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HunkLine {
+    /// A line present in both the original and the modified text.
+    Context(Vec<u8>),
+    /// A line only present in the original text.
+    Removed(Vec<u8>),
+    /// A line only present in the modified text.
+    Added(Vec<u8>),
+}
+
+impl HunkLine {
+    /// The text of the line, including its line ending.
+    pub fn text(&self) -> &[u8] {
+        match self {
+            HunkLine::Context(text) | HunkLine::Removed(text) | HunkLine::Added(text) => text,
+        }
+    }
+
+    /// Whether the line ends with a line ending.
+    pub fn has_line_ending(&self) -> bool {
+        matches!(self.text().last(), Some(b'\n') | Some(b'\r'))
+    }
+
+    pub(crate) fn strip_line_ending(&mut self) {
+        let text = match self {
+            HunkLine::Context(text) | HunkLine::Removed(text) | HunkLine::Added(text) => text,
+        };
+        if text.last() == Some(&b'\n') {
+            text.pop();
+        }
+        if text.last() == Some(&b'\r') {
+            text.pop();
+        }
+    }
+
+    fn marker(&self) -> u8 {
+        match self {
+            HunkLine::Context(_) => b' ',
+            HunkLine::Removed(_) => b'-',
+            HunkLine::Added(_) => b'+',
+        }
+    }
+}
+
+/// A hunk of a text or property change.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Hunk {
+    /// The first line of the hunk in the original text, one-based; zero if
+    /// the hunk has no original lines at the start of the text.
+    pub original_start: u64,
+    /// The first line of the hunk in the modified text, one-based; zero if
+    /// the hunk has no modified lines at the start of the text.
+    pub modified_start: u64,
+    /// The lines of the hunk.
+    pub lines: Vec<HunkLine>,
+}
+
+impl Hunk {
+    /// Create an empty hunk starting at the given lines.
+    pub fn new(original_start: u64, modified_start: u64) -> Self {
+        Self {
+            original_start,
+            modified_start,
+            lines: Vec::new(),
+        }
+    }
+
+    /// Append a line to the hunk.
+    pub fn push(&mut self, line: HunkLine) {
+        self.lines.push(line);
+    }
+
+    /// The number of lines the hunk covers in the original text.
+    pub fn original_length(&self) -> u64 {
+        self.lines
+            .iter()
+            .filter(|line| !matches!(line, HunkLine::Added(_)))
+            .count() as u64
+    }
+
+    /// The number of lines the hunk covers in the modified text.
+    pub fn modified_length(&self) -> u64 {
+        self.lines
+            .iter()
+            .filter(|line| !matches!(line, HunkLine::Removed(_)))
+            .count() as u64
+    }
+
+    /// Build a hunk replacing all of `original` with all of `modified`.
+    fn replacing(original: &[u8], modified: &[u8]) -> Self {
+        let original = super::line_ranges(original)
+            .into_iter()
+            .map(|range| HunkLine::Removed(original[range].to_vec()));
+        let modified = super::line_ranges(modified)
+            .into_iter()
+            .map(|range| HunkLine::Added(modified[range].to_vec()));
+        let mut hunk = Hunk::new(0, 0);
+        hunk.lines = original.chain(modified).collect();
+        if hunk.original_length() > 0 {
+            hunk.original_start = 1;
+        }
+        if hunk.modified_length() > 0 {
+            hunk.modified_start = 1;
+        }
+        hunk
+    }
+}
+
+impl TryFrom<&super::DiffHunk<'_>> for Hunk {
+    type Error = Error<'static>;
+
+    fn try_from(hunk: &super::DiffHunk<'_>) -> Result<Self, Self::Error> {
+        Ok(Self {
+            original_start: hunk.original_start(),
+            modified_start: hunk.modified_start(),
+            lines: hunk.lines()?,
+        })
+    }
+}
+
+/// The change to a single property.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropPatch {
+    /// The name of the property.
+    pub name: String,
+    /// Whether the property is added, deleted or modified.
+    pub operation: PatchOperation,
+    /// The hunks that make up the change to the property value.
+    pub hunks: Vec<Hunk>,
+}
+
+impl PropPatch {
+    /// A property added with the given value.
+    pub fn added(name: &str, value: &[u8]) -> Self {
+        Self {
+            name: name.to_string(),
+            operation: PatchOperation::Added,
+            hunks: vec![Hunk::replacing(b"", value)],
+        }
+    }
+
+    /// A property with the given value deleted.
+    pub fn deleted(name: &str, value: &[u8]) -> Self {
+        Self {
+            name: name.to_string(),
+            operation: PatchOperation::Deleted,
+            hunks: vec![Hunk::replacing(value, b"")],
+        }
+    }
+
+    /// A property whose value changed from `old_value` to `new_value`.
+    pub fn modified(name: &str, old_value: &[u8], new_value: &[u8]) -> Self {
+        Self {
+            name: name.to_string(),
+            operation: PatchOperation::Modified,
+            hunks: vec![Hunk::replacing(old_value, new_value)],
+        }
+    }
+}
+
+impl TryFrom<&super::PropPatch<'_>> for PropPatch {
+    type Error = Error<'static>;
+
+    fn try_from(prop_patch: &super::PropPatch<'_>) -> Result<Self, Self::Error> {
+        Ok(Self {
+            name: prop_patch.name().to_string(),
+            operation: prop_patch.operation(),
+            hunks: prop_patch
+                .hunks()
+                .iter()
+                .map(Hunk::try_from)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+/// The full contents of a binary file before and after a patch.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BinaryPatch {
+    /// The contents before the patch.
+    pub original: Vec<u8>,
+    /// The contents after the patch.
+    pub result: Vec<u8>,
+}
+
+impl TryFrom<&super::BinaryPatch<'_>> for BinaryPatch {
+    type Error = Error<'static>;
+
+    fn try_from(binary_patch: &super::BinaryPatch<'_>) -> Result<Self, Self::Error> {
+        Ok(Self {
+            original: binary_patch.original()?,
+            result: binary_patch.result()?,
+        })
+    }
+}
+
+/// The changes to a single file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Patch {
+    /// The path of the file before the patch.
+    pub old_filename: String,
+    /// The path of the file after the patch.
+    pub new_filename: String,
+    /// The tree operation performed on the file.
+    pub operation: PatchOperation,
+    /// The changes to the text of the file.
+    pub hunks: Vec<Hunk>,
+    /// The changes to the properties of the file.
+    pub prop_patches: Vec<PropPatch>,
+    /// The binary contents of the file, for binary files.
+    pub binary_patch: Option<BinaryPatch>,
+    /// Whether the file was executable before the patch, if known.
+    pub old_executable_bit: Option<bool>,
+    /// Whether the file is executable after the patch, if known.
+    pub new_executable_bit: Option<bool>,
+    /// Whether the file was a symlink before the patch, if known.
+    pub old_symlink_bit: Option<bool>,
+    /// Whether the file is a symlink after the patch, if known.
+    pub new_symlink_bit: Option<bool>,
+}
+
+impl Patch {
+    /// Create a patch with no changes.
+    pub fn new(old_filename: &str, new_filename: &str, operation: PatchOperation) -> Self {
+        Self {
+            old_filename: old_filename.to_string(),
+            new_filename: new_filename.to_string(),
+            operation,
+            hunks: Vec::new(),
+            prop_patches: Vec::new(),
+            binary_patch: None,
+            old_executable_bit: None,
+            new_executable_bit: None,
+            old_symlink_bit: None,
+            new_symlink_bit: None,
+        }
+    }
+
+    /// Write the patch out as a unified diff in the given format.
+    pub fn to_bytes(&self, format: PatchFormat) -> Result<Vec<u8>, Error<'static>> {
+        let mut writer = PatchWriter::new(Vec::new(), format);
+        writer.write_patch(self)?;
+        Ok(writer.into_inner())
+    }
+
+    /// The path the patch is reported under: the new path, unless the file
+    /// is deleted.
+    fn target(&self) -> &str {
+        if self.operation == PatchOperation::Deleted {
+            &self.old_filename
+        } else {
+            &self.new_filename
+        }
+    }
+}
+
+impl TryFrom<&super::Patch<'_>> for Patch {
+    type Error = Error<'static>;
+
+    fn try_from(patch: &super::Patch<'_>) -> Result<Self, Self::Error> {
+        Ok(Self {
+            old_filename: patch.old_filename().unwrap_or_default().to_string(),
+            new_filename: patch.new_filename().unwrap_or_default().to_string(),
+            operation: patch.operation(),
+            hunks: patch
+                .hunks()
+                .iter()
+                .map(Hunk::try_from)
+                .collect::<Result<_, _>>()?,
+            prop_patches: patch
+                .prop_patches()
+                .iter()
+                .map(PropPatch::try_from)
+                .collect::<Result<_, _>>()?,
+            binary_patch: patch
+                .binary_patch()
+                .as_ref()
+                .map(BinaryPatch::try_from)
+                .transpose()?,
+            old_executable_bit: patch.old_executable_bit(),
+            new_executable_bit: patch.new_executable_bit(),
+            old_symlink_bit: patch.old_symlink_bit(),
+            new_symlink_bit: patch.new_symlink_bit(),
+        })
+    }
+}
+
+/// The flavour of unified diff written by a [`PatchWriter`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PatchFormat {
+    /// Subversion style, with `Index:` headers.  Binary contents cannot be
+    /// represented and are omitted.
+    #[default]
+    Svn,
+    /// Git style, with `diff --git` headers, copy, rename and mode
+    /// metadata, and `GIT binary patch` sections.
+    Git,
+}
+
+/// Writes [`Patch`]es as unified diffs.
+///
+/// Property changes are written as `Property changes on:` sections in both
+/// formats, as `svn diff` does and as [`super::PatchFile`] expects.
+pub struct PatchWriter<W: Write> {
+    writer: W,
+    format: PatchFormat,
+}
+
+impl<W: Write> PatchWriter<W> {
+    /// Create a writer producing the given format.
+    pub fn new(writer: W, format: PatchFormat) -> Self {
+        Self { writer, format }
+    }
+
+    /// The format this writer produces.
+    pub fn format(&self) -> PatchFormat {
+        self.format
+    }
+
+    /// Return the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Write a single patch.
+    pub fn write_patch(&mut self, patch: &Patch) -> std::io::Result<()> {
+        match self.format {
+            PatchFormat::Svn => self.write_svn_header(patch)?,
+            PatchFormat::Git => self.write_git_header(patch)?,
+        }
+        for hunk in &patch.hunks {
+            self.write_hunk(hunk, "@@", "file")?;
+        }
+        if !patch.prop_patches.is_empty() {
+            write!(
+                self.writer,
+                "\nProperty changes on: {}\n{}\n",
+                patch.target(),
+                PROPERTY_SEPARATOR
+            )?;
+            for prop_patch in &patch.prop_patches {
+                let label = match prop_patch.operation {
+                    PatchOperation::Added => "Added",
+                    PatchOperation::Deleted => "Deleted",
+                    _ => "Modified",
+                };
+                writeln!(self.writer, "{}: {}", label, prop_patch.name)?;
+                for hunk in &prop_patch.hunks {
+                    self.write_hunk(hunk, "##", "property")?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn write_svn_header(&mut self, patch: &Patch) -> std::io::Result<()> {
+        writeln!(self.writer, "Index: {}", patch.target())?;
+        writeln!(self.writer, "{}", INDEX_SEPARATOR)?;
+        if patch.binary_patch.is_some() && patch.hunks.is_empty() {
+            writeln!(self.writer, "Cannot display: file marked as a binary type.")?;
+            return Ok(());
+        }
+        writeln!(self.writer, "--- {}", patch.old_filename)?;
+        writeln!(self.writer, "+++ {}", patch.new_filename)?;
+        Ok(())
+    }
+
+    fn write_git_header(&mut self, patch: &Patch) -> std::io::Result<()> {
+        writeln!(
+            self.writer,
+            "diff --git a/{} b/{}",
+            patch.old_filename, patch.new_filename
+        )?;
+        let old_mode = git_mode(patch.old_executable_bit, patch.old_symlink_bit);
+        let new_mode = git_mode(patch.new_executable_bit, patch.new_symlink_bit);
+        match patch.operation {
+            PatchOperation::Added => writeln!(self.writer, "new file mode {}", new_mode)?,
+            PatchOperation::Deleted => writeln!(self.writer, "deleted file mode {}", old_mode)?,
+            _ if old_mode != new_mode => {
+                writeln!(self.writer, "old mode {}", old_mode)?;
+                writeln!(self.writer, "new mode {}", new_mode)?;
+            }
+            _ => {}
+        }
+        match patch.operation {
+            PatchOperation::Copied => {
+                writeln!(self.writer, "copy from {}", patch.old_filename)?;
+                writeln!(self.writer, "copy to {}", patch.new_filename)?;
+            }
+            PatchOperation::Moved => {
+                writeln!(self.writer, "rename from {}", patch.old_filename)?;
+                writeln!(self.writer, "rename to {}", patch.new_filename)?;
+            }
+            _ => {}
+        }
+
+        if let Some(binary_patch) = &patch.binary_patch {
+            // git apply refuses binary patches without full object names.
+            let original = match patch.operation {
+                PatchOperation::Added => GIT_NULL_ID.to_string(),
+                _ => git_blob_id(&binary_patch.original)?,
+            };
+            let result = match patch.operation {
+                PatchOperation::Deleted => GIT_NULL_ID.to_string(),
+                _ => git_blob_id(&binary_patch.result)?,
+            };
+            match patch.operation {
+                PatchOperation::Added | PatchOperation::Deleted => {
+                    writeln!(self.writer, "index {}..{}", original, result)?
+                }
+                _ if old_mode != new_mode => {
+                    writeln!(self.writer, "index {}..{}", original, result)?
+                }
+                _ => writeln!(self.writer, "index {}..{} {}", original, result, new_mode)?,
+            }
+            writeln!(self.writer, "GIT binary patch")?;
+            self.write_binary_literal(&binary_patch.result)?;
+            self.write_binary_literal(&binary_patch.original)?;
+        } else if !patch.hunks.is_empty() {
+            if patch.operation == PatchOperation::Added {
+                writeln!(self.writer, "--- /dev/null")?;
+            } else {
+                writeln!(self.writer, "--- a/{}", patch.old_filename)?;
+            }
+            if patch.operation == PatchOperation::Deleted {
+                writeln!(self.writer, "+++ /dev/null")?;
+            } else {
+                writeln!(self.writer, "+++ b/{}", patch.new_filename)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_hunk(&mut self, hunk: &Hunk, delimiter: &str, what: &str) -> std::io::Result<()> {
+        writeln!(
+            self.writer,
+            "{} -{} +{} {}",
+            delimiter,
+            hunk_range(hunk.original_start, hunk.original_length()),
+            hunk_range(hunk.modified_start, hunk.modified_length()),
+            delimiter
+        )?;
+        for line in &hunk.lines {
+            self.writer.write_all(&[line.marker()])?;
+            self.writer.write_all(line.text())?;
+            if !line.has_line_ending() {
+                write!(self.writer, "\n\\ No newline at end of {}\n", what)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write `data` as a zlib-compressed, base85-encoded `literal` block.
+    fn write_binary_literal(&mut self, data: &[u8]) -> std::io::Result<()> {
+        let mut compressed = crate::io::StringBuf::new();
+        {
+            let mut output = crate::io::Stream::from_stringbuf(&mut compressed);
+            let mut stream = output.compressed();
+            stream.write_all(data)?;
+            stream.close()?;
+        }
+
+        writeln!(self.writer, "literal {}", data.len())?;
+        for chunk in compressed.as_bytes().chunks(52) {
+            let length = if chunk.len() <= 26 {
+                b'A' + chunk.len() as u8 - 1
+            } else {
+                b'a' + chunk.len() as u8 - 27
+            };
+            self.writer.write_all(&[length])?;
+            for group in chunk.chunks(4) {
+                let mut value = group
+                    .iter()
+                    .chain(std::iter::repeat(&0))
+                    .take(4)
+                    .fold(0u32, |acc, &byte| acc << 8 | byte as u32);
+                let mut encoded = [0u8; 5];
+                for c in encoded.iter_mut().rev() {
+                    *c = BASE85_ALPHABET[(value % 85) as usize];
+                    value /= 85;
+                }
+                self.writer.write_all(&encoded)?;
+            }
+            writeln!(self.writer)?;
+        }
+        writeln!(self.writer)?;
+        Ok(())
+    }
+}
+
+/// The object id git gives a blob holding `data`: the SHA-1 of a
+/// `blob <length>` header followed by the contents.
+fn git_blob_id(data: &[u8]) -> Result<String, Error<'static>> {
+    let mut object = format!("blob {}\0", data.len()).into_bytes();
+    object.extend_from_slice(data);
+    let pool = apr::Pool::new();
+    Ok(crate::checksum(crate::ChecksumKind::SHA1, &object, &pool)?.to_hex(&pool))
+}
+
+/// The git file mode for a file with the given bits.
+fn git_mode(executable: Option<bool>, symlink: Option<bool>) -> &'static str {
+    if symlink == Some(true) {
+        "120000"
+    } else if executable == Some(true) {
+        "100755"
+    } else {
+        "100644"
+    }
+}
+
+fn hunk_range(start: u64, length: u64) -> String {
+    if length == 1 {
+        start.to_string()
+    } else {
+        format!("{},{}", start, length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::PatchFile;
+    use tempfile::tempdir;
+
+    fn sample_patch() -> Patch {
+        let mut patch = Patch::new("trunk/foo.c", "trunk/foo.c", PatchOperation::Modified);
+        let mut hunk = Hunk::new(2, 2);
+        hunk.push(HunkLine::Context(b"int a;\n".to_vec()));
+        hunk.push(HunkLine::Removed(b"int b;\n".to_vec()));
+        hunk.push(HunkLine::Added(b"int c;".to_vec()));
+        patch.hunks.push(hunk);
+        patch
+            .prop_patches
+            .push(PropPatch::added("svn:eol-style", b"native"));
+        patch
+            .prop_patches
+            .push(PropPatch::modified("test:prop", b"one\n", b"two\n"));
+        patch
+    }
+
+    fn parse(text: &[u8]) -> Result<Vec<Patch>, Box<dyn std::error::Error>> {
+        let temp_dir = tempdir()?;
+        let patch_path = temp_dir.path().join("change.patch");
+        std::fs::write(&patch_path, text)?;
+        let mut patch_file = PatchFile::open(&patch_path)?;
+        let mut patches = Vec::new();
+        while let Some(patch) = patch_file.next_patch(false, false)? {
+            patches.push(Patch::try_from(&patch)?);
+        }
+        Ok(patches)
+    }
+
+    #[test]
+    fn test_write_svn() {
+        let text = sample_patch().to_bytes(PatchFormat::Svn).unwrap();
+        assert_eq!(
+            String::from_utf8(text).unwrap(),
+            format!(
+                "Index: trunk/foo.c\n{}\n--- trunk/foo.c\n+++ trunk/foo.c\n\
+                 @@ -2,2 +2,2 @@\n int a;\n-int b;\n+int c;\n\\ No newline at end of file\n\
+                 \nProperty changes on: trunk/foo.c\n{}\n\
+                 Added: svn:eol-style\n## -0,0 +1 ##\n+native\n\\ No newline at end of property\n\
+                 Modified: test:prop\n## -1 +1 ##\n-one\n+two\n",
+                INDEX_SEPARATOR, PROPERTY_SEPARATOR
+            )
+        );
+    }
+
+    #[test]
+    fn test_write_git_headers() {
+        let mut patch = Patch::new("old.sh", "new.sh", PatchOperation::Moved);
+        patch.old_executable_bit = Some(false);
+        patch.new_executable_bit = Some(true);
+        let text = patch.to_bytes(PatchFormat::Git).unwrap();
+        assert_eq!(
+            String::from_utf8(text).unwrap(),
+            "diff --git a/old.sh b/new.sh\nold mode 100644\nnew mode 100755\n\
+             rename from old.sh\nrename to new.sh\n"
+        );
+
+        let mut patch = Patch::new("gone.txt", "gone.txt", PatchOperation::Deleted);
+        let mut hunk = Hunk::new(1, 0);
+        hunk.push(HunkLine::Removed(b"bye\n".to_vec()));
+        patch.hunks.push(hunk);
+        let text = patch.to_bytes(PatchFormat::Git).unwrap();
+        assert_eq!(
+            String::from_utf8(text).unwrap(),
+            "diff --git a/gone.txt b/gone.txt\ndeleted file mode 100644\n\
+             --- a/gone.txt\n+++ /dev/null\n@@ -1 +0,0 @@\n-bye\n"
+        );
+    }
+
+    #[test]
+    fn test_roundtrip_svn() -> Result<(), Box<dyn std::error::Error>> {
+        let patch = sample_patch();
+        let parsed = parse(&patch.to_bytes(PatchFormat::Svn)?)?;
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].new_filename, "trunk/foo.c");
+        assert_eq!(parsed[0].hunks, patch.hunks);
+        assert_eq!(parsed[0].prop_patches, patch.prop_patches);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_blank_context_line() -> Result<(), Box<dyn std::error::Error>> {
+        let text = format!(
+            "Index: a.txt\n{}\n--- a.txt\n+++ a.txt\n@@ -1,3 +1,3 @@\n one\n\n-two\n+TWO\n",
+            INDEX_SEPARATOR
+        );
+        let parsed = parse(text.as_bytes())?;
+        let hunk = &parsed[0].hunks[0];
+        assert_eq!(
+            hunk.lines,
+            [
+                HunkLine::Context(b"one\n".to_vec()),
+                HunkLine::Context(b"\n".to_vec()),
+                HunkLine::Removed(b"two\n".to_vec()),
+                HunkLine::Added(b"TWO\n".to_vec()),
+            ]
+        );
+        assert_eq!(hunk.original_length(), 3);
+        assert_eq!(hunk.modified_length(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_roundtrip_git_binary() -> Result<(), Box<dyn std::error::Error>> {
+        let mut patch = Patch::new("image.png", "image.png", PatchOperation::Modified);
+        patch.binary_patch = Some(BinaryPatch {
+            original: (0u8..=255).collect(),
+            result: b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".repeat(10),
+        });
+        let parsed = parse(&patch.to_bytes(PatchFormat::Git)?)?;
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].new_filename, "image.png");
+        assert_eq!(parsed[0].binary_patch, patch.binary_patch);
+        Ok(())
+    }
+
+    #[test]
+    fn test_git_binary_index() -> Result<(), Box<dyn std::error::Error>> {
+        // The names `git hash-object` gives an empty file and "hello\n".
+        let empty = "e69de29bb2d1d6434b8b29ae775ad8c2e48c5391";
+        let hello = "ce013625030ba8dba906f756967f9e9ca394464a";
+
+        let mut patch = Patch::new("data.bin", "data.bin", PatchOperation::Modified);
+        patch.binary_patch = Some(BinaryPatch {
+            original: Vec::new(),
+            result: b"hello\n".to_vec(),
+        });
+        let text = String::from_utf8(patch.to_bytes(PatchFormat::Git)?)?;
+        assert!(text.contains(&format!(
+            "\nindex {}..{} 100644\nGIT binary patch\n",
+            empty, hello
+        )));
+        assert_eq!(parse(text.as_bytes())?[0].binary_patch, patch.binary_patch);
+
+        patch.operation = PatchOperation::Added;
+        let text = String::from_utf8(patch.to_bytes(PatchFormat::Git)?)?;
+        assert!(text.contains(&format!(
+            "new file mode 100644\nindex {}..{}\nGIT binary patch\n",
+            GIT_NULL_ID, hello
+        )));
+
+        patch.operation = PatchOperation::Deleted;
+        let text = String::from_utf8(patch.to_bytes(PatchFormat::Git)?)?;
+        assert!(text.contains(&format!("\nindex {}..{}\n", empty, GIT_NULL_ID)));
+        Ok(())
+    }
+}
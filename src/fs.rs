@@ -518,6 +518,53 @@ impl<'pool> Fs<'pool> {
         }
     }
 
+    /// Create a new filesystem at `path` with the typed `config`.
+    ///
+    /// The creation-time settings are passed to `svn_fs_create2`; the
+    /// remaining settings are then written to the backend's configuration
+    /// file and the filesystem is reopened so that they take effect.
+    pub fn create_with_config(
+        path: &std::path::Path,
+        config: &FsConfig,
+    ) -> Result<Fs<'static>, Error<'static>> {
+        crate::init::initialize()?;
+        config.validate()?;
+
+        let path_c = CString::new(
+            path.to_str()
+                .ok_or_else(|| Error::from_message("Invalid path"))?,
+        )?;
+        let entries = config
+            .fs_config()
+            .into_iter()
+            .map(|(key, value)| Ok((key, CString::new(value)?)))
+            .collect::<Result<Vec<_>, Error<'static>>>()?;
+        {
+            let pool = apr::Pool::new();
+            let mut hash = apr::hash::Hash::new(&pool);
+            for (key, value) in &entries {
+                unsafe {
+                    hash.insert(key.as_bytes(), value.as_ptr() as *mut std::ffi::c_void);
+                }
+            }
+            let mut fs_ptr = std::ptr::null_mut();
+            with_tmp_pool(|scratch_pool| unsafe {
+                let err = subversion_sys::svn_fs_create2(
+                    &mut fs_ptr,
+                    path_c.as_ptr(),
+                    hash.as_mut_ptr(),
+                    pool.as_mut_ptr(),
+                    scratch_pool.as_mut_ptr(),
+                );
+                svn_result(err)
+            })?;
+            // Dropping the pool closes the filesystem again.
+        }
+
+        config.write(path)?;
+        Fs::open(path).map_err(|e| e.into_static())
+    }
+
     /// Gets the path to the filesystem.
     pub fn path(&self) -> std::path::PathBuf {
         unsafe {
@@ -1747,6 +1794,594 @@ pub fn upgrade(
     svn_result(err)
 }
 
+/// Filesystem backend type, as stored in a filesystem's `fs-type` file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FsBackend {
+    /// The FSFS backend.
+    Fsfs,
+    /// The experimental FSX backend.
+    Fsx,
+    /// The Berkeley DB backend.
+    Bdb,
+}
+
+impl FsBackend {
+    /// The name used for this backend in the `fs-type` configuration key.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FsBackend::Fsfs => "fsfs",
+            FsBackend::Fsx => "fsx",
+            FsBackend::Bdb => "bdb",
+        }
+    }
+
+    /// Name of the backend's configuration file inside the filesystem
+    /// directory, if it has one.
+    pub fn conf_file_name(&self) -> Option<&'static str> {
+        match self {
+            FsBackend::Fsfs => Some("fsfs.conf"),
+            FsBackend::Fsx => Some("fsx.conf"),
+            FsBackend::Bdb => None,
+        }
+    }
+}
+
+impl std::fmt::Display for FsBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for FsBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fsfs" => Ok(FsBackend::Fsfs),
+            "fsx" => Ok(FsBackend::Fsx),
+            "bdb" => Ok(FsBackend::Bdb),
+            _ => Err(format!("Unknown filesystem type: {}", s)),
+        }
+    }
+}
+
+/// Compression applied to representations, as configured by the
+/// `compression` option in `fsfs.conf`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// Store representations uncompressed.
+    None,
+    /// zlib compression, optionally at an explicit level between 1 and 9.
+    Zlib(Option<u8>),
+    /// LZ4 compression (Subversion 1.10 and later).
+    Lz4,
+}
+
+impl std::fmt::Display for Compression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Compression::None => f.write_str("none"),
+            Compression::Zlib(None) => f.write_str("zlib"),
+            Compression::Zlib(Some(level)) => write!(f, "zlib-{}", level),
+            Compression::Lz4 => f.write_str("lz4"),
+        }
+    }
+}
+
+impl std::str::FromStr for Compression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Compression::None),
+            "zlib" => Ok(Compression::Zlib(None)),
+            "lz4" => Ok(Compression::Lz4),
+            _ => s
+                .strip_prefix("zlib-")
+                .and_then(|level| level.parse().ok())
+                .map(|level| Compression::Zlib(Some(level)))
+                .ok_or_else(|| format!("Invalid compression: {}", s)),
+        }
+    }
+}
+
+/// Typed filesystem configuration.
+///
+/// The backend, compatible version, shard size and addressing mode are
+/// fixed when a filesystem is created and are passed as `fs_config` keys;
+/// the remaining settings live in the backend's configuration file
+/// (`db/fsfs.conf` for FSFS) and can be changed later with
+/// [`FsConfig::write`].  Settings left as `None` keep the library default,
+/// or the existing value when writing.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FsConfig {
+    /// Backend to create.
+    pub backend: Option<FsBackend>,
+    /// Oldest Subversion release, as `(major, minor)`, that must be able to
+    /// read the filesystem.  Only used at creation time.
+    pub compatible_version: Option<(i32, i32)>,
+    /// Representation compression.
+    pub compression: Option<Compression>,
+    /// Number of revisions per shard; 0 selects the linear layout.
+    pub shard_size: Option<u32>,
+    /// Whether identical representations are shared.
+    pub rep_sharing: Option<bool>,
+    /// Whether directories are stored as deltas.
+    pub dir_deltification: Option<bool>,
+    /// Whether property lists are stored as deltas.
+    pub props_deltification: Option<bool>,
+    /// Target size in kilobytes of packed revprop files.
+    pub revprop_pack_size: Option<u32>,
+    /// Whether packed revprop files are compressed.
+    pub compress_packed_revprops: Option<bool>,
+    /// Whether to use logical rather than physical addressing.
+    pub log_addressing: Option<bool>,
+}
+
+fn parse_conf_bool(option: &str, value: &str) -> Result<bool, Error<'static>> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Ok(true),
+        "false" | "no" | "off" | "0" => Ok(false),
+        _ => Err(Error::from_message(&format!(
+            "Invalid boolean value '{}' for {}",
+            value, option
+        ))),
+    }
+}
+
+fn parse_conf_number<T: std::str::FromStr>(option: &str, value: &str) -> Result<T, Error<'static>> {
+    value.parse().map_err(|_| {
+        Error::from_message(&format!("Invalid numeric value '{}' for {}", value, option))
+    })
+}
+
+impl FsConfig {
+    /// Create an empty configuration that uses the library defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the backend.
+    pub fn with_backend(mut self, backend: FsBackend) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// Set the oldest compatible Subversion release.
+    pub fn with_compatible_version(mut self, major: i32, minor: i32) -> Self {
+        self.compatible_version = Some((major, minor));
+        self
+    }
+
+    /// Set the representation compression.
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    /// Set the shard size; 0 selects the linear layout.
+    pub fn with_shard_size(mut self, shard_size: u32) -> Self {
+        self.shard_size = Some(shard_size);
+        self
+    }
+
+    /// Enable or disable representation sharing.
+    pub fn with_rep_sharing(mut self, enable: bool) -> Self {
+        self.rep_sharing = Some(enable);
+        self
+    }
+
+    /// Enable or disable directory deltification.
+    pub fn with_dir_deltification(mut self, enable: bool) -> Self {
+        self.dir_deltification = Some(enable);
+        self
+    }
+
+    /// Enable or disable property deltification.
+    pub fn with_props_deltification(mut self, enable: bool) -> Self {
+        self.props_deltification = Some(enable);
+        self
+    }
+
+    /// Configure packed revprops: the target pack size in kilobytes and
+    /// whether packs are compressed.
+    pub fn with_packed_revprops(mut self, pack_size: u32, compress: bool) -> Self {
+        self.revprop_pack_size = Some(pack_size);
+        self.compress_packed_revprops = Some(compress);
+        self
+    }
+
+    /// Enable or disable logical addressing.
+    pub fn with_log_addressing(mut self, enable: bool) -> Self {
+        self.log_addressing = Some(enable);
+        self
+    }
+
+    fn has_conf_settings(&self) -> bool {
+        self.compression.is_some()
+            || self.rep_sharing.is_some()
+            || self.dir_deltification.is_some()
+            || self.props_deltification.is_some()
+            || self.revprop_pack_size.is_some()
+            || self.compress_packed_revprops.is_some()
+    }
+
+    /// Check that the configuration is consistent and supported by the
+    /// linked filesystem library.
+    pub fn validate(&self) -> Result<(), Error<'static>> {
+        let library = version();
+        let library_version = (library.major(), library.minor());
+        let require = |feature: &str, min: (i32, i32)| -> Result<(), Error<'static>> {
+            if library_version < min {
+                return Err(Error::from_message(&format!(
+                    "{} requires Subversion {}.{} or later, but the linked library is {}",
+                    feature, min.0, min.1, library
+                )));
+            }
+            match self.compatible_version {
+                Some(compat) if compat < min => Err(Error::from_message(&format!(
+                    "{} is not supported with compatible-version {}.{}",
+                    feature, compat.0, compat.1
+                ))),
+                _ => Ok(()),
+            }
+        };
+
+        if let Some(compat) = self.compatible_version {
+            if compat < (1, 0) || compat > library_version {
+                return Err(Error::from_message(&format!(
+                    "compatible-version {}.{} is not supported by the linked library {}",
+                    compat.0, compat.1, library
+                )));
+            }
+        }
+        match self.backend {
+            Some(FsBackend::Fsx) => require("The fsx backend", (1, 9))?,
+            Some(FsBackend::Bdb) => {
+                if self.has_conf_settings()
+                    || self.shard_size.is_some()
+                    || self.log_addressing.is_some()
+                {
+                    return Err(Error::from_message(
+                        "The bdb backend does not support fsfs configuration options",
+                    ));
+                }
+            }
+            _ => {}
+        }
+        match self.compression {
+            Some(Compression::Zlib(Some(level))) if !(1..=9).contains(&level) => {
+                return Err(Error::from_message(&format!(
+                    "Invalid zlib compression level: {}",
+                    level
+                )));
+            }
+            Some(Compression::Lz4) => require("LZ4 compression", (1, 10))?,
+            _ => {}
+        }
+        if self.shard_size.is_some() {
+            require("Configuring the shard size", (1, 9))?;
+        }
+        if self.log_addressing == Some(true) {
+            require("Logical addressing", (1, 9))?;
+        }
+        if self.revprop_pack_size.is_some() || self.compress_packed_revprops.is_some() {
+            require("Packed revprops", (1, 6))?;
+        }
+        Ok(())
+    }
+
+    /// The creation-time settings as the raw `fs_config` map accepted by
+    /// `svn_fs_create2` and [`crate::repos::Repos::create_with_config`].
+    pub fn fs_config(&self) -> std::collections::HashMap<String, String> {
+        let mut config = std::collections::HashMap::new();
+        if let Some(backend) = self.backend {
+            config.insert("fs-type".to_string(), backend.to_string());
+        }
+        if let Some((major, minor)) = self.compatible_version {
+            config.insert(
+                "compatible-version".to_string(),
+                format!("{}.{}", major, minor),
+            );
+        }
+        if let Some(shard_size) = self.shard_size {
+            config.insert("fsfs-shard-size".to_string(), shard_size.to_string());
+        }
+        if let Some(log_addressing) = self.log_addressing {
+            config.insert(
+                "fsfs-log-addressing".to_string(),
+                log_addressing.to_string(),
+            );
+        }
+        config
+    }
+
+    /// Read the configuration of the existing filesystem at `fs_path`
+    /// (the `db` directory of a repository).
+    ///
+    /// The backend, shard size and addressing mode are taken from the
+    /// filesystem's `fs-type` and `format` files, the other settings from
+    /// its configuration file.  The compatible version cannot be recovered
+    /// and is always `None`.
+    pub fn read(fs_path: &std::path::Path) -> Result<Self, Error<'static>> {
+        let backend: FsBackend = std::fs::read_to_string(fs_path.join("fs-type"))?
+            .trim()
+            .parse()
+            .map_err(|e: String| Error::from_message(&e))?;
+        let mut config = FsConfig::new().with_backend(backend);
+        let conf_file_name = match backend.conf_file_name() {
+            Some(name) => name,
+            None => return Ok(config),
+        };
+
+        let format = std::fs::read_to_string(fs_path.join("format"))?;
+        for line in format.lines().skip(1) {
+            match line.split_whitespace().collect::<Vec<_>>().as_slice() {
+                ["layout", "sharded", size] => {
+                    config.shard_size = Some(parse_conf_number("layout", size)?)
+                }
+                ["layout", "linear"] => config.shard_size = Some(0),
+                ["addressing", mode] => config.log_addressing = Some(*mode == "logical"),
+                _ => {}
+            }
+        }
+
+        let conf_path = fs_path.join(conf_file_name);
+        if conf_path.exists() {
+            config.load_conf(&FsfsConf::read(&conf_path)?)?;
+        }
+        Ok(config)
+    }
+
+    /// Take the configuration-file settings from `conf`.
+    pub fn load_conf(&mut self, conf: &FsfsConf) -> Result<(), Error<'static>> {
+        let get_bool = |section: &str, option: &str| -> Result<Option<bool>, Error<'static>> {
+            conf.get(section, option)
+                .map(|value| parse_conf_bool(option, value))
+                .transpose()
+        };
+        if let Some(value) = get_bool("rep-sharing", "enable-rep-sharing")? {
+            self.rep_sharing = Some(value);
+        }
+        if let Some(value) = get_bool("deltification", "enable-dir-deltification")? {
+            self.dir_deltification = Some(value);
+        }
+        if let Some(value) = get_bool("deltification", "enable-props-deltification")? {
+            self.props_deltification = Some(value);
+        }
+        if let Some(value) = conf.get("deltification", "compression") {
+            self.compression = Some(value.parse().map_err(|e: String| Error::from_message(&e))?);
+        } else if let Some(value) = conf.get("deltification", "compression-level") {
+            self.compression = Some(match parse_conf_number("compression-level", value)? {
+                0 => Compression::None,
+                level => Compression::Zlib(Some(level)),
+            });
+        }
+        if let Some(value) = conf.get("packed-revprops", "revprop-pack-size") {
+            self.revprop_pack_size = Some(parse_conf_number("revprop-pack-size", value)?);
+        }
+        if let Some(value) = get_bool("packed-revprops", "compress-packed-revprops")? {
+            self.compress_packed_revprops = Some(value);
+        }
+        Ok(())
+    }
+
+    /// Store the configuration-file settings that are set in `conf`.
+    ///
+    /// Libraries older than 1.10 only understand a numeric
+    /// `compression-level`, which is written instead of `compression`.
+    pub fn apply_conf(&self, conf: &mut FsfsConf) {
+        let set_bool = |conf: &mut FsfsConf, section: &str, option: &str, value: Option<bool>| {
+            if let Some(value) = value {
+                conf.set(section, option, &value.to_string());
+            }
+        };
+        set_bool(conf, "rep-sharing", "enable-rep-sharing", self.rep_sharing);
+        set_bool(
+            conf,
+            "deltification",
+            "enable-dir-deltification",
+            self.dir_deltification,
+        );
+        set_bool(
+            conf,
+            "deltification",
+            "enable-props-deltification",
+            self.props_deltification,
+        );
+        if let Some(compression) = self.compression {
+            let library = version();
+            if (library.major(), library.minor()) >= (1, 10) {
+                conf.set("deltification", "compression", &compression.to_string());
+            } else {
+                let level = match compression {
+                    Compression::None | Compression::Lz4 => 0,
+                    Compression::Zlib(level) => level.unwrap_or(5),
+                };
+                conf.set("deltification", "compression-level", &level.to_string());
+            }
+        }
+        if let Some(pack_size) = self.revprop_pack_size {
+            conf.set(
+                "packed-revprops",
+                "revprop-pack-size",
+                &pack_size.to_string(),
+            );
+        }
+        set_bool(
+            conf,
+            "packed-revprops",
+            "compress-packed-revprops",
+            self.compress_packed_revprops,
+        );
+    }
+
+    /// Write the configuration-file settings to the existing filesystem at
+    /// `fs_path`, keeping comments and unrelated options intact.
+    ///
+    /// Creation-time settings cannot be changed; if they are set they must
+    /// match the filesystem on disk.  The filesystem has to be reopened for
+    /// the new settings to take effect.
+    pub fn write(&self, fs_path: &std::path::Path) -> Result<(), Error<'static>> {
+        self.validate()?;
+        let on_disk = FsConfig::read(fs_path)?;
+        let fixed = [
+            (
+                "fs-type",
+                self.backend.is_some() && self.backend != on_disk.backend,
+            ),
+            (
+                "shard size",
+                on_disk.shard_size.is_some()
+                    && self.shard_size.is_some()
+                    && self.shard_size != on_disk.shard_size,
+            ),
+            (
+                "addressing mode",
+                on_disk.log_addressing.is_some()
+                    && self.log_addressing.is_some()
+                    && self.log_addressing != on_disk.log_addressing,
+            ),
+        ];
+        if let Some((setting, _)) = fixed.iter().find(|(_, differs)| *differs) {
+            return Err(Error::from_message(&format!(
+                "The {} of an existing filesystem cannot be changed",
+                setting
+            )));
+        }
+
+        let conf_file_name = match on_disk.backend.and_then(|b| b.conf_file_name()) {
+            Some(name) => name,
+            None if self.has_conf_settings() => {
+                return Err(Error::from_message(
+                    "The bdb backend does not support fsfs configuration options",
+                ))
+            }
+            None => return Ok(()),
+        };
+        let conf_path = fs_path.join(conf_file_name);
+        let mut conf = if conf_path.exists() {
+            FsfsConf::read(&conf_path)?
+        } else {
+            FsfsConf::default()
+        };
+        self.apply_conf(&mut conf);
+        conf.write(&conf_path)
+    }
+}
+
+/// A filesystem configuration file such as `db/fsfs.conf`.
+///
+/// This is a small line-based editor for the INI format used by
+/// Subversion: lookups and updates work on `[section]` / `option = value`
+/// lines, and everything else, including the commented-out defaults that
+/// Subversion writes, is preserved verbatim.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FsfsConf {
+    lines: Vec<String>,
+}
+
+fn conf_section_name(line: &str) -> Option<&str> {
+    line.trim()
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .map(str::trim)
+}
+
+fn conf_option(line: &str) -> Option<(&str, &str)> {
+    // Options start in the first column; indented lines are continuations.
+    if line.starts_with(|c: char| c.is_whitespace() || c == '#' || c == ';' || c == '[') {
+        return None;
+    }
+    let sep = line.find(['=', ':'])?;
+    Some((line[..sep].trim(), line[sep + 1..].trim()))
+}
+
+impl FsfsConf {
+    /// Parse configuration file contents.
+    pub fn parse(text: &str) -> Self {
+        Self {
+            lines: text.lines().map(str::to_string).collect(),
+        }
+    }
+
+    /// Read the configuration file at `path`.
+    pub fn read(path: &std::path::Path) -> Result<Self, Error<'static>> {
+        Ok(Self::parse(&std::fs::read_to_string(path)?))
+    }
+
+    /// Write the configuration to `path`.
+    pub fn write(&self, path: &std::path::Path) -> Result<(), Error<'static>> {
+        std::fs::write(path, self.to_string())?;
+        Ok(())
+    }
+
+    fn find(&self, section: &str, option: &str) -> Option<usize> {
+        let mut current = None;
+        for (i, line) in self.lines.iter().enumerate() {
+            if let Some(name) = conf_section_name(line) {
+                current = Some(name);
+            } else if current == Some(section) {
+                if let Some((name, _)) = conf_option(line) {
+                    if name == option {
+                        return Some(i);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Get the value of `option` in `section`.
+    pub fn get(&self, section: &str, option: &str) -> Option<&str> {
+        self.find(section, option)
+            .and_then(|i| conf_option(&self.lines[i]))
+            .map(|(_, value)| value)
+    }
+
+    /// Set `option` in `section` to `value`, replacing an existing setting
+    /// in place or adding it below the section header.  The section is
+    /// appended if it does not exist yet.
+    pub fn set(&mut self, section: &str, option: &str, value: &str) {
+        let line = format!("{} = {}", option, value);
+        if let Some(i) = self.find(section, option) {
+            self.lines[i] = line;
+        } else if let Some(i) = self
+            .lines
+            .iter()
+            .position(|l| conf_section_name(l) == Some(section))
+        {
+            self.lines.insert(i + 1, line);
+        } else {
+            if self.lines.last().is_some_and(|l| !l.trim().is_empty()) {
+                self.lines.push(String::new());
+            }
+            self.lines.push(format!("[{}]", section));
+            self.lines.push(line);
+        }
+    }
+
+    /// Remove `option` from `section`.  Returns whether it was set.
+    pub fn remove(&mut self, section: &str, option: &str) -> bool {
+        match self.find(section, option) {
+            Some(i) => {
+                self.lines.remove(i);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl std::fmt::Display for FsfsConf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for line in &self.lines {
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
 /// Returns the version of the Subversion filesystem library.
 ///
 /// Wraps `svn_fs_version`.
@@ -2900,6 +3535,491 @@ impl<'fs> Root<'fs> {
             Ok(relation.into())
         })
     }
+
+    /// Compare the tree at `path` in this root with the tree at `target_path`
+    /// in `target`, and return a record for every node that differs.
+    ///
+    /// This root is treated as the source (old) side and `target` as the new
+    /// side; either may be a revision root or a transaction root (see
+    /// [`TxnRoot::as_root`]), but both must belong to the same filesystem.
+    /// Paths in the returned [`TreeChange`]s are relative to the two anchors,
+    /// with the empty string denoting the anchors themselves.  Parents are
+    /// reported before their children, and siblings in name order.
+    ///
+    /// Added and replaced directories are reported together with every node
+    /// below them; deleted directories are reported once.
+    pub fn diff_tree(
+        &self,
+        path: impl TryInto<FsPath, Error = Error<'static>>,
+        target: &Root,
+        target_path: impl TryInto<FsPath, Error = Error<'static>>,
+        options: &TreeDiffOptions,
+    ) -> Result<Vec<TreeChange>, Error<'static>> {
+        let source_path = path.try_into()?;
+        let target_path = target_path.try_into()?;
+        let mut differ = TreeDiffer {
+            source: self,
+            target,
+            options,
+            changes: Vec::new(),
+        };
+        differ.diff_node(source_path.as_str(), target_path.as_str(), "")?;
+        Ok(differ.changes)
+    }
+
+    /// Walk the history of the node at `path` backwards, annotating each
+    /// location with the copies it was created by.
+    ///
+    /// Unlike [`Root::node_history`], the returned iterator resolves where
+    /// each copy came from, so the origin of a file that was branched or
+    /// renamed can be found in one call.  See [`HistoryOptions`] for how to
+    /// limit the walk.
+    pub fn follow_history(
+        &self,
+        path: impl TryInto<FsPath, Error = Error<'static>>,
+        options: &HistoryOptions,
+    ) -> Result<HistoryIter<'_>, Error<'static>> {
+        let history = self.node_history(path)?;
+        Ok(HistoryIter {
+            root: self,
+            history,
+            options: *options,
+            crossed_copy: false,
+            done: false,
+        })
+    }
+
+    /// Open a revision root in the filesystem this root belongs to.
+    fn sibling_revision_root(&self, rev: Revnum) -> Result<Root<'fs>, Error<'static>> {
+        let pool = apr::Pool::new();
+        unsafe {
+            let mut root_ptr = std::ptr::null_mut();
+            let err = subversion_sys::svn_fs_revision_root(
+                &mut root_ptr,
+                subversion_sys::svn_fs_root_fs(self.ptr),
+                rev.0,
+                pool.as_mut_ptr(),
+            );
+            svn_result(err)?;
+            Ok(Root {
+                ptr: root_ptr,
+                pool: apr::PoolHandle::owned(pool),
+                _marker: std::marker::PhantomData,
+            })
+        }
+    }
+}
+
+/// The kind of change reported for a node by [`Root::diff_tree`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeChangeKind {
+    /// The node only exists in the target tree.
+    Added,
+    /// The node only exists in the source tree.
+    Deleted,
+    /// The node exists in both trees, and its text or properties differ.
+    Modified,
+    /// The node in the target tree replaces a node of a different kind, or
+    /// one it shares no history with.
+    Replaced,
+}
+
+/// A single property difference reported by [`Root::diff_tree`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropChange {
+    /// Name of the property.
+    pub name: String,
+    /// Value in the source tree, or `None` if the property was added.
+    pub old_value: Option<Vec<u8>>,
+    /// Value in the target tree, or `None` if the property was deleted.
+    pub new_value: Option<Vec<u8>>,
+}
+
+/// A change to a single node, as reported by [`Root::diff_tree`].
+pub struct TreeChange {
+    /// Path relative to the compared anchors; empty for the anchors themselves.
+    pub path: String,
+    /// What happened to the node.
+    pub kind: TreeChangeKind,
+    /// Kind of the node; for deletions, the kind of the deleted node.
+    pub node_kind: crate::NodeKind,
+    /// Copy source of an added or replaced node, if it was copied.
+    pub copyfrom: Option<(Revnum, String)>,
+    /// Whether the file contents differ.  Always true for added files.
+    pub text_changed: bool,
+    /// Property differences, sorted by name.
+    pub prop_changes: Vec<PropChange>,
+    /// Delta windows transforming the source text into the target text, if
+    /// requested with [`TreeDiffOptions::text_deltas`].  Added and replaced
+    /// files are expressed against an empty source.
+    #[cfg(feature = "delta")]
+    pub text_delta: Option<Vec<crate::delta::TxDeltaWindow>>,
+    /// Unified diff of the text, if requested with
+    /// [`TreeDiffOptions::unified_diffs`] and both sides are valid UTF-8.
+    pub unified_diff: Option<String>,
+}
+
+impl TreeChange {
+    fn new(path: &str, kind: TreeChangeKind, node_kind: crate::NodeKind) -> Self {
+        Self {
+            path: path.to_string(),
+            kind,
+            node_kind,
+            copyfrom: None,
+            text_changed: false,
+            prop_changes: Vec::new(),
+            #[cfg(feature = "delta")]
+            text_delta: None,
+            unified_diff: None,
+        }
+    }
+
+    /// Whether any properties differ.
+    pub fn props_changed(&self) -> bool {
+        !self.prop_changes.is_empty()
+    }
+}
+
+impl std::fmt::Debug for TreeChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut s = f.debug_struct("TreeChange");
+        s.field("path", &self.path)
+            .field("kind", &self.kind)
+            .field("node_kind", &self.node_kind)
+            .field("copyfrom", &self.copyfrom)
+            .field("text_changed", &self.text_changed)
+            .field("prop_changes", &self.prop_changes);
+        #[cfg(feature = "delta")]
+        s.field(
+            "text_delta",
+            &self.text_delta.as_ref().map(|windows| windows.len()),
+        );
+        s.field("unified_diff", &self.unified_diff).finish()
+    }
+}
+
+/// Options for [`Root::diff_tree`].
+#[derive(Debug, Clone, Copy)]
+pub struct TreeDiffOptions {
+    /// Compare nodes of the same kind as modified even if they share no
+    /// history, instead of reporting them as replaced.
+    pub ignore_ancestry: bool,
+    /// Collect delta windows for changed files.
+    #[cfg(feature = "delta")]
+    pub text_deltas: bool,
+    /// Generate unified diffs for changed text files.
+    pub unified_diffs: bool,
+    /// Number of context lines in unified diffs.
+    pub context_size: i32,
+    /// Whitespace and EOL handling for unified diffs.
+    pub diff_options: crate::diff::FileOptions,
+}
+
+impl Default for TreeDiffOptions {
+    fn default() -> Self {
+        Self {
+            ignore_ancestry: false,
+            #[cfg(feature = "delta")]
+            text_deltas: false,
+            unified_diffs: false,
+            context_size: 3,
+            diff_options: crate::diff::FileOptions::default(),
+        }
+    }
+}
+
+impl TreeDiffOptions {
+    /// Creates new tree diff options with default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether to ignore ancestry when matching up nodes.
+    pub fn with_ignore_ancestry(mut self, ignore: bool) -> Self {
+        self.ignore_ancestry = ignore;
+        self
+    }
+
+    /// Sets whether to collect delta windows for changed files.
+    #[cfg(feature = "delta")]
+    pub fn with_text_deltas(mut self, text_deltas: bool) -> Self {
+        self.text_deltas = text_deltas;
+        self
+    }
+
+    /// Sets whether to generate unified diffs for changed text files.
+    pub fn with_unified_diffs(mut self, unified_diffs: bool) -> Self {
+        self.unified_diffs = unified_diffs;
+        self
+    }
+
+    /// Sets the number of context lines in unified diffs.
+    pub fn with_context_size(mut self, context_size: i32) -> Self {
+        self.context_size = context_size;
+        self
+    }
+
+    /// Sets the whitespace and EOL handling for unified diffs.
+    pub fn with_diff_options(mut self, diff_options: crate::diff::FileOptions) -> Self {
+        self.diff_options = diff_options;
+        self
+    }
+}
+
+/// Walks two trees in parallel on behalf of [`Root::diff_tree`].
+struct TreeDiffer<'a> {
+    source: &'a Root<'a>,
+    target: &'a Root<'a>,
+    options: &'a TreeDiffOptions,
+    changes: Vec<TreeChange>,
+}
+
+impl TreeDiffer<'_> {
+    fn diff_node(&mut self, spath: &str, tpath: &str, relpath: &str) -> Result<(), Error<'static>> {
+        let skind = self.source.check_path(spath)?;
+        let tkind = self.target.check_path(tpath)?;
+        match (skind, tkind) {
+            (crate::NodeKind::None, crate::NodeKind::None) => Ok(()),
+            (crate::NodeKind::None, _) => {
+                self.add_node(tpath, relpath, tkind, TreeChangeKind::Added)
+            }
+            (_, crate::NodeKind::None) => {
+                self.changes
+                    .push(TreeChange::new(relpath, TreeChangeKind::Deleted, skind));
+                Ok(())
+            }
+            _ if skind != tkind => self.add_node(tpath, relpath, tkind, TreeChangeKind::Replaced),
+            _ => match self.source.node_relation(spath, self.target, tpath)? {
+                crate::NodeRelation::Unchanged => Ok(()),
+                crate::NodeRelation::Unrelated if !self.options.ignore_ancestry => {
+                    self.add_node(tpath, relpath, tkind, TreeChangeKind::Replaced)
+                }
+                _ => self.modify_node(spath, tpath, relpath, tkind),
+            },
+        }
+    }
+
+    fn add_node(
+        &mut self,
+        tpath: &str,
+        relpath: &str,
+        node_kind: crate::NodeKind,
+        kind: TreeChangeKind,
+    ) -> Result<(), Error<'static>> {
+        let mut change = TreeChange::new(relpath, kind, node_kind);
+        change.copyfrom = self.target.copied_from(tpath)?;
+        let new_props = self.target.proplist(tpath).map_err(|e| e.into_static())?;
+        change.prop_changes = prop_changes(&std::collections::HashMap::new(), &new_props);
+        if node_kind == crate::NodeKind::File {
+            change.text_changed = true;
+            self.add_text(&mut change, None, tpath)?;
+        }
+        self.changes.push(change);
+
+        if node_kind == crate::NodeKind::Dir {
+            let entries = self
+                .target
+                .dir_entries(tpath)
+                .map_err(|e| e.into_static())?;
+            let mut names: Vec<_> = entries.iter().map(|(n, e)| (n.clone(), e.kind())).collect();
+            names.sort_by(|a, b| a.0.cmp(&b.0));
+            for (name, entry_kind) in names {
+                self.add_node(
+                    &join_fs_path(tpath, &name),
+                    &join_relpath(relpath, &name),
+                    entry_kind,
+                    TreeChangeKind::Added,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn modify_node(
+        &mut self,
+        spath: &str,
+        tpath: &str,
+        relpath: &str,
+        node_kind: crate::NodeKind,
+    ) -> Result<(), Error<'static>> {
+        let mut change = TreeChange::new(relpath, TreeChangeKind::Modified, node_kind);
+        if node_kind == crate::NodeKind::File {
+            change.text_changed = self.source.contents_different(spath, self.target, tpath)?;
+        }
+        if self.source.props_different(spath, self.target, tpath)? {
+            let old_props = self.source.proplist(spath).map_err(|e| e.into_static())?;
+            let new_props = self.target.proplist(tpath).map_err(|e| e.into_static())?;
+            change.prop_changes = prop_changes(&old_props, &new_props);
+        }
+        if change.text_changed {
+            self.add_text(&mut change, Some(spath), tpath)?;
+        }
+        if change.text_changed || change.props_changed() {
+            self.changes.push(change);
+        }
+
+        if node_kind == crate::NodeKind::Dir {
+            let mut names = std::collections::BTreeSet::new();
+            names.extend(
+                self.source
+                    .dir_entries(spath)
+                    .map_err(|e| e.into_static())?
+                    .into_keys(),
+            );
+            names.extend(
+                self.target
+                    .dir_entries(tpath)
+                    .map_err(|e| e.into_static())?
+                    .into_keys(),
+            );
+            for name in names {
+                self.diff_node(
+                    &join_fs_path(spath, &name),
+                    &join_fs_path(tpath, &name),
+                    &join_relpath(relpath, &name),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Fill in the text delta and unified diff of a changed file, if requested.
+    fn add_text(
+        &self,
+        change: &mut TreeChange,
+        spath: Option<&str>,
+        tpath: &str,
+    ) -> Result<(), Error<'static>> {
+        #[cfg(feature = "delta")]
+        if self.options.text_deltas {
+            change.text_delta = Some(self.text_delta(spath, tpath)?);
+        }
+        if self.options.unified_diffs {
+            change.unified_diff = self.unified_diff(spath, tpath)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "delta")]
+    fn text_delta(
+        &self,
+        spath: Option<&str>,
+        tpath: &str,
+    ) -> Result<Vec<crate::delta::TxDeltaWindow>, Error<'static>> {
+        let mut windows = Vec::new();
+        match spath {
+            Some(spath) => {
+                let mut stream = self
+                    .source
+                    .get_file_delta_stream(spath, self.target, tpath)?;
+                while let Some(window) = stream.next_window().map_err(|e| e.into_static())? {
+                    windows.push(window.dup());
+                }
+            }
+            None => {
+                let mut source = crate::io::Stream::empty();
+                let mut target = self.target.file_contents(tpath)?;
+                let mut stream = crate::delta::txdelta(&mut source, &mut target);
+                while let Some(window) = stream.next_window().map_err(|e| e.into_static())? {
+                    windows.push(window.dup());
+                }
+            }
+        }
+        Ok(windows)
+    }
+
+    fn unified_diff(
+        &self,
+        spath: Option<&str>,
+        tpath: &str,
+    ) -> Result<Option<String>, Error<'static>> {
+        let old = match spath {
+            Some(spath) => read_file_contents(self.source, spath)?,
+            None => Vec::new(),
+        };
+        let new = read_file_contents(self.target, tpath)?;
+        let (old, new) = match (String::from_utf8(old), String::from_utf8(new)) {
+            (Ok(old), Ok(new)) => (old, new),
+            _ => return Ok(None),
+        };
+
+        let original_header = match spath {
+            Some(spath) => format!("{}\t{}", spath, root_label(self.source)),
+            None => format!("{}\t(nonexistent)", tpath),
+        };
+        let modified_header = format!("{}\t{}", tpath, root_label(self.target));
+
+        let diff = crate::diff::mem_string_diff(&old, &new, self.options.diff_options)?;
+        let mut stringbuf = crate::io::StringBuf::new();
+        let mut stream = crate::io::Stream::from_stringbuf(&mut stringbuf);
+        crate::diff::mem_string_output_unified(
+            &mut stream,
+            &diff,
+            true,
+            None,
+            &original_header,
+            &modified_header,
+            "UTF-8",
+            &old,
+            &new,
+            self.options.context_size,
+        )?;
+        Ok(Some(stringbuf.to_string()))
+    }
+}
+
+fn read_file_contents(root: &Root, path: &str) -> Result<Vec<u8>, Error<'static>> {
+    use std::io::Read;
+    let mut contents = Vec::new();
+    root.file_contents(path)?.read_to_end(&mut contents)?;
+    Ok(contents)
+}
+
+fn root_label(root: &Root) -> String {
+    if root.is_txn_root() {
+        "(transaction)".to_string()
+    } else {
+        format!("(revision {})", root.revision().0)
+    }
+}
+
+fn join_fs_path(parent: &str, name: &str) -> String {
+    if parent.ends_with('/') {
+        format!("{}{}", parent, name)
+    } else {
+        format!("{}/{}", parent, name)
+    }
+}
+
+fn join_relpath(parent: &str, name: &str) -> String {
+    if parent.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", parent, name)
+    }
+}
+
+/// Compute the property differences between two property lists, sorted by name.
+fn prop_changes(
+    old: &std::collections::HashMap<String, Vec<u8>>,
+    new: &std::collections::HashMap<String, Vec<u8>>,
+) -> Vec<PropChange> {
+    let names: std::collections::BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let old_value = old.get(name);
+            let new_value = new.get(name);
+            if old_value == new_value {
+                return None;
+            }
+            Some(PropChange {
+                name: name.clone(),
+                old_value: old_value.cloned(),
+                new_value: new_value.cloned(),
+            })
+        })
+        .collect()
 }
 
 /// Represents the history of a node in the filesystem
@@ -2964,6 +4084,147 @@ impl Drop for NodeHistory {
     }
 }
 
+/// Options for [`Root::follow_history`].
+#[derive(Debug, Clone, Copy)]
+pub struct HistoryOptions {
+    /// Continue into the copy source when the node was created by a copy.
+    pub cross_copies: bool,
+    /// Stop before the first location older than this revision.
+    pub stop_at_revision: Option<Revnum>,
+    /// Stop after the location at which the node was created by a copy,
+    /// i.e. at branch creation.
+    pub stop_on_copy: bool,
+}
+
+impl Default for HistoryOptions {
+    fn default() -> Self {
+        Self {
+            cross_copies: true,
+            stop_at_revision: None,
+            stop_on_copy: false,
+        }
+    }
+}
+
+impl HistoryOptions {
+    /// Creates new history options with default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether to follow the node across copies.
+    pub fn with_cross_copies(mut self, cross_copies: bool) -> Self {
+        self.cross_copies = cross_copies;
+        self
+    }
+
+    /// Sets the oldest revision to report.
+    pub fn with_stop_at_revision(mut self, rev: Revnum) -> Self {
+        self.stop_at_revision = Some(rev);
+        self
+    }
+
+    /// Sets whether to stop at the copy that created the node.
+    pub fn with_stop_on_copy(mut self, stop_on_copy: bool) -> Self {
+        self.stop_on_copy = stop_on_copy;
+        self
+    }
+}
+
+/// A location in the history of a node, as yielded by [`HistoryIter`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    /// Path of the node at this location.
+    pub path: String,
+    /// Revision of this location.
+    pub revision: Revnum,
+    /// Revision and path of the closest copy at or above `path`, as returned
+    /// by [`Root::closest_copy`].
+    pub closest_copy: Option<(Revnum, String)>,
+    /// If the node was created in `revision` by a copy, either of itself or
+    /// of a parent directory, the revision and path it was copied from.
+    pub copied_from: Option<(Revnum, String)>,
+    /// Whether this location was reached by crossing a copy from the
+    /// previous (younger) location.
+    pub crossed_copy: bool,
+}
+
+/// Iterator over the history of a node, returned by [`Root::follow_history`].
+///
+/// Locations are yielded youngest first.
+pub struct HistoryIter<'a> {
+    root: &'a Root<'a>,
+    history: NodeHistory,
+    options: HistoryOptions,
+    crossed_copy: bool,
+    done: bool,
+}
+
+impl HistoryIter<'_> {
+    fn step(&mut self) -> Result<Option<HistoryEntry>, Error<'static>> {
+        let (path, revision) = match self
+            .history
+            .prev(self.options.cross_copies)
+            .map_err(|e| e.into_static())?
+        {
+            Some(location) => location,
+            None => return Ok(None),
+        };
+        if let Some(stop) = self.options.stop_at_revision {
+            if revision < stop {
+                return Ok(None);
+            }
+        }
+
+        let rev_root = self.root.sibling_revision_root(revision)?;
+        let closest_copy = rev_root
+            .closest_copy(path.as_str())
+            .map_err(|e| e.into_static())?
+            .map(|(copy_root, copy_path)| (copy_root.revision(), copy_path));
+        let copied_from = match &closest_copy {
+            Some((copy_rev, copy_path)) if *copy_rev == revision => rev_root
+                .copied_from(copy_path.as_str())?
+                .map(|(src_rev, src_path)| {
+                    (src_rev, format!("{}{}", src_path, &path[copy_path.len()..]))
+                }),
+            _ => None,
+        };
+
+        let crossed_copy = std::mem::replace(&mut self.crossed_copy, copied_from.is_some());
+        if copied_from.is_some() && self.options.stop_on_copy {
+            self.done = true;
+        }
+        Ok(Some(HistoryEntry {
+            path,
+            revision,
+            closest_copy,
+            copied_from,
+            crossed_copy,
+        }))
+    }
+}
+
+impl Iterator for HistoryIter<'_> {
+    type Item = Result<HistoryEntry, Error<'static>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.step() {
+            Ok(Some(entry)) => Some(Ok(entry)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
 /// Represents a node ID in the filesystem
 pub struct NodeId {
     ptr: *const subversion_sys::svn_fs_id_t,
@@ -3476,6 +4737,33 @@ impl<'txn> TxnRoot<'txn> {
         }
     }
 
+    /// Set a property on a node to `value`, even if it is empty, unlike
+    /// [`Self::change_node_prop`] which deletes the property then.
+    fn set_node_prop(
+        &mut self,
+        path: &str,
+        name: &str,
+        value: &[u8],
+    ) -> Result<(), Error<'static>> {
+        let fs_path = FsPath::try_from(path)?;
+        let name_cstr = std::ffi::CString::new(name)?;
+        let value_str = subversion_sys::svn_string_t {
+            data: value.as_ptr() as *mut _,
+            len: value.len(),
+        };
+        let pool = apr::Pool::new();
+        let err = unsafe {
+            subversion_sys::svn_fs_change_node_prop(
+                self.ptr,
+                fs_path.as_ptr(),
+                name_cstr.as_ptr(),
+                &value_str,
+                pool.as_mut_ptr(),
+            )
+        };
+        svn_result(err)
+    }
+
     /// Check if a path exists and what kind of node it is
     pub fn check_path(
         &self,
@@ -3588,6 +4876,14 @@ impl<'txn> TxnRoot<'txn> {
         })
     }
 
+    /// Borrow this transaction root as a read-only [`Root`].
+    ///
+    /// This gives access to the query methods of [`Root`], such as
+    /// [`Root::diff_tree`], for the transaction's tree.
+    pub fn as_root(&self) -> Root<'_> {
+        unsafe { Root::from_raw(self.ptr, self._pool.as_mut_ptr()) }
+    }
+
     /// Return the base revision on which this transaction root is based.
     ///
     /// Wraps `svn_fs_txn_root_base_revision`.
@@ -3693,7 +4989,317 @@ impl<'txn> TxnRoot<'txn> {
         if inherited_props.is_null() {
             return Ok(Vec::new());
         }
-        parse_inherited_props_array(inherited_props)
+        parse_inherited_props_array(inherited_props)
+    }
+}
+
+/// Options for [`TxnRoot::import_tree`].
+#[derive(Default)]
+pub struct ImportOptions<'a> {
+    /// Glob patterns matched against entry names; matching entries are skipped.
+    pub ignore_patterns: Vec<String>,
+    /// Automatic properties: a glob pattern matched against file names, and
+    /// the properties to set on matching files.
+    pub auto_props: Vec<(String, Vec<(String, String)>)>,
+    /// Optional callback invoked after each node is imported.
+    pub progress_func: Option<&'a dyn Fn(&ImportProgress)>,
+    /// Optional cancellation callback.
+    pub cancel_func: Option<&'a dyn Fn() -> Result<(), Error<'static>>>,
+}
+
+impl<'a> ImportOptions<'a> {
+    /// Creates new ImportOptions with default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds glob patterns for entries to skip.
+    pub fn with_ignore_patterns(mut self, patterns: &[&str]) -> Self {
+        self.ignore_patterns
+            .extend(patterns.iter().map(|p| p.to_string()));
+        self
+    }
+
+    /// Adds an automatic property rule for files whose name matches `pattern`.
+    pub fn with_auto_props(mut self, pattern: &str, props: &[(&str, &str)]) -> Self {
+        self.auto_props.push((
+            pattern.to_string(),
+            props
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        ));
+        self
+    }
+
+    /// Adds the `global-ignores` patterns and, if `enable-auto-props` is set,
+    /// the `[auto-props]` rules from a client configuration.
+    pub fn with_config(mut self, config: &crate::config::Config) -> Result<Self, Error<'static>> {
+        if let crate::config::ConfigValue::String(ignores) =
+            config.get(crate::config::ConfigOption::GlobalIgnores(""))?
+        {
+            self.ignore_patterns
+                .extend(ignores.split_whitespace().map(|p| p.to_string()));
+        }
+        if config.get(crate::config::ConfigOption::EnableAutoProps(false))?
+            == crate::config::ConfigValue::Bool(true)
+        {
+            config.enumerate("auto-props", |pattern, value| {
+                self.auto_props
+                    .push((pattern.to_string(), parse_auto_props(value)));
+                true
+            })?;
+        }
+        Ok(self)
+    }
+
+    /// Sets the progress callback.
+    pub fn with_progress_func(mut self, progress_func: &'a dyn Fn(&ImportProgress)) -> Self {
+        self.progress_func = Some(progress_func);
+        self
+    }
+
+    /// Sets the cancellation callback.
+    pub fn with_cancel_func(
+        mut self,
+        cancel_func: &'a dyn Fn() -> Result<(), Error<'static>>,
+    ) -> Self {
+        self.cancel_func = Some(cancel_func);
+        self
+    }
+}
+
+/// Parse an `[auto-props]` value such as `svn:eol-style=native;svn:keywords=Id`.
+///
+/// A doubled `;;` stands for a literal semicolon.  Properties without a
+/// value get `*` if they are boolean properties.
+fn parse_auto_props(value: &str) -> Vec<(String, String)> {
+    let mut props = Vec::new();
+    let mut current = String::new();
+    let mut chars = value.chars().peekable();
+    loop {
+        match chars.next() {
+            Some(';') if chars.peek() == Some(&';') => {
+                chars.next();
+                current.push(';');
+            }
+            Some(c) if c != ';' => current.push(c),
+            end => {
+                let (name, value) = match current.split_once('=') {
+                    Some((name, value)) => (name.trim(), value.trim()),
+                    None => (current.trim(), ""),
+                };
+                if !name.is_empty() {
+                    let value =
+                        if value.is_empty() && crate::props::is_boolean(name).unwrap_or(false) {
+                            "*"
+                        } else {
+                            value
+                        };
+                    props.push((name.to_string(), value.to_string()));
+                }
+                current.clear();
+                if end.is_none() {
+                    break;
+                }
+            }
+        }
+    }
+    props
+}
+
+/// Progress report for a node imported by [`TxnRoot::import_tree`].
+#[derive(Debug)]
+pub struct ImportProgress<'a> {
+    /// Local path the node was imported from.
+    pub local_path: &'a std::path::Path,
+    /// Filesystem path the node was imported to.
+    pub path: &'a str,
+    /// Kind of the imported node; symlinks are reported as
+    /// [`crate::NodeKind::Symlink`].
+    pub kind: crate::NodeKind,
+    /// Number of content bytes written for the node.
+    pub bytes: u64,
+}
+
+/// Summary of a [`TxnRoot::import_tree`] run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImportStats {
+    /// Number of directories created, including the target itself if it
+    /// did not exist yet.
+    pub directories: usize,
+    /// Number of regular files imported.
+    pub files: usize,
+    /// Number of symbolic links imported.
+    pub symlinks: usize,
+    /// Total number of content bytes written.
+    pub bytes: u64,
+    /// Number of entries skipped because they were ignored or are neither
+    /// files, directories nor symbolic links.
+    pub skipped: usize,
+}
+
+impl TxnRoot<'_> {
+    /// Import the local directory `local_dir` into this transaction at
+    /// `target_path`, which is created if it does not exist yet.
+    ///
+    /// Symbolic links are stored as `svn:special` files and files with an
+    /// executable bit get `svn:executable`.  File contents are streamed
+    /// into the filesystem rather than read into memory.
+    pub fn import_tree(
+        &mut self,
+        local_dir: &std::path::Path,
+        target_path: impl TryInto<FsPath, Error = Error<'static>>,
+        options: &ImportOptions,
+    ) -> Result<ImportStats, Error<'static>> {
+        let target_path = target_path.try_into()?;
+        if !std::fs::metadata(local_dir)?.is_dir() {
+            return Err(Error::from_message(&format!(
+                "'{}' is not a directory",
+                local_dir.display()
+            )));
+        }
+
+        let mut stats = ImportStats::default();
+        match self.check_path(target_path.as_str())? {
+            crate::NodeKind::None => {
+                self.make_dir(target_path.as_str())?;
+                stats.directories += 1;
+                report_import_progress(
+                    options,
+                    local_dir,
+                    target_path.as_str(),
+                    crate::NodeKind::Dir,
+                    0,
+                );
+            }
+            crate::NodeKind::Dir => {}
+            _ => {
+                return Err(Error::from_message(&format!(
+                    "'{}' already exists and is not a directory",
+                    target_path
+                )));
+            }
+        }
+
+        let ignore_patterns: Vec<&str> =
+            options.ignore_patterns.iter().map(|p| p.as_str()).collect();
+        self.import_dir(
+            local_dir,
+            target_path.as_str(),
+            options,
+            &ignore_patterns,
+            &mut stats,
+        )?;
+        Ok(stats)
+    }
+
+    fn import_dir(
+        &mut self,
+        local_dir: &std::path::Path,
+        path: &str,
+        options: &ImportOptions,
+        ignore_patterns: &[&str],
+        stats: &mut ImportStats,
+    ) -> Result<(), Error<'static>> {
+        let mut entries = std::fs::read_dir(local_dir)?.collect::<Result<Vec<_>, _>>()?;
+        entries.sort_by_key(|e| e.file_name());
+
+        for entry in entries {
+            if let Some(cancel_func) = options.cancel_func {
+                cancel_func()?;
+            }
+
+            let file_name = entry.file_name();
+            let name = file_name.to_str().ok_or_else(|| {
+                Error::from_message(&format!("'{}' is not valid UTF-8", entry.path().display()))
+            })?;
+            if crate::string::match_glob_list(name, ignore_patterns)? {
+                stats.skipped += 1;
+                continue;
+            }
+
+            let local_path = entry.path();
+            let child_path = join_fs_path(path, name);
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                self.make_dir(child_path.as_str())?;
+                stats.directories += 1;
+                report_import_progress(options, &local_path, &child_path, crate::NodeKind::Dir, 0);
+                self.import_dir(&local_path, &child_path, options, ignore_patterns, stats)?;
+            } else if file_type.is_symlink() {
+                let target = std::fs::read_link(&local_path)?;
+                let contents = format!("link {}", target.to_string_lossy());
+                self.make_file(child_path.as_str())?;
+                self.set_file_contents(child_path.as_str(), contents.as_bytes())?;
+                self.change_node_prop(child_path.as_str(), "svn:special", b"*")?;
+                stats.symlinks += 1;
+                stats.bytes += contents.len() as u64;
+                report_import_progress(
+                    options,
+                    &local_path,
+                    &child_path,
+                    crate::NodeKind::Symlink,
+                    contents.len() as u64,
+                );
+            } else if file_type.is_file() {
+                self.make_file(child_path.as_str())?;
+                let mut file = std::fs::File::open(&local_path)?;
+                let mut stream = self.apply_text(child_path.as_str(), None)?;
+                let bytes = std::io::copy(&mut file, &mut stream)?;
+                stream.close()?;
+
+                for (pattern, props) in &options.auto_props {
+                    if crate::string::match_glob_list(name, &[pattern.as_str()])? {
+                        for (prop_name, prop_value) in props {
+                            // An auto-prop without a value sets an empty one.
+                            self.set_node_prop(
+                                child_path.as_str(),
+                                prop_name,
+                                prop_value.as_bytes(),
+                            )?;
+                        }
+                    }
+                }
+                #[cfg(unix)]
+                {
+                    use std::os::unix::fs::PermissionsExt;
+                    if entry.metadata()?.permissions().mode() & 0o111 != 0 {
+                        self.change_node_prop(child_path.as_str(), "svn:executable", b"*")?;
+                    }
+                }
+
+                stats.files += 1;
+                stats.bytes += bytes;
+                report_import_progress(
+                    options,
+                    &local_path,
+                    &child_path,
+                    crate::NodeKind::File,
+                    bytes,
+                );
+            } else {
+                stats.skipped += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn report_import_progress(
+    options: &ImportOptions,
+    local_path: &std::path::Path,
+    path: &str,
+    kind: crate::NodeKind,
+    bytes: u64,
+) {
+    if let Some(progress_func) = options.progress_func {
+        progress_func(&ImportProgress {
+            local_path,
+            path,
+            kind,
+            bytes,
+        });
     }
 }
 
@@ -3901,6 +5507,199 @@ impl<'pool> Fs<'pool> {
     }
 }
 
+/// Summary of an uncommitted transaction, as returned by
+/// [`Fs::list_transactions_info`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxnInfo {
+    /// Name of the transaction.
+    pub name: String,
+    /// Revision the transaction is based on.
+    pub base_revision: Revnum,
+    /// Creation time, taken from the transaction's `svn:date` property.
+    pub created: Option<apr::time::Time>,
+    /// Value of the `svn:author` transaction property.
+    pub author: Option<String>,
+    /// Value of the `svn:log` transaction property.
+    pub log: Option<String>,
+    /// Number of bytes the transaction occupies on disk.  Only the FSFS
+    /// layout is measured; other backends, including FSX, report 0.
+    pub size: u64,
+}
+
+impl TxnInfo {
+    /// Age of the transaction relative to `now`, if its creation time is
+    /// known.  Transactions dated in the future have an age of zero.
+    pub fn age(&self, now: apr::time::Time) -> Option<std::time::Duration> {
+        let created = self.created?;
+        let micros = (now.as_micros() - created.as_micros()).max(0);
+        Some(std::time::Duration::from_micros(micros as u64))
+    }
+}
+
+/// Options for [`Fs::reap_transactions`].
+///
+/// A transaction is selected when it matches every criterion that is set;
+/// with no criteria set, every transaction is selected.
+#[derive(Default)]
+pub struct ReapOptions<'a> {
+    /// Only select transactions created longer ago than this.  Transactions
+    /// without a usable `svn:date` are never selected by age.
+    pub older_than: Option<std::time::Duration>,
+    /// Only select transactions for which this returns true.
+    pub filter: Option<&'a dyn Fn(&TxnInfo) -> bool>,
+    /// Report which transactions would be purged without removing them.
+    pub dry_run: bool,
+}
+
+impl<'a> ReapOptions<'a> {
+    /// Create new reap options that select every transaction.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only select transactions older than `age`.
+    pub fn with_older_than(mut self, age: std::time::Duration) -> Self {
+        self.older_than = Some(age);
+        self
+    }
+
+    /// Only select transactions matching `filter`.
+    pub fn with_filter(mut self, filter: &'a dyn Fn(&TxnInfo) -> bool) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Set whether to only report what would be purged.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    fn selects(&self, info: &TxnInfo, now: apr::time::Time) -> bool {
+        if let Some(older_than) = self.older_than {
+            match info.age(now) {
+                Some(age) if age > older_than => {}
+                _ => return false,
+            }
+        }
+        match self.filter {
+            Some(filter) => filter(info),
+            None => true,
+        }
+    }
+}
+
+/// Whether `err` reports a transaction that does not exist.
+fn is_no_such_transaction(err: &Error<'_>) -> bool {
+    err.raw_apr_err() == subversion_sys::svn_errno_t_SVN_ERR_FS_NO_SUCH_TRANSACTION as i32
+}
+
+/// Total size in bytes of the file or directory tree at `path`, or 0 if it
+/// does not exist.
+fn disk_usage(path: &std::path::Path) -> Result<u64, Error<'static>> {
+    let metadata = match std::fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e.into()),
+    };
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+    let mut total = 0;
+    for entry in std::fs::read_dir(path)? {
+        total += disk_usage(&entry?.path())?;
+    }
+    Ok(total)
+}
+
+impl Fs<'_> {
+    /// Describe the uncommitted transaction `name`.
+    pub fn transaction_info(&self, name: &str) -> Result<TxnInfo, Error<'static>> {
+        let txn = self.open_txn(name)?;
+        let text_prop = |prop: &str| -> Result<Option<String>, Error<'static>> {
+            Ok(txn
+                .prop(prop)
+                .map_err(|e| e.into_static())?
+                .map(|v| String::from_utf8_lossy(&v).into_owned()))
+        };
+        // A malformed date only means the age is unknown.
+        let created = text_prop("svn:date")?.and_then(|date| crate::time::from_cstring(&date).ok());
+
+        let fs_path = self.path();
+        let size = disk_usage(&fs_path.join("transactions").join(format!("{}.txn", name)))?
+            + disk_usage(&fs_path.join("txn-protorevs").join(format!("{}.rev", name)))?
+            + disk_usage(
+                &fs_path
+                    .join("txn-protorevs")
+                    .join(format!("{}.rev-lock", name)),
+            )?;
+
+        Ok(TxnInfo {
+            name: name.to_string(),
+            base_revision: txn.base_revision()?,
+            created,
+            author: text_prop("svn:author")?,
+            log: text_prop("svn:log")?,
+            size,
+        })
+    }
+
+    /// Describe all uncommitted transactions, sorted by name.
+    ///
+    /// This is the equivalent of `svnadmin lstxns` with extra detail.
+    pub fn list_transactions_info(&self) -> Result<Vec<TxnInfo>, Error<'static>> {
+        let mut names = self.list_transactions().map_err(|e| e.into_static())?;
+        names.sort();
+        let mut infos = Vec::with_capacity(names.len());
+        for name in &names {
+            // The transaction may have been committed or purged since it
+            // was listed.
+            match self.transaction_info(name) {
+                Ok(info) => infos.push(info),
+                Err(e) if is_no_such_transaction(&e) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(infos)
+    }
+
+    /// Purge the uncommitted transactions selected by `options`, like
+    /// `svnadmin rmtxns` with filtering.
+    ///
+    /// Returns the transactions that were purged, or that would have been
+    /// purged when [`ReapOptions::dry_run`] is set.
+    pub fn reap_transactions(
+        &self,
+        options: &ReapOptions<'_>,
+    ) -> Result<Vec<TxnInfo>, Error<'static>> {
+        let now = apr::time::Time::now();
+        let mut reaped = Vec::new();
+        for info in self.list_transactions_info()? {
+            if !options.selects(&info, now) {
+                continue;
+            }
+            if !options.dry_run {
+                if let Err(e) = self.purge_txn(&info.name) {
+                    // Depending on the backend, purging a transaction that
+                    // was removed concurrently fails with
+                    // SVN_ERR_FS_NO_SUCH_TRANSACTION or a plain I/O error.
+                    let vanished = is_no_such_transaction(&e)
+                        || !self
+                            .list_transactions()
+                            .map_err(|e| e.into_static())?
+                            .contains(&info.name);
+                    if vanished {
+                        continue;
+                    }
+                    return Err(e);
+                }
+            }
+            reaped.push(info);
+        }
+        Ok(reaped)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -6529,4 +8328,510 @@ mod tests {
             .unwrap();
         assert_eq!(contents, b"updated");
     }
+
+    #[test]
+    fn test_diff_tree_between_revisions() {
+        let dir = tempdir().unwrap();
+        let fs = Fs::create(&dir.path().join("fs")).unwrap();
+
+        let mut txn = fs.begin_txn(Revnum(0), 0).unwrap();
+        let mut root = txn.root().unwrap();
+        root.make_dir("/trunk").unwrap();
+        root.make_file("/trunk/a.txt").unwrap();
+        root.set_file_contents("/trunk/a.txt", b"one\n").unwrap();
+        root.make_file("/trunk/b.txt").unwrap();
+        root.set_file_contents("/trunk/b.txt", b"bee\n").unwrap();
+        txn.commit().unwrap();
+
+        let mut txn = fs.begin_txn(Revnum(1), 0).unwrap();
+        let mut root = txn.root().unwrap();
+        let rev1_root = fs.revision_root(Revnum(1)).unwrap();
+        root.copy(&rev1_root, "/trunk", "/branch").unwrap();
+        root.set_file_contents("/trunk/a.txt", b"two\n").unwrap();
+        root.delete("/trunk/b.txt").unwrap();
+        root.make_file("/trunk/c.txt").unwrap();
+        root.change_node_prop("/trunk", "custom:prop", b"value")
+            .unwrap();
+        txn.commit().unwrap();
+
+        let rev2_root = fs.revision_root(Revnum(2)).unwrap();
+        let changes = rev1_root
+            .diff_tree("/", &rev2_root, "/", &TreeDiffOptions::new())
+            .unwrap();
+        let summary: Vec<_> = changes
+            .iter()
+            .map(|c| (c.path.as_str(), c.kind, c.node_kind))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("branch", TreeChangeKind::Added, crate::NodeKind::Dir),
+                ("branch/a.txt", TreeChangeKind::Added, crate::NodeKind::File),
+                ("branch/b.txt", TreeChangeKind::Added, crate::NodeKind::File),
+                ("trunk", TreeChangeKind::Modified, crate::NodeKind::Dir),
+                (
+                    "trunk/a.txt",
+                    TreeChangeKind::Modified,
+                    crate::NodeKind::File
+                ),
+                (
+                    "trunk/b.txt",
+                    TreeChangeKind::Deleted,
+                    crate::NodeKind::File
+                ),
+                ("trunk/c.txt", TreeChangeKind::Added, crate::NodeKind::File),
+            ]
+        );
+
+        // Only the copy root carries copyfrom information.
+        assert_eq!(changes[0].copyfrom, Some((Revnum(1), "/trunk".to_string())));
+        assert_eq!(changes[1].copyfrom, None);
+
+        let trunk = &changes[3];
+        assert!(!trunk.text_changed);
+        assert_eq!(
+            trunk.prop_changes,
+            vec![PropChange {
+                name: "custom:prop".to_string(),
+                old_value: None,
+                new_value: Some(b"value".to_vec()),
+            }]
+        );
+        assert!(changes[4].text_changed);
+        assert!(!changes[4].props_changed());
+
+        // Comparing the trunk subtrees yields paths relative to the anchors.
+        let changes = rev1_root
+            .diff_tree("/trunk", &rev2_root, "/branch", &TreeDiffOptions::new())
+            .unwrap();
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn test_diff_tree_txn_root_with_text() {
+        let dir = tempdir().unwrap();
+        let fs = Fs::create(&dir.path().join("fs")).unwrap();
+
+        let mut txn = fs.begin_txn(Revnum(0), 0).unwrap();
+        let mut root = txn.root().unwrap();
+        root.make_file("/file.txt").unwrap();
+        root.set_file_contents("/file.txt", b"one\nsame\n").unwrap();
+        txn.commit().unwrap();
+
+        let rev1_root = fs.revision_root(Revnum(1)).unwrap();
+        let mut txn = fs.begin_txn(Revnum(1), 0).unwrap();
+        let mut root = txn.root().unwrap();
+        root.set_file_contents("/file.txt", b"two\nsame\n").unwrap();
+        root.make_file("/binary.bin").unwrap();
+        root.set_file_contents("/binary.bin", b"\xff\xfe\x00")
+            .unwrap();
+
+        let options = TreeDiffOptions::new().with_unified_diffs(true);
+        #[cfg(feature = "delta")]
+        let options = options.with_text_deltas(true);
+        let changes = rev1_root
+            .diff_tree("/", &root.as_root(), "/", &options)
+            .unwrap();
+        assert_eq!(changes.len(), 2);
+
+        let binary = &changes[0];
+        assert_eq!(binary.path, "binary.bin");
+        assert_eq!(binary.kind, TreeChangeKind::Added);
+        assert!(binary.unified_diff.is_none());
+
+        let file = &changes[1];
+        assert_eq!(file.path, "file.txt");
+        assert_eq!(file.kind, TreeChangeKind::Modified);
+        let unified = file.unified_diff.as_deref().unwrap();
+        assert!(unified.contains("--- /file.txt\t(revision 1)"));
+        assert!(unified.contains("+++ /file.txt\t(transaction)"));
+        assert!(unified.contains("-one\n"));
+        assert!(unified.contains("+two\n"));
+
+        #[cfg(feature = "delta")]
+        {
+            let windows = file.text_delta.as_ref().unwrap();
+            assert!(!windows.is_empty());
+            let mut source = b"one\nsame\n".to_vec();
+            let mut target = Vec::new();
+            windows[0]
+                .dup()
+                .apply_instructions(&mut source, &mut target)
+                .unwrap();
+            assert_eq!(target, b"two\nsame\n");
+        }
+    }
+
+    #[test]
+    fn test_follow_history_across_copies() {
+        let dir = tempdir().unwrap();
+        let fs = Fs::create(&dir.path().join("fs")).unwrap();
+
+        // r1: add /trunk/file.txt; r2: modify it.
+        let mut txn = fs.begin_txn(Revnum(0), 0).unwrap();
+        let mut root = txn.root().unwrap();
+        root.make_dir("/trunk").unwrap();
+        root.make_file("/trunk/file.txt").unwrap();
+        root.set_file_contents("/trunk/file.txt", b"v1").unwrap();
+        txn.commit().unwrap();
+        let mut txn = fs.begin_txn(Revnum(1), 0).unwrap();
+        let mut root = txn.root().unwrap();
+        root.set_file_contents("/trunk/file.txt", b"v2").unwrap();
+        txn.commit().unwrap();
+
+        // r3: branch /trunk to /branch; r4: modify the branched file.
+        let mut txn = fs.begin_txn(Revnum(2), 0).unwrap();
+        let mut root = txn.root().unwrap();
+        let rev2_root = fs.revision_root(Revnum(2)).unwrap();
+        root.copy(&rev2_root, "/trunk", "/branch").unwrap();
+        txn.commit().unwrap();
+        let mut txn = fs.begin_txn(Revnum(3), 0).unwrap();
+        let mut root = txn.root().unwrap();
+        root.set_file_contents("/branch/file.txt", b"v3").unwrap();
+        txn.commit().unwrap();
+
+        // r5: rename /branch/file.txt to /branch/renamed.txt.
+        let mut txn = fs.begin_txn(Revnum(4), 0).unwrap();
+        let mut root = txn.root().unwrap();
+        let rev4_root = fs.revision_root(Revnum(4)).unwrap();
+        root.copy(&rev4_root, "/branch/file.txt", "/branch/renamed.txt")
+            .unwrap();
+        root.delete("/branch/file.txt").unwrap();
+        txn.commit().unwrap();
+
+        let rev5_root = fs.revision_root(Revnum(5)).unwrap();
+        let entries: Vec<HistoryEntry> = rev5_root
+            .follow_history("/branch/renamed.txt", &HistoryOptions::new())
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        let first = &entries[0];
+        assert_eq!(first.path, "/branch/renamed.txt");
+        assert_eq!(first.revision, Revnum(5));
+        assert_eq!(
+            first.closest_copy,
+            Some((Revnum(5), "/branch/renamed.txt".to_string()))
+        );
+        assert_eq!(
+            first.copied_from,
+            Some((Revnum(4), "/branch/file.txt".to_string()))
+        );
+        assert!(!first.crossed_copy);
+
+        let second = &entries[1];
+        assert_eq!(second.path, "/branch/file.txt");
+        assert!(second.crossed_copy);
+
+        // The branch creation is traced back to the file on trunk.
+        let branch_point = entries
+            .iter()
+            .find(|e| e.path == "/branch/file.txt" && e.revision == Revnum(3))
+            .unwrap();
+        assert_eq!(
+            branch_point.closest_copy,
+            Some((Revnum(3), "/branch".to_string()))
+        );
+        assert_eq!(
+            branch_point.copied_from,
+            Some((Revnum(2), "/trunk/file.txt".to_string()))
+        );
+        let last = entries.last().unwrap();
+        assert_eq!(last.path, "/trunk/file.txt");
+        assert_eq!(last.revision, Revnum(1));
+        assert!(entries
+            .iter()
+            .any(|e| e.crossed_copy && e.path == "/trunk/file.txt"));
+        assert!(entries.windows(2).all(|w| w[0].revision >= w[1].revision));
+
+        // Stopping on copy ends the walk at the rename.
+        let entries: Vec<HistoryEntry> = rev5_root
+            .follow_history(
+                "/branch/renamed.txt",
+                &HistoryOptions::new().with_stop_on_copy(true),
+            )
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].revision, Revnum(5));
+
+        // Stopping at a revision omits anything older.
+        let entries: Vec<HistoryEntry> = rev5_root
+            .follow_history(
+                "/branch/renamed.txt",
+                &HistoryOptions::new().with_stop_at_revision(Revnum(3)),
+            )
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert!(!entries.is_empty());
+        assert!(entries.iter().all(|e| e.revision >= Revnum(3)));
+        assert!(entries.iter().all(|e| e.path.starts_with("/branch/")));
+
+        // Without crossing copies only the renamed path is reported.
+        let entries: Vec<HistoryEntry> = rev5_root
+            .follow_history(
+                "/branch/renamed.txt",
+                &HistoryOptions::new().with_cross_copies(false),
+            )
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert!(entries.iter().all(|e| e.path == "/branch/renamed.txt"));
+    }
+
+    #[test]
+    fn test_parse_auto_props() {
+        assert_eq!(
+            parse_auto_props("svn:eol-style=native;svn:keywords=Id Rev"),
+            vec![
+                ("svn:eol-style".to_string(), "native".to_string()),
+                ("svn:keywords".to_string(), "Id Rev".to_string()),
+            ]
+        );
+        assert_eq!(
+            parse_auto_props("svn:executable;custom=a;;b"),
+            vec![
+                ("svn:executable".to_string(), "*".to_string()),
+                ("custom".to_string(), "a;b".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_import_tree() {
+        let dir = tempdir().unwrap();
+        let fs = Fs::create(&dir.path().join("fs")).unwrap();
+
+        let local = dir.path().join("local");
+        std::fs::create_dir_all(local.join("sub")).unwrap();
+        std::fs::write(local.join("a.txt"), b"alpha\n").unwrap();
+        std::fs::write(local.join("sub").join("b.c"), b"int b;\n").unwrap();
+        std::fs::write(local.join("junk.o"), b"\x7fELF").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::write(local.join("run.sh"), b"#!/bin/sh\n").unwrap();
+            std::fs::set_permissions(local.join("run.sh"), std::fs::Permissions::from_mode(0o755))
+                .unwrap();
+            std::os::unix::fs::symlink("a.txt", local.join("link")).unwrap();
+        }
+
+        let imported = std::cell::RefCell::new(Vec::new());
+        let progress = |p: &ImportProgress| imported.borrow_mut().push(p.path.to_string());
+        let options = ImportOptions::new()
+            .with_ignore_patterns(&["*.o"])
+            .with_auto_props("*.c", &[("svn:eol-style", "native"), ("test:empty", "")])
+            .with_progress_func(&progress);
+
+        let mut txn = fs.begin_txn(Revnum(0), 0).unwrap();
+        let mut root = txn.root().unwrap();
+        let stats = root.import_tree(&local, "/project", &options).unwrap();
+
+        assert_eq!(stats.directories, 2);
+        assert_eq!(stats.skipped, 1);
+        assert_eq!(root.file_contents("/project/a.txt").unwrap(), b"alpha\n");
+        assert_eq!(root.file_contents("/project/sub/b.c").unwrap(), b"int b;\n");
+        assert_eq!(
+            root.check_path("/project/junk.o").unwrap(),
+            crate::NodeKind::None
+        );
+
+        let view = root.as_root();
+        assert_eq!(
+            view.node_prop("/project/sub/b.c", "svn:eol-style").unwrap(),
+            Some(b"native".to_vec())
+        );
+        assert_eq!(
+            view.node_prop("/project/sub/b.c", "test:empty").unwrap(),
+            Some(Vec::new())
+        );
+        assert_eq!(
+            view.node_prop("/project/a.txt", "svn:executable").unwrap(),
+            None
+        );
+        #[cfg(unix)]
+        {
+            assert_eq!(stats.files, 3);
+            assert_eq!(stats.symlinks, 1);
+            assert_eq!(
+                view.node_prop("/project/run.sh", "svn:executable").unwrap(),
+                Some(b"*".to_vec())
+            );
+            assert_eq!(
+                view.node_prop("/project/link", "svn:special").unwrap(),
+                Some(b"*".to_vec())
+            );
+            assert_eq!(root.file_contents("/project/link").unwrap(), b"link a.txt");
+        }
+        drop(view);
+
+        let imported = imported.borrow();
+        assert_eq!(imported[0], "/project");
+        assert!(imported.contains(&"/project/sub/b.c".to_string()));
+        assert!(!imported.contains(&"/project/junk.o".to_string()));
+
+        // Importing over an existing file is refused.
+        assert!(root
+            .import_tree(&local, "/project/a.txt", &ImportOptions::new())
+            .is_err());
+    }
+
+    #[test]
+    fn test_reap_transactions() {
+        let dir = tempdir().unwrap();
+        let fs = Fs::create(&dir.path().join("fs")).unwrap();
+
+        let (old_name, new_name) = {
+            let mut old = fs.begin_txn(Revnum(0), 0).unwrap();
+            old.change_prop("svn:date", "2000-01-01T00:00:00.000000Z")
+                .unwrap();
+            old.change_prop("svn:author", "alice").unwrap();
+            old.change_prop("svn:log", "abandoned").unwrap();
+            {
+                let mut root = old.root().unwrap();
+                root.make_file("file.txt").unwrap();
+                root.set_file_contents("file.txt", b"hello\n").unwrap();
+            }
+
+            let mut new = fs.begin_txn(Revnum(0), 0).unwrap();
+            new.change_prop("svn:author", "bob").unwrap();
+            (old.name().unwrap(), new.name().unwrap())
+        };
+
+        let infos = fs.list_transactions_info().unwrap();
+        assert_eq!(infos.len(), 2);
+        let old_info = infos.iter().find(|i| i.name == old_name).unwrap();
+        assert_eq!(old_info.base_revision, Revnum(0));
+        assert_eq!(old_info.author.as_deref(), Some("alice"));
+        assert_eq!(old_info.log.as_deref(), Some("abandoned"));
+        assert!(old_info.size > 0);
+        let day = std::time::Duration::from_secs(24 * 60 * 60);
+        assert!(old_info.age(apr::time::Time::now()).unwrap() > day);
+        let new_info = infos.iter().find(|i| i.name == new_name).unwrap();
+        assert!(new_info.created.is_some());
+        assert!(new_info.age(apr::time::Time::now()).unwrap() < day);
+
+        // A dry run reports the old transaction but leaves it in place.
+        let reaped = fs
+            .reap_transactions(&ReapOptions::new().with_older_than(day).with_dry_run(true))
+            .unwrap();
+        assert_eq!(reaped.len(), 1);
+        assert_eq!(reaped[0].name, old_name);
+        assert_eq!(fs.list_transactions().unwrap().len(), 2);
+
+        let reaped = fs
+            .reap_transactions(&ReapOptions::new().with_older_than(day))
+            .unwrap();
+        assert_eq!(reaped.len(), 1);
+        assert_eq!(fs.list_transactions().unwrap(), vec![new_name.clone()]);
+
+        let by_bob = |info: &TxnInfo| info.author.as_deref() == Some("bob");
+        let reaped = fs
+            .reap_transactions(&ReapOptions::new().with_filter(&by_bob))
+            .unwrap();
+        assert_eq!(reaped.len(), 1);
+        assert_eq!(reaped[0].name, new_name);
+        assert!(fs.list_transactions().unwrap().is_empty());
+
+        // A transaction that vanishes between listing and purging is skipped.
+        let gone = fs.begin_txn(Revnum(0), 0).unwrap().name().unwrap();
+        let purge_first = |info: &TxnInfo| {
+            fs.purge_txn(&info.name).unwrap();
+            true
+        };
+        let reaped = fs
+            .reap_transactions(&ReapOptions::new().with_filter(&purge_first))
+            .unwrap();
+        assert!(reaped.is_empty());
+        assert!(is_no_such_transaction(
+            &fs.transaction_info(&gone).unwrap_err()
+        ));
+    }
+
+    #[test]
+    fn test_fsfs_conf_edit() {
+        let mut conf = FsfsConf::parse(
+            "### Comment\n[rep-sharing]\n# enable-rep-sharing = true\n\n[deltification]\nenable-dir-deltification = true\n",
+        );
+        assert_eq!(conf.get("rep-sharing", "enable-rep-sharing"), None);
+        assert_eq!(
+            conf.get("deltification", "enable-dir-deltification"),
+            Some("true")
+        );
+
+        conf.set("rep-sharing", "enable-rep-sharing", "false");
+        conf.set("deltification", "enable-dir-deltification", "false");
+        conf.set("packed-revprops", "revprop-pack-size", "32");
+        assert_eq!(
+            conf.to_string(),
+            "### Comment\n[rep-sharing]\nenable-rep-sharing = false\n# enable-rep-sharing = true\n\n[deltification]\nenable-dir-deltification = false\n\n[packed-revprops]\nrevprop-pack-size = 32\n"
+        );
+
+        let mut config = FsConfig::new();
+        config.load_conf(&conf).unwrap();
+        assert_eq!(config.rep_sharing, Some(false));
+        assert_eq!(config.dir_deltification, Some(false));
+        assert_eq!(config.revprop_pack_size, Some(32));
+
+        assert!(conf.remove("packed-revprops", "revprop-pack-size"));
+        assert!(!conf.remove("packed-revprops", "revprop-pack-size"));
+    }
+
+    #[test]
+    fn test_fs_config_validate() {
+        assert_eq!("zlib-3".parse(), Ok(Compression::Zlib(Some(3))));
+        assert_eq!("lz4".parse(), Ok(Compression::Lz4));
+        assert!("gzip".parse::<Compression>().is_err());
+        assert_eq!("fsfs".parse(), Ok(FsBackend::Fsfs));
+
+        assert!(FsConfig::new().validate().is_ok());
+        assert!(FsConfig::new()
+            .with_compression(Compression::Zlib(Some(12)))
+            .validate()
+            .is_err());
+        assert!(FsConfig::new()
+            .with_compatible_version(99, 0)
+            .validate()
+            .is_err());
+        assert!(FsConfig::new()
+            .with_compatible_version(1, 8)
+            .with_log_addressing(true)
+            .validate()
+            .is_err());
+        assert!(FsConfig::new()
+            .with_backend(FsBackend::Bdb)
+            .with_rep_sharing(false)
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn test_create_with_config() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("fs");
+        let config = FsConfig::new()
+            .with_backend(FsBackend::Fsfs)
+            .with_shard_size(4)
+            .with_rep_sharing(false)
+            .with_props_deltification(false)
+            .with_compression(Compression::Zlib(Some(3)));
+        let fs = Fs::create_with_config(&path, &config).unwrap();
+        assert_eq!(fs.youngest_revision().unwrap(), Revnum(0));
+
+        let on_disk = FsConfig::read(&path).unwrap();
+        assert_eq!(on_disk.backend, Some(FsBackend::Fsfs));
+        assert_eq!(on_disk.shard_size, Some(4));
+        assert_eq!(on_disk.rep_sharing, Some(false));
+        assert_eq!(on_disk.props_deltification, Some(false));
+        assert_eq!(on_disk.compression, Some(Compression::Zlib(Some(3))));
+
+        // Configuration-file settings can be updated later on...
+        FsConfig::new().with_rep_sharing(true).write(&path).unwrap();
+        assert_eq!(FsConfig::read(&path).unwrap().rep_sharing, Some(true));
+
+        // ...but creation-time settings cannot.
+        assert!(FsConfig::new().with_shard_size(8).write(&path).is_err());
+    }
 }
@@ -1743,6 +1743,10 @@ pub enum ClientConflictOptionId {
     IncomingDeleteIgnore,
     /// Accept the incoming deletion
     IncomingDeleteAccept,
+    /// Apply local changes to the moved file and accept the incoming move
+    IncomingMoveFileTextMerge,
+    /// Apply local changes to the moved directory and accept the incoming move
+    IncomingMoveDirMerge,
 }
 
 #[cfg(feature = "client")]
@@ -1769,6 +1773,8 @@ impl From<ClientConflictOptionId> for subversion_sys::svn_client_conflict_option
             ClientConflictOptionId::IncomingAddedDirReplaceAndMerge => subversion_sys::svn_client_conflict_option_id_t_svn_client_conflict_option_incoming_added_dir_replace_and_merge,
             ClientConflictOptionId::IncomingDeleteIgnore => subversion_sys::svn_client_conflict_option_id_t_svn_client_conflict_option_incoming_delete_ignore,
             ClientConflictOptionId::IncomingDeleteAccept => subversion_sys::svn_client_conflict_option_id_t_svn_client_conflict_option_incoming_delete_accept,
+            ClientConflictOptionId::IncomingMoveFileTextMerge => subversion_sys::svn_client_conflict_option_id_t_svn_client_conflict_option_incoming_move_file_text_merge,
+            ClientConflictOptionId::IncomingMoveDirMerge => subversion_sys::svn_client_conflict_option_id_t_svn_client_conflict_option_incoming_move_dir_merge,
         }
     }
 }
@@ -1797,6 +1803,8 @@ impl From<subversion_sys::svn_client_conflict_option_id_t> for ClientConflictOpt
             subversion_sys::svn_client_conflict_option_id_t_svn_client_conflict_option_incoming_added_dir_replace_and_merge => ClientConflictOptionId::IncomingAddedDirReplaceAndMerge,
             subversion_sys::svn_client_conflict_option_id_t_svn_client_conflict_option_incoming_delete_ignore => ClientConflictOptionId::IncomingDeleteIgnore,
             subversion_sys::svn_client_conflict_option_id_t_svn_client_conflict_option_incoming_delete_accept => ClientConflictOptionId::IncomingDeleteAccept,
+            subversion_sys::svn_client_conflict_option_id_t_svn_client_conflict_option_incoming_move_file_text_merge => ClientConflictOptionId::IncomingMoveFileTextMerge,
+            subversion_sys::svn_client_conflict_option_id_t_svn_client_conflict_option_incoming_move_dir_merge => ClientConflictOptionId::IncomingMoveDirMerge,
             _ => ClientConflictOptionId::Undefined,
         }
     }
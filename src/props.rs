@@ -257,6 +257,11 @@ impl<'a> PropHash<'a> {
     pub fn len(&self) -> usize {
         self.inner.len()
     }
+
+    /// Get the raw APR hash pointer
+    pub(crate) fn as_mut_ptr(&mut self) -> *mut apr_sys::apr_hash_t {
+        unsafe { self.inner.as_mut_ptr() }
+    }
 }
 
 #[cfg(test)]
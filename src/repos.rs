@@ -33,12 +33,13 @@
 //! println!("Repository is at revision {}", youngest);
 //! ```
 
+use crate::diff::split_lines;
 use crate::{svn_result, with_tmp_pool, Error, Revnum};
-use std::ffi::CString;
+use std::ffi::{CStr, CString};
 use std::marker::PhantomData;
 use subversion_sys::{
-    svn_repos_create, svn_repos_dump_fs4, svn_repos_find_root_path, svn_repos_load_fs6,
-    svn_repos_recover4, svn_repos_t, svn_repos_verify_fs3,
+    svn_repos_create, svn_repos_dump_fs4, svn_repos_find_root_path, svn_repos_recover4,
+    svn_repos_t, svn_repos_verify_fs3,
 };
 
 // Helper functions for properly boxing callback batons
@@ -169,6 +170,37 @@ pub struct LoadOptions<'a> {
     pub notify_func: Option<&'a dyn Fn(&Notify)>,
     /// Optional cancellation callback.
     pub cancel_func: Option<&'a dyn Fn() -> Result<(), Error<'static>>>,
+    /// Optional callback to rewrite revision properties.  It receives the
+    /// revision number from the dump stream, the property name and value,
+    /// and returns the value to store or `None` to drop the property.
+    pub revprop_func:
+        Option<&'a dyn Fn(Revnum, &str, &[u8]) -> Result<Option<Vec<u8>>, Error<'static>>>,
+    /// Optional callback to rename node paths.  It is applied to both node
+    /// paths and copy sources, and returns the new path or `None` to drop
+    /// the node.  Dropping a node that is later used as a copy source makes
+    /// the load fail.
+    ///
+    /// Paths are passed exactly as they appear in the dump stream: relative
+    /// to the repository root without a leading slash (`trunk/a.txt`), and
+    /// before [`LoadOptions::parent_dir`] is prepended.  The returned path
+    /// must use the same form.  Paths inside `svn:mergeinfo` values are not
+    /// rewritten; use [`LoadOptions::node_prop_func`] to adjust them.
+    pub path_func: Option<&'a dyn Fn(&str) -> Result<Option<String>, Error<'static>>>,
+    /// Optional callback to rewrite node properties.  It receives the
+    /// (renamed) node path, the property name and value, and returns the
+    /// value to store or `None` to ignore the property change.
+    pub node_prop_func:
+        Option<&'a dyn Fn(&str, &str, &[u8]) -> Result<Option<Vec<u8>>, Error<'static>>>,
+    /// Optional callback to replace file contents.  It is called for every
+    /// node that carries text, with the revision number from the dump
+    /// stream and the (renamed) node path, and returns the replacement
+    /// contents or `None` to keep the original text.
+    ///
+    /// The dump must carry full texts (`svnadmin dump` without `--deltas`).
+    /// A later delta node is stored against the original text, so once that
+    /// text is replaced its `Text-delta-base-*` checksums no longer match and
+    /// the load fails in that later revision.
+    pub text_func: Option<&'a dyn Fn(Revnum, &str) -> Result<Option<Vec<u8>>, Error<'static>>>,
 }
 
 impl<'a> LoadOptions<'a> {
@@ -230,6 +262,43 @@ impl<'a> LoadOptions<'a> {
         self.normalize_props = normalize;
         self
     }
+
+    /// Sets the revision property rewrite callback.
+    pub fn with_revprop_func(
+        mut self,
+        revprop_func: &'a dyn Fn(Revnum, &str, &[u8]) -> Result<Option<Vec<u8>>, Error<'static>>,
+    ) -> Self {
+        self.revprop_func = Some(revprop_func);
+        self
+    }
+
+    /// Sets the node path rewrite callback.
+    pub fn with_path_func(
+        mut self,
+        path_func: &'a dyn Fn(&str) -> Result<Option<String>, Error<'static>>,
+    ) -> Self {
+        self.path_func = Some(path_func);
+        self
+    }
+
+    /// Sets the node property rewrite callback.
+    pub fn with_node_prop_func(
+        mut self,
+        node_prop_func: &'a dyn Fn(&str, &str, &[u8]) -> Result<Option<Vec<u8>>, Error<'static>>,
+    ) -> Self {
+        self.node_prop_func = Some(node_prop_func);
+        self
+    }
+
+    /// Sets the file contents replacement callback; see
+    /// [`LoadOptions::text_func`] for the dumps it supports.
+    pub fn with_text_func(
+        mut self,
+        text_func: &'a dyn Fn(Revnum, &str) -> Result<Option<Vec<u8>>, Error<'static>>,
+    ) -> Self {
+        self.text_func = Some(text_func);
+        self
+    }
 }
 
 /// Options for repository verify operations.
@@ -634,12 +703,16 @@ impl Repos {
             std::ptr::null_mut()
         };
 
-        // Convert fs_config HashMap to APR hash if provided
-        let fs_config_hash = if let Some(cfg) = fs_config {
-            let byte_pairs: Vec<_> = cfg
-                .iter()
-                .map(|(k, v)| (k.as_bytes(), v.as_bytes()))
-                .collect();
+        // Convert fs_config HashMap to APR hash if provided.  The library
+        // reads the values as C strings, so they need to be NUL-terminated.
+        let fs_config_values = fs_config
+            .map(|cfg| {
+                cfg.iter()
+                    .map(|(k, v)| Ok((k.as_bytes(), CString::new(v.as_str())?)))
+                    .collect::<Result<Vec<_>, Error<'static>>>()
+            })
+            .transpose()?;
+        let fs_config_hash = if let Some(byte_pairs) = fs_config_values.as_ref() {
             let mut hash = apr::hash::Hash::new(&pool);
             for (k, v) in byte_pairs.iter() {
                 unsafe {
@@ -671,6 +744,27 @@ impl Repos {
         }
     }
 
+    /// Creates a new repository with a typed filesystem configuration.
+    ///
+    /// The creation-time settings of `fs_config` are passed on when the
+    /// filesystem is created; the others are written to its configuration
+    /// file (`db/fsfs.conf` for FSFS) before the repository is reopened.
+    pub fn create_with_fs_config(
+        path: &std::path::Path,
+        fs_config: &crate::fs::FsConfig,
+    ) -> Result<Repos, Error<'static>> {
+        fs_config.validate()?;
+        let fs_path = {
+            let repos = Self::create_with_config(path, None, Some(&fs_config.fs_config()))?;
+            repos
+                .fs()
+                .ok_or_else(|| Error::from_message("Repository has no filesystem"))?
+                .path()
+        };
+        fs_config.write(&fs_path)?;
+        Self::open(path)
+    }
+
     /// Opens an existing repository.
     pub fn open(path: &std::path::Path) -> Result<Repos, Error<'static>> {
         // Ensure SVN libraries are initialized
@@ -1867,11 +1961,16 @@ impl Repos {
     }
 
     /// Load repository contents from a dump stream for restoration
+    ///
+    /// The rewrite callbacks in `options` can transform revision
+    /// properties, node paths and node properties on the way in.  Returns
+    /// the map from revision numbers in the dump stream to the revisions
+    /// they were committed as.
     pub fn load(
         &self,
         dumpstream: &mut crate::io::Stream,
         options: &LoadOptions,
-    ) -> Result<(), Error<'static>> {
+    ) -> Result<std::collections::BTreeMap<Revnum, Revnum>, Error<'static>> {
         let pool = apr::Pool::new();
         let parent_dir_cstr = options
             .parent_dir
@@ -1881,13 +1980,24 @@ impl Repos {
             .map(|s| s.as_ptr())
             .unwrap_or(std::ptr::null());
 
-        let notify_baton = options
-            .notify_func
-            .map(|notify_func| {
-                let boxed: Box<dyn FnMut(&Notify)> = Box::new(move |n| notify_func(n));
-                Box::into_raw(Box::new(boxed)) as *mut std::ffi::c_void
-            })
-            .unwrap_or(std::ptr::null_mut());
+        let rev_map = std::cell::RefCell::new(std::collections::BTreeMap::new());
+        let record_rev_map = |n: &Notify| {
+            if n.action()
+                == subversion_sys::svn_repos_notify_action_t_svn_repos_notify_load_txn_committed
+                    as u32
+            {
+                rev_map
+                    .borrow_mut()
+                    .insert(n.old_revision(), n.new_revision());
+            }
+            if let Some(notify_func) = options.notify_func {
+                notify_func(n);
+            }
+        };
+        let notify_baton = {
+            let boxed: Box<dyn FnMut(&Notify)> = Box::new(record_rev_map);
+            Box::into_raw(Box::new(boxed)) as *mut std::ffi::c_void
+        };
         let cancel_baton = options
             .cancel_func
             .map(|cancel_func| {
@@ -1896,39 +2006,68 @@ impl Repos {
             })
             .unwrap_or(std::ptr::null_mut());
 
+        // This is what svn_repos_load_fs6 does, with room to put a rewriting
+        // parser in front of the one that builds the filesystem.
+        let mut parser = std::ptr::null();
+        let mut parse_baton = std::ptr::null_mut();
         let ret = unsafe {
-            svn_repos_load_fs6(
+            let err = subversion_sys::svn_repos_get_fs_build_parser6(
+                &mut parser,
+                &mut parse_baton,
                 self.ptr,
-                dumpstream.as_mut_ptr(),
                 options.start_rev.map(|r| r.into()).unwrap_or(-1),
                 options.end_rev.map(|r| r.into()).unwrap_or(-1),
+                1, // use_history
+                options.validate_props.into(),
                 options.uuid_action.into(),
                 parent_dir_ptr,
                 options.use_pre_commit_hook.into(),
                 options.use_post_commit_hook.into(),
-                options.validate_props.into(),
                 options.ignore_dates.into(),
                 options.normalize_props.into(),
-                if options.notify_func.is_some() {
-                    Some(wrap_notify_func)
-                } else {
-                    None
-                },
+                Some(wrap_notify_func),
                 notify_baton,
-                if options.cancel_func.is_some() {
-                    Some(crate::wrap_cancel_func)
-                } else {
-                    None
-                },
-                cancel_baton,
                 pool.as_mut_ptr(),
-            )
+            );
+            if err.is_null() {
+                let rewriter = LoadRewriter {
+                    inner: parser,
+                    inner_baton: parse_baton,
+                    revprop_func: options.revprop_func,
+                    path_func: options.path_func,
+                    node_prop_func: options.node_prop_func,
+                    text_func: options.text_func,
+                    revision: std::cell::RefCell::new(None),
+                    node: std::cell::RefCell::new(None),
+                };
+                let (parse_fns, parse_baton) = if rewriter.is_active() {
+                    (
+                        &LOAD_REWRITE_FNS as *const _,
+                        &rewriter as *const LoadRewriter as *mut std::ffi::c_void,
+                    )
+                } else {
+                    (parser, parse_baton)
+                };
+                subversion_sys::svn_repos_parse_dumpstream3(
+                    dumpstream.as_mut_ptr(),
+                    parse_fns,
+                    parse_baton,
+                    0, // deltas_are_text
+                    if options.cancel_func.is_some() {
+                        Some(crate::wrap_cancel_func)
+                    } else {
+                        None
+                    },
+                    cancel_baton,
+                    pool.as_mut_ptr(),
+                )
+            } else {
+                err
+            }
         };
 
         // Free callback batons
-        if !notify_baton.is_null() {
-            unsafe { drop(Box::from_raw(notify_baton as *mut Box<dyn Fn(&Notify)>)) };
-        }
+        unsafe { drop(Box::from_raw(notify_baton as *mut Box<dyn FnMut(&Notify)>)) };
         if !cancel_baton.is_null() {
             unsafe {
                 drop(Box::from_raw(
@@ -1938,7 +2077,7 @@ impl Repos {
         }
 
         Error::from_raw(ret)?;
-        Ok(())
+        Ok(rev_map.into_inner())
     }
 
     /// Verify repository integrity
@@ -2208,6 +2347,16 @@ impl<'a> Notify<'a> {
         unsafe { (*self.ptr).action as u32 }
     }
 
+    /// Get the revision number in the dump stream for a load notification
+    pub fn old_revision(&self) -> Revnum {
+        unsafe { Revnum((*self.ptr).old_revision) }
+    }
+
+    /// Get the newly committed revision number for a load notification
+    pub fn new_revision(&self) -> Revnum {
+        unsafe { Revnum((*self.ptr).new_revision) }
+    }
+
     /// Check if this is a verify_rev_end notification
     pub fn is_verify_rev_end(&self) -> bool {
         // svn_repos_notify_verify_rev_end value
@@ -2262,6 +2411,473 @@ extern "C" fn wrap_notify_func(
     }
 }
 
+/// State for rewriting a dump stream on its way into the fs build parser.
+///
+/// The dump stream parser opens revisions and nodes strictly one after the
+/// other, so the rewriter itself is passed as parse, revision and node
+/// baton and keeps the state of the current revision and node.
+struct LoadRewriter<'a> {
+    inner: *const subversion_sys::svn_repos_parse_fns3_t,
+    inner_baton: *mut std::ffi::c_void,
+    revprop_func:
+        Option<&'a dyn Fn(Revnum, &str, &[u8]) -> Result<Option<Vec<u8>>, Error<'static>>>,
+    path_func: Option<&'a dyn Fn(&str) -> Result<Option<String>, Error<'static>>>,
+    node_prop_func:
+        Option<&'a dyn Fn(&str, &str, &[u8]) -> Result<Option<Vec<u8>>, Error<'static>>>,
+    text_func: Option<&'a dyn Fn(Revnum, &str) -> Result<Option<Vec<u8>>, Error<'static>>>,
+    revision: std::cell::RefCell<Option<LoadRewriteRevision>>,
+    node: std::cell::RefCell<Option<LoadRewriteNode>>,
+}
+
+struct LoadRewriteRevision {
+    baton: *mut std::ffi::c_void,
+    revision: Revnum,
+    // Holds rewritten property values until the revision is closed.
+    pool: apr::Pool<'static>,
+}
+
+struct LoadRewriteNode {
+    // None if the node is dropped.
+    baton: Option<*mut std::ffi::c_void>,
+    path: String,
+    // Text to store instead of the node's contents in the dump stream.
+    replacement: Option<Vec<u8>>,
+    pool: apr::Pool<'static>,
+}
+
+impl LoadRewriter<'_> {
+    fn is_active(&self) -> bool {
+        self.revprop_func.is_some()
+            || self.path_func.is_some()
+            || self.node_prop_func.is_some()
+            || self.text_func.is_some()
+    }
+
+    unsafe fn fns(&self) -> &subversion_sys::svn_repos_parse_fns3_t {
+        &*self.inner
+    }
+
+    fn rewrite_path(&self, path: &str) -> Result<Option<String>, Error<'static>> {
+        match self.path_func {
+            Some(path_func) => path_func(path),
+            None => Ok(Some(path.to_string())),
+        }
+    }
+
+    fn node_baton(&self) -> Option<*mut std::ffi::c_void> {
+        self.node.borrow().as_ref().and_then(|node| node.baton)
+    }
+
+    fn current_revision(&self) -> Revnum {
+        self.revision
+            .borrow()
+            .as_ref()
+            .map_or(Revnum(-1), |rev| rev.revision)
+    }
+}
+
+unsafe fn load_rewriter<'a>(baton: *mut std::ffi::c_void) -> &'a LoadRewriter<'a> {
+    &*(baton as *const LoadRewriter)
+}
+
+fn into_svn_error(result: Result<(), Error<'static>>) -> *mut subversion_sys::svn_error_t {
+    match result {
+        Ok(()) => std::ptr::null_mut(),
+        Err(e) => unsafe { e.into_raw() },
+    }
+}
+
+/// Look up a dump stream header; `name` must be NUL-terminated.
+unsafe fn dump_header(headers: *mut apr_sys::apr_hash_t, name: &'static [u8]) -> Option<String> {
+    let value = apr_sys::apr_hash_get(
+        headers,
+        name.as_ptr() as *const std::ffi::c_void,
+        apr_sys::APR_HASH_KEY_STRING as apr_sys::apr_ssize_t,
+    );
+    if value.is_null() {
+        None
+    } else {
+        Some(
+            CStr::from_ptr(value as *const std::ffi::c_char)
+                .to_string_lossy()
+                .into_owned(),
+        )
+    }
+}
+
+unsafe fn set_dump_header(
+    headers: *mut apr_sys::apr_hash_t,
+    name: &'static [u8],
+    value: &str,
+    pool: *mut apr_sys::apr_pool_t,
+) -> Result<(), Error<'static>> {
+    let value = CString::new(value)?;
+    apr_sys::apr_hash_set(
+        headers,
+        name.as_ptr() as *const std::ffi::c_void,
+        apr_sys::APR_HASH_KEY_STRING as apr_sys::apr_ssize_t,
+        apr_sys::apr_pstrdup(pool, value.as_ptr()) as *const std::ffi::c_void,
+    );
+    Ok(())
+}
+
+unsafe fn rewrite_prop_value(
+    value: *const subversion_sys::svn_string_t,
+    rewrite: impl FnOnce(&[u8]) -> Result<Option<Vec<u8>>, Error<'static>>,
+    pool: &apr::Pool,
+) -> Result<Option<*const subversion_sys::svn_string_t>, Error<'static>> {
+    if value.is_null() {
+        return Ok(Some(value));
+    }
+    let data = std::slice::from_raw_parts((*value).data as *const u8, (*value).len);
+    Ok(rewrite(data)?.map(|new_value| {
+        crate::svn_string_helpers::svn_string_ncreate(&new_value, pool) as *const _
+    }))
+}
+
+extern "C" fn rewrite_magic_header_record(
+    version: std::ffi::c_int,
+    parse_baton: *mut std::ffi::c_void,
+    pool: *mut apr_sys::apr_pool_t,
+) -> *mut subversion_sys::svn_error_t {
+    unsafe {
+        let rewriter = load_rewriter(parse_baton);
+        match rewriter.fns().magic_header_record {
+            Some(f) => f(version, rewriter.inner_baton, pool),
+            None => std::ptr::null_mut(),
+        }
+    }
+}
+
+extern "C" fn rewrite_uuid_record(
+    uuid: *const std::ffi::c_char,
+    parse_baton: *mut std::ffi::c_void,
+    pool: *mut apr_sys::apr_pool_t,
+) -> *mut subversion_sys::svn_error_t {
+    unsafe {
+        let rewriter = load_rewriter(parse_baton);
+        match rewriter.fns().uuid_record {
+            Some(f) => f(uuid, rewriter.inner_baton, pool),
+            None => std::ptr::null_mut(),
+        }
+    }
+}
+
+extern "C" fn rewrite_new_revision_record(
+    revision_baton: *mut *mut std::ffi::c_void,
+    headers: *mut apr_sys::apr_hash_t,
+    parse_baton: *mut std::ffi::c_void,
+    pool: *mut apr_sys::apr_pool_t,
+) -> *mut subversion_sys::svn_error_t {
+    unsafe {
+        let rewriter = load_rewriter(parse_baton);
+        let mut inner_baton = std::ptr::null_mut();
+        if let Some(f) = rewriter.fns().new_revision_record {
+            let err = f(&mut inner_baton, headers, rewriter.inner_baton, pool);
+            if !err.is_null() {
+                return err;
+            }
+        }
+        let revision = dump_header(headers, b"Revision-number\0")
+            .and_then(|rev| rev.parse().ok())
+            .map_or(Revnum(-1), Revnum);
+        *rewriter.revision.borrow_mut() = Some(LoadRewriteRevision {
+            baton: inner_baton,
+            revision,
+            pool: apr::Pool::new(),
+        });
+        *revision_baton = parse_baton;
+        std::ptr::null_mut()
+    }
+}
+
+extern "C" fn rewrite_new_node_record(
+    node_baton: *mut *mut std::ffi::c_void,
+    headers: *mut apr_sys::apr_hash_t,
+    revision_baton: *mut std::ffi::c_void,
+    pool: *mut apr_sys::apr_pool_t,
+) -> *mut subversion_sys::svn_error_t {
+    unsafe {
+        let rewriter = load_rewriter(revision_baton);
+        *node_baton = revision_baton;
+        let result = (|| -> Result<(), Error<'static>> {
+            let old_path = dump_header(headers, b"Node-path\0").unwrap_or_default();
+            let Some(path) = rewriter.rewrite_path(&old_path)? else {
+                *rewriter.node.borrow_mut() = Some(LoadRewriteNode {
+                    baton: None,
+                    path: old_path,
+                    replacement: None,
+                    pool: apr::Pool::new(),
+                });
+                return Ok(());
+            };
+            set_dump_header(headers, b"Node-path\0", &path, pool)?;
+            if let Some(copyfrom) = dump_header(headers, b"Node-copyfrom-path\0") {
+                let new_copyfrom = rewriter.rewrite_path(&copyfrom)?.ok_or_else(|| {
+                    Error::from_message(&format!(
+                        "Copy source '{}' of '{}' was dropped",
+                        copyfrom, old_path
+                    ))
+                })?;
+                set_dump_header(headers, b"Node-copyfrom-path\0", &new_copyfrom, pool)?;
+            }
+            let replacement = match rewriter.text_func {
+                Some(text_func) if dump_header(headers, b"Text-content-length\0").is_some() => {
+                    text_func(rewriter.current_revision(), &path)?
+                }
+                _ => None,
+            };
+            if replacement.is_some() {
+                // The checksums of the original text no longer apply.
+                for name in [&b"Text-content-md5\0"[..], b"Text-content-sha1\0"] {
+                    apr_sys::apr_hash_set(
+                        headers,
+                        name.as_ptr() as *const std::ffi::c_void,
+                        apr_sys::APR_HASH_KEY_STRING as apr_sys::apr_ssize_t,
+                        std::ptr::null(),
+                    );
+                }
+            }
+
+            let revision = rewriter
+                .revision
+                .borrow()
+                .as_ref()
+                .map_or(std::ptr::null_mut(), |rev| rev.baton);
+            let mut inner_baton = std::ptr::null_mut();
+            if let Some(f) = rewriter.fns().new_node_record {
+                Error::from_raw(f(&mut inner_baton, headers, revision, pool))?;
+            }
+            *rewriter.node.borrow_mut() = Some(LoadRewriteNode {
+                baton: Some(inner_baton),
+                path,
+                replacement,
+                pool: apr::Pool::new(),
+            });
+            Ok(())
+        })();
+        into_svn_error(result)
+    }
+}
+
+extern "C" fn rewrite_set_revision_property(
+    revision_baton: *mut std::ffi::c_void,
+    name: *const std::ffi::c_char,
+    value: *const subversion_sys::svn_string_t,
+) -> *mut subversion_sys::svn_error_t {
+    unsafe {
+        let rewriter = load_rewriter(revision_baton);
+        let result = (|| -> Result<(), Error<'static>> {
+            let state = rewriter.revision.borrow();
+            let Some(state) = state.as_ref() else {
+                return Ok(());
+            };
+            let value = match rewriter.revprop_func {
+                Some(revprop_func) => {
+                    let name = CStr::from_ptr(name).to_str()?;
+                    match rewrite_prop_value(
+                        value,
+                        |v| revprop_func(state.revision, name, v),
+                        &state.pool,
+                    )? {
+                        Some(value) => value,
+                        None => return Ok(()),
+                    }
+                }
+                None => value,
+            };
+            if let Some(f) = rewriter.fns().set_revision_property {
+                Error::from_raw(f(state.baton, name, value))?;
+            }
+            Ok(())
+        })();
+        into_svn_error(result)
+    }
+}
+
+extern "C" fn rewrite_set_node_property(
+    node_baton: *mut std::ffi::c_void,
+    name: *const std::ffi::c_char,
+    value: *const subversion_sys::svn_string_t,
+) -> *mut subversion_sys::svn_error_t {
+    unsafe {
+        let rewriter = load_rewriter(node_baton);
+        let result = (|| -> Result<(), Error<'static>> {
+            let state = rewriter.node.borrow();
+            let Some((baton, state)) = state
+                .as_ref()
+                .and_then(|node| node.baton.map(|baton| (baton, node)))
+            else {
+                return Ok(());
+            };
+            let value = match rewriter.node_prop_func {
+                Some(node_prop_func) => {
+                    let name = CStr::from_ptr(name).to_str()?;
+                    match rewrite_prop_value(
+                        value,
+                        |v| node_prop_func(&state.path, name, v),
+                        &state.pool,
+                    )? {
+                        Some(value) => value,
+                        None => return Ok(()),
+                    }
+                }
+                None => value,
+            };
+            if let Some(f) = rewriter.fns().set_node_property {
+                Error::from_raw(f(baton, name, value))?;
+            }
+            Ok(())
+        })();
+        into_svn_error(result)
+    }
+}
+
+extern "C" fn rewrite_delete_node_property(
+    node_baton: *mut std::ffi::c_void,
+    name: *const std::ffi::c_char,
+) -> *mut subversion_sys::svn_error_t {
+    unsafe {
+        let rewriter = load_rewriter(node_baton);
+        match (rewriter.node_baton(), rewriter.fns().delete_node_property) {
+            (Some(baton), Some(f)) => f(baton, name),
+            _ => std::ptr::null_mut(),
+        }
+    }
+}
+
+extern "C" fn rewrite_remove_node_props(
+    node_baton: *mut std::ffi::c_void,
+) -> *mut subversion_sys::svn_error_t {
+    unsafe {
+        let rewriter = load_rewriter(node_baton);
+        match (rewriter.node_baton(), rewriter.fns().remove_node_props) {
+            (Some(baton), Some(f)) => f(baton),
+            _ => std::ptr::null_mut(),
+        }
+    }
+}
+
+extern "C" fn rewrite_set_fulltext(
+    stream: *mut *mut subversion_sys::svn_stream_t,
+    node_baton: *mut std::ffi::c_void,
+) -> *mut subversion_sys::svn_error_t {
+    unsafe {
+        let rewriter = load_rewriter(node_baton);
+        // A null stream makes the parser skip the text of dropped nodes.
+        *stream = std::ptr::null_mut();
+        let (Some(baton), Some(f)) = (rewriter.node_baton(), rewriter.fns().set_fulltext) else {
+            return std::ptr::null_mut();
+        };
+        let err = f(stream, baton);
+        let node = rewriter.node.borrow();
+        let Some((node, replacement)) = node
+            .as_ref()
+            .and_then(|node| node.replacement.as_ref().map(|r| (node, r)))
+        else {
+            return err;
+        };
+        if !err.is_null() {
+            return err;
+        }
+        // Write the replacement to the filesystem and let the parser write
+        // the original text into an empty stream.
+        let mut len = replacement.len();
+        let err = subversion_sys::svn_stream_write(
+            *stream,
+            replacement.as_ptr() as *const std::ffi::c_char,
+            &mut len,
+        );
+        if !err.is_null() {
+            return err;
+        }
+        let err = subversion_sys::svn_stream_close(*stream);
+        *stream = subversion_sys::svn_stream_empty(node.pool.as_mut_ptr());
+        err
+    }
+}
+
+extern "C" fn rewrite_apply_textdelta(
+    handler: *mut subversion_sys::svn_txdelta_window_handler_t,
+    handler_baton: *mut *mut std::ffi::c_void,
+    node_baton: *mut std::ffi::c_void,
+) -> *mut subversion_sys::svn_error_t {
+    unsafe {
+        let rewriter = load_rewriter(node_baton);
+        *handler = None;
+        *handler_baton = std::ptr::null_mut();
+        let (Some(baton), Some(f)) = (rewriter.node_baton(), rewriter.fns().apply_textdelta) else {
+            return std::ptr::null_mut();
+        };
+        let err = f(handler, handler_baton, baton);
+        let node = rewriter.node.borrow();
+        let Some((node, replacement)) = node
+            .as_ref()
+            .and_then(|node| node.replacement.as_ref().map(|r| (node, r)))
+        else {
+            return err;
+        };
+        if !err.is_null() {
+            return err;
+        }
+        // Send the replacement as a self-contained delta and let the parser
+        // discard the original one.
+        let text = crate::svn_string_helpers::svn_string_ncreate(replacement, &node.pool);
+        let err = subversion_sys::svn_txdelta_send_string(
+            text,
+            *handler,
+            *handler_baton,
+            node.pool.as_mut_ptr(),
+        );
+        *handler = None;
+        *handler_baton = std::ptr::null_mut();
+        err
+    }
+}
+
+extern "C" fn rewrite_close_node(
+    node_baton: *mut std::ffi::c_void,
+) -> *mut subversion_sys::svn_error_t {
+    unsafe {
+        let rewriter = load_rewriter(node_baton);
+        let node = rewriter.node.borrow_mut().take();
+        match (node.and_then(|node| node.baton), rewriter.fns().close_node) {
+            (Some(baton), Some(f)) => f(baton),
+            _ => std::ptr::null_mut(),
+        }
+    }
+}
+
+extern "C" fn rewrite_close_revision(
+    revision_baton: *mut std::ffi::c_void,
+) -> *mut subversion_sys::svn_error_t {
+    unsafe {
+        let rewriter = load_rewriter(revision_baton);
+        // Keep the revision's pool alive until the inner parser is done.
+        let revision = rewriter.revision.borrow_mut().take();
+        match (revision.as_ref(), rewriter.fns().close_revision) {
+            (Some(revision), Some(f)) => f(revision.baton),
+            _ => std::ptr::null_mut(),
+        }
+    }
+}
+
+const LOAD_REWRITE_FNS: subversion_sys::svn_repos_parse_fns3_t =
+    subversion_sys::svn_repos_parse_fns3_t {
+        magic_header_record: Some(rewrite_magic_header_record),
+        uuid_record: Some(rewrite_uuid_record),
+        new_revision_record: Some(rewrite_new_revision_record),
+        new_node_record: Some(rewrite_new_node_record),
+        set_revision_property: Some(rewrite_set_revision_property),
+        set_node_property: Some(rewrite_set_node_property),
+        delete_node_property: Some(rewrite_delete_node_property),
+        remove_node_props: Some(rewrite_remove_node_props),
+        set_fulltext: Some(rewrite_set_fulltext),
+        apply_textdelta: Some(rewrite_apply_textdelta),
+        close_node: Some(rewrite_close_node),
+        close_revision: Some(rewrite_close_revision),
+    };
+
 /// Upgrades a repository to the latest filesystem format.
 pub fn upgrade(
     path: &std::path::Path,
@@ -4401,37 +5017,906 @@ pub fn list(
     })
 }
 
-#[cfg(test)]
-mod additional_tests {
-    use super::*;
-    use std::path::Path;
+/// Options for [`blame`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BlameOptions {
+    /// Also attribute lines to the revisions they were merged from.
+    pub include_merged_revisions: bool,
+    /// Whitespace and EOL handling used when comparing revisions.
+    pub diff_options: crate::diff::FileOptions,
+}
 
-    #[test]
-    fn test_fs_pack_invalid_path() {
-        let invalid_path = Path::new("/non/existent/repo");
+impl BlameOptions {
+    /// Creates new blame options with default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-        // Test fs_pack with invalid repository path (should fail gracefully)
-        let result = fs_pack(invalid_path, None, None);
+    /// Sets whether to include merged revision information.
+    pub fn with_include_merged_revisions(mut self, include: bool) -> Self {
+        self.include_merged_revisions = include;
+        self
+    }
 
-        // Should return error for invalid repository
-        assert!(result.is_err());
+    /// Sets whether to ignore whitespace changes.
+    pub fn with_ignore_whitespace(mut self, ignore: bool) -> Self {
+        self.diff_options = self.diff_options.with_ignore_whitespace(ignore);
+        self
     }
 
-    #[test]
-    #[cfg(feature = "delta")]
-    fn test_get_commit_editor_basic() {
-        // Create a temporary repository for testing
-        let temp_dir = tempfile::tempdir().unwrap();
-        let repo_path = temp_dir.path().join("test_repo");
+    /// Sets whether to ignore end-of-line style differences.
+    pub fn with_ignore_eol_style(mut self, ignore: bool) -> Self {
+        self.diff_options = self.diff_options.with_ignore_eol_style(ignore);
+        self
+    }
+}
 
-        // Create repository using the correct function
-        let repo = Repos::create(&repo_path).unwrap();
+/// A line of a file annotated by [`blame`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlameLine {
+    /// Zero-based line number.
+    pub line_no: usize,
+    /// Revision that last changed the line, or `None` if the line was last
+    /// changed before the start of the blamed range.
+    pub revision: Option<Revnum>,
+    /// Author of `revision`.
+    pub author: Option<String>,
+    /// Date of `revision`.
+    pub date: Option<apr::time::Time>,
+    /// Revision the line was merged from, if it differs from `revision`.
+    /// Only set when merged revisions were requested.
+    pub merged_revision: Option<Revnum>,
+    /// Author of `merged_revision`.
+    pub merged_author: Option<String>,
+    /// Date of `merged_revision`.
+    pub merged_date: Option<apr::time::Time>,
+    /// Path of the file in `merged_revision`.
+    pub merged_path: Option<String>,
+    /// Contents of the line, without its line ending.
+    pub line: String,
+}
 
-        // Build the repository URL from the path
-        let repo_url = crate::path_to_file_url(&repo_path);
+/// The path, revision and author information of a file revision reported
+/// by `get_file_revs`.
+struct BlameRevision {
+    path: String,
+    revision: Revnum,
+    author: Option<String>,
+    date: Option<apr::time::Time>,
+}
 
-        // Test get_commit_editor with basic parameters
-        let result = repo.get_commit_editor(
+/// Line attributions for one sequence of file revisions.
+#[derive(Default)]
+struct BlameChain {
+    text: String,
+    lines: Vec<usize>,
+}
+
+impl BlameChain {
+    /// Advance the chain to `text`, attributing changed lines to `revision`.
+    fn update(
+        &mut self,
+        text: String,
+        revision: usize,
+        options: &crate::diff::FileOptions,
+    ) -> Result<(), Error<'static>> {
+        let diff = crate::diff::mem_string_diff(&self.text, &text, *options)?;
+        let new_len = split_lines(&text).len();
+        let mut lines = Vec::with_capacity(new_len);
+        let mut old = 0;
+        for (original_start, original_length, modified_start, modified_length) in
+            crate::diff::modified_ranges(&diff)?
+        {
+            while lines.len() < modified_start {
+                lines.push(self.lines.get(old).copied().unwrap_or(revision));
+                old += 1;
+            }
+            lines.extend(std::iter::repeat(revision).take(modified_length));
+            old = original_start + original_length;
+        }
+        while lines.len() < new_len {
+            lines.push(self.lines.get(old).copied().unwrap_or(revision));
+            old += 1;
+        }
+        self.text = text;
+        self.lines = lines;
+        Ok(())
+    }
+}
+
+/// Annotate each line of the file at `path` in revision `end` with the
+/// revision that last changed it, considering revisions from `start` to `end`.
+///
+/// This is the repository-side counterpart of `client::Context::blame`: it
+/// reads the repository directly instead of going through an RA session.
+/// Revisions are found with [`Repos::get_file_revs`], and consecutive
+/// versions of the file are compared line by line with
+/// [`crate::diff::mem_string_diff`].  File contents that are not valid UTF-8
+/// are compared lossily.
+pub fn blame(
+    repos: &Repos,
+    path: &str,
+    start: Revnum,
+    end: Revnum,
+    options: &BlameOptions,
+) -> Result<Vec<BlameLine>, Error<'static>> {
+    let fs = repos
+        .fs()
+        .ok_or_else(|| Error::from_message("Repository has no filesystem"))?;
+
+    let mut revisions: Vec<BlameRevision> = Vec::new();
+    let mut chain = BlameChain::default();
+    let mut merged_chain = BlameChain::default();
+    repos.get_file_revs(
+        path,
+        start,
+        end,
+        options.include_merged_revisions,
+        |rev_path, rev, rev_props, result_of_merge, _prop_diffs| {
+            let mut contents = Vec::new();
+            std::io::Read::read_to_end(
+                &mut fs.revision_root(rev)?.file_contents(rev_path)?,
+                &mut contents,
+            )?;
+            let text = String::from_utf8_lossy(&contents).into_owned();
+
+            let index = revisions.len();
+            revisions.push(BlameRevision {
+                path: rev_path.to_string(),
+                revision: rev,
+                author: rev_props
+                    .get("svn:author")
+                    .map(|v| String::from_utf8_lossy(v).into_owned()),
+                date: rev_props
+                    .get("svn:date")
+                    .and_then(|v| std::str::from_utf8(v).ok())
+                    .and_then(|v| crate::time::from_cstring(v).ok()),
+            });
+
+            if options.include_merged_revisions {
+                merged_chain.update(text.clone(), index, &options.diff_options)?;
+            }
+            if !result_of_merge {
+                chain.update(text, index, &options.diff_options)?;
+            }
+            Ok(())
+        },
+    )?;
+
+    let lines = split_lines(&chain.text);
+    let mut result = Vec::with_capacity(lines.len());
+    for (line_no, line) in lines.into_iter().enumerate() {
+        let mut blame_line = BlameLine {
+            line_no,
+            revision: None,
+            author: None,
+            date: None,
+            merged_revision: None,
+            merged_author: None,
+            merged_date: None,
+            merged_path: None,
+            line: line.trim_end_matches(['\r', '\n']).to_string(),
+        };
+        let index = chain.lines[line_no];
+        let rev = &revisions[index];
+        if rev.revision >= start {
+            blame_line.revision = Some(rev.revision);
+            blame_line.author = rev.author.clone();
+            blame_line.date = rev.date;
+        }
+        if let Some(&merged_index) = merged_chain.lines.get(line_no) {
+            let merged = &revisions[merged_index];
+            if merged_index != index && merged.revision >= start {
+                blame_line.merged_revision = Some(merged.revision);
+                blame_line.merged_author = merged.author.clone();
+                blame_line.merged_date = merged.date;
+                blame_line.merged_path = Some(merged.path.clone());
+            }
+        }
+        result.push(blame_line);
+    }
+    Ok(result)
+}
+
+/// Options for [`BackupManager::backup`] and [`BackupManager::backup_all`].
+pub struct BackupOptions<'a> {
+    /// Hold a [`freeze`] on the source repository while copying, so that no
+    /// commits happen during the backup.
+    pub freeze: bool,
+    /// Verify the revisions added to the backup after copying.
+    pub verify: bool,
+    /// Called with the outcome for each repository as it finishes.
+    pub report_func: Option<&'a dyn Fn(&BackupReport)>,
+    /// Optional cancellation callback.
+    pub cancel_func: Option<&'a dyn Fn() -> Result<(), Error<'static>>>,
+}
+
+impl Default for BackupOptions<'_> {
+    fn default() -> Self {
+        Self {
+            freeze: false,
+            verify: true,
+            report_func: None,
+            cancel_func: None,
+        }
+    }
+}
+
+impl<'a> BackupOptions<'a> {
+    /// Creates new BackupOptions with default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether to freeze the source repository while copying.
+    pub fn with_freeze(mut self, freeze: bool) -> Self {
+        self.freeze = freeze;
+        self
+    }
+
+    /// Sets whether to verify the backup after copying.
+    pub fn with_verify(mut self, verify: bool) -> Self {
+        self.verify = verify;
+        self
+    }
+
+    /// Sets the per-repository report callback.
+    pub fn with_report_func(mut self, report_func: &'a dyn Fn(&BackupReport)) -> Self {
+        self.report_func = Some(report_func);
+        self
+    }
+
+    /// Sets the cancellation callback.
+    pub fn with_cancel_func(
+        mut self,
+        cancel_func: &'a dyn Fn() -> Result<(), Error<'static>>,
+    ) -> Self {
+        self.cancel_func = Some(cancel_func);
+        self
+    }
+}
+
+/// A single backup copy of a repository.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupSlot {
+    /// Index of the slot, between 0 and the retention count.
+    pub index: usize,
+    /// Path of the backup repository.
+    pub path: std::path::PathBuf,
+    /// Youngest revision contained in the backup.
+    pub revision: Revnum,
+    /// When the backup was last updated.
+    pub time: apr::time::Time,
+}
+
+/// Outcome of backing up one repository.
+pub struct BackupReport {
+    /// Path of the source repository.
+    pub repos_path: std::path::PathBuf,
+    /// Revision of the most recent backup before this run, if any.
+    pub previous_revision: Option<Revnum>,
+    /// Whether an existing backup was updated incrementally rather than
+    /// copied from scratch.
+    pub incremental: bool,
+    /// The updated backup, or the error that stopped it.
+    pub result: Result<BackupSlot, Error<'static>>,
+}
+
+/// Maintains a fixed number of rotating hotcopy backups per repository.
+///
+/// Backups of a repository named `NAME` live in `ROOT/NAME/0`,
+/// `ROOT/NAME/1`, ... and each run incrementally updates the slot that was
+/// updated least recently, so the backups trail the repository by one to
+/// `retain` runs.  The revision and time of every slot are recorded in
+/// `ROOT/NAME/backup.state`, and the canonical path of the repository that
+/// owns `ROOT/NAME` in `ROOT/NAME/backup.source`.  Backing up a different
+/// repository with the same name below the same root is refused.
+pub struct BackupManager {
+    root: std::path::PathBuf,
+    retain: usize,
+}
+
+const BACKUP_STATE_FILE: &str = "backup.state";
+const BACKUP_SOURCE_FILE: &str = "backup.source";
+
+impl BackupManager {
+    /// Create a manager keeping `retain` backups of each repository below
+    /// `root`.
+    pub fn new(root: &std::path::Path, retain: usize) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            retain: retain.max(1),
+        }
+    }
+
+    fn backup_dir(
+        &self,
+        repos_path: &std::path::Path,
+    ) -> Result<std::path::PathBuf, Error<'static>> {
+        let name = repos_path
+            .file_name()
+            .ok_or_else(|| Error::from_message("Repository path has no file name"))?;
+        let dir = self.root.join(name);
+        let source = std::fs::canonicalize(repos_path)?;
+        match std::fs::read_to_string(dir.join(BACKUP_SOURCE_FILE)) {
+            Ok(owner) => {
+                let owner = owner.trim_end_matches('\n');
+                if std::path::Path::new(owner) != source {
+                    return Err(Error::from_message(&format!(
+                        "Backup directory '{}' belongs to repository '{}', not '{}'",
+                        dir.display(),
+                        owner,
+                        source.display()
+                    )));
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        Ok(dir)
+    }
+
+    /// Record the repository at `repos_path` as the owner of its backup
+    /// directory, unless an owner is already recorded.
+    fn claim_backup_dir(
+        &self,
+        repos_path: &std::path::Path,
+    ) -> Result<std::path::PathBuf, Error<'static>> {
+        let dir = self.backup_dir(repos_path)?;
+        std::fs::create_dir_all(&dir)?;
+        let source_file = dir.join(BACKUP_SOURCE_FILE);
+        if !source_file.exists() {
+            let source = std::fs::canonicalize(repos_path)?;
+            let source = source
+                .to_str()
+                .ok_or_else(|| Error::from_message("Invalid repository path"))?;
+            std::fs::write(source_file, format!("{}\n", source))?;
+        }
+        Ok(dir)
+    }
+
+    /// List the recorded backups of the repository at `repos_path`, oldest
+    /// first.
+    pub fn backups(&self, repos_path: &std::path::Path) -> Result<Vec<BackupSlot>, Error<'static>> {
+        let dir = self.backup_dir(repos_path)?;
+        let state = match std::fs::read_to_string(dir.join(BACKUP_STATE_FILE)) {
+            Ok(state) => state,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let invalid = || Error::from_message("Malformed backup state file");
+        let mut slots = state
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let mut fields = line.split_whitespace();
+                let mut next = || fields.next().ok_or_else(invalid);
+                let index: usize = next()?.parse().map_err(|_| invalid())?;
+                let revision = Revnum(next()?.parse().map_err(|_| invalid())?);
+                let time = apr::time::Time::from_micros(next()?.parse().map_err(|_| invalid())?);
+                Ok(BackupSlot {
+                    index,
+                    path: dir.join(index.to_string()),
+                    revision,
+                    time,
+                })
+            })
+            .collect::<Result<Vec<_>, Error<'static>>>()?;
+        slots.sort_by_key(|slot| (slot.time, slot.index));
+        Ok(slots)
+    }
+
+    /// The most recent backup of the repository at `repos_path`.
+    pub fn latest(
+        &self,
+        repos_path: &std::path::Path,
+    ) -> Result<Option<BackupSlot>, Error<'static>> {
+        Ok(self.backups(repos_path)?.pop())
+    }
+
+    fn write_state(
+        &self,
+        repos_path: &std::path::Path,
+        slots: &[BackupSlot],
+    ) -> Result<(), Error<'static>> {
+        let dir = self.backup_dir(repos_path)?;
+        let mut state = String::new();
+        for slot in slots {
+            state.push_str(&format!(
+                "{} {} {}\n",
+                slot.index,
+                slot.revision.0,
+                slot.time.as_micros()
+            ));
+        }
+        // Write a temporary file first so a crash never leaves a truncated
+        // state file behind.
+        let tmp = dir.join(format!("{}.tmp", BACKUP_STATE_FILE));
+        std::fs::write(&tmp, state)?;
+        std::fs::rename(&tmp, dir.join(BACKUP_STATE_FILE))?;
+        Ok(())
+    }
+
+    /// Back up the repository at `repos_path` into the next slot.
+    ///
+    /// The outcome is also passed to [`BackupOptions::report_func`].
+    pub fn backup(
+        &self,
+        repos_path: &std::path::Path,
+        options: &BackupOptions<'_>,
+    ) -> BackupReport {
+        let mut previous_revision = None;
+        let mut incremental = false;
+        let result = self.backup_into_slot(
+            repos_path,
+            options,
+            &mut previous_revision,
+            &mut incremental,
+        );
+        let report = BackupReport {
+            repos_path: repos_path.to_path_buf(),
+            previous_revision,
+            incremental,
+            result,
+        };
+        if let Some(report_func) = options.report_func {
+            report_func(&report);
+        }
+        report
+    }
+
+    /// Back up each repository in `repos_paths`, carrying on past failures.
+    pub fn backup_all(
+        &self,
+        repos_paths: &[&std::path::Path],
+        options: &BackupOptions<'_>,
+    ) -> Vec<BackupReport> {
+        repos_paths
+            .iter()
+            .map(|repos_path| self.backup(repos_path, options))
+            .collect()
+    }
+
+    fn backup_into_slot(
+        &self,
+        repos_path: &std::path::Path,
+        options: &BackupOptions<'_>,
+        previous_revision: &mut Option<Revnum>,
+        incremental: &mut bool,
+    ) -> Result<BackupSlot, Error<'static>> {
+        let dir = self.claim_backup_dir(repos_path)?;
+        let mut slots = self.backups(repos_path)?;
+        *previous_revision = slots.last().map(|slot| slot.revision);
+
+        // Fill unused slots first, then reuse the least recently updated one.
+        let index = (0..self.retain)
+            .find(|i| !slots.iter().any(|slot| slot.index == *i))
+            .or_else(|| {
+                slots
+                    .iter()
+                    .map(|slot| slot.index)
+                    .find(|i| *i < self.retain)
+            })
+            .expect("every slot below the retention count is in use");
+        let previous = slots
+            .iter()
+            .position(|slot| slot.index == index)
+            .map(|i| slots.remove(i));
+        let slot_path = dir.join(index.to_string());
+        // The slot is only trusted again once this run has succeeded.
+        self.write_state(repos_path, &slots)?;
+
+        // A slot directory without a recorded state is left over from a
+        // failed run and may be inconsistent, so start it from scratch.
+        if previous.is_none() && slot_path.exists() {
+            std::fs::remove_dir_all(&slot_path)?;
+        }
+        *incremental = previous.is_some();
+
+        let copy = || {
+            hotcopy(
+                repos_path,
+                &slot_path,
+                false,
+                true,
+                None::<&fn(&Notify)>,
+                options.cancel_func.as_ref(),
+            )
+        };
+        if options.freeze {
+            let path = repos_path
+                .to_str()
+                .ok_or_else(|| Error::from_message("Invalid repository path"))?;
+            freeze(&[path], Some(&copy))?;
+        } else {
+            copy()?;
+        }
+
+        let backup = Repos::open(&slot_path)?;
+        let revision = backup.youngest_rev()?;
+        if options.verify {
+            let start = previous.as_ref().map_or(Revnum(0), |slot| {
+                Revnum((slot.revision.0 + 1).min(revision.0))
+            });
+            let mut verify_options = VerifyOptions::new()
+                .with_start_rev(start)
+                .with_end_rev(revision);
+            verify_options.cancel_func = options.cancel_func;
+            backup.verify(&verify_options)?;
+        }
+
+        let slot = BackupSlot {
+            index,
+            path: slot_path,
+            revision,
+            time: apr::time::Time::now(),
+        };
+        slots.push(slot.clone());
+        // Drop slots beyond the retention count, e.g. after it was lowered.
+        let (kept, stale): (Vec<_>, Vec<_>) =
+            slots.into_iter().partition(|slot| slot.index < self.retain);
+        self.write_state(repos_path, &kept)?;
+        for slot in stale {
+            std::fs::remove_dir_all(&slot.path)?;
+        }
+        Ok(slot)
+    }
+}
+
+/// Options for [`obliterate`].
+pub struct ObliterateOptions<'a> {
+    /// Paths to remove from every revision, together with everything below
+    /// them.  Copies of these paths made elsewhere are kept unless they are
+    /// listed too.
+    pub paths: Vec<String>,
+    /// File contents to replace, as `(revision, path)` pairs naming the
+    /// revision in which the text was committed.
+    pub contents: Vec<(Revnum, String)>,
+    /// Text stored instead of replaced file contents.
+    pub placeholder: Vec<u8>,
+    /// Optional notification callback, passed on to the load.
+    pub notify_func: Option<&'a dyn Fn(&Notify)>,
+    /// Optional cancellation callback.
+    pub cancel_func: Option<&'a dyn Fn() -> Result<(), Error<'static>>>,
+}
+
+impl Default for ObliterateOptions<'_> {
+    fn default() -> Self {
+        Self {
+            paths: Vec::new(),
+            contents: Vec::new(),
+            placeholder: b"This file has been obliterated.\n".to_vec(),
+            notify_func: None,
+            cancel_func: None,
+        }
+    }
+}
+
+impl<'a> ObliterateOptions<'a> {
+    /// Creates new ObliterateOptions with default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a path to remove from the repository.
+    pub fn with_path(mut self, path: &str) -> Self {
+        self.paths.push(path.to_string());
+        self
+    }
+
+    /// Adds file contents to replace with the placeholder.
+    pub fn with_contents(mut self, revision: Revnum, path: &str) -> Self {
+        self.contents.push((revision, path.to_string()));
+        self
+    }
+
+    /// Sets the text stored instead of replaced file contents.
+    pub fn with_placeholder(mut self, placeholder: &[u8]) -> Self {
+        self.placeholder = placeholder.to_vec();
+        self
+    }
+
+    /// Sets the notification callback.
+    pub fn with_notify_func(mut self, notify_func: &'a dyn Fn(&Notify)) -> Self {
+        self.notify_func = Some(notify_func);
+        self
+    }
+
+    /// Sets the cancellation callback.
+    pub fn with_cancel_func(
+        mut self,
+        cancel_func: &'a dyn Fn() -> Result<(), Error<'static>>,
+    ) -> Self {
+        self.cancel_func = Some(cancel_func);
+        self
+    }
+
+    fn is_replaced(&self, revision: Revnum, path: &str) -> bool {
+        let path = path.trim_matches('/');
+        self.contents
+            .iter()
+            .any(|(rev, p)| *rev == revision && p.trim_matches('/') == path)
+    }
+}
+
+/// Summary of an [`obliterate`] run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ObliterateReport {
+    /// Nodes left out of the rebuilt repository, as `(revision, path)`.
+    pub removed: Vec<(Revnum, String)>,
+    /// File contents that were replaced, as `(revision, path)`.
+    pub replaced: Vec<(Revnum, String)>,
+    /// Every revision that differs from the original, in ascending order.
+    pub affected_revisions: Vec<Revnum>,
+}
+
+fn copy_dir_all(src: &std::path::Path, dst: &std::path::Path) -> Result<(), Error<'static>> {
+    std::fs::create_dir_all(dst)?;
+    for entry in std::fs::read_dir(src)? {
+        let entry = entry?;
+        let target = dst.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writing half of the in-memory pipe that carries the dump stream from
+/// the dumping thread to the load.
+struct DumpPipeWriter {
+    sender: std::sync::mpsc::SyncSender<Vec<u8>>,
+    // Set once the load has stopped reading.
+    reader_gone: std::sync::Arc<std::sync::atomic::AtomicBool>,
+}
+
+impl std::io::Write for DumpPipeWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.sender.send(buf.to_vec()).map_err(|_| {
+            self.reader_gone
+                .store(true, std::sync::atomic::Ordering::SeqCst);
+            std::io::Error::new(
+                std::io::ErrorKind::BrokenPipe,
+                "Dump stream reader has gone away",
+            )
+        })?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Reading half of the dump pipe.  Reads only come up short once the
+/// writer is gone, as the dump stream parser expects.
+struct DumpPipeReader {
+    receiver: std::sync::mpsc::Receiver<Vec<u8>>,
+    chunk: Vec<u8>,
+    pos: usize,
+}
+
+impl std::io::Read for DumpPipeReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            if self.pos == self.chunk.len() {
+                match self.receiver.recv() {
+                    Ok(chunk) => {
+                        self.chunk = chunk;
+                        self.pos = 0;
+                        continue;
+                    }
+                    Err(_) => break,
+                }
+            }
+            let n = (self.chunk.len() - self.pos).min(buf.len() - filled);
+            buf[filled..filled + n].copy_from_slice(&self.chunk[self.pos..self.pos + n]);
+            self.pos += n;
+            filled += n;
+        }
+        Ok(filled)
+    }
+}
+
+/// An error handed back from the dumping thread.
+struct DumpThreadError(Error<'static>);
+
+// An svn_error_t lives in a pool of its own, so it can be handed to
+// another thread once the dump has stopped touching it.
+unsafe impl Send for DumpThreadError {}
+
+/// Dump the repository at `src_path` into `pipe`, leaving out `paths`.  Returns the nodes that were left out.
+fn dump_filtered(
+    src_path: &std::path::Path,
+    paths: &[String],
+    pipe: DumpPipeWriter,
+) -> Result<std::collections::BTreeSet<(Revnum, String)>, Error<'static>> {
+    let src = Repos::open(src_path)?;
+    let removed = std::cell::RefCell::new(std::collections::BTreeSet::new());
+    let mut stream = crate::io::Stream::from_backend(crate::io::WriteOnlyBackend::new(
+        std::io::BufWriter::with_capacity(64 * 1024, pipe),
+    ))?;
+    let mut dump_options = DumpOptions::new()
+        .with_include_revprops(true)
+        .with_include_changes(true);
+    dump_options.filter_func = Some(Box::new(|root: &crate::fs::Root, path: &str| {
+        let excluded = is_obliterated(paths, path);
+        if excluded {
+            removed
+                .borrow_mut()
+                .insert((root.revision(), path.to_string()));
+        }
+        Ok(!excluded)
+    }));
+    src.dump(&mut stream, &mut dump_options)?;
+    stream.close()?;
+    Ok(removed.into_inner())
+}
+
+/// Whether `path` is one of `paths` or below one of them.
+fn is_obliterated(paths: &[String], path: &str) -> bool {
+    let path = path.trim_matches('/');
+    paths.iter().any(|prefix| {
+        let prefix = prefix.trim_matches('/');
+        prefix.is_empty()
+            || path == prefix
+            || path
+                .strip_prefix(prefix)
+                .is_some_and(|rest| rest.starts_with('/'))
+    })
+}
+
+/// Rebuild the repository at `src_path` into a new repository at
+/// `dst_path`, leaving out the paths and file contents named in `options`.
+///
+/// The source is dumped without deltas and loaded into a new repository
+/// with the same filesystem configuration and UUID.  Obliterated paths are
+/// left out while dumping and file contents are replaced while loading;
+/// the dump stream is passed from one to the other in memory, so the
+/// removed data is never written to disk.  Revisions keep their numbers
+/// and properties, even those that become empty.  Locks, hooks and the
+/// repository configuration are copied afterwards.
+///
+/// The source repository is left untouched; swapping the two is up to the
+/// caller.  If the rebuild fails, `dst_path` is removed again.
+pub fn obliterate(
+    src_path: &std::path::Path,
+    dst_path: &std::path::Path,
+    options: &ObliterateOptions<'_>,
+) -> Result<ObliterateReport, Error<'static>> {
+    let src_fs_path = Repos::open(src_path)?
+        .fs()
+        .ok_or_else(|| Error::from_message("Repository has no filesystem"))?
+        .path();
+    let dst = Repos::create_with_fs_config(dst_path, &crate::fs::FsConfig::read(&src_fs_path)?)?;
+    let result = rebuild_obliterated(src_path, &src_fs_path, &dst, dst_path, options);
+    drop(dst);
+    if result.is_err() {
+        // The rebuild error is what matters; a half-built repository that
+        // cannot be removed is left for the caller to clean up.
+        let _ = std::fs::remove_dir_all(dst_path);
+    }
+    result
+}
+
+fn rebuild_obliterated(
+    src_path: &std::path::Path,
+    src_fs_path: &std::path::Path,
+    dst: &Repos,
+    dst_path: &std::path::Path,
+    options: &ObliterateOptions<'_>,
+) -> Result<ObliterateReport, Error<'static>> {
+    let dst_fs_path = dst
+        .fs()
+        .ok_or_else(|| Error::from_message("Repository has no filesystem"))?
+        .path();
+
+    let replaced = std::cell::RefCell::new(std::collections::BTreeSet::new());
+    let text_func = |revision: Revnum, path: &str| {
+        if options.is_replaced(revision, path) {
+            replaced
+                .borrow_mut()
+                .insert((revision, format!("/{}", path.trim_start_matches('/'))));
+            Ok(Some(options.placeholder.clone()))
+        } else {
+            Ok(None)
+        }
+    };
+    let mut load_options = LoadOptions::new()
+        .with_uuid_action(LoadUUID::Force)
+        .with_text_func(&text_func);
+    load_options.notify_func = options.notify_func;
+    load_options.cancel_func = options.cancel_func;
+
+    // The callbacks in `options` can only be used on this thread, so the
+    // dump runs without them.  Cancelling the load closes the pipe, which
+    // stops the dump as well.
+    let paths = &options.paths;
+    let reader_gone = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let (dump_result, load_result) = std::thread::scope(|scope| {
+        let (sender, receiver) = std::sync::mpsc::sync_channel(16);
+        let pipe = DumpPipeWriter {
+            sender,
+            reader_gone: reader_gone.clone(),
+        };
+        let dumper =
+            scope.spawn(move || dump_filtered(src_path, paths, pipe).map_err(DumpThreadError));
+        let load_result =
+            crate::io::Stream::from_backend(crate::io::ReadOnlyBackend::new(DumpPipeReader {
+                receiver,
+                chunk: Vec::new(),
+                pos: 0,
+            }))
+            .and_then(|mut stream| dst.load(&mut stream, &load_options));
+        let dump_result = match dumper.join() {
+            Ok(result) => result.map_err(|e| e.0),
+            Err(_) => Err(Error::from_message("Dumping the repository panicked")),
+        };
+        (dump_result, load_result)
+    });
+    // A failed load closes the pipe, which makes the dump fail too; a
+    // failed dump cuts the stream short, which the load may not notice.
+    let removed = match (dump_result, load_result) {
+        (Ok(removed), Ok(_)) => removed,
+        (Err(_), Err(e)) if reader_gone.load(std::sync::atomic::Ordering::SeqCst) => return Err(e),
+        (Err(e), _) | (Ok(_), Err(e)) => return Err(e),
+    };
+
+    let locks = src_fs_path.join("locks");
+    if locks.is_dir() {
+        copy_dir_all(&locks, &dst_fs_path.join("locks"))?;
+    }
+    for dir in ["hooks", "conf"] {
+        copy_dir_all(&src_path.join(dir), &dst_path.join(dir))?;
+    }
+
+    let replaced = replaced.into_inner();
+    let affected: std::collections::BTreeSet<_> = removed
+        .iter()
+        .chain(replaced.iter())
+        .map(|(revision, _)| *revision)
+        .collect();
+    Ok(ObliterateReport {
+        removed: removed.into_iter().collect(),
+        replaced: replaced.into_iter().collect(),
+        affected_revisions: affected.into_iter().collect(),
+    })
+}
+
+#[cfg(test)]
+mod additional_tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_fs_pack_invalid_path() {
+        let invalid_path = Path::new("/non/existent/repo");
+
+        // Test fs_pack with invalid repository path (should fail gracefully)
+        let result = fs_pack(invalid_path, None, None);
+
+        // Should return error for invalid repository
+        assert!(result.is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "delta")]
+    fn test_get_commit_editor_basic() {
+        // Create a temporary repository for testing
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo_path = temp_dir.path().join("test_repo");
+
+        // Create repository using the correct function
+        let repo = Repos::create(&repo_path).unwrap();
+
+        // Build the repository URL from the path
+        let repo_url = crate::path_to_file_url(&repo_path);
+
+        // Test get_commit_editor with basic parameters
+        let result = repo.get_commit_editor(
             &repo_url,
             "", // base path (repository root - empty string in fspath format)
             "Test commit message",
@@ -5971,4 +7456,334 @@ admin = rw
         assert_eq!(locks.len(), 1, "Should have exactly one lock");
         assert_eq!(locks[0].path(), "/locked.txt");
     }
+
+    #[test]
+    fn test_split_lines() {
+        assert_eq!(split_lines(""), Vec::<&str>::new());
+        assert_eq!(split_lines("a\nb"), vec!["a\n", "b"]);
+        assert_eq!(split_lines("a\r\nb\rc\n"), vec!["a\r\n", "b\r", "c\n"]);
+    }
+
+    #[test]
+    fn test_blame() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo = Repos::create(&temp_dir.path().join("test_repo")).unwrap();
+        let fs = repo.fs().unwrap();
+
+        let commit = |base: i64, author: &str, contents: &[u8]| {
+            let mut txn = fs.begin_txn(crate::Revnum(base), 0).unwrap();
+            txn.change_prop("svn:author", author).unwrap();
+            let mut root = txn.root().unwrap();
+            if base == 0 {
+                root.make_file("/file.txt").unwrap();
+            }
+            root.set_file_contents("/file.txt", contents).unwrap();
+            txn.commit().unwrap()
+        };
+        let rev1 = commit(0, "alice", b"one\ntwo words\n");
+        let rev2 = commit(1, "bob", b"one\ntwo words\nthree\n");
+        let rev3 = commit(2, "carol", b"one\ntwo    words\nthree\n");
+
+        let lines = blame(&repo, "/file.txt", rev1, rev3, &BlameOptions::new()).unwrap();
+        let summary: Vec<_> = lines
+            .iter()
+            .map(|l| (l.line_no, l.revision, l.author.as_deref(), l.line.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (0, Some(rev1), Some("alice"), "one"),
+                (1, Some(rev3), Some("carol"), "two    words"),
+                (2, Some(rev2), Some("bob"), "three"),
+            ]
+        );
+        assert!(lines.iter().all(|l| l.date.is_some()));
+        assert!(lines.iter().all(|l| l.merged_revision.is_none()));
+
+        // Ignoring whitespace attributes the reformatted line to its author.
+        let options = BlameOptions::new().with_ignore_whitespace(true);
+        let lines = blame(&repo, "/file.txt", rev1, rev3, &options).unwrap();
+        let revisions: Vec<_> = lines.iter().map(|l| l.revision).collect();
+        assert_eq!(revisions, vec![Some(rev1), Some(rev1), Some(rev2)]);
+
+        // Blaming an earlier revision reports that revision's contents.
+        let lines = blame(&repo, "/file.txt", rev1, rev2, &BlameOptions::new()).unwrap();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1].line, "two words");
+        assert_eq!(lines[1].revision, Some(rev1));
+    }
+
+    #[test]
+    fn test_create_with_fs_config() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("test_repo");
+        let fs_config = crate::fs::FsConfig::new()
+            .with_shard_size(16)
+            .with_rep_sharing(false);
+        let repo = Repos::create_with_fs_config(&path, &fs_config).unwrap();
+
+        let on_disk = crate::fs::FsConfig::read(&repo.fs().unwrap().path()).unwrap();
+        assert_eq!(on_disk.shard_size, Some(16));
+        assert_eq!(on_disk.rep_sharing, Some(false));
+    }
+
+    #[test]
+    fn test_backup_manager_rotation() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let repos_path = temp_dir.path().join("project");
+        let repo = Repos::create(&repos_path).unwrap();
+        let fs = repo.fs().unwrap();
+        let commit = |name: &str| {
+            let mut txn = fs.begin_txn(fs.youngest_revision().unwrap(), 0).unwrap();
+            txn.root().unwrap().make_dir(name).unwrap();
+            txn.commit().unwrap();
+        };
+
+        let manager = BackupManager::new(&temp_dir.path().join("backups"), 2);
+        let reported = std::cell::RefCell::new(Vec::new());
+        let report_func = |report: &BackupReport| {
+            reported
+                .borrow_mut()
+                .push(report.result.as_ref().map(|slot| slot.index).ok());
+        };
+        let options = BackupOptions::new().with_report_func(&report_func);
+
+        commit("a");
+        let first = manager.backup(&repos_path, &options);
+        let first = first.result.unwrap();
+        assert_eq!((first.index, first.revision), (0, Revnum(1)));
+
+        commit("b");
+        let second = manager.backup(&repos_path, &options);
+        assert_eq!(second.previous_revision, Some(Revnum(1)));
+        assert!(!second.incremental);
+        let second = second.result.unwrap();
+        assert_eq!((second.index, second.revision), (1, Revnum(2)));
+
+        // With both slots in use, the oldest one is updated incrementally.
+        commit("c");
+        let third = manager
+            .backup_all(&[repos_path.as_path()], &options.with_freeze(true))
+            .pop()
+            .unwrap();
+        assert!(third.incremental);
+        let third = third.result.unwrap();
+        assert_eq!((third.index, third.revision), (0, Revnum(3)));
+        assert_eq!(
+            Repos::open(&third.path).unwrap().youngest_rev().unwrap(),
+            Revnum(3)
+        );
+
+        let backups = manager.backups(&repos_path).unwrap();
+        assert_eq!(
+            backups.iter().map(|b| b.index).collect::<Vec<_>>(),
+            vec![1, 0]
+        );
+        assert_eq!(manager.latest(&repos_path).unwrap(), Some(third));
+        assert_eq!(*reported.borrow(), vec![Some(0), Some(1), Some(0)]);
+
+        // Lowering the retention count prunes the extra slot.
+        let manager = BackupManager::new(&temp_dir.path().join("backups"), 1);
+        let fourth = manager
+            .backup(&repos_path, &BackupOptions::new())
+            .result
+            .unwrap();
+        assert_eq!(fourth.index, 0);
+        assert_eq!(manager.backups(&repos_path).unwrap().len(), 1);
+        assert!(!temp_dir.path().join("backups/project/1").exists());
+
+        // Another repository with the same name does not share the backups.
+        let other_path = temp_dir.path().join("other").join("project");
+        std::fs::create_dir(temp_dir.path().join("other")).unwrap();
+        Repos::create(&other_path).unwrap();
+        assert!(manager
+            .backup(&other_path, &BackupOptions::new())
+            .result
+            .is_err());
+        assert!(manager.backups(&other_path).is_err());
+        assert_eq!(manager.backups(&repos_path).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_load_with_rewrites() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let source = Repos::create(&temp_dir.path().join("source")).unwrap();
+        let fs = source.fs().unwrap();
+        let commit = |author: &str, build: &dyn Fn(&mut crate::fs::TxnRoot)| {
+            let mut txn = fs.begin_txn(fs.youngest_revision().unwrap(), 0).unwrap();
+            txn.change_prop("svn:author", author).unwrap();
+            txn.change_prop("svn:log", "change").unwrap();
+            {
+                let mut root = txn.root().unwrap();
+                build(&mut root);
+            }
+            txn.commit().unwrap();
+        };
+        commit("alice", &|root| {
+            root.make_dir("/trunk").unwrap();
+            root.make_file("/trunk/a.txt").unwrap();
+            root.set_file_contents("/trunk/a.txt", b"hello\n").unwrap();
+            root.change_node_prop("/trunk/a.txt", "svn:keywords", b"Id")
+                .unwrap();
+            root.change_node_prop("/trunk/a.txt", "custom", b"keep")
+                .unwrap();
+            root.make_dir("/secret").unwrap();
+        });
+        commit("bob", &|root| {
+            root.make_file("/secret/key").unwrap();
+            root.set_file_contents("/secret/key", b"hunter2\n").unwrap();
+        });
+
+        let mut dump = Vec::new();
+        {
+            let mut stream = crate::io::wrap_write(&mut dump).unwrap();
+            let mut options = DumpOptions {
+                include_revprops: true,
+                include_changes: true,
+                ..Default::default()
+            };
+            source.dump(&mut stream, &mut options).unwrap();
+        }
+
+        // Give the target one revision of its own so revisions shift.
+        let target = Repos::create(&temp_dir.path().join("target")).unwrap();
+        let target_fs = target.fs().unwrap();
+        let mut txn = target_fs.begin_txn(Revnum(0), 0).unwrap();
+        {
+            let mut root = txn.root().unwrap();
+            root.make_dir("/existing").unwrap();
+        }
+        txn.commit().unwrap();
+
+        let revprop_func = |_rev: Revnum, name: &str, value: &[u8]| match name {
+            "svn:author" if value == b"alice" => Ok(Some(b"alice@example.com".to_vec())),
+            "svn:log" => Ok(Some([&b"[imported] "[..], value].concat())),
+            _ => Ok(Some(value.to_vec())),
+        };
+        let path_func = |path: &str| {
+            Ok(if path == "secret" || path.starts_with("secret/") {
+                None
+            } else {
+                Some(path.replacen("trunk", "project", 1))
+            })
+        };
+        let node_prop_func = |_path: &str, name: &str, value: &[u8]| {
+            Ok((name != "svn:keywords").then(|| value.to_vec()))
+        };
+        let options = LoadOptions::new()
+            .with_revprop_func(&revprop_func)
+            .with_path_func(&path_func)
+            .with_node_prop_func(&node_prop_func);
+        let mut stream = crate::io::Stream::from(dump);
+        let rev_map = target.load(&mut stream, &options).unwrap();
+        assert_eq!(
+            rev_map.into_iter().collect::<Vec<_>>(),
+            vec![(Revnum(1), Revnum(2)), (Revnum(2), Revnum(3))]
+        );
+
+        let revprops = target.rev_proplist(Revnum(2)).unwrap();
+        assert_eq!(revprops["svn:author"], b"alice@example.com");
+        assert_eq!(revprops["svn:log"], b"[imported] change");
+        assert_eq!(
+            target.rev_proplist(Revnum(3)).unwrap()["svn:author"],
+            b"bob"
+        );
+
+        let root = target_fs.revision_root(Revnum(3)).unwrap();
+        let mut contents = Vec::new();
+        std::io::Read::read_to_end(
+            &mut root.file_contents("/project/a.txt").unwrap(),
+            &mut contents,
+        )
+        .unwrap();
+        assert_eq!(contents, b"hello\n");
+        assert_eq!(
+            root.node_prop("/project/a.txt", "custom").unwrap(),
+            Some(b"keep".to_vec())
+        );
+        assert_eq!(
+            root.node_prop("/project/a.txt", "svn:keywords").unwrap(),
+            None
+        );
+        assert_eq!(root.check_path("/secret").unwrap(), crate::NodeKind::None);
+        assert_eq!(root.check_path("/trunk").unwrap(), crate::NodeKind::None);
+    }
+
+    #[test]
+    fn test_obliterate() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let src_path = temp_dir.path().join("source");
+        let source = Repos::create(&src_path).unwrap();
+        let fs = source.fs().unwrap();
+        let commit = |build: &dyn Fn(&mut crate::fs::TxnRoot)| {
+            let mut txn = fs.begin_txn(fs.youngest_revision().unwrap(), 0).unwrap();
+            txn.change_prop("svn:author", "alice").unwrap();
+            {
+                let mut root = txn.root().unwrap();
+                build(&mut root);
+            }
+            txn.commit().unwrap()
+        };
+        let rev1 = commit(&|root| {
+            root.make_dir("/trunk").unwrap();
+            root.make_file("/trunk/a.txt").unwrap();
+            root.set_file_contents("/trunk/a.txt", b"password=hunter2\n")
+                .unwrap();
+            root.make_dir("/secret").unwrap();
+        });
+        let rev2 = commit(&|root| {
+            root.set_file_contents("/trunk/a.txt", b"password=REDACTED\n")
+                .unwrap();
+            root.make_file("/secret/key").unwrap();
+            root.set_file_contents("/secret/key", b"private\n").unwrap();
+        });
+        std::fs::write(src_path.join("hooks").join("pre-commit"), "#!/bin/sh\n").unwrap();
+
+        let dst_path = temp_dir.path().join("rebuilt");
+        let options = ObliterateOptions::new()
+            .with_path("/secret")
+            .with_contents(rev1, "/trunk/a.txt")
+            .with_placeholder(b"obliterated\n");
+        let report = obliterate(&src_path, &dst_path, &options).unwrap();
+        assert_eq!(report.replaced, vec![(rev1, "/trunk/a.txt".to_string())]);
+        assert!(report
+            .removed
+            .iter()
+            .all(|(_, path)| path.trim_start_matches('/').starts_with("secret")));
+        assert_eq!(report.affected_revisions, vec![rev1, rev2]);
+
+        let rebuilt = Repos::open(&dst_path).unwrap();
+        assert_eq!(rebuilt.uuid().unwrap(), source.uuid().unwrap());
+        assert_eq!(rebuilt.youngest_rev().unwrap(), rev2);
+        assert_eq!(rebuilt.rev_proplist(rev2).unwrap()["svn:author"], b"alice");
+        assert!(dst_path.join("hooks").join("pre-commit").exists());
+
+        let rebuilt_fs = rebuilt.fs().unwrap();
+        let read = |rev: Revnum, path: &str| {
+            let mut contents = Vec::new();
+            let root = rebuilt_fs.revision_root(rev).unwrap();
+            std::io::Read::read_to_end(&mut root.file_contents(path).unwrap(), &mut contents)
+                .unwrap();
+            contents
+        };
+        assert_eq!(read(rev1, "/trunk/a.txt"), b"obliterated\n");
+        assert_eq!(read(rev2, "/trunk/a.txt"), b"password=REDACTED\n");
+        let root = rebuilt_fs.revision_root(rev2).unwrap();
+        assert_eq!(root.check_path("/secret").unwrap(), crate::NodeKind::None);
+
+        // The dump stream never touches the disk.
+        let mut entries: Vec<_> = std::fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        entries.sort();
+        assert_eq!(entries, ["rebuilt", "source"]);
+
+        // A failed rebuild removes the partial repository.
+        let failed_path = temp_dir.path().join("failed");
+        let cancel = || -> Result<(), Error<'static>> { Err(Error::from_message("cancelled")) };
+        let options = options.with_cancel_func(&cancel);
+        assert!(obliterate(&src_path, &failed_path, &options).is_err());
+        assert!(!failed_path.exists());
+    }
 }
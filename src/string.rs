@@ -78,6 +78,29 @@ pub type String<'pool> = BStr<'pool>;
 // BStr can be used directly as values in the new APR hash API
 // The Hash<K, V> type uses lifetimes to ensure values outlive the hash
 
+/// Check whether `s` matches any of the glob `patterns`.
+///
+/// Wraps `svn_cstring_match_glob_list`.
+pub fn match_glob_list(s: &str, patterns: &[&str]) -> Result<bool, crate::Error<'static>> {
+    let s_cstr = std::ffi::CString::new(s)?;
+    let pattern_cstrs = patterns
+        .iter()
+        .map(|p| std::ffi::CString::new(*p))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    crate::with_tmp_pool(|pool| {
+        let mut patterns_array =
+            apr::tables::TypedArray::<*const i8>::new(pool, patterns.len() as i32);
+        for pattern_cstr in &pattern_cstrs {
+            patterns_array.push(pattern_cstr.as_ptr());
+        }
+        let matched = unsafe {
+            subversion_sys::svn_cstring_match_glob_list(s_cstr.as_ptr(), patterns_array.as_ptr())
+        };
+        Ok(matched != 0)
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -171,4 +194,12 @@ mod tests {
         assert!(!mut_ptr.is_null());
         assert_eq!(mut_ptr as *const svn_string_t, const_ptr);
     }
+
+    #[test]
+    fn test_match_glob_list() {
+        assert!(match_glob_list("foo.o", &["*.tmp", "*.o"]).unwrap());
+        assert!(match_glob_list("README", &["READ*"]).unwrap());
+        assert!(!match_glob_list("foo.c", &["*.o"]).unwrap());
+        assert!(!match_glob_list("foo.o", &[]).unwrap());
+    }
 }
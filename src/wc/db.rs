@@ -0,0 +1,936 @@
+//! Read-only inspection of working copy databases.
+//!
+//! A working copy keeps all of its metadata in `.svn/wc.db`, an SQLite
+//! database.  [`WcDb`] exposes the rows of its tables as typed values, which
+//! is useful to diagnose broken working copies without the `sqlite3` command
+//! line tool.  The database is opened read-only and no working copy locks are
+//! taken, so it can be inspected while other clients are stuck on it.
+//!
+//! The queries follow the format 31 schema used by Subversion 1.8 and later.
+
+use crate::{svn_result, Error, NodeKind, Revnum};
+use std::marker::PhantomData;
+
+/// A parsed skel, the serialization format used for properties, conflict
+/// descriptions and work queue items in `wc.db`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Skel {
+    /// An atom (a byte string).
+    Atom(Vec<u8>),
+    /// A list of skels.
+    List(Vec<Skel>),
+}
+
+fn is_skel_space(c: u8) -> bool {
+    matches!(c, b' ' | b'\t' | b'\n' | b'\r' | b'\x0c')
+}
+
+impl Skel {
+    /// Parse a skel from `data`.
+    pub fn parse(data: &[u8]) -> Result<Skel, Error<'static>> {
+        let mut pos = 0;
+        let skel = Self::parse_at(data, &mut pos)?;
+        if data[pos..].iter().any(|c| !is_skel_space(*c)) {
+            return Err(Error::from_message("Trailing data after skel"));
+        }
+        Ok(skel)
+    }
+
+    fn parse_at(data: &[u8], pos: &mut usize) -> Result<Skel, Error<'static>> {
+        let malformed = || Error::from_message("Malformed skel");
+        while data.get(*pos).is_some_and(|c| is_skel_space(*c)) {
+            *pos += 1;
+        }
+        match data.get(*pos).copied().ok_or_else(malformed)? {
+            b'(' => {
+                *pos += 1;
+                let mut items = Vec::new();
+                loop {
+                    while data.get(*pos).is_some_and(|c| is_skel_space(*c)) {
+                        *pos += 1;
+                    }
+                    match data.get(*pos) {
+                        Some(b')') => {
+                            *pos += 1;
+                            return Ok(Skel::List(items));
+                        }
+                        Some(_) => items.push(Self::parse_at(data, pos)?),
+                        None => return Err(malformed()),
+                    }
+                }
+            }
+            c if c.is_ascii_digit() => {
+                let start = *pos;
+                while data.get(*pos).is_some_and(|c| c.is_ascii_digit()) {
+                    *pos += 1;
+                }
+                let len: usize = std::str::from_utf8(&data[start..*pos])?
+                    .parse()
+                    .map_err(|_| malformed())?;
+                // Exactly one whitespace character separates the length
+                // from the data.
+                if !data.get(*pos).is_some_and(|c| is_skel_space(*c)) {
+                    return Err(malformed());
+                }
+                *pos += 1;
+                let atom = data.get(*pos..*pos + len).ok_or_else(malformed)?;
+                *pos += len;
+                Ok(Skel::Atom(atom.to_vec()))
+            }
+            c if c.is_ascii_alphabetic() => {
+                let start = *pos;
+                while data
+                    .get(*pos)
+                    .is_some_and(|c| !is_skel_space(*c) && *c != b'(' && *c != b')')
+                {
+                    *pos += 1;
+                }
+                Ok(Skel::Atom(data[start..*pos].to_vec()))
+            }
+            _ => Err(malformed()),
+        }
+    }
+
+    /// Serialize the skel, using the implicit-length form for short atoms
+    /// where possible.
+    pub fn unparse(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.unparse_into(&mut out);
+        out
+    }
+
+    fn unparse_into(&self, out: &mut Vec<u8>) {
+        match self {
+            Skel::Atom(atom) => {
+                let implicit = atom.len() < 100
+                    && atom.first().is_some_and(|c| c.is_ascii_alphabetic())
+                    && atom
+                        .iter()
+                        .all(|c| !is_skel_space(*c) && *c != b'(' && *c != b')');
+                if !implicit {
+                    out.extend_from_slice(format!("{} ", atom.len()).as_bytes());
+                }
+                out.extend_from_slice(atom);
+            }
+            Skel::List(items) => {
+                out.push(b'(');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.push(b' ');
+                    }
+                    item.unparse_into(out);
+                }
+                out.push(b')');
+            }
+        }
+    }
+
+    /// The atom's bytes, if this is an atom.
+    pub fn as_atom(&self) -> Option<&[u8]> {
+        match self {
+            Skel::Atom(atom) => Some(atom),
+            Skel::List(_) => None,
+        }
+    }
+
+    /// The list items, if this is a list.
+    pub fn as_list(&self) -> Option<&[Skel]> {
+        match self {
+            Skel::Atom(_) => None,
+            Skel::List(items) => Some(items),
+        }
+    }
+}
+
+impl std::fmt::Display for Skel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&String::from_utf8_lossy(&self.unparse()))
+    }
+}
+
+/// Parse a property skel, a list of alternating names and values.
+pub fn parse_props(data: &[u8]) -> Result<Vec<(String, Vec<u8>)>, Error<'static>> {
+    let skel = Skel::parse(data)?;
+    let malformed = || Error::from_message("Malformed property skel");
+    let items = skel.as_list().ok_or_else(malformed)?;
+    if items.len() % 2 != 0 {
+        return Err(malformed());
+    }
+    items
+        .chunks(2)
+        .map(|pair| {
+            let name = pair[0].as_atom().ok_or_else(malformed)?;
+            let value = pair[1].as_atom().ok_or_else(malformed)?;
+            Ok((String::from_utf8_lossy(name).into_owned(), value.to_vec()))
+        })
+        .collect()
+}
+
+/// Presence of a node in the `NODES` table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Presence {
+    /// The node is present.
+    Normal,
+    /// The node was excluded by the server (e.g. because of authz).
+    ServerExcluded,
+    /// The node was excluded by the user (`svn update --set-depth exclude`).
+    Excluded,
+    /// The node is not present at this revision; a placeholder.
+    NotPresent,
+    /// The node is known but its contents have not been fetched.
+    Incomplete,
+    /// The node is deleted in a WORKING layer.
+    BaseDeleted,
+    /// A value not defined by the schema.
+    Other(String),
+}
+
+impl From<&str> for Presence {
+    fn from(s: &str) -> Self {
+        match s {
+            "normal" => Presence::Normal,
+            "server-excluded" => Presence::ServerExcluded,
+            "excluded" => Presence::Excluded,
+            "not-present" => Presence::NotPresent,
+            "incomplete" => Presence::Incomplete,
+            "base-deleted" => Presence::BaseDeleted,
+            other => Presence::Other(other.to_string()),
+        }
+    }
+}
+
+fn parse_kind(kind: Option<String>) -> NodeKind {
+    match kind.as_deref() {
+        Some("file") => NodeKind::File,
+        Some("dir") => NodeKind::Dir,
+        Some("symlink") => NodeKind::Symlink,
+        None => NodeKind::None,
+        Some(_) => NodeKind::Unknown,
+    }
+}
+
+/// A row of the `WCROOT` table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WcRootRow {
+    /// Id of the working copy root.
+    pub id: i64,
+    /// Absolute path of the root, or `None` for the root containing the
+    /// database.
+    pub local_abspath: Option<String>,
+}
+
+/// A row of the `REPOSITORY` table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepositoryRow {
+    /// Id referenced by node rows.
+    pub id: i64,
+    /// Repository root URL.
+    pub root: String,
+    /// Repository UUID.
+    pub uuid: String,
+}
+
+/// A row of the `NODES` table: one layer of a node.
+///
+/// Op-depth 0 is the BASE layer, as last updated from the repository.
+/// Higher op-depths are WORKING layers created by local adds, copies,
+/// moves and deletes; the layer with the highest op-depth is the one in
+/// effect.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeRow {
+    /// Id of the working copy root.
+    pub wc_id: i64,
+    /// Path relative to the working copy root; `""` is the root itself.
+    pub local_relpath: String,
+    /// Depth of the operation that created this layer.
+    pub op_depth: i64,
+    /// Relative path of the parent, `None` for the root.
+    pub parent_relpath: Option<String>,
+    /// Repository the node comes from.
+    pub repos_id: Option<i64>,
+    /// Path of the node in the repository.
+    pub repos_path: Option<String>,
+    /// Revision of the node in the repository.
+    pub revision: Option<Revnum>,
+    /// Presence of the node in this layer.
+    pub presence: Presence,
+    /// Whether the node is the target of a move.
+    pub moved_here: bool,
+    /// Where the node was moved to, if it was moved away.
+    pub moved_to: Option<String>,
+    /// Kind of the node.
+    pub kind: NodeKind,
+    /// Pristine properties, as a property skel (see [`parse_props`]).
+    pub properties: Option<Vec<u8>>,
+    /// Depth of a directory.
+    pub depth: Option<crate::Depth>,
+    /// Checksum of the pristine text of a file.
+    pub checksum: Option<String>,
+    /// Target of a symbolic link.
+    pub symlink_target: Option<String>,
+    /// Last changed revision.
+    pub changed_revision: Option<Revnum>,
+    /// Last changed date.
+    pub changed_date: Option<apr::time::Time>,
+    /// Last changed author.
+    pub changed_author: Option<String>,
+    /// Size of the working file after translation, if recorded.
+    pub translated_size: Option<i64>,
+    /// Modification time of the working file when last checked.
+    pub last_mod_time: Option<apr::time::Time>,
+    /// Whether this is a file external.
+    pub file_external: bool,
+    /// Cached inherited properties, as a skel.
+    pub inherited_props: Option<Vec<u8>>,
+}
+
+impl NodeRow {
+    /// Whether this row is the BASE layer.
+    pub fn is_base(&self) -> bool {
+        self.op_depth == 0
+    }
+}
+
+/// A row of the `ACTUAL_NODE` table: local property changes, changelists
+/// and conflicts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActualNodeRow {
+    /// Id of the working copy root.
+    pub wc_id: i64,
+    /// Path relative to the working copy root.
+    pub local_relpath: String,
+    /// Relative path of the parent.
+    pub parent_relpath: Option<String>,
+    /// Actual properties, as a property skel, if they differ from the
+    /// pristine ones.
+    pub properties: Option<Vec<u8>>,
+    /// Changelist the node belongs to.
+    pub changelist: Option<String>,
+    /// Conflict description skel.
+    pub conflict_data: Option<Vec<u8>>,
+    /// Pre-1.8 text conflict marker files: old, new and working.
+    pub conflict_old: Option<String>,
+    /// See [`ActualNodeRow::conflict_old`].
+    pub conflict_new: Option<String>,
+    /// See [`ActualNodeRow::conflict_old`].
+    pub conflict_working: Option<String>,
+    /// Pre-1.8 property reject file.
+    pub prop_reject: Option<String>,
+    /// Pre-1.8 tree conflict data.
+    pub tree_conflict_data: Option<String>,
+}
+
+/// A row of the `WORK_QUEUE` table: an operation that still has to be run
+/// by `svn cleanup`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkQueueRow {
+    /// Id of the item; items run in ascending order.
+    pub id: i64,
+    /// The work item skel.
+    pub work: Vec<u8>,
+}
+
+impl WorkQueueRow {
+    /// Parse the work item.
+    pub fn parse(&self) -> Result<Skel, Error<'static>> {
+        Skel::parse(&self.work)
+    }
+}
+
+/// A row of the `WC_LOCK` table: a working copy write lock.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WcLockRow {
+    /// Id of the working copy root.
+    pub wc_id: i64,
+    /// Locked directory, relative to the working copy root.
+    pub local_dir_relpath: String,
+    /// Number of levels locked below the directory; -1 for infinity.
+    pub locked_levels: i64,
+}
+
+/// A row of the `LOCK` table: a repository lock held by this working copy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockRow {
+    /// Repository the lock belongs to.
+    pub repos_id: i64,
+    /// Locked path in the repository.
+    pub repos_relpath: String,
+    /// Lock token.
+    pub lock_token: String,
+    /// Lock owner.
+    pub lock_owner: Option<String>,
+    /// Lock comment.
+    pub lock_comment: Option<String>,
+    /// When the lock was created.
+    pub lock_date: Option<apr::time::Time>,
+}
+
+/// A row of the `EXTERNALS` table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExternalRow {
+    /// Id of the working copy root.
+    pub wc_id: i64,
+    /// Path of the external, relative to the working copy root.
+    pub local_relpath: String,
+    /// Relative path of the parent.
+    pub parent_relpath: String,
+    /// Repository of the external.
+    pub repos_id: i64,
+    /// Presence of the external.
+    pub presence: Presence,
+    /// Kind of the external.
+    pub kind: NodeKind,
+    /// Directory whose `svn:externals` property defines the external.
+    pub def_local_relpath: String,
+    /// Path of the external in its repository.
+    pub def_repos_relpath: String,
+    /// Operative revision of the definition.
+    pub def_operational_revision: Option<Revnum>,
+    /// Peg revision of the definition.
+    pub def_revision: Option<Revnum>,
+}
+
+/// A row of the `PRISTINE` table, with the number of nodes that actually
+/// refer to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PristineRow {
+    /// SHA-1 checksum, as stored in node rows (`$sha1$...`).
+    pub checksum: String,
+    /// MD5 checksum.
+    pub md5_checksum: String,
+    /// Size of the pristine text.
+    pub size: i64,
+    /// Reference count as recorded in the table.
+    pub refcount: i64,
+    /// Number of `NODES` and `ACTUAL_NODE` rows referring to the text.  A
+    /// mismatch with [`PristineRow::refcount`] indicates corruption.
+    pub references: i64,
+}
+
+/// A working copy database, opened read-only.
+pub struct WcDb {
+    ptr: *mut subversion_sys::svn_sqlite__db_t,
+    path: std::path::PathBuf,
+    // The database is closed by a cleanup handler on this pool.
+    _pool: apr::Pool<'static>,
+}
+
+struct Statement<'db> {
+    ptr: *mut subversion_sys::svn_sqlite__stmt_t,
+    _db: PhantomData<&'db WcDb>,
+}
+
+impl Drop for Statement<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            let err = subversion_sys::svn_sqlite__finalize(self.ptr);
+            if !err.is_null() {
+                subversion_sys::svn_error_clear(err);
+            }
+        }
+    }
+}
+
+impl Statement<'_> {
+    fn step(&mut self) -> Result<bool, Error<'static>> {
+        let mut got_row = 0;
+        svn_result(unsafe { subversion_sys::svn_sqlite__step(&mut got_row, self.ptr) })?;
+        Ok(got_row != 0)
+    }
+
+    fn is_null(&self, column: i32) -> bool {
+        unsafe { subversion_sys::svn_sqlite__column_is_null(self.ptr, column) != 0 }
+    }
+
+    fn int(&self, column: i32) -> Option<i64> {
+        if self.is_null(column) {
+            None
+        } else {
+            Some(unsafe { subversion_sys::svn_sqlite__column_int64(self.ptr, column) })
+        }
+    }
+
+    fn bool(&self, column: i32) -> bool {
+        self.int(column).is_some_and(|v| v != 0)
+    }
+
+    fn revnum(&self, column: i32) -> Option<Revnum> {
+        self.int(column).and_then(|v| Revnum::from_raw(v as _))
+    }
+
+    fn time(&self, column: i32) -> Option<apr::time::Time> {
+        self.int(column)
+            .filter(|v| *v != 0)
+            .map(apr::time::Time::from_micros)
+    }
+
+    fn text(&self, column: i32) -> Option<String> {
+        if self.is_null(column) {
+            return None;
+        }
+        // Without a result pool the text lives until the next step.
+        let ptr = unsafe {
+            subversion_sys::svn_sqlite__column_text(self.ptr, column, std::ptr::null_mut())
+        };
+        if ptr.is_null() {
+            None
+        } else {
+            Some(
+                unsafe { std::ffi::CStr::from_ptr(ptr) }
+                    .to_string_lossy()
+                    .into_owned(),
+            )
+        }
+    }
+
+    fn blob(&self, column: i32) -> Option<Vec<u8>> {
+        if self.is_null(column) {
+            return None;
+        }
+        let mut len = 0;
+        let ptr = unsafe {
+            subversion_sys::svn_sqlite__column_blob(
+                self.ptr,
+                column,
+                &mut len,
+                std::ptr::null_mut(),
+            )
+        };
+        if ptr.is_null() {
+            Some(Vec::new())
+        } else {
+            Some(unsafe { std::slice::from_raw_parts(ptr as *const u8, len) }.to_vec())
+        }
+    }
+}
+
+const NODE_COLUMNS: &str = "wc_id, local_relpath, op_depth, parent_relpath, repos_id, \
+     repos_path, revision, presence, moved_here, moved_to, kind, properties, depth, checksum, \
+     symlink_target, changed_revision, changed_date, changed_author, translated_size, \
+     last_mod_time, file_external, inherited_props";
+
+fn node_row(stmt: &Statement) -> NodeRow {
+    NodeRow {
+        wc_id: stmt.int(0).unwrap_or_default(),
+        local_relpath: stmt.text(1).unwrap_or_default(),
+        op_depth: stmt.int(2).unwrap_or_default(),
+        parent_relpath: stmt.text(3),
+        repos_id: stmt.int(4),
+        repos_path: stmt.text(5),
+        revision: stmt.revnum(6),
+        presence: Presence::from(stmt.text(7).unwrap_or_default().as_str()),
+        moved_here: stmt.bool(8),
+        moved_to: stmt.text(9),
+        kind: parse_kind(stmt.text(10)),
+        properties: stmt.blob(11),
+        depth: stmt.text(12).and_then(|depth| depth.parse().ok()),
+        checksum: stmt.text(13),
+        symlink_target: stmt.text(14),
+        changed_revision: stmt.revnum(15),
+        changed_date: stmt.time(16),
+        changed_author: stmt.text(17),
+        translated_size: stmt.int(18),
+        last_mod_time: stmt.time(19),
+        file_external: stmt.bool(20),
+        inherited_props: stmt.blob(21),
+    }
+}
+
+const ACTUAL_NODE_COLUMNS: &str = "wc_id, local_relpath, parent_relpath, properties, \
+     changelist, conflict_data, conflict_old, conflict_new, conflict_working, prop_reject, \
+     tree_conflict_data";
+
+fn actual_node_row(stmt: &Statement) -> ActualNodeRow {
+    ActualNodeRow {
+        wc_id: stmt.int(0).unwrap_or_default(),
+        local_relpath: stmt.text(1).unwrap_or_default(),
+        parent_relpath: stmt.text(2),
+        properties: stmt.blob(3),
+        changelist: stmt.text(4),
+        conflict_data: stmt.blob(5),
+        conflict_old: stmt.text(6),
+        conflict_new: stmt.text(7),
+        conflict_working: stmt.text(8),
+        prop_reject: stmt.text(9),
+        tree_conflict_data: stmt.text(10),
+    }
+}
+
+impl WcDb {
+    /// Open the database of the working copy rooted at `wc_root`, or the
+    /// database file itself if `wc_root` names a file.
+    pub fn open(wc_root: &std::path::Path) -> Result<Self, Error<'static>> {
+        crate::init::initialize()?;
+        let path = if wc_root.is_file() {
+            wc_root.to_path_buf()
+        } else {
+            wc_root.join(".svn").join("wc.db")
+        };
+        if !path.is_file() {
+            return Err(Error::from_message(&format!(
+                "'{}' is not a working copy database",
+                path.display()
+            )));
+        }
+        let path_c = std::ffi::CString::new(
+            path.to_str()
+                .ok_or_else(|| Error::from_message("Invalid path"))?,
+        )?;
+        let pool = apr::Pool::new();
+        let statements = [std::ptr::null::<std::ffi::c_char>()];
+        let mut ptr = std::ptr::null_mut();
+        crate::with_tmp_pool(|scratch_pool| unsafe {
+            svn_result(subversion_sys::svn_sqlite__open(
+                &mut ptr,
+                path_c.as_ptr(),
+                subversion_sys::svn_sqlite__mode_readonly,
+                statements.as_ptr(),
+                0,
+                std::ptr::null(),
+                0,
+                pool.as_mut_ptr(),
+                scratch_pool.as_mut_ptr(),
+            ))
+        })?;
+        Ok(Self {
+            ptr,
+            path,
+            _pool: pool,
+        })
+    }
+
+    /// Path of the database file.
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+
+    fn query<T>(
+        &self,
+        sql: &str,
+        row: impl FnMut(&Statement) -> T,
+    ) -> Result<Vec<T>, Error<'static>> {
+        self.query_with(sql, &[], row)
+    }
+
+    /// Run `sql` with `params` bound to its `?1`, `?2`, ... parameters.
+    fn query_with<T>(
+        &self,
+        sql: &str,
+        params: &[&str],
+        mut row: impl FnMut(&Statement) -> T,
+    ) -> Result<Vec<T>, Error<'static>> {
+        let sql = std::ffi::CString::new(sql)?;
+        let params = params
+            .iter()
+            .map(|param| std::ffi::CString::new(*param))
+            .collect::<Result<Vec<_>, _>>()?;
+        // Dropped after the statement has been finalized.
+        let pool = apr::Pool::new();
+        let mut ptr = std::ptr::null_mut();
+        svn_result(unsafe {
+            subversion_sys::svn_sqlite__prepare(&mut ptr, self.ptr, sql.as_ptr(), pool.as_mut_ptr())
+        })?;
+        let mut stmt = Statement {
+            ptr,
+            _db: PhantomData,
+        };
+        for (slot, param) in params.iter().enumerate() {
+            svn_result(unsafe {
+                subversion_sys::svn_sqlite__bind_text(stmt.ptr, slot as i32 + 1, param.as_ptr())
+            })?;
+        }
+        let mut rows = Vec::new();
+        while stmt.step()? {
+            rows.push(row(&stmt));
+        }
+        Ok(rows)
+    }
+
+    /// The schema format of the database (`PRAGMA user_version`).
+    pub fn format(&self) -> Result<i64, Error<'static>> {
+        Ok(self
+            .query("PRAGMA user_version", |stmt| stmt.int(0))?
+            .into_iter()
+            .next()
+            .flatten()
+            .unwrap_or_default())
+    }
+
+    /// Rows of the `WCROOT` table.
+    pub fn wcroots(&self) -> Result<Vec<WcRootRow>, Error<'static>> {
+        self.query("SELECT id, local_abspath FROM wcroot ORDER BY id", |stmt| {
+            WcRootRow {
+                id: stmt.int(0).unwrap_or_default(),
+                local_abspath: stmt.text(1),
+            }
+        })
+    }
+
+    /// Rows of the `REPOSITORY` table.
+    pub fn repositories(&self) -> Result<Vec<RepositoryRow>, Error<'static>> {
+        self.query(
+            "SELECT id, root, uuid FROM repository ORDER BY id",
+            |stmt| RepositoryRow {
+                id: stmt.int(0).unwrap_or_default(),
+                root: stmt.text(1).unwrap_or_default(),
+                uuid: stmt.text(2).unwrap_or_default(),
+            },
+        )
+    }
+
+    /// All node layers, ordered by path and op-depth.
+    pub fn nodes(&self) -> Result<Vec<NodeRow>, Error<'static>> {
+        self.query(
+            &format!(
+                "SELECT {} FROM nodes ORDER BY local_relpath, op_depth",
+                NODE_COLUMNS
+            ),
+            node_row,
+        )
+    }
+
+    /// The layers of the node at `local_relpath`, lowest op-depth first.
+    pub fn node_layers(&self, local_relpath: &str) -> Result<Vec<NodeRow>, Error<'static>> {
+        self.query_with(
+            &format!(
+                "SELECT {} FROM nodes WHERE local_relpath = ?1 ORDER BY op_depth",
+                NODE_COLUMNS
+            ),
+            &[local_relpath],
+            node_row,
+        )
+    }
+
+    /// The BASE layer of the node at `local_relpath`.
+    pub fn base_node(&self, local_relpath: &str) -> Result<Option<NodeRow>, Error<'static>> {
+        Ok(self
+            .node_layers(local_relpath)?
+            .into_iter()
+            .find(NodeRow::is_base))
+    }
+
+    /// The layer in effect for the node at `local_relpath`, i.e. the one
+    /// with the highest op-depth.
+    pub fn working_node(&self, local_relpath: &str) -> Result<Option<NodeRow>, Error<'static>> {
+        Ok(self.node_layers(local_relpath)?.pop())
+    }
+
+    /// All rows of the `ACTUAL_NODE` table, ordered by path.
+    pub fn actual_nodes(&self) -> Result<Vec<ActualNodeRow>, Error<'static>> {
+        self.query(
+            &format!(
+                "SELECT {} FROM actual_node ORDER BY local_relpath",
+                ACTUAL_NODE_COLUMNS
+            ),
+            actual_node_row,
+        )
+    }
+
+    /// The `ACTUAL_NODE` row of `local_relpath`.
+    pub fn actual_node(
+        &self,
+        local_relpath: &str,
+    ) -> Result<Option<ActualNodeRow>, Error<'static>> {
+        Ok(self
+            .query_with(
+                &format!(
+                    "SELECT {} FROM actual_node WHERE local_relpath = ?1",
+                    ACTUAL_NODE_COLUMNS
+                ),
+                &[local_relpath],
+                actual_node_row,
+            )?
+            .pop())
+    }
+
+    /// Pending work queue items, in execution order.
+    pub fn work_queue(&self) -> Result<Vec<WorkQueueRow>, Error<'static>> {
+        self.query("SELECT id, work FROM work_queue ORDER BY id", |stmt| {
+            WorkQueueRow {
+                id: stmt.int(0).unwrap_or_default(),
+                work: stmt.blob(1).unwrap_or_default(),
+            }
+        })
+    }
+
+    /// Working copy write locks.
+    pub fn wc_locks(&self) -> Result<Vec<WcLockRow>, Error<'static>> {
+        self.query(
+            "SELECT wc_id, local_dir_relpath, locked_levels FROM wc_lock \
+             ORDER BY local_dir_relpath",
+            |stmt| WcLockRow {
+                wc_id: stmt.int(0).unwrap_or_default(),
+                local_dir_relpath: stmt.text(1).unwrap_or_default(),
+                locked_levels: stmt.int(2).unwrap_or_default(),
+            },
+        )
+    }
+
+    /// Repository locks known to the working copy.
+    pub fn locks(&self) -> Result<Vec<LockRow>, Error<'static>> {
+        self.query(
+            "SELECT repos_id, repos_relpath, lock_token, lock_owner, lock_comment, lock_date \
+             FROM lock ORDER BY repos_id, repos_relpath",
+            |stmt| LockRow {
+                repos_id: stmt.int(0).unwrap_or_default(),
+                repos_relpath: stmt.text(1).unwrap_or_default(),
+                lock_token: stmt.text(2).unwrap_or_default(),
+                lock_owner: stmt.text(3),
+                lock_comment: stmt.text(4),
+                lock_date: stmt.time(5),
+            },
+        )
+    }
+
+    /// Rows of the `EXTERNALS` table.
+    pub fn externals(&self) -> Result<Vec<ExternalRow>, Error<'static>> {
+        self.query(
+            "SELECT wc_id, local_relpath, parent_relpath, repos_id, presence, kind, \
+             def_local_relpath, def_repos_relpath, def_operational_revision, def_revision \
+             FROM externals ORDER BY local_relpath",
+            |stmt| ExternalRow {
+                wc_id: stmt.int(0).unwrap_or_default(),
+                local_relpath: stmt.text(1).unwrap_or_default(),
+                parent_relpath: stmt.text(2).unwrap_or_default(),
+                repos_id: stmt.int(3).unwrap_or_default(),
+                presence: Presence::from(stmt.text(4).unwrap_or_default().as_str()),
+                kind: parse_kind(stmt.text(5)),
+                def_local_relpath: stmt.text(6).unwrap_or_default(),
+                def_repos_relpath: stmt.text(7).unwrap_or_default(),
+                def_operational_revision: stmt.revnum(8),
+                def_revision: stmt.revnum(9),
+            },
+        )
+    }
+
+    /// Rows of the `PRISTINE` table, with the number of references to each
+    /// text counted from the node tables.
+    pub fn pristines(&self) -> Result<Vec<PristineRow>, Error<'static>> {
+        self.query(
+            "SELECT checksum, md5_checksum, size, refcount, \
+             (SELECT COUNT(*) FROM nodes WHERE nodes.checksum = pristine.checksum) + \
+             (SELECT COUNT(*) FROM actual_node \
+              WHERE actual_node.older_checksum = pristine.checksum \
+                 OR actual_node.left_checksum = pristine.checksum \
+                 OR actual_node.right_checksum = pristine.checksum) \
+             FROM pristine ORDER BY checksum",
+            |stmt| PristineRow {
+                checksum: stmt.text(0).unwrap_or_default(),
+                md5_checksum: stmt.text(1).unwrap_or_default(),
+                size: stmt.int(2).unwrap_or_default(),
+                refcount: stmt.int(3).unwrap_or_default(),
+                references: stmt.int(4).unwrap_or_default(),
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skel_round_trip() {
+        let data = b"(svn:eol-style 6 native 12 svn:keywords 5 Id Rev)";
+        let skel = Skel::parse(data).unwrap();
+        assert_eq!(
+            skel,
+            Skel::List(vec![
+                Skel::Atom(b"svn:eol-style".to_vec()),
+                Skel::Atom(b"native".to_vec()),
+                Skel::Atom(b"svn:keywords".to_vec()),
+                Skel::Atom(b"Id Rev".to_vec()),
+            ])
+        );
+        assert_eq!(
+            skel.to_string(),
+            "(svn:eol-style native svn:keywords 6 Id Rev)"
+        );
+        assert_eq!(Skel::parse(&skel.unparse()).unwrap(), skel);
+
+        assert_eq!(
+            parse_props(data).unwrap(),
+            vec![
+                ("svn:eol-style".to_string(), b"native".to_vec()),
+                ("svn:keywords".to_string(), b"Id Rev".to_vec()),
+            ]
+        );
+        assert!(parse_props(b"(a)").is_err());
+        assert!(Skel::parse(b"(5 abc)").is_err());
+        assert!(Skel::parse(b"(a b").is_err());
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn test_inspect_checkout() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let repos_path = temp.path().join("repos");
+        let wc_path = temp.path().join("wc");
+        let repos = crate::repos::Repos::create(&repos_path).unwrap();
+        let fs = repos.fs().unwrap();
+        let mut txn = fs.begin_txn(Revnum(0), 0).unwrap();
+        {
+            let mut root = txn.root().unwrap();
+            root.make_file("/a.txt").unwrap();
+            root.set_file_contents("/a.txt", b"hello\n").unwrap();
+            root.change_node_prop("/a.txt", "svn:eol-style", b"native")
+                .unwrap();
+        }
+        txn.commit().unwrap();
+
+        let url = crate::path_to_file_url(&repos_path);
+        let mut ctx = crate::client::Context::new().unwrap();
+        ctx.checkout(
+            crate::uri::Uri::new(&url).unwrap(),
+            &wc_path,
+            &crate::client::CheckoutOptions {
+                peg_revision: crate::Revision::Head,
+                revision: crate::Revision::Head,
+                depth: crate::Depth::Infinity,
+                ignore_externals: false,
+                allow_unver_obstructions: false,
+            },
+        )
+        .unwrap();
+
+        let db = WcDb::open(&wc_path).unwrap();
+        assert!(db.format().unwrap() >= 31);
+        let repositories = db.repositories().unwrap();
+        assert_eq!(repositories.len(), 1);
+        assert_eq!(repositories[0].uuid, repos.uuid().unwrap());
+
+        let root = db.base_node("").unwrap().unwrap();
+        assert_eq!(root.kind, NodeKind::Dir);
+        assert_eq!(root.presence, Presence::Normal);
+        assert_eq!(root.depth, Some(crate::Depth::Infinity));
+
+        let layers = db.node_layers("a.txt").unwrap();
+        assert_eq!(layers.len(), 1);
+        let file = &layers[0];
+        assert!(file.is_base());
+        assert_eq!(file.kind, NodeKind::File);
+        assert_eq!(file.revision, Some(Revnum(1)));
+        assert_eq!(file.parent_relpath.as_deref(), Some(""));
+        assert_eq!(
+            parse_props(file.properties.as_ref().unwrap()).unwrap(),
+            vec![("svn:eol-style".to_string(), b"native".to_vec())]
+        );
+        // Paths are bound as parameters, not spliced into the SQL.
+        assert!(db.node_layers("a.txt' OR '1'='1").unwrap().is_empty());
+        assert!(db.actual_node("it's").unwrap().is_none());
+
+        let pristines = db.pristines().unwrap();
+        assert_eq!(pristines.len(), 1);
+        assert_eq!(Some(&pristines[0].checksum), file.checksum.as_ref());
+        assert_eq!(pristines[0].size, 6);
+        assert_eq!(pristines[0].references, 1);
+
+        assert!(db.work_queue().unwrap().is_empty());
+        assert!(db.wc_locks().unwrap().is_empty());
+        assert!(db.externals().unwrap().is_empty());
+        assert!(db.actual_nodes().unwrap().is_empty());
+        assert!(db.actual_node("a.txt").unwrap().is_none());
+
+        assert!(WcDb::open(temp.path()).is_err());
+    }
+}
@@ -0,0 +1,680 @@
+//! Editable model of `svn:externals` property values.
+//!
+//! [`parse_externals_description`](super::parse_externals_description) hands
+//! the property to libsvn_wc and returns the resolved items, which loses
+//! comments, ordering details and the syntax that was used.  The
+//! [`ExternalsDescription`] type instead keeps every line of the property,
+//! so definitions can be edited and written back with only the touched lines
+//! changing.
+//!
+//! Both the pre-1.5 syntax (`dir [-r N] URL`) and the current syntax
+//! (`[-r N] URL[@PEG] dir`) are understood, as are the relative URL forms
+//! `^/`, `//`, `/` and `../`.
+
+use crate::{Error, Revnum};
+use std::fmt;
+
+/// The revision a definition refers to, as written in the property.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExternalRevision {
+    /// A revision number.
+    Number(Revnum),
+    /// A date, without the surrounding braces.
+    Date(String),
+    /// The `HEAD` keyword.
+    Head,
+}
+
+impl ExternalRevision {
+    fn parse(s: &str) -> Option<ExternalRevision> {
+        if let Some(date) = s.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+            if date.is_empty() {
+                return None;
+            }
+            return Some(ExternalRevision::Date(date.to_string()));
+        }
+        if s.eq_ignore_ascii_case("HEAD") {
+            return Some(ExternalRevision::Head);
+        }
+        if s.is_empty() || !s.bytes().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        s.parse().ok().map(|n| ExternalRevision::Number(Revnum(n)))
+    }
+}
+
+impl fmt::Display for ExternalRevision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExternalRevision::Number(n) => write!(f, "{}", n.0),
+            ExternalRevision::Date(d) => write!(f, "{{{}}}", d),
+            ExternalRevision::Head => f.write_str("HEAD"),
+        }
+    }
+}
+
+impl From<Revnum> for ExternalRevision {
+    fn from(rev: Revnum) -> Self {
+        ExternalRevision::Number(rev)
+    }
+}
+
+/// Which syntax a definition was written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExternalSyntax {
+    /// The pre-1.5 syntax: `dir [-r N] URL`.
+    Old,
+    /// The 1.5+ syntax: `[-r N] URL[@PEG] dir`.
+    New,
+}
+
+/// How the URL of a definition is anchored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExternalUrlKind {
+    /// An absolute URL such as `https://host/repos/trunk`.
+    Absolute,
+    /// Relative to the repository root (`^/`).
+    RepositoryRelative,
+    /// Relative to the scheme of the parent directory URL (`//`).
+    SchemeRelative,
+    /// Relative to the server root of the parent directory URL (`/`).
+    ServerRelative,
+    /// Relative to the URL of the directory the property is set on (`../`).
+    DirectoryRelative,
+}
+
+fn is_absolute_url(s: &str) -> bool {
+    match s.find("://") {
+        Some(i) if i > 0 => s[..i]
+            .bytes()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, b'+' | b'-' | b'.')),
+        _ => false,
+    }
+}
+
+fn url_kind(url: &str) -> Option<ExternalUrlKind> {
+    if is_absolute_url(url) {
+        Some(ExternalUrlKind::Absolute)
+    } else if url.starts_with("^/") {
+        Some(ExternalUrlKind::RepositoryRelative)
+    } else if url.starts_with("//") {
+        Some(ExternalUrlKind::SchemeRelative)
+    } else if url.starts_with('/') {
+        Some(ExternalUrlKind::ServerRelative)
+    } else if url.starts_with("../") {
+        Some(ExternalUrlKind::DirectoryRelative)
+    } else {
+        None
+    }
+}
+
+/// Split an absolute URL into `scheme://authority` and its path.
+fn split_url(url: &str) -> Result<(&str, &str), Error<'static>> {
+    let start = url
+        .find("://")
+        .filter(|_| is_absolute_url(url))
+        .ok_or_else(|| Error::from_message(&format!("'{}' is not an absolute URL", url)))?
+        + 3;
+    match url[start..].find('/') {
+        Some(i) => Ok(url.split_at(start + i)),
+        None => Ok((url, "")),
+    }
+}
+
+/// Append the `/`-separated `relative` path to `base`, resolving `.` and
+/// `..` segments.
+fn join_url(base: &str, relative: &str) -> Result<String, Error<'static>> {
+    let (prefix, path) = split_url(base)?;
+    let mut segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    for segment in relative.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                if segments.pop().is_none() {
+                    return Err(Error::from_message(&format!(
+                        "Relative URL '{}' points above the root of '{}'",
+                        relative, base
+                    )));
+                }
+            }
+            s => segments.push(s),
+        }
+    }
+    let mut url = prefix.to_string();
+    for segment in segments {
+        url.push('/');
+        url.push_str(segment);
+    }
+    Ok(url)
+}
+
+/// A single external definition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExternalDefinition {
+    /// The directory (or file) the external is checked out into, relative to
+    /// the directory carrying the property.
+    pub target: String,
+    /// The URL as written, possibly relative.
+    pub url: String,
+    /// The operative revision given with `-r`.
+    pub revision: Option<ExternalRevision>,
+    /// The peg revision given with `@`.
+    pub peg_revision: Option<ExternalRevision>,
+    /// The syntax the definition is written in.
+    pub syntax: ExternalSyntax,
+}
+
+impl ExternalDefinition {
+    /// Create a definition of `url` into `target` using the current syntax.
+    pub fn new(url: &str, target: &str) -> Self {
+        Self {
+            target: target.to_string(),
+            url: url.to_string(),
+            revision: None,
+            peg_revision: None,
+            syntax: ExternalSyntax::New,
+        }
+    }
+
+    /// Parse a single definition line.
+    pub fn parse(line: &str) -> Result<Self, Error<'static>> {
+        let invalid = |why: &str| {
+            Error::from_message(&format!("Invalid svn:externals line '{}': {}", line, why))
+        };
+        let mut tokens = tokenize(line).map_err(invalid)?;
+
+        let mut revision = None;
+        if let Some(i) = tokens.iter().position(|t| t.starts_with("-r")) {
+            let value = if tokens[i] == "-r" {
+                if i + 1 >= tokens.len() {
+                    return Err(invalid("missing revision after -r"));
+                }
+                tokens.remove(i + 1)
+            } else {
+                tokens[i][2..].to_string()
+            };
+            tokens.remove(i);
+            revision = Some(
+                ExternalRevision::parse(&value)
+                    .ok_or_else(|| invalid(&format!("invalid revision '{}'", value)))?,
+            );
+        }
+
+        let [first, second]: [String; 2] = tokens
+            .try_into()
+            .map_err(|_| invalid("expected a URL and a target directory"))?;
+
+        let def = if is_absolute_url(&second) {
+            if is_absolute_url(&first) {
+                return Err(invalid("cannot use two absolute URLs"));
+            }
+            ExternalDefinition {
+                target: first,
+                url: second,
+                revision,
+                peg_revision: None,
+                syntax: ExternalSyntax::Old,
+            }
+        } else {
+            let (url, peg_revision) = split_peg(&first);
+            if url_kind(url).is_none() {
+                return Err(invalid(&format!("'{}' is not a valid URL", url)));
+            }
+            ExternalDefinition {
+                target: second,
+                url: url.to_string(),
+                revision,
+                peg_revision,
+                syntax: ExternalSyntax::New,
+            }
+        };
+
+        if def.target.is_empty()
+            || def.target.starts_with('/')
+            || def.target.split('/').any(|s| s == "..")
+        {
+            return Err(invalid(&format!(
+                "invalid target directory '{}'",
+                def.target
+            )));
+        }
+        Ok(def)
+    }
+
+    /// How the URL is anchored.
+    ///
+    /// Old syntax definitions always use absolute URLs.
+    pub fn url_kind(&self) -> ExternalUrlKind {
+        url_kind(&self.url).unwrap_or(ExternalUrlKind::Absolute)
+    }
+
+    /// Resolve the URL of this definition to an absolute URL.
+    ///
+    /// `parent_url` is the URL of the directory the property is set on and
+    /// `repos_root_url` the root URL of its repository.
+    pub fn resolve_url(
+        &self,
+        parent_url: &str,
+        repos_root_url: &str,
+    ) -> Result<String, Error<'static>> {
+        match self.url_kind() {
+            ExternalUrlKind::Absolute => Ok(self.url.clone()),
+            ExternalUrlKind::RepositoryRelative => join_url(repos_root_url, &self.url[2..]),
+            ExternalUrlKind::SchemeRelative => {
+                let (prefix, _) = split_url(parent_url)?;
+                let scheme = &prefix[..prefix.find("://").unwrap()];
+                Ok(format!("{}:{}", scheme, self.url))
+            }
+            ExternalUrlKind::ServerRelative => {
+                let (prefix, _) = split_url(parent_url)?;
+                join_url(prefix, &self.url)
+            }
+            ExternalUrlKind::DirectoryRelative => join_url(parent_url, &self.url),
+        }
+    }
+
+    /// Pin the definition to `rev`.
+    ///
+    /// Definitions in the current syntax get a peg revision, which also
+    /// fixes the URL the external is looked up at; old syntax definitions
+    /// get an operative revision since they cannot express a peg.
+    pub fn pin(&mut self, rev: Revnum) {
+        match self.effective_syntax() {
+            ExternalSyntax::New => {
+                self.revision = None;
+                self.peg_revision = Some(ExternalRevision::Number(rev));
+            }
+            ExternalSyntax::Old => {
+                self.revision = Some(ExternalRevision::Number(rev));
+            }
+        }
+    }
+
+    /// Drop any revisions, making the definition follow `HEAD`.
+    pub fn unpin(&mut self) {
+        self.revision = None;
+        self.peg_revision = None;
+    }
+
+    /// The revision the external is fixed at, if any.
+    pub fn pinned_revision(&self) -> Option<&ExternalRevision> {
+        self.revision.as_ref().or(self.peg_revision.as_ref())
+    }
+
+    /// The syntax used to serialize this definition; the old syntax can only
+    /// be kept while the URL is absolute and there is no peg revision.
+    fn effective_syntax(&self) -> ExternalSyntax {
+        if self.syntax == ExternalSyntax::Old
+            && self.peg_revision.is_none()
+            && is_absolute_url(&self.url)
+        {
+            ExternalSyntax::Old
+        } else {
+            ExternalSyntax::New
+        }
+    }
+}
+
+impl fmt::Display for ExternalDefinition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let revision = self.revision.as_ref().map(|r| format!("-r{} ", r));
+        let revision = revision.as_deref().unwrap_or("");
+        match self.effective_syntax() {
+            ExternalSyntax::Old => write!(
+                f,
+                "{} {}{}",
+                quote(&self.target),
+                revision,
+                quote(&self.url)
+            ),
+            ExternalSyntax::New => {
+                let mut url = self.url.clone();
+                match &self.peg_revision {
+                    Some(peg) => url.push_str(&format!("@{}", peg)),
+                    // Protect an '@' in the URL from being read as a peg.
+                    None if url.contains('@') => url.push('@'),
+                    None => {}
+                }
+                write!(f, "{}{} {}", revision, quote(&url), quote(&self.target))
+            }
+        }
+    }
+}
+
+/// Split a trailing `@PEG` off a URL, as `svn_opt_parse_path` does.
+fn split_peg(url: &str) -> (&str, Option<ExternalRevision>) {
+    match url.rfind('@') {
+        Some(i) if i + 1 == url.len() => (&url[..i], None),
+        Some(i) => match ExternalRevision::parse(&url[i + 1..]) {
+            Some(peg) => (&url[..i], Some(peg)),
+            None => (url, None),
+        },
+        None => (url, None),
+    }
+}
+
+/// Split a line into whitespace separated tokens, honouring quotes and
+/// backslash escapes the way `apr_tokenize_to_argv` does.
+fn tokenize(line: &str) -> Result<Vec<String>, &'static str> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            return Ok(tokens);
+        }
+        let mut token = String::new();
+        let mut quote = None;
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => token.push(chars.next().ok_or("trailing backslash")?),
+                '"' | '\'' if quote == Some(c) => quote = None,
+                '"' | '\'' if quote.is_none() => quote = Some(c),
+                c if c.is_whitespace() && quote.is_none() => break,
+                c => token.push(c),
+            }
+        }
+        if quote.is_some() {
+            return Err("unterminated quote");
+        }
+        tokens.push(token);
+    }
+}
+
+fn quote(token: &str) -> String {
+    if !token.is_empty()
+        && !token
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '\\'))
+    {
+        return token.to_string();
+    }
+    let mut quoted = String::from("\"");
+    for c in token.chars() {
+        if matches!(c, '"' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[derive(Debug, Clone)]
+enum LineKind {
+    /// A comment or blank line, kept verbatim.
+    Verbatim,
+    /// A definition, along with the value it had when parsed.
+    Definition {
+        original: ExternalDefinition,
+        current: ExternalDefinition,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Line {
+    text: String,
+    eol: String,
+    kind: LineKind,
+}
+
+/// A line of an externals description.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExternalsLine<'a> {
+    /// A comment, including the leading `#`.
+    Comment(&'a str),
+    /// An empty or whitespace-only line.
+    Blank,
+    /// An external definition.
+    Definition(&'a ExternalDefinition),
+}
+
+/// The contents of an `svn:externals` property.
+///
+/// Serializing with [`Display`](fmt::Display) reproduces the original text
+/// exactly, except for definitions that were modified, which are rewritten.
+#[derive(Debug, Clone, Default)]
+pub struct ExternalsDescription {
+    lines: Vec<Line>,
+}
+
+impl ExternalsDescription {
+    /// Create an empty description.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse a property value.
+    pub fn parse(text: &str) -> Result<Self, Error<'static>> {
+        let mut lines = Vec::new();
+        for raw in text.split_inclusive('\n') {
+            let body = raw.trim_end_matches(['\n', '\r']);
+            let eol = raw[body.len()..].to_string();
+            let trimmed = body.trim();
+            let kind = if trimmed.is_empty() || trimmed.starts_with('#') {
+                LineKind::Verbatim
+            } else {
+                let def = ExternalDefinition::parse(trimmed)?;
+                LineKind::Definition {
+                    original: def.clone(),
+                    current: def,
+                }
+            };
+            lines.push(Line {
+                text: body.to_string(),
+                eol,
+                kind,
+            });
+        }
+        Ok(Self { lines })
+    }
+
+    /// Iterate over all lines.
+    pub fn lines(&self) -> impl Iterator<Item = ExternalsLine<'_>> {
+        self.lines.iter().map(|line| match &line.kind {
+            LineKind::Definition { current, .. } => ExternalsLine::Definition(current),
+            LineKind::Verbatim if line.text.trim().is_empty() => ExternalsLine::Blank,
+            LineKind::Verbatim => ExternalsLine::Comment(line.text.trim()),
+        })
+    }
+
+    /// Iterate over the definitions, in order.
+    pub fn definitions(&self) -> impl Iterator<Item = &ExternalDefinition> {
+        self.lines.iter().filter_map(|line| match &line.kind {
+            LineKind::Definition { current, .. } => Some(current),
+            LineKind::Verbatim => None,
+        })
+    }
+
+    /// Iterate mutably over the definitions, in order.
+    pub fn definitions_mut(&mut self) -> impl Iterator<Item = &mut ExternalDefinition> {
+        self.lines
+            .iter_mut()
+            .filter_map(|line| match &mut line.kind {
+                LineKind::Definition { current, .. } => Some(current),
+                LineKind::Verbatim => None,
+            })
+    }
+
+    /// Find the definition checked out into `target`.
+    pub fn get(&self, target: &str) -> Option<&ExternalDefinition> {
+        self.definitions().find(|d| d.target == target)
+    }
+
+    /// Find the definition checked out into `target`, mutably.
+    pub fn get_mut(&mut self, target: &str) -> Option<&mut ExternalDefinition> {
+        self.definitions_mut().find(|d| d.target == target)
+    }
+
+    /// Append a definition.
+    pub fn push(&mut self, def: ExternalDefinition) {
+        let eol = self
+            .lines
+            .first()
+            .map(|line| line.eol.clone())
+            .filter(|eol| !eol.is_empty())
+            .unwrap_or_else(|| "\n".to_string());
+        if let Some(last) = self.lines.last_mut() {
+            if last.eol.is_empty() {
+                last.eol = eol.clone();
+            }
+        }
+        self.lines.push(Line {
+            text: def.to_string(),
+            eol,
+            kind: LineKind::Definition {
+                original: def.clone(),
+                current: def,
+            },
+        });
+    }
+
+    /// Remove the definition checked out into `target`, returning it.
+    pub fn remove(&mut self, target: &str) -> Option<ExternalDefinition> {
+        let i = self.lines.iter().position(|line| {
+            matches!(&line.kind, LineKind::Definition { current, .. } if current.target == target)
+        })?;
+        match self.lines.remove(i).kind {
+            LineKind::Definition { current, .. } => Some(current),
+            LineKind::Verbatim => unreachable!(),
+        }
+    }
+
+    /// Whether there are no definitions.
+    pub fn is_empty(&self) -> bool {
+        self.definitions().next().is_none()
+    }
+}
+
+impl fmt::Display for ExternalsDescription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            match &line.kind {
+                LineKind::Definition { original, current } if original != current => {
+                    write!(f, "{}", current)?
+                }
+                _ => f.write_str(&line.text)?,
+            }
+            f.write_str(&line.eol)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for ExternalsDescription {
+    type Err = Error<'static>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DESC: &str = "# third party code\n\
+        third-party/sounds http://svn.example.com/repos/sounds\n\
+        third-party/skins -r148 http://svn.example.com/skinproj\n\
+        \n\
+        -r 21 ^/libs/common@20 common\n\
+        //svn.example.com/repos/tools tools\n\
+        /repos/docs \"my docs\"\n\
+        ../sibling@HEAD sibling\n";
+
+    #[test]
+    fn test_parse_and_round_trip() {
+        let desc = ExternalsDescription::parse(DESC).unwrap();
+        assert_eq!(desc.to_string(), DESC);
+
+        let defs: Vec<_> = desc.definitions().collect();
+        assert_eq!(defs.len(), 6);
+        assert_eq!(defs[0].syntax, ExternalSyntax::Old);
+        assert_eq!(defs[0].target, "third-party/sounds");
+        assert_eq!(
+            defs[1].revision,
+            Some(ExternalRevision::Number(Revnum(148)))
+        );
+        assert_eq!(defs[2].syntax, ExternalSyntax::New);
+        assert_eq!(defs[2].url, "^/libs/common");
+        assert_eq!(defs[2].revision, Some(ExternalRevision::Number(Revnum(21))));
+        assert_eq!(
+            defs[2].peg_revision,
+            Some(ExternalRevision::Number(Revnum(20)))
+        );
+        assert_eq!(defs[3].url_kind(), ExternalUrlKind::SchemeRelative);
+        assert_eq!(defs[4].target, "my docs");
+        assert_eq!(defs[4].url_kind(), ExternalUrlKind::ServerRelative);
+        assert_eq!(defs[5].peg_revision, Some(ExternalRevision::Head));
+        assert_eq!(defs[5].url_kind(), ExternalUrlKind::DirectoryRelative);
+
+        let lines: Vec<_> = desc.lines().collect();
+        assert_eq!(lines[0], ExternalsLine::Comment("# third party code"));
+        assert_eq!(lines[3], ExternalsLine::Blank);
+    }
+
+    #[test]
+    fn test_edit_preserves_other_lines() {
+        let mut desc = ExternalsDescription::parse(DESC).unwrap();
+        desc.get_mut("third-party/sounds").unwrap().pin(Revnum(7));
+        desc.get_mut("common").unwrap().pin(Revnum(30));
+        desc.get_mut("sibling").unwrap().unpin();
+        desc.remove("tools").unwrap();
+        desc.push(ExternalDefinition::new("^/a b", "new"));
+
+        assert_eq!(
+            desc.to_string(),
+            "# third party code\n\
+             third-party/sounds -r7 http://svn.example.com/repos/sounds\n\
+             third-party/skins -r148 http://svn.example.com/skinproj\n\
+             \n\
+             ^/libs/common@30 common\n\
+             /repos/docs \"my docs\"\n\
+             ../sibling sibling\n\
+             \"^/a b\" new\n"
+        );
+
+        let reparsed = ExternalsDescription::parse(&desc.to_string()).unwrap();
+        assert_eq!(
+            reparsed.definitions().collect::<Vec<_>>(),
+            desc.definitions().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_resolve_url() {
+        let parent = "http://svn.example.com/repos/project/trunk";
+        let root = "http://svn.example.com/repos";
+        let resolve = |url: &str| {
+            ExternalDefinition::new(url, "x")
+                .resolve_url(parent, root)
+                .unwrap()
+        };
+        assert_eq!(resolve("^/lib"), "http://svn.example.com/repos/lib");
+        assert_eq!(
+            resolve("^/../other/lib"),
+            "http://svn.example.com/other/lib"
+        );
+        assert_eq!(
+            resolve("//mirror.example.com/lib"),
+            "http://mirror.example.com/lib"
+        );
+        assert_eq!(resolve("/svn/lib"), "http://svn.example.com/svn/lib");
+        assert_eq!(
+            resolve("../branches/b1"),
+            "http://svn.example.com/repos/project/branches/b1"
+        );
+        assert!(ExternalDefinition::new("../../../../x", "x")
+            .resolve_url(parent, root)
+            .is_err());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(ExternalsDescription::parse("just-one-token\n").is_err());
+        assert!(ExternalsDescription::parse("http://a/b http://c/d\n").is_err());
+        assert!(ExternalsDescription::parse("-r http://a/b dir\n").is_err());
+        assert!(ExternalsDescription::parse("http://a/b ../escape\n").is_err());
+        assert!(ExternalsDescription::parse("\"http://a/b dir\n").is_err());
+    }
+}
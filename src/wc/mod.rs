@@ -553,7 +553,9 @@ pub struct FileChange<'a> {
 // --- Diff callback trampolines (module-level so they can be shared) ---
 
 /// Convert a raw apr_array_header_t of svn_prop_t into Vec<PropChange>.
-unsafe fn diff_prop_array_to_vec(arr: *const apr_sys::apr_array_header_t) -> Vec<PropChange> {
+pub(crate) unsafe fn diff_prop_array_to_vec(
+    arr: *const apr_sys::apr_array_header_t,
+) -> Vec<PropChange> {
     if arr.is_null() {
         return Vec::new();
     }
@@ -581,7 +583,7 @@ unsafe fn diff_prop_array_to_vec(arr: *const apr_sys::apr_array_header_t) -> Vec
 }
 
 /// Turn a nullable C string into Option<&str>.
-unsafe fn diff_opt_str<'a>(p: *const std::os::raw::c_char) -> Option<&'a str> {
+pub(crate) unsafe fn diff_opt_str<'a>(p: *const std::os::raw::c_char) -> Option<&'a str> {
     if p.is_null() {
         None
     } else {
@@ -1093,6 +1095,11 @@ impl Drop for Context {
 }
 
 pub mod adm;
+pub mod db;
+pub mod externals;
+pub mod pristine;
+#[cfg(all(feature = "status-cache", target_os = "linux"))]
+pub mod status_cache;
 #[allow(deprecated)]
 pub use adm::Adm;
 
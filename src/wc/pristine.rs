@@ -0,0 +1,455 @@
+//! Pristine store management.
+//!
+//! Every working copy keeps an unmodified copy of each file text it knows
+//! about in `.svn/pristine`, named after the SHA-1 checksum of the text.
+//! The functions here list, verify and garbage collect that store for a
+//! whole working copy, rather than per path as
+//! [`Context::get_pristine_contents`] does.
+
+use super::db::{PristineRow, WcDb};
+use super::Context;
+use crate::{svn_result, ChecksumKind, Error};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+const SHA1_PREFIX: &str = "$sha1$";
+const MD5_PREFIX: &str = "$md5 $";
+
+/// A text in the pristine store.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pristine {
+    /// Hex SHA-1 checksum of the text; the key of the store.
+    pub sha1: String,
+    /// Hex MD5 checksum of the text.
+    pub md5: String,
+    /// Size of the text, as recorded in the database.
+    pub size: u64,
+    /// Reference count, as recorded in the database.
+    pub refcount: i64,
+    /// Location of the text on disk.
+    pub path: PathBuf,
+    /// Nodes referring to the text, relative to the working copy root.
+    pub references: Vec<String>,
+}
+
+/// A problem found by [`Context::verify_pristines`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PristineProblem {
+    /// The text is recorded in the database but missing on disk.
+    Missing {
+        /// SHA-1 checksum of the text.
+        sha1: String,
+    },
+    /// The size of the file on disk differs from the recorded size.
+    SizeMismatch {
+        /// SHA-1 checksum of the text.
+        sha1: String,
+        /// Recorded size.
+        expected: u64,
+        /// Size on disk.
+        actual: u64,
+    },
+    /// The checksum of the file on disk differs from the recorded one.
+    ChecksumMismatch {
+        /// SHA-1 checksum of the text.
+        sha1: String,
+        /// Kind of the mismatching checksum.
+        kind: ChecksumKind,
+        /// Hex checksum of the file on disk.
+        actual: String,
+    },
+    /// The recorded reference count differs from the number of database
+    /// rows referring to the text.
+    RefcountMismatch {
+        /// SHA-1 checksum of the text.
+        sha1: String,
+        /// Recorded reference count.
+        recorded: i64,
+        /// Number of references found.
+        actual: i64,
+    },
+    /// A file in the pristine store that is not recorded in the database.
+    Unrecorded {
+        /// Path of the file.
+        path: PathBuf,
+    },
+}
+
+/// Result of [`Context::gc_pristines`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PristineGcReport {
+    /// Pristine texts that were removed.
+    pub removed: Vec<Pristine>,
+    /// Bytes freed on disk.
+    pub bytes_freed: u64,
+}
+
+/// Find the root of the working copy containing `path`.
+pub(super) fn find_wcroot(path: &Path) -> Result<PathBuf, Error<'static>> {
+    let adm_dir = super::get_adm_dir();
+    let path = crate::dirent::to_absolute_cstring(path)?;
+    let path = PathBuf::from(path.to_str()?);
+    path.ancestors()
+        .find(|dir| dir.join(&adm_dir).join("wc.db").is_file())
+        .map(Path::to_path_buf)
+        .ok_or_else(|| Error::from_message(&format!("'{}' is not a working copy", path.display())))
+}
+
+fn pristine_dir(wcroot: &Path) -> PathBuf {
+    wcroot.join(super::get_adm_dir()).join("pristine")
+}
+
+fn pristine_path(wcroot: &Path, sha1: &str) -> PathBuf {
+    pristine_dir(wcroot)
+        .join(sha1.get(..2).unwrap_or_default())
+        .join(format!("{}.svn-base", sha1))
+}
+
+/// List the pristine texts of `wcroot`, each with the number of database
+/// rows referring to it, which is what the recorded refcount tracks.
+fn list_pristines(wcroot: &Path) -> Result<Vec<(Pristine, i64)>, Error<'static>> {
+    let db = WcDb::open(wcroot)?;
+    let mut references: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for node in db.nodes()? {
+        if let Some(checksum) = node.checksum {
+            let paths = references.entry(checksum).or_default();
+            if !paths.contains(&node.local_relpath) {
+                paths.push(node.local_relpath);
+            }
+        }
+    }
+    Ok(db
+        .pristines()?
+        .into_iter()
+        .map(|row: PristineRow| {
+            let sha1 = row
+                .checksum
+                .strip_prefix(SHA1_PREFIX)
+                .unwrap_or(&row.checksum)
+                .to_string();
+            let pristine = Pristine {
+                md5: row
+                    .md5_checksum
+                    .strip_prefix(MD5_PREFIX)
+                    .unwrap_or(&row.md5_checksum)
+                    .to_string(),
+                size: row.size.max(0) as u64,
+                refcount: row.refcount,
+                path: pristine_path(wcroot, &sha1),
+                references: references.remove(&row.checksum).unwrap_or_default(),
+                sha1,
+            };
+            (pristine, row.references)
+        })
+        .collect())
+}
+
+fn file_checksums(path: &Path) -> Result<(String, String), Error<'static>> {
+    use std::io::Read;
+    let pool = apr::Pool::new();
+    let mut sha1 = crate::ChecksumContext::new(ChecksumKind::SHA1, &pool)?;
+    let mut md5 = crate::ChecksumContext::new(ChecksumKind::MD5, &pool)?;
+    let mut file = std::fs::File::open(path)?;
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        sha1.update(&buf[..n])?;
+        md5.update(&buf[..n])?;
+    }
+    let sha1 = sha1
+        .finish(&pool)
+        .map_err(Error::into_static)?
+        .to_hex(&pool);
+    let md5 = md5.finish(&pool).map_err(Error::into_static)?.to_hex(&pool);
+    Ok((sha1, md5))
+}
+
+impl Context {
+    /// List the texts in the pristine store of the working copy containing
+    /// `path`, ordered by SHA-1 checksum.
+    pub fn pristines(&self, path: &Path) -> Result<Vec<Pristine>, Error<'static>> {
+        Ok(list_pristines(&find_wcroot(path)?)?
+            .into_iter()
+            .map(|(pristine, _)| pristine)
+            .collect())
+    }
+
+    /// Look up a pristine text by its hex SHA-1 or MD5 checksum, with or
+    /// without the `$sha1$`/`$md5 $` prefix used in the database.
+    pub fn find_pristine(
+        &self,
+        path: &Path,
+        checksum: &str,
+    ) -> Result<Option<Pristine>, Error<'static>> {
+        let checksum = checksum
+            .strip_prefix(SHA1_PREFIX)
+            .or_else(|| checksum.strip_prefix(MD5_PREFIX))
+            .unwrap_or(checksum)
+            .to_ascii_lowercase();
+        Ok(self
+            .pristines(path)?
+            .into_iter()
+            .find(|p| p.sha1 == checksum || p.md5 == checksum))
+    }
+
+    /// Total size on disk of the pristine store of the working copy
+    /// containing `path`.
+    pub fn pristine_size(&self, path: &Path) -> Result<u64, Error<'static>> {
+        let mut total = 0;
+        for pristine in self.pristines(path)? {
+            match std::fs::metadata(&pristine.path) {
+                Ok(metadata) => total += metadata.len(),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(total)
+    }
+
+    /// Check the pristine store of the working copy containing `path`.
+    ///
+    /// Every recorded text is read back and its size and checksums are
+    /// compared with the database, as is its reference count.  Files in the
+    /// store that are not recorded in the database are reported as well.
+    /// An empty result means the store is consistent.
+    pub fn verify_pristines(&self, path: &Path) -> Result<Vec<PristineProblem>, Error<'static>> {
+        let wcroot = find_wcroot(path)?;
+        let pristines = list_pristines(&wcroot)?;
+        let mut problems = Vec::new();
+
+        for (pristine, actual) in &pristines {
+            let sha1 = pristine.sha1.clone();
+            let actual = *actual;
+            if pristine.refcount != actual {
+                problems.push(PristineProblem::RefcountMismatch {
+                    sha1: sha1.clone(),
+                    recorded: pristine.refcount,
+                    actual,
+                });
+            }
+            let metadata = match std::fs::metadata(&pristine.path) {
+                Ok(metadata) => metadata,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    problems.push(PristineProblem::Missing { sha1 });
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+            if metadata.len() != pristine.size {
+                problems.push(PristineProblem::SizeMismatch {
+                    sha1: sha1.clone(),
+                    expected: pristine.size,
+                    actual: metadata.len(),
+                });
+            }
+            let (actual_sha1, actual_md5) = file_checksums(&pristine.path)?;
+            if actual_sha1 != pristine.sha1 {
+                problems.push(PristineProblem::ChecksumMismatch {
+                    sha1: sha1.clone(),
+                    kind: ChecksumKind::SHA1,
+                    actual: actual_sha1,
+                });
+            }
+            if actual_md5 != pristine.md5 {
+                problems.push(PristineProblem::ChecksumMismatch {
+                    sha1,
+                    kind: ChecksumKind::MD5,
+                    actual: actual_md5,
+                });
+            }
+        }
+
+        let dir = pristine_dir(&wcroot);
+        if dir.is_dir() {
+            for shard in std::fs::read_dir(&dir)? {
+                let shard = shard?.path();
+                if !shard.is_dir() {
+                    continue;
+                }
+                for entry in std::fs::read_dir(&shard)? {
+                    let path = entry?.path();
+                    if !pristines.iter().any(|(p, _)| p.path == path) {
+                        problems.push(PristineProblem::Unrecorded { path });
+                    }
+                }
+            }
+        }
+        Ok(problems)
+    }
+
+    /// Remove the pristine texts no longer referenced by any node of the
+    /// working copy containing `path`.
+    ///
+    /// This runs `svn cleanup` with only pristine vacuuming enabled, so it
+    /// takes a write lock on the working copy and runs any pending work
+    /// queue items first.  Unrecorded files in the store are left alone.
+    pub fn gc_pristines(&self, path: &Path) -> Result<PristineGcReport, Error<'static>> {
+        let wcroot = find_wcroot(path)?;
+        let before = self.pristines(&wcroot)?;
+        let wcroot_c = crate::dirent::to_absolute_cstring(&wcroot)?;
+        let pool = apr::Pool::new();
+        svn_result(unsafe {
+            subversion_sys::svn_wc_cleanup4(
+                self.ptr,
+                wcroot_c.as_ptr(),
+                0, // break_locks
+                0, // fix_recorded_timestamps
+                0, // clear_dav_cache
+                1, // vacuum_pristines
+                None,
+                std::ptr::null_mut(),
+                None,
+                std::ptr::null_mut(),
+                pool.as_mut_ptr(),
+            )
+        })?;
+        let after = self.pristines(&wcroot)?;
+
+        let mut report = PristineGcReport::default();
+        for pristine in before {
+            if !after.iter().any(|p| p.sha1 == pristine.sha1) && !pristine.path.exists() {
+                report.bytes_freed += pristine.size;
+                report.removed.push(pristine);
+            }
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(all(test, feature = "client"))]
+mod tests {
+    use super::*;
+    use crate::Revnum;
+
+    #[test]
+    fn test_pristine_store() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let repos_path = temp.path().join("repos");
+        let wc_path = temp.path().join("wc");
+        let repos = crate::repos::Repos::create(&repos_path).unwrap();
+        let fs = repos.fs().unwrap();
+        let mut txn = fs.begin_txn(Revnum(0), 0).unwrap();
+        {
+            let mut root = txn.root().unwrap();
+            root.make_dir("/dir").unwrap();
+            for path in ["/a.txt", "/dir/b.txt"] {
+                root.make_file(path).unwrap();
+                root.set_file_contents(path, b"same\n").unwrap();
+            }
+            root.make_file("/c.txt").unwrap();
+            root.set_file_contents("/c.txt", b"other\n").unwrap();
+        }
+        txn.commit().unwrap();
+
+        let url = crate::path_to_file_url(&repos_path);
+        let mut client_ctx = crate::client::Context::new().unwrap();
+        client_ctx
+            .checkout(
+                crate::uri::Uri::new(&url).unwrap(),
+                &wc_path,
+                &crate::client::CheckoutOptions {
+                    peg_revision: crate::Revision::Head,
+                    revision: crate::Revision::Head,
+                    depth: crate::Depth::Infinity,
+                    ignore_externals: false,
+                    allow_unver_obstructions: false,
+                },
+            )
+            .unwrap();
+
+        let ctx = Context::new().unwrap();
+        let pristines = ctx.pristines(&wc_path.join("dir")).unwrap();
+        assert_eq!(pristines.len(), 2);
+        let same = pristines.iter().find(|p| p.references.len() == 2).unwrap();
+        assert_eq!(same.references, vec!["a.txt", "dir/b.txt"]);
+        assert_eq!(same.size, 5);
+        assert!(same.path.is_file());
+
+        let found = ctx.find_pristine(&wc_path, &same.md5).unwrap().unwrap();
+        assert_eq!(&found, same);
+        let found = ctx
+            .find_pristine(&wc_path, &format!("$sha1${}", same.sha1))
+            .unwrap()
+            .unwrap();
+        assert_eq!(&found, same);
+        assert!(ctx.find_pristine(&wc_path, "0000").unwrap().is_none());
+
+        assert_eq!(ctx.pristine_size(&wc_path).unwrap(), 11);
+        assert!(ctx.verify_pristines(&wc_path).unwrap().is_empty());
+
+        // Nothing is unreferenced yet.
+        let report = ctx.gc_pristines(&wc_path).unwrap();
+        assert!(report.removed.is_empty());
+        assert_eq!(ctx.pristines(&wc_path).unwrap().len(), 2);
+
+        // Committing a new text for c.txt leaves its old text unreferenced.
+        let old_text = pristines
+            .iter()
+            .find(|p| p.references == ["c.txt"])
+            .unwrap();
+        std::fs::write(wc_path.join("c.txt"), b"changed c\n").unwrap();
+        client_ctx
+            .commit(
+                &[wc_path.to_str().unwrap()],
+                &crate::client::CommitOptions::default(),
+                std::collections::HashMap::from([("svn:log", "change c")]),
+                None,
+                &mut |_| Ok(()),
+            )
+            .unwrap();
+        let unreferenced: Vec<_> = ctx
+            .pristines(&wc_path)
+            .unwrap()
+            .into_iter()
+            .filter(|p| p.references.is_empty())
+            .collect();
+        assert_eq!(
+            unreferenced,
+            vec![Pristine {
+                references: vec![],
+                refcount: 0,
+                ..old_text.clone()
+            }]
+        );
+
+        let report = ctx.gc_pristines(&wc_path).unwrap();
+        assert_eq!(report.removed.len(), 1);
+        assert_eq!(report.removed[0].sha1, old_text.sha1);
+        assert_eq!(report.bytes_freed, 6);
+        assert!(!old_text.path.exists());
+        let remaining = ctx.pristines(&wc_path).unwrap();
+        assert_eq!(remaining.len(), 2);
+        assert!(remaining.iter().any(|p| p.sha1 == same.sha1));
+        assert!(same.path.is_file());
+
+        // Corrupt a text and drop a stray file into the store.
+        std::fs::write(&same.path, b"changed\n").unwrap();
+        let stray = same.path.with_file_name("stray.svn-base");
+        std::fs::write(&stray, b"").unwrap();
+        let problems = ctx.verify_pristines(&wc_path).unwrap();
+        assert!(problems.contains(&PristineProblem::SizeMismatch {
+            sha1: same.sha1.clone(),
+            expected: 5,
+            actual: 8,
+        }));
+        assert!(problems.iter().any(|p| matches!(
+            p,
+            PristineProblem::ChecksumMismatch { sha1, kind: ChecksumKind::SHA1, .. }
+                if sha1 == &same.sha1
+        )));
+        assert!(problems.contains(&PristineProblem::Unrecorded { path: stray }));
+
+        std::fs::remove_file(&same.path).unwrap();
+        assert!(ctx
+            .verify_pristines(&wc_path)
+            .unwrap()
+            .contains(&PristineProblem::Missing {
+                sha1: same.sha1.clone()
+            }));
+
+        assert!(ctx.pristines(temp.path()).is_err());
+    }
+}
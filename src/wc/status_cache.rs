@@ -0,0 +1,674 @@
+//! Incremental status of large working copies.
+//!
+//! Walking the status of a working copy stats every file in it, which gets
+//! slow for very large checkouts.  [`StatusCache`] walks the working copy
+//! once and then listens for inotify events below its root; only the paths
+//! that changed since the last query are checked again.  Any change to the
+//! working copy database (an `svn` command run against the working copy) or
+//! an overflow of the kernel event queue causes a full walk, so that the
+//! cached statuses are always those a fresh walk would report.
+//!
+//! Which unversioned paths are ignored depends on `svn:ignore` and
+//! `svn:global-ignores`, which live in the working copy database, and on
+//! the `global-ignores` runtime configuration option.  The configuration
+//! directories are watched too, and a change to either source of ignore
+//! patterns also causes a full walk.
+//!
+//! This module is only available on Linux.
+
+use super::db::WcDb;
+use super::{Context, Status, StatusKind};
+use crate::Error;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ffi::{CString, OsStr, OsString};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+
+/// Events watched on versioned directories.
+const DIR_MASK: u32 = libc::IN_CREATE
+    | libc::IN_DELETE
+    | libc::IN_MODIFY
+    | libc::IN_CLOSE_WRITE
+    | libc::IN_ATTRIB
+    | libc::IN_MOVED_FROM
+    | libc::IN_MOVED_TO
+    | libc::IN_DELETE_SELF
+    | libc::IN_MOVE_SELF
+    | libc::IN_ONLYDIR
+    | libc::IN_DONT_FOLLOW
+    | libc::IN_EXCL_UNLINK;
+
+/// Events watched on the runtime configuration directories.
+const CONFIG_MASK: u32 = libc::IN_CREATE
+    | libc::IN_DELETE
+    | libc::IN_MODIFY
+    | libc::IN_CLOSE_WRITE
+    | libc::IN_MOVED_FROM
+    | libc::IN_MOVED_TO
+    | libc::IN_ONLYDIR;
+
+/// Directory holding the system-wide runtime configuration.
+const SYSTEM_CONFIG_DIR: &str = "/etc/subversion";
+
+/// Events watched on the administrative directory; only the database
+/// and its journal are of interest there.
+const ADM_MASK: u32 = libc::IN_CREATE
+    | libc::IN_DELETE
+    | libc::IN_MODIFY
+    | libc::IN_MOVED_FROM
+    | libc::IN_MOVED_TO
+    | libc::IN_ONLYDIR;
+
+enum Event {
+    /// Events were dropped by the kernel.
+    Overflow,
+    /// Something happened to `name` in `dir`, or to `dir` itself.
+    Changed {
+        dir: PathBuf,
+        name: Option<OsString>,
+    },
+}
+
+/// An inotify instance and its watches.
+struct Inotify {
+    fd: libc::c_int,
+    watches: HashMap<libc::c_int, PathBuf>,
+    watched: HashMap<PathBuf, libc::c_int>,
+}
+
+impl Drop for Inotify {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.fd);
+        }
+    }
+}
+
+impl Inotify {
+    fn new() -> Result<Self, Error<'static>> {
+        let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(Self {
+            fd,
+            watches: HashMap::new(),
+            watched: HashMap::new(),
+        })
+    }
+
+    /// Watch `dir`; directories that do not exist are skipped.
+    fn watch(&mut self, dir: &Path, mask: u32) -> Result<(), Error<'static>> {
+        if self.watched.contains_key(dir) {
+            return Ok(());
+        }
+        let dir_c = CString::new(dir.as_os_str().as_bytes())?;
+        let wd = unsafe { libc::inotify_add_watch(self.fd, dir_c.as_ptr(), mask) };
+        if wd < 0 {
+            let err = std::io::Error::last_os_error();
+            return match err.raw_os_error() {
+                Some(libc::ENOENT) | Some(libc::ENOTDIR) => Ok(()),
+                _ => Err(err.into()),
+            };
+        }
+        // The same directory may have been watched under another name
+        // before it was moved.
+        if let Some(old) = self.watches.insert(wd, dir.to_path_buf()) {
+            self.watched.remove(&old);
+        }
+        self.watched.insert(dir.to_path_buf(), wd);
+        Ok(())
+    }
+
+    /// Read all pending events without blocking.
+    fn read_events(&mut self) -> Result<Vec<Event>, Error<'static>> {
+        let header = std::mem::size_of::<libc::inotify_event>();
+        let mut buf = vec![0u8; 64 * 1024];
+        let mut events = Vec::new();
+        loop {
+            let n =
+                unsafe { libc::read(self.fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len()) };
+            if n < 0 {
+                let err = std::io::Error::last_os_error();
+                match err.kind() {
+                    std::io::ErrorKind::WouldBlock => return Ok(events),
+                    std::io::ErrorKind::Interrupted => continue,
+                    _ => return Err(err.into()),
+                }
+            }
+            let n = n as usize;
+            let mut offset = 0;
+            while offset + header <= n {
+                let event: libc::inotify_event = unsafe {
+                    std::ptr::read_unaligned(buf[offset..].as_ptr() as *const libc::inotify_event)
+                };
+                let name_bytes = &buf[offset + header..offset + header + event.len as usize];
+                offset += header + event.len as usize;
+
+                if event.mask & libc::IN_Q_OVERFLOW != 0 {
+                    events.push(Event::Overflow);
+                    continue;
+                }
+                let dir = if event.mask & libc::IN_IGNORED != 0 {
+                    // The watch is gone, usually because the directory was
+                    // removed; its parent reports the removal itself.
+                    if let Some(dir) = self.watches.remove(&event.wd) {
+                        self.watched.remove(&dir);
+                    }
+                    continue;
+                } else if let Some(dir) = self.watches.get(&event.wd) {
+                    dir.clone()
+                } else {
+                    continue;
+                };
+                let name_len = name_bytes
+                    .iter()
+                    .position(|c| *c == 0)
+                    .unwrap_or(name_bytes.len());
+                let name = if name_len == 0 {
+                    None
+                } else {
+                    Some(OsStr::from_bytes(&name_bytes[..name_len]).to_os_string())
+                };
+                events.push(Event::Changed { dir, name });
+            }
+        }
+    }
+}
+
+/// The user's runtime configuration directory: `config_dir` itself, or
+/// the default location if it is `None`.
+fn user_config_dir(config_dir: Option<&Path>) -> Result<Option<PathBuf>, Error<'static>> {
+    let config_dir_c = config_dir
+        .map(|dir| CString::new(dir.as_os_str().as_bytes()))
+        .transpose()?;
+    let pool = apr::Pool::new();
+    let mut path = std::ptr::null();
+    crate::svn_result(unsafe {
+        subversion_sys::svn_config_get_user_config_path(
+            &mut path,
+            config_dir_c
+                .as_ref()
+                .map_or(std::ptr::null(), |dir| dir.as_ptr()),
+            std::ptr::null(),
+            pool.as_mut_ptr(),
+        )
+    })?;
+    if path.is_null() {
+        return Ok(None);
+    }
+    let path = unsafe { std::ffi::CStr::from_ptr(path) };
+    Ok(Some(PathBuf::from(OsStr::from_bytes(path.to_bytes()))))
+}
+
+/// The `global-ignores` patterns of the runtime configuration in
+/// `config_dir`, or Subversion's defaults if the option is not set.
+fn global_ignores(config_dir: Option<&Path>) -> Result<Vec<String>, Error<'static>> {
+    let (config, _servers) = crate::config::get_config(config_dir)?;
+    match config.get(crate::config::ConfigOption::GlobalIgnores(""))? {
+        crate::config::ConfigValue::String(ignores) => Ok(ignores
+            .split_whitespace()
+            .map(|pattern| pattern.to_string())
+            .collect()),
+        _ => super::get_default_ignores(),
+    }
+}
+
+/// Cached statuses of a working copy, kept up to date from file system
+/// change notifications.
+///
+/// The cache holds the same statuses as
+/// `Context::walk_status(root, Depth::Infinity, get_all = true,
+/// no_ignore = false, ignore_text_mods = false, patterns)`, where
+/// `patterns` is the `global-ignores` option of the runtime
+/// configuration.  Pending change
+/// notifications are processed at the start of every query, so a query
+/// reflects all changes that completed before it was made.
+pub struct StatusCache {
+    ctx: Context,
+    root: PathBuf,
+    wcroot: PathBuf,
+    adm_name: OsString,
+    /// Runtime configuration directory, or `None` for the default.
+    config_dir: Option<PathBuf>,
+    /// Configuration directories that are watched; a change to the
+    /// configuration there may change `ignore_patterns`.
+    config_dirs: Vec<PathBuf>,
+    /// The `global-ignores` patterns.
+    ignore_patterns: Vec<String>,
+    entries: BTreeMap<PathBuf, Status<'static>>,
+    /// Directories known to the working copy database; the walk descends
+    /// into these and only these are watched.
+    versioned_dirs: BTreeSet<PathBuf>,
+    /// Whether unversioned paths are reported by the walk, i.e. are not
+    /// ignored.  Cleared whenever the ignore patterns may have changed.
+    unversioned_visible: HashMap<PathBuf, bool>,
+    inotify: Inotify,
+}
+
+impl StatusCache {
+    /// Walk the working copy below `root` and start watching it for changes.
+    pub fn new(root: &Path) -> Result<Self, Error<'static>> {
+        Self::with_config_dir(root, None)
+    }
+
+    /// Like [`StatusCache::new`], but read the runtime configuration from
+    /// `config_dir` instead of the default location.
+    pub fn with_config_dir(root: &Path, config_dir: Option<&Path>) -> Result<Self, Error<'static>> {
+        let root = crate::dirent::to_absolute_cstring(root)?;
+        let root = PathBuf::from(root.to_str()?);
+        let wcroot = super::pristine::find_wcroot(&root)?;
+        let mut config_dirs: Vec<PathBuf> = user_config_dir(config_dir)?.into_iter().collect();
+        if config_dir.is_none() {
+            // The system configuration is only read without an explicit
+            // configuration directory.
+            config_dirs.push(PathBuf::from(SYSTEM_CONFIG_DIR));
+        }
+        let mut cache = Self {
+            ctx: Context::new()?,
+            root,
+            wcroot,
+            adm_name: OsString::from(super::get_adm_dir()),
+            config_dir: config_dir.map(Path::to_path_buf),
+            config_dirs,
+            ignore_patterns: Vec::new(),
+            entries: BTreeMap::new(),
+            versioned_dirs: BTreeSet::new(),
+            unversioned_visible: HashMap::new(),
+            inotify: Inotify::new()?,
+        };
+        cache.rebuild()?;
+        Ok(cache)
+    }
+
+    /// The root of the cached tree.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Drop all cached state, reread the ignore patterns and walk the
+    /// whole tree again.
+    pub fn rebuild(&mut self) -> Result<(), Error<'static>> {
+        self.entries.clear();
+        self.unversioned_visible.clear();
+        self.inotify = Inotify::new()?;
+        self.inotify
+            .watch(&self.wcroot.join(&self.adm_name), ADM_MASK)?;
+        for dir in &self.config_dirs {
+            self.inotify.watch(dir, CONFIG_MASK)?;
+        }
+        self.ignore_patterns = global_ignores(self.config_dir.as_deref())?;
+
+        let db = WcDb::open(&self.wcroot)?;
+        let mut layers: BTreeMap<String, super::db::NodeRow> = BTreeMap::new();
+        for node in db.nodes()? {
+            // Rows are ordered by op-depth, so the topmost layer wins.
+            layers.insert(node.local_relpath.clone(), node);
+        }
+        drop(db);
+        self.versioned_dirs = layers
+            .into_values()
+            .filter(|node| {
+                node.kind == crate::NodeKind::Dir
+                    && !matches!(
+                        node.presence,
+                        super::db::Presence::NotPresent
+                            | super::db::Presence::Excluded
+                            | super::db::Presence::ServerExcluded
+                    )
+            })
+            .map(|node| self.wcroot.join(&node.local_relpath))
+            .filter(|dir| dir.starts_with(&self.root))
+            .collect();
+
+        let root = self.root.clone();
+        self.rewalk(&root)
+    }
+
+    /// Process pending change notifications.
+    pub fn refresh(&mut self) -> Result<(), Error<'static>> {
+        let adm_dir = self.wcroot.join(&self.adm_name);
+        let mut dirty = BTreeSet::new();
+        for event in self.inotify.read_events()? {
+            match event {
+                Event::Overflow => return self.rebuild(),
+                Event::Changed { dir, name } if dir == adm_dir => {
+                    // Property changes, including those to svn:ignore,
+                    // end up here as well.
+                    if name.is_some_and(|name| name.as_bytes().starts_with(b"wc.db")) {
+                        return self.rebuild();
+                    }
+                }
+                Event::Changed { dir, .. } if self.config_dirs.contains(&dir) => {
+                    return self.rebuild();
+                }
+                Event::Changed {
+                    name: Some(name), ..
+                } if name == self.adm_name => {
+                    // A working copy appeared or vanished inside this one.
+                    return self.rebuild();
+                }
+                Event::Changed {
+                    dir,
+                    name: Some(name),
+                } => {
+                    dirty.insert(dir.join(name));
+                }
+                Event::Changed { dir, name: None } => {
+                    dirty.insert(dir);
+                }
+            }
+        }
+
+        // A directory is walked recursively, so skip anything below it.
+        let mut walked: Option<PathBuf> = None;
+        for path in dirty {
+            if walked.as_ref().is_some_and(|dir| path.starts_with(dir)) {
+                continue;
+            }
+            if self.recheck(&path)? {
+                walked = Some(path);
+            }
+        }
+        Ok(())
+    }
+
+    /// Recompute the status of `path`; returns whether its subtree was
+    /// walked.
+    fn recheck(&mut self, path: &Path) -> Result<bool, Error<'static>> {
+        if !path.starts_with(&self.root) {
+            return Ok(false);
+        }
+        if self.versioned_dirs.contains(path) {
+            self.rewalk(path)?;
+            return Ok(true);
+        }
+        // The walk does not descend into unversioned directories.
+        if !path
+            .parent()
+            .is_some_and(|parent| self.versioned_dirs.contains(parent))
+        {
+            return Ok(false);
+        }
+
+        let status = self.ctx.status(path)?;
+        self.remove_tree(path);
+        if status.versioned() {
+            self.entries.insert(path.to_path_buf(), status);
+        } else if status.node_status() != StatusKind::None && self.is_visible(path)? {
+            self.entries.insert(path.to_path_buf(), status);
+        }
+        Ok(false)
+    }
+
+    /// Whether the walk reports the unversioned `path`, i.e. whether it is
+    /// not ignored.
+    fn is_visible(&mut self, path: &Path) -> Result<bool, Error<'static>> {
+        if let Some(visible) = self.unversioned_visible.get(path) {
+            return Ok(*visible);
+        }
+        // Ignore patterns only apply to children found by walking the
+        // parent; an explicit target is never ignored.
+        let parent = path.parent().unwrap_or(path);
+        let mut visible = false;
+        let patterns: Vec<&str> = self.ignore_patterns.iter().map(String::as_str).collect();
+        self.ctx.walk_status(
+            parent,
+            crate::Depth::Immediates,
+            false, // get_all
+            false, // no_ignore
+            true,  // ignore_text_mods
+            Some(&patterns),
+            |found, _status| {
+                if Path::new(found) == path {
+                    visible = true;
+                }
+                Ok(())
+            },
+        )?;
+        self.unversioned_visible.insert(path.to_path_buf(), visible);
+        Ok(visible)
+    }
+
+    fn remove_tree(&mut self, path: &Path) {
+        let below: Vec<PathBuf> = self
+            .entries
+            .range(path.to_path_buf()..)
+            .take_while(|(p, _)| p.starts_with(path))
+            .map(|(p, _)| p.clone())
+            .collect();
+        for p in below {
+            self.entries.remove(&p);
+        }
+    }
+
+    /// Walk the versioned directory `dir` and everything below it.
+    fn rewalk(&mut self, dir: &Path) -> Result<(), Error<'static>> {
+        // Watch before walking, so that no change made during the walk is
+        // lost.
+        for versioned in self
+            .versioned_dirs
+            .range(dir.to_path_buf()..)
+            .take_while(|d| d.starts_with(dir))
+        {
+            self.inotify.watch(versioned, DIR_MASK)?;
+        }
+
+        self.remove_tree(dir);
+        let entries = &mut self.entries;
+        let unversioned_visible = &mut self.unversioned_visible;
+        let patterns: Vec<&str> = self.ignore_patterns.iter().map(String::as_str).collect();
+        self.ctx.walk_status(
+            dir,
+            crate::Depth::Infinity,
+            true,  // get_all
+            false, // no_ignore
+            false, // ignore_text_mods
+            Some(&patterns),
+            |path, status| {
+                let path = PathBuf::from(path);
+                if !status.versioned() {
+                    unversioned_visible.insert(path.clone(), true);
+                }
+                entries.insert(path, status.dup());
+                Ok(())
+            },
+        )
+    }
+
+    /// The status of `path`, or `None` if a walk would not report it.
+    pub fn status(&mut self, path: &Path) -> Result<Option<&Status<'static>>, Error<'static>> {
+        self.refresh()?;
+        let path = crate::dirent::to_absolute_cstring(path)?;
+        Ok(self.entries.get(Path::new(path.to_str()?)))
+    }
+
+    /// All statuses, ordered by path.
+    pub fn statuses(
+        &mut self,
+    ) -> Result<impl Iterator<Item = (&Path, &Status<'static>)>, Error<'static>> {
+        self.refresh()?;
+        Ok(self.entries.iter().map(|(p, s)| (p.as_path(), s)))
+    }
+
+    /// Statuses of the nodes whose node status is not normal, ordered by
+    /// path; what `svn status` lists.
+    pub fn changes(
+        &mut self,
+    ) -> Result<impl Iterator<Item = (&Path, &Status<'static>)>, Error<'static>> {
+        Ok(self
+            .statuses()?
+            .filter(|(_, s)| s.node_status() != StatusKind::Normal))
+    }
+}
+
+#[cfg(all(test, feature = "client"))]
+mod tests {
+    use super::*;
+    use crate::Revnum;
+
+    type Summary = BTreeMap<PathBuf, (StatusKind, StatusKind, StatusKind)>;
+
+    fn summary<'a>(statuses: impl Iterator<Item = (&'a Path, &'a Status<'static>)>) -> Summary {
+        statuses
+            .map(|(p, s)| {
+                (
+                    p.to_path_buf(),
+                    (s.node_status(), s.text_status(), s.prop_status()),
+                )
+            })
+            .collect()
+    }
+
+    fn fresh_walk(root: &Path) -> Summary {
+        let mut ctx = Context::new().unwrap();
+        let mut result = Summary::new();
+        ctx.walk_status(
+            root,
+            crate::Depth::Infinity,
+            true,
+            false,
+            false,
+            None,
+            |path, s| {
+                result.insert(
+                    PathBuf::from(path),
+                    (s.node_status(), s.text_status(), s.prop_status()),
+                );
+                Ok(())
+            },
+        )
+        .unwrap();
+        result
+    }
+
+    #[test]
+    fn test_status_cache_tracks_changes() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let repos_path = temp.path().join("repos");
+        let repos = crate::repos::Repos::create(&repos_path).unwrap();
+        let fs = repos.fs().unwrap();
+        let mut txn = fs.begin_txn(Revnum(0), 0).unwrap();
+        {
+            let mut root = txn.root().unwrap();
+            root.make_dir("/dir").unwrap();
+            root.make_file("/dir/a.txt").unwrap();
+            root.set_file_contents("/dir/a.txt", b"a\n").unwrap();
+            root.make_file("/b.txt").unwrap();
+            root.set_file_contents("/b.txt", b"b\n").unwrap();
+        }
+        txn.commit().unwrap();
+
+        let url = crate::path_to_file_url(&repos_path);
+        let mut client_ctx = crate::client::Context::new().unwrap();
+        client_ctx
+            .checkout(
+                crate::uri::Uri::new(&url).unwrap(),
+                temp.path().join("wc"),
+                &crate::client::CheckoutOptions {
+                    peg_revision: crate::Revision::Head,
+                    revision: crate::Revision::Head,
+                    depth: crate::Depth::Infinity,
+                    ignore_externals: false,
+                    allow_unver_obstructions: false,
+                },
+            )
+            .unwrap();
+
+        let config_dir = temp.path().join("config");
+        std::fs::create_dir(&config_dir).unwrap();
+        let mut cache =
+            StatusCache::with_config_dir(&temp.path().join("wc"), Some(&config_dir)).unwrap();
+        let wc = cache.root().to_path_buf();
+        assert_eq!(summary(cache.statuses().unwrap()), fresh_walk(&wc));
+        assert_eq!(cache.changes().unwrap().count(), 0);
+
+        // Local edits, an unversioned file, an ignored file and a missing
+        // file.
+        std::fs::write(wc.join("dir/a.txt"), b"changed\n").unwrap();
+        std::fs::write(wc.join("new.txt"), b"new\n").unwrap();
+        std::fs::write(wc.join("dir/ignored.o"), b"").unwrap();
+        std::fs::remove_file(wc.join("b.txt")).unwrap();
+        std::fs::create_dir(wc.join("newdir")).unwrap();
+        std::fs::write(wc.join("newdir/inner.txt"), b"").unwrap();
+
+        assert_eq!(
+            cache
+                .status(&wc.join("dir/a.txt"))
+                .unwrap()
+                .unwrap()
+                .node_status(),
+            StatusKind::Modified
+        );
+        assert_eq!(
+            cache
+                .status(&wc.join("new.txt"))
+                .unwrap()
+                .unwrap()
+                .node_status(),
+            StatusKind::Unversioned
+        );
+        assert!(cache.status(&wc.join("dir/ignored.o")).unwrap().is_none());
+        assert!(cache
+            .status(&wc.join("newdir/inner.txt"))
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            cache
+                .status(&wc.join("b.txt"))
+                .unwrap()
+                .unwrap()
+                .node_status(),
+            StatusKind::Missing
+        );
+        assert_eq!(summary(cache.statuses().unwrap()), fresh_walk(&wc));
+
+        // Reverting the edit brings the file back to normal.
+        std::fs::write(wc.join("dir/a.txt"), b"a\n").unwrap();
+        assert_eq!(
+            cache
+                .status(&wc.join("dir/a.txt"))
+                .unwrap()
+                .unwrap()
+                .node_status(),
+            StatusKind::Normal
+        );
+
+        // Changes made through Subversion are picked up as well.
+        client_ctx
+            .add(&wc.join("new.txt"), &crate::client::AddOptions::default())
+            .unwrap();
+        assert_eq!(
+            cache
+                .status(&wc.join("new.txt"))
+                .unwrap()
+                .unwrap()
+                .node_status(),
+            StatusKind::Added
+        );
+        assert_eq!(summary(cache.statuses().unwrap()), fresh_walk(&wc));
+
+        // Setting svn:ignore hides a file that was already reported.
+        std::fs::write(wc.join("scratch.txt"), b"").unwrap();
+        assert!(cache.status(&wc.join("scratch.txt")).unwrap().is_some());
+        client_ctx
+            .propset(
+                "svn:ignore",
+                Some(b"scratch.txt\n"),
+                wc.to_str().unwrap(),
+                &crate::client::PropSetOptions::default(),
+            )
+            .unwrap();
+        assert!(cache.status(&wc.join("scratch.txt")).unwrap().is_none());
+
+        // So does adding a pattern to global-ignores.
+        std::fs::write(wc.join("dir/trace.log"), b"").unwrap();
+        assert!(cache.status(&wc.join("dir/trace.log")).unwrap().is_some());
+        std::fs::write(
+            config_dir.join("config"),
+            "[miscellany]\nglobal-ignores = *.log\n",
+        )
+        .unwrap();
+        assert!(cache.status(&wc.join("dir/trace.log")).unwrap().is_none());
+    }
+}
@@ -35,3 +35,387 @@ extern "C" {
         scratch_pool: *mut apr_pool_t,
     ) -> *mut svn_error_t;
 }
+
+// Private libsvn_subr SQLite helpers (declared in private/svn_sqlite.h). They
+// are exported from libsvn_subr-1.so for use by libsvn_wc and libsvn_fs_fs,
+// and give read access to working copy databases (wc.db) through the same
+// SQLite library Subversion itself uses. The signatures are those of
+// Subversion 1.9 and later.
+#[cfg(feature = "wc")]
+#[repr(C)]
+pub struct svn_sqlite__db_t {
+    _private: [u8; 0],
+}
+
+#[cfg(feature = "wc")]
+#[repr(C)]
+pub struct svn_sqlite__stmt_t {
+    _private: [u8; 0],
+}
+
+/// Values of `svn_sqlite__mode_t`.
+#[cfg(feature = "wc")]
+pub const svn_sqlite__mode_readonly: std::os::raw::c_int = 0;
+#[cfg(feature = "wc")]
+pub const svn_sqlite__mode_readwrite: std::os::raw::c_int = 1;
+#[cfg(feature = "wc")]
+pub const svn_sqlite__mode_rwcreate: std::os::raw::c_int = 2;
+
+#[cfg(feature = "wc")]
+extern "C" {
+    pub fn svn_sqlite__open(
+        db: *mut *mut svn_sqlite__db_t,
+        repos_path: *const std::os::raw::c_char,
+        mode: std::os::raw::c_int,
+        statements: *const *const std::os::raw::c_char,
+        latest_schema: std::os::raw::c_int,
+        upgrade_sql: *const *const std::os::raw::c_char,
+        timeout: apr_int32_t,
+        result_pool: *mut apr_pool_t,
+        scratch_pool: *mut apr_pool_t,
+    ) -> *mut svn_error_t;
+
+    pub fn svn_sqlite__close(db: *mut svn_sqlite__db_t) -> *mut svn_error_t;
+
+    pub fn svn_sqlite__prepare(
+        stmt: *mut *mut svn_sqlite__stmt_t,
+        db: *mut svn_sqlite__db_t,
+        text: *const std::os::raw::c_char,
+        result_pool: *mut apr_pool_t,
+    ) -> *mut svn_error_t;
+
+    pub fn svn_sqlite__step(
+        got_row: *mut svn_boolean_t,
+        stmt: *mut svn_sqlite__stmt_t,
+    ) -> *mut svn_error_t;
+
+    pub fn svn_sqlite__finalize(stmt: *mut svn_sqlite__stmt_t) -> *mut svn_error_t;
+
+    pub fn svn_sqlite__bind_text(
+        stmt: *mut svn_sqlite__stmt_t,
+        slot: std::os::raw::c_int,
+        val: *const std::os::raw::c_char,
+    ) -> *mut svn_error_t;
+
+    pub fn svn_sqlite__column_is_null(
+        stmt: *mut svn_sqlite__stmt_t,
+        column: std::os::raw::c_int,
+    ) -> svn_boolean_t;
+
+    pub fn svn_sqlite__column_int64(
+        stmt: *mut svn_sqlite__stmt_t,
+        column: std::os::raw::c_int,
+    ) -> apr_int64_t;
+
+    pub fn svn_sqlite__column_text(
+        stmt: *mut svn_sqlite__stmt_t,
+        column: std::os::raw::c_int,
+        result_pool: *mut apr_pool_t,
+    ) -> *const std::os::raw::c_char;
+
+    pub fn svn_sqlite__column_blob(
+        stmt: *mut svn_sqlite__stmt_t,
+        column: std::os::raw::c_int,
+        len: *mut apr_size_t,
+        result_pool: *mut apr_pool_t,
+    ) -> *const std::os::raw::c_void;
+}
+
+// Ev2 editor and the delta editor shims (declared in svn_editor.h and
+// private/svn_delta_private.h, neither of which is installed). The symbols
+// are exported from libsvn_delta-1.so.
+#[cfg(feature = "delta")]
+mod ev2 {
+    use super::*;
+    use std::os::raw::{c_char, c_void};
+
+    #[repr(C)]
+    pub struct svn_editor_t {
+        _private: [u8; 0],
+    }
+
+    pub type svn_editor_cb_add_directory_t = Option<
+        unsafe extern "C" fn(
+            *mut c_void,
+            *const c_char,
+            *const apr_array_header_t,
+            *mut apr_hash_t,
+            svn_revnum_t,
+            *mut apr_pool_t,
+        ) -> *mut svn_error_t,
+    >;
+    pub type svn_editor_cb_add_file_t = Option<
+        unsafe extern "C" fn(
+            *mut c_void,
+            *const c_char,
+            *const svn_checksum_t,
+            *mut svn_stream_t,
+            *mut apr_hash_t,
+            svn_revnum_t,
+            *mut apr_pool_t,
+        ) -> *mut svn_error_t,
+    >;
+    pub type svn_editor_cb_add_symlink_t = Option<
+        unsafe extern "C" fn(
+            *mut c_void,
+            *const c_char,
+            *const c_char,
+            *mut apr_hash_t,
+            svn_revnum_t,
+            *mut apr_pool_t,
+        ) -> *mut svn_error_t,
+    >;
+    pub type svn_editor_cb_add_absent_t = Option<
+        unsafe extern "C" fn(
+            *mut c_void,
+            *const c_char,
+            svn_node_kind_t,
+            svn_revnum_t,
+            *mut apr_pool_t,
+        ) -> *mut svn_error_t,
+    >;
+    pub type svn_editor_cb_alter_directory_t = Option<
+        unsafe extern "C" fn(
+            *mut c_void,
+            *const c_char,
+            svn_revnum_t,
+            *const apr_array_header_t,
+            *mut apr_hash_t,
+            *mut apr_pool_t,
+        ) -> *mut svn_error_t,
+    >;
+    pub type svn_editor_cb_alter_file_t = Option<
+        unsafe extern "C" fn(
+            *mut c_void,
+            *const c_char,
+            svn_revnum_t,
+            *const svn_checksum_t,
+            *mut svn_stream_t,
+            *mut apr_hash_t,
+            *mut apr_pool_t,
+        ) -> *mut svn_error_t,
+    >;
+    pub type svn_editor_cb_alter_symlink_t = Option<
+        unsafe extern "C" fn(
+            *mut c_void,
+            *const c_char,
+            svn_revnum_t,
+            *const c_char,
+            *mut apr_hash_t,
+            *mut apr_pool_t,
+        ) -> *mut svn_error_t,
+    >;
+    pub type svn_editor_cb_delete_t = Option<
+        unsafe extern "C" fn(
+            *mut c_void,
+            *const c_char,
+            svn_revnum_t,
+            *mut apr_pool_t,
+        ) -> *mut svn_error_t,
+    >;
+    pub type svn_editor_cb_copy_t = Option<
+        unsafe extern "C" fn(
+            *mut c_void,
+            *const c_char,
+            svn_revnum_t,
+            *const c_char,
+            svn_revnum_t,
+            *mut apr_pool_t,
+        ) -> *mut svn_error_t,
+    >;
+    pub type svn_editor_cb_complete_t =
+        Option<unsafe extern "C" fn(*mut c_void, *mut apr_pool_t) -> *mut svn_error_t>;
+
+    #[repr(C)]
+    pub struct svn_editor_cb_many_t {
+        pub cb_add_directory: svn_editor_cb_add_directory_t,
+        pub cb_add_file: svn_editor_cb_add_file_t,
+        pub cb_add_symlink: svn_editor_cb_add_symlink_t,
+        pub cb_add_absent: svn_editor_cb_add_absent_t,
+        pub cb_alter_directory: svn_editor_cb_alter_directory_t,
+        pub cb_alter_file: svn_editor_cb_alter_file_t,
+        pub cb_alter_symlink: svn_editor_cb_alter_symlink_t,
+        pub cb_delete: svn_editor_cb_delete_t,
+        pub cb_copy: svn_editor_cb_copy_t,
+        pub cb_move: svn_editor_cb_copy_t,
+        pub cb_complete: svn_editor_cb_complete_t,
+        pub cb_abort: svn_editor_cb_complete_t,
+    }
+
+    pub type svn_delta__start_edit_func_t =
+        Option<unsafe extern "C" fn(*mut c_void, svn_revnum_t) -> *mut svn_error_t>;
+    pub type svn_delta__target_revision_func_t = Option<
+        unsafe extern "C" fn(*mut c_void, svn_revnum_t, *mut apr_pool_t) -> *mut svn_error_t,
+    >;
+    pub type svn_delta__unlock_func_t = Option<
+        unsafe extern "C" fn(*mut c_void, *const c_char, *mut apr_pool_t) -> *mut svn_error_t,
+    >;
+
+    #[repr(C)]
+    pub struct svn_delta__extra_baton {
+        pub start_edit: svn_delta__start_edit_func_t,
+        pub target_revision: svn_delta__target_revision_func_t,
+        pub baton: *mut c_void,
+    }
+
+    extern "C" {
+        pub fn svn_editor_create(
+            editor: *mut *mut svn_editor_t,
+            editor_baton: *mut c_void,
+            cancel_func: svn_cancel_func_t,
+            cancel_baton: *mut c_void,
+            result_pool: *mut apr_pool_t,
+            scratch_pool: *mut apr_pool_t,
+        ) -> *mut svn_error_t;
+        pub fn svn_editor_setcb_many(
+            editor: *mut svn_editor_t,
+            many: *const svn_editor_cb_many_t,
+            scratch_pool: *mut apr_pool_t,
+        ) -> *mut svn_error_t;
+        pub fn svn_editor_add_directory(
+            editor: *mut svn_editor_t,
+            relpath: *const c_char,
+            children: *const apr_array_header_t,
+            props: *mut apr_hash_t,
+            replaces_rev: svn_revnum_t,
+        ) -> *mut svn_error_t;
+        pub fn svn_editor_add_file(
+            editor: *mut svn_editor_t,
+            relpath: *const c_char,
+            checksum: *const svn_checksum_t,
+            contents: *mut svn_stream_t,
+            props: *mut apr_hash_t,
+            replaces_rev: svn_revnum_t,
+        ) -> *mut svn_error_t;
+        pub fn svn_editor_add_symlink(
+            editor: *mut svn_editor_t,
+            relpath: *const c_char,
+            target: *const c_char,
+            props: *mut apr_hash_t,
+            replaces_rev: svn_revnum_t,
+        ) -> *mut svn_error_t;
+        pub fn svn_editor_add_absent(
+            editor: *mut svn_editor_t,
+            relpath: *const c_char,
+            kind: svn_node_kind_t,
+            replaces_rev: svn_revnum_t,
+        ) -> *mut svn_error_t;
+        pub fn svn_editor_alter_directory(
+            editor: *mut svn_editor_t,
+            relpath: *const c_char,
+            revision: svn_revnum_t,
+            children: *const apr_array_header_t,
+            props: *mut apr_hash_t,
+        ) -> *mut svn_error_t;
+        pub fn svn_editor_alter_file(
+            editor: *mut svn_editor_t,
+            relpath: *const c_char,
+            revision: svn_revnum_t,
+            checksum: *const svn_checksum_t,
+            contents: *mut svn_stream_t,
+            props: *mut apr_hash_t,
+        ) -> *mut svn_error_t;
+        pub fn svn_editor_alter_symlink(
+            editor: *mut svn_editor_t,
+            relpath: *const c_char,
+            revision: svn_revnum_t,
+            target: *const c_char,
+            props: *mut apr_hash_t,
+        ) -> *mut svn_error_t;
+        pub fn svn_editor_delete(
+            editor: *mut svn_editor_t,
+            relpath: *const c_char,
+            revision: svn_revnum_t,
+        ) -> *mut svn_error_t;
+        pub fn svn_editor_copy(
+            editor: *mut svn_editor_t,
+            src_relpath: *const c_char,
+            src_revision: svn_revnum_t,
+            dst_relpath: *const c_char,
+            replaces_rev: svn_revnum_t,
+        ) -> *mut svn_error_t;
+        pub fn svn_editor_move(
+            editor: *mut svn_editor_t,
+            src_relpath: *const c_char,
+            src_revision: svn_revnum_t,
+            dst_relpath: *const c_char,
+            replaces_rev: svn_revnum_t,
+        ) -> *mut svn_error_t;
+        pub fn svn_editor_complete(editor: *mut svn_editor_t) -> *mut svn_error_t;
+        pub fn svn_editor_abort(editor: *mut svn_editor_t) -> *mut svn_error_t;
+
+        pub fn svn_delta__editor_from_delta(
+            editor_p: *mut *mut svn_editor_t,
+            exb: *mut *mut svn_delta__extra_baton,
+            unlock_func: *mut svn_delta__unlock_func_t,
+            unlock_baton: *mut *mut c_void,
+            deditor: *const svn_delta_editor_t,
+            dedit_baton: *mut c_void,
+            send_abs_paths: *mut svn_boolean_t,
+            repos_root: *const c_char,
+            base_relpath: *const c_char,
+            cancel_func: svn_cancel_func_t,
+            cancel_baton: *mut c_void,
+            fetch_kind_func: svn_delta_fetch_kind_func_t,
+            fetch_kind_baton: *mut c_void,
+            fetch_props_func: svn_delta_fetch_props_func_t,
+            fetch_props_baton: *mut c_void,
+            result_pool: *mut apr_pool_t,
+            scratch_pool: *mut apr_pool_t,
+        ) -> *mut svn_error_t;
+        pub fn svn_delta__delta_from_editor(
+            deditor: *mut *const svn_delta_editor_t,
+            dedit_baton: *mut *mut c_void,
+            editor: *mut svn_editor_t,
+            unlock_func: svn_delta__unlock_func_t,
+            unlock_baton: *mut c_void,
+            found_abs_paths: *mut svn_boolean_t,
+            repos_root: *const c_char,
+            base_relpath: *const c_char,
+            fetch_props_func: svn_delta_fetch_props_func_t,
+            fetch_props_baton: *mut c_void,
+            fetch_base_func: svn_delta_fetch_base_func_t,
+            fetch_base_baton: *mut c_void,
+            exb: *mut svn_delta__extra_baton,
+            pool: *mut apr_pool_t,
+        ) -> *mut svn_error_t;
+    }
+}
+#[cfg(feature = "delta")]
+pub use ev2::*;
+
+// Diff tree processors (declared in private/svn_diff_tree.h,
+// private/svn_wc_private.h and private/svn_client_private.h). They let a
+// caller drive libsvn_client's repository diff editor with its own
+// svn_wc_diff_callbacks4_t. The symbols are exported from libsvn_wc-1.so and
+// libsvn_client-1.so; the signatures are those of Subversion 1.9 and later.
+#[cfg(feature = "client")]
+#[repr(C)]
+pub struct svn_diff_tree_processor_t {
+    _private: [u8; 0],
+}
+
+#[cfg(feature = "client")]
+extern "C" {
+    pub fn svn_wc__wrap_diff_callbacks(
+        diff_processor: *mut *const svn_diff_tree_processor_t,
+        callbacks: *const svn_wc_diff_callbacks4_t,
+        callback_baton: *mut std::os::raw::c_void,
+        walk_deleted_dirs: svn_boolean_t,
+        result_pool: *mut apr_pool_t,
+        scratch_pool: *mut apr_pool_t,
+    ) -> *mut svn_error_t;
+
+    pub fn svn_client__get_diff_editor2(
+        editor: *mut *const svn_delta_editor_t,
+        edit_baton: *mut *mut std::os::raw::c_void,
+        ra_session: *mut svn_ra_session_t,
+        depth: svn_depth_t,
+        revision: svn_revnum_t,
+        text_deltas: svn_boolean_t,
+        processor: *const svn_diff_tree_processor_t,
+        cancel_func: svn_cancel_func_t,
+        cancel_baton: *mut std::os::raw::c_void,
+        result_pool: *mut apr_pool_t,
+    ) -> *mut svn_error_t;
+}